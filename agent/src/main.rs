@@ -0,0 +1,113 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+
+use clap::Parser;
+use ecies_core::keys::wipe;
+use ecies_core::{EciesErrorCode, Keypair};
+use ecies_ffi::agent::Keyring;
+
+/*
+Daemon holding secret keys in its own process and serving the `ecies_agent_*` clients of the C API over a Unix
+domain socket, so the keys never live in the app process. The protocol is described in ios/src/agent.rs.
+
+Keys are loaded at startup from files holding a hex or base64 secret key, such as the output of
+`ecies-cli keygen`, each under the name clients ask for, or from a key directory of `<name>.key` files, which also
+receives the keys clients generate:
+
+    ecies-agent --socket /run/ecies/agent.sock --key signing=/etc/ecies/signing.key
+    ecies-agent --socket /run/ecies/agent.sock --key-dir /var/lib/ecies/keys
+
+The socket is made readable and writable by its owner only, but is briefly open to the umask after it is bound,
+so put it in a directory only the owner can enter. A stale socket left by a previous run is replaced. Each
+connection is served on its own thread.
+*/
+
+#[derive(Parser)]
+#[command(name = "ecies-agent", version, about = "Holds secret keys and serves ECIES requests over a Unix socket")]
+struct Options {
+    /// Path of the Unix domain socket to listen on
+    #[arg(long, value_name = "PATH")]
+    socket: PathBuf,
+    /// Secret key to serve, repeatable
+    #[arg(long = "key", value_name = "NAME=FILE", required_unless_present = "key_dir", value_parser = parse_key)]
+    keys: Vec<(String, PathBuf)>,
+    /// Directory of `<name>.key` files to serve, where generated keys are written as well
+    #[arg(long, value_name = "DIR")]
+    key_dir: Option<PathBuf>,
+}
+
+fn parse_key(entry: &str) -> Result<(String, PathBuf), String> {
+    entry
+        .split_once('=')
+        .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+        .ok_or_else(|| format!("expected NAME=FILE, got `{entry}`"))
+}
+
+fn load_keyring(key_dir: Option<&Path>, keys: &[(String, PathBuf)]) -> Result<Keyring, String> {
+    let mut keyring = match key_dir {
+        Some(directory) => Keyring::open(directory).map_err(|code| format!("{}: {code}", directory.display()))?,
+        None => Keyring::new(),
+    };
+    for (name, path) in keys {
+        if keyring.names().contains(name) {
+            return Err(format!("key `{name}` is given twice"));
+        }
+        let mut text = fs::read(path).map_err(|error| format!("{}: {error}", path.display()))?;
+        let keypair = std::str::from_utf8(&text)
+            .map_err(|_| EciesErrorCode::InvalidUtf8)
+            .and_then(|text| Keypair::from_text(text.trim()));
+        // The file contents are a copy of the secret key
+        wipe(&mut text);
+
+        keyring.insert(name, keypair.map_err(|code| format!("{}: {code}", path.display()))?);
+    }
+    Ok(keyring)
+}
+
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    // A socket nobody answers on is left over from a previous run
+    if path.exists() && UnixStream::connect(path).is_err() {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+    Ok(listener)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let keyring = Arc::new(load_keyring(options.key_dir.as_deref(), &options.keys)?);
+    let listener = bind(&options.socket).map_err(|error| format!("{}: {error}", options.socket.display()))?;
+    eprintln!("ecies-agent: serving {} on {}", keyring.names().join(", "), options.socket.display());
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(mut stream) => {
+                let keyring = Arc::clone(&keyring);
+                thread::spawn(move || {
+                    if let Err(code) = keyring.serve(&mut stream) {
+                        eprintln!("ecies-agent: connection dropped: {code}");
+                    }
+                });
+            }
+            Err(error) => eprintln!("ecies-agent: {error}"),
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Options::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("ecies-agent: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,8 @@
+fn main() {
+    // Baked into the JNI layer by `option_env!`, see `JNI_CLASS` in lib.rs
+    println!("cargo:rerun-if-env-changed=ECIES_JNI_CLASS");
+
+    // Lets the Node.js addon resolve the N-API symbols from the node binary when it is loaded, see ios/src/napi_bindings.rs
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
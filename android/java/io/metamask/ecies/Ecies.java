@@ -0,0 +1,139 @@
+package io.metamask.ecies;
+
+import android.content.res.AssetManager;
+import android.os.ParcelFileDescriptor;
+
+import java.nio.ByteBuffer;
+import java.util.Map;
+
+/**
+ * Java side of the JNI layer in {@code android/src/lib.rs}, which documents each native method.
+ *
+ * <p>The static methods take keys on every call. An instance instead owns a parsed secret key in native memory:
+ * <pre>
+ * try (Ecies identity = Ecies.fromSecretKey(secret)) {
+ *     byte[] ciphertext = identity.encryptTo(recipientPublicKey, message);
+ *     byte[] plaintext = identity.decrypt(received);
+ * }
+ * </pre>
+ * {@link #close()} clears and releases the key, so the secret does not linger as a String on the JVM heap.
+ */
+public final class Ecies implements AutoCloseable {
+    static {
+        System.loadLibrary("ecies");
+    }
+
+    private long secretKeyHandle;
+
+    /** Takes ownership of a handle from {@link #parseSecretKey}, released by {@link #close()}. */
+    public Ecies(long secretKeyHandle) {
+        if (secretKeyHandle == 0) {
+            throw new IllegalArgumentException("secretKeyHandle is 0");
+        }
+        this.secretKeyHandle = secretKeyHandle;
+    }
+
+    public static Ecies fromSecretKey(String secret) {
+        return new Ecies(parseSecretKey(secret));
+    }
+
+    private long handle() {
+        if (secretKeyHandle == 0) {
+            throw new IllegalStateException("Ecies is closed");
+        }
+        return secretKeyHandle;
+    }
+
+    /** Encrypts a message for {@code pubkey} into a binary envelope signed with this secret key. */
+    public synchronized byte[] encryptTo(byte[] pubkey, byte[] message) {
+        return encryptSignedWithHandle(handle(), pubkey, message);
+    }
+
+    /** Decrypts a binary envelope, signed or not, or a legacy ciphertext. */
+    public synchronized byte[] decrypt(byte[] ciphertext) {
+        return decryptWithHandle(handle(), ciphertext);
+    }
+
+    /** Clears and releases the secret key. Further calls throw IllegalStateException; closing twice is a no-op. */
+    @Override
+    public synchronized void close() {
+        if (secretKeyHandle != 0) {
+            freeKey(secretKeyHandle);
+            secretKeyHandle = 0;
+        }
+    }
+
+    public static native String generateSecretKey();
+
+    public static native String derivePublicKeyFrom(String secret);
+
+    public static native String encryptMessage(String pubkey, String message);
+
+    public static native String decryptMessage(String secret, String message);
+
+    public static native byte[] encryptBytes(byte[] pubkey, byte[] message);
+
+    public static native byte[] decryptBytes(byte[] secret, byte[] ciphertext);
+
+    public static native long parseSecretKey(String secret);
+
+    public static native long parsePublicKey(String pubkey);
+
+    public static native byte[] encryptWithHandle(long keyHandle, byte[] message);
+
+    public static native byte[] decryptWithHandle(long keyHandle, byte[] ciphertext);
+
+    public static native byte[] encryptSignedWithHandle(long keyHandle, byte[] pubkey, byte[] message);
+
+    public static native void freeKey(long keyHandle);
+
+    public static native int encryptedLength(int messageLength);
+
+    public static native int encryptDirect(byte[] pubkey, ByteBuffer input, ByteBuffer output);
+
+    public static native int decryptDirect(byte[] secret, ByteBuffer input, ByteBuffer output);
+
+    public static native Map<String, Object> inspectMessage(String message);
+
+    public static native Map<String, Object> decryptMessageToMap(String secret, String message);
+
+    // Only in libraries built with the `keystore` feature
+    public static native Map<String, Object> parseKeyAttestation(byte[] certificate);
+
+    public static native boolean encryptFileDescriptor(String pubkey, ParcelFileDescriptor input, ParcelFileDescriptor output);
+
+    public static native boolean decryptFileDescriptor(String secret, ParcelFileDescriptor input, ParcelFileDescriptor output);
+
+    public static native boolean encryptAsset(String pubkey, AssetManager assets, String name, ParcelFileDescriptor output);
+
+    public static native boolean decryptAsset(String secret, AssetManager assets, String name, ParcelFileDescriptor output);
+
+    public static native void encryptAsync(String pubkey, String message, EciesCallback callback);
+
+    public static native void decryptAsync(String secret, String message, EciesCallback callback);
+
+    public static native void encryptWithContinuation(String pubkey, String message, EciesContinuation continuation);
+
+    public static native void decryptWithContinuation(String secret, String message, EciesContinuation continuation);
+
+    public static native void serveAgent(int fd, String keyDirectory);
+
+    public static native long agentConnect(int fd);
+
+    public static native byte[] agentPublicKey(long agent, String keyName);
+
+    public static native byte[] agentGenerateKey(long agent, String keyName);
+
+    public static native byte[] agentEncrypt(long agent, String keyName, byte[] pubkey, byte[] message);
+
+    public static native byte[] agentDecrypt(long agent, String keyName, byte[] ciphertext);
+
+    public static native byte[] agentSign(long agent, String keyName, byte[] message);
+
+    public static native void agentClose(long agent);
+
+    // Only in libraries built with the `sqlite` feature
+    public static native long sqliteAddSecretKey(String secret);
+
+    public static native void sqliteRemoveSecretKey(long handle);
+}
@@ -0,0 +1,96 @@
+package io.metamask.ecies;
+
+import android.os.IBinder;
+import android.os.Parcel;
+import android.os.ParcelFileDescriptor;
+import android.os.RemoteException;
+
+import java.io.IOException;
+
+/**
+ * Client of {@link EciesKeyService}: runs the secret key operations in the helper process, so a compromised app
+ * process can use the keys while it runs but cannot read them out. Bind the service and connect once it is bound:
+ * <pre>
+ * context.bindService(new Intent(context, EciesKeyService.class), new ServiceConnection() {
+ *     public void onServiceConnected(ComponentName name, IBinder service) {
+ *         agent = EciesAgent.connect(service);
+ *         byte[] publicKey = agent.generateKey("identity");
+ *     }
+ *     public void onServiceDisconnected(ComponentName name) {
+ *         agent.close();
+ *     }
+ * }, Context.BIND_AUTO_CREATE);
+ * </pre>
+ * Keys are named; the helper loads them from its key directory and saves the ones it generates there. Failures
+ * throw the {@link EciesException} subclass of their error code, {@code UnknownKey} for names the helper does not
+ * hold and {@code IoFailed} once the helper process is gone. Instances are safe to share between threads.
+ */
+public final class EciesAgent implements AutoCloseable {
+    private long agentHandle;
+
+    private EciesAgent(long agentHandle) {
+        this.agentHandle = agentHandle;
+    }
+
+    /** Opens a connection to the helper process through the binder of a bound {@link EciesKeyService}. */
+    public static EciesAgent connect(IBinder service) throws IOException, RemoteException {
+        ParcelFileDescriptor[] sockets = ParcelFileDescriptor.createSocketPair();
+        Parcel data = Parcel.obtain();
+        Parcel reply = Parcel.obtain();
+        try {
+            data.writeFileDescriptor(sockets[1].getFileDescriptor());
+            service.transact(EciesKeyService.TRANSACTION_CONNECT, data, reply, 0);
+            reply.readException();
+        } catch (RemoteException | RuntimeException error) {
+            sockets[0].close();
+            throw error;
+        } finally {
+            // The helper received its own copy of the descriptor
+            sockets[1].close();
+            data.recycle();
+            reply.recycle();
+        }
+        return new EciesAgent(Ecies.agentConnect(sockets[0].detachFd()));
+    }
+
+    private long handle() {
+        if (agentHandle == 0) {
+            throw new IllegalStateException("EciesAgent is closed");
+        }
+        return agentHandle;
+    }
+
+    /** The compressed public key of a key held by the helper. */
+    public synchronized byte[] publicKey(String keyName) {
+        return Ecies.agentPublicKey(handle(), keyName);
+    }
+
+    /** Generates a key in the helper, saved under {@code keyName}, and returns its compressed public key. */
+    public synchronized byte[] generateKey(String keyName) {
+        return Ecies.agentGenerateKey(handle(), keyName);
+    }
+
+    /** Encrypts a message for {@code pubkey} into a binary envelope signed with the named key. */
+    public synchronized byte[] encryptTo(String keyName, byte[] pubkey, byte[] message) {
+        return Ecies.agentEncrypt(handle(), keyName, pubkey, message);
+    }
+
+    /** Decrypts a binary envelope, signed or not, or a legacy ciphertext with the named key. */
+    public synchronized byte[] decrypt(String keyName, byte[] ciphertext) {
+        return Ecies.agentDecrypt(handle(), keyName, ciphertext);
+    }
+
+    /** Signs the SHA-256 digest of a message: 64-byte compact ECDSA signature followed by the recovery ID. */
+    public synchronized byte[] sign(String keyName, byte[] message) {
+        return Ecies.agentSign(handle(), keyName, message);
+    }
+
+    /** Closes the connection. Further calls throw IllegalStateException; closing twice is a no-op. */
+    @Override
+    public synchronized void close() {
+        if (agentHandle != 0) {
+            Ecies.agentClose(agentHandle);
+            agentHandle = 0;
+        }
+    }
+}
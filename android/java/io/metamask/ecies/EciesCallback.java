@@ -0,0 +1,9 @@
+package io.metamask.ecies;
+
+/** Receives the result of {@link Ecies#encryptAsync} or {@link Ecies#decryptAsync}, on a native worker thread. */
+public interface EciesCallback {
+    void onSuccess(String result);
+
+    /** {@code code} is an {@code EciesErrorCode} value. */
+    void onError(int code);
+}
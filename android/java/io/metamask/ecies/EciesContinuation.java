@@ -0,0 +1,11 @@
+package io.metamask.ecies;
+
+/**
+ * Resumed once by {@link Ecies#encryptWithContinuation} or {@link Ecies#decryptWithContinuation}, on a native
+ * worker thread. {@code EciesCoroutines.kt} adapts it to a Kotlin coroutine continuation.
+ */
+public interface EciesContinuation {
+    void resume(String result);
+
+    void resumeWithException(EciesException error);
+}
@@ -0,0 +1,27 @@
+package io.metamask.ecies;
+
+/**
+ * Thrown by the native methods of {@link Ecies} that report failures as exceptions. The subclasses group the
+ * causes callers usually branch on; every exception also carries the {@code EciesErrorCode} of ecies.h.
+ */
+public class EciesException extends RuntimeException {
+    private final int code;
+    private final String error;
+
+    // Called from the JNI layer with the EciesErrorCode value and variant name
+    public EciesException(int code, String error) {
+        super(error);
+        this.code = code;
+        this.error = error;
+    }
+
+    /** The {@code EciesErrorCode} value. */
+    public int getCode() {
+        return code;
+    }
+
+    /** The {@code EciesErrorCode} variant name, e.g. {@code "InvalidEnvelope"}. */
+    public String getError() {
+        return error;
+    }
+}
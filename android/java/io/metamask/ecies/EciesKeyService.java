@@ -0,0 +1,62 @@
+package io.metamask.ecies;
+
+import android.app.Service;
+import android.content.Intent;
+import android.os.Binder;
+import android.os.IBinder;
+import android.os.Parcel;
+import android.os.ParcelFileDescriptor;
+import android.os.RemoteException;
+
+import java.io.File;
+
+/**
+ * Holds the secret keys of the app in a helper process, so they are never loaded into the app process. Declare it
+ * in its own process, which the library manifest does for {@code io.metamask.ecies}:
+ * <pre>
+ * &lt;service android:name="io.metamask.ecies.EciesKeyService"
+ *          android:process=":ecies"
+ *          android:exported="false" /&gt;
+ * </pre>
+ * and talk to it through {@link EciesAgent}. Each connection is one end of a socketpair, served by
+ * {@code Ecies.serveAgent} on a native thread with the protocol of {@code ios/src/agent.rs}. Keys are kept in
+ * {@code noBackupFilesDir/ecies-keys}, or in memory only when the service runs with
+ * {@code android:isolatedProcess="true"}, which has no file access.
+ */
+public class EciesKeyService extends Service {
+    // Transaction carrying the ParcelFileDescriptor of the helper end of a socketpair
+    static final int TRANSACTION_CONNECT = IBinder.FIRST_CALL_TRANSACTION;
+
+    private final IBinder binder = new Binder() {
+        @Override
+        protected boolean onTransact(int code, Parcel data, Parcel reply, int flags) throws RemoteException {
+            if (code != TRANSACTION_CONNECT) {
+                return super.onTransact(code, data, reply, flags);
+            }
+            ParcelFileDescriptor socket = data.readFileDescriptor();
+            if (socket == null) {
+                throw new IllegalArgumentException("Missing socket");
+            }
+            // The native side owns the descriptor from here on
+            Ecies.serveAgent(socket.detachFd(), keyDirectory());
+            if (reply != null) {
+                reply.writeNoException();
+            }
+            return true;
+        }
+    };
+
+    // Null in an isolated process, where keys then live in memory only
+    private String keyDirectory() {
+        File directory = new File(getNoBackupFilesDir(), "ecies-keys");
+        if (!directory.isDirectory() && !directory.mkdirs()) {
+            return null;
+        }
+        return directory.getAbsolutePath();
+    }
+
+    @Override
+    public IBinder onBind(Intent intent) {
+        return binder;
+    }
+}
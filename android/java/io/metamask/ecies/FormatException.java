@@ -0,0 +1,11 @@
+package io.metamask.ecies;
+
+/**
+ * The input is not a well-formed ciphertext or encoding, e.g. {@code InvalidEnvelope}, {@code InvalidBase64} or
+ * {@code UnsupportedVersion}.
+ */
+public class FormatException extends EciesException {
+    public FormatException(int code, String error) {
+        super(code, error);
+    }
+}
@@ -0,0 +1,8 @@
+package io.metamask.ecies;
+
+/** A secret or public key could not be parsed: {@code InvalidSecretKey} or {@code InvalidPublicKey}. */
+public class InvalidKeyException extends EciesException {
+    public InvalidKeyException(int code, String error) {
+        super(code, error);
+    }
+}
@@ -0,0 +1,11 @@
+package io.metamask.ecies;
+
+/**
+ * The ciphertext failed authentication ({@code DecryptionFailed}): it was tampered with, truncated, or encrypted
+ * for another key.
+ */
+public class MacMismatchException extends EciesException {
+    public MacMismatchException(int code, String error) {
+        super(code, error);
+    }
+}
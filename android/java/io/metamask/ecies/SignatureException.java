@@ -0,0 +1,8 @@
+package io.metamask.ecies;
+
+/** The sender signature of an envelope is invalid or missing: {@code InvalidSignature} or {@code MissingSignature}. */
+public class SignatureException extends EciesException {
+    public SignatureException(int code, String error) {
+        super(code, error);
+    }
+}
@@ -0,0 +1,37 @@
+// Suspending wrappers over the native job queue, bundled as source: add this file next to `android/java` and
+// depend on kotlinx-coroutines-core. The work runs on the native worker threads, so callers need no dispatcher
+// of their own and never block the one they suspend on, Dispatchers.Main included:
+//
+//     lifecycleScope.launch {
+//         val ciphertext = EciesCoroutines.encrypt(publicKey, message)
+//     }
+//
+// Failures are thrown as the EciesException subclass of their error code.
+
+package io.metamask.ecies
+
+import kotlin.coroutines.resume
+import kotlin.coroutines.resumeWithException
+import kotlinx.coroutines.CancellableContinuation
+import kotlinx.coroutines.suspendCancellableCoroutine
+
+object EciesCoroutines {
+    // Queued jobs cannot be stopped: on cancellation the coroutine resumes right away and the result is dropped
+    private fun CancellableContinuation<String>.asEcies() = object : EciesContinuation {
+        override fun resume(result: String) {
+            if (isActive) this@asEcies.resume(result)
+        }
+
+        override fun resumeWithException(error: EciesException) {
+            if (isActive) this@asEcies.resumeWithException(error)
+        }
+    }
+
+    suspend fun encrypt(pubkey: String, message: String): String = suspendCancellableCoroutine { continuation ->
+        Ecies.encryptWithContinuation(pubkey, message, continuation.asEcies())
+    }
+
+    suspend fun decrypt(secret: String, message: String): String = suspendCancellableCoroutine { continuation ->
+        Ecies.decryptWithContinuation(secret, message, continuation.asEcies())
+    }
+}
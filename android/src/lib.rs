@@ -1,199 +1,1297 @@
-use std::mem::ManuallyDrop;
-use std::os::raw::c_char;
-use std::ffi::{CStr, CString};
-use ecies::{PublicKey, SecretKey};
-use ecies::{encrypt, decrypt, utils::generate_keypair};
-
 /*
-This module implements a Rust Foreign Function Interface (FFI) crypto framework to C-based libraries e.g .a staticlib, dylib, xcframework etc
+The Android library: the C API of `ios/` (re-exported below, so its functions are exported by this cdylib as
+well) and the JNI natives of `io.metamask.ecies.Ecies`. Everything but the JNI plumbing lives in `ecies-ffi-c`
+and `ecies-core`.
+*/
 
-Summary:
-    - Generate a private key, 
-    - Generate a public key from a private key, 
-    - Encrypt a message using a public key, and 
-    - Decrypt a message using a private key. 
+pub use ecies_ffi::*;
 
-This module uses the Elliptic Curve Integrated Encryption Scheme (ECIES)
-*/
+/// Expose the JNI interface for android below
+#[cfg(all(target_os = "android", feature = "jni"))]
+#[allow(non_snake_case)]
+pub mod android {
+    extern crate jni;
 
-/*
-Generates a new secret key using the libsecp256k1 library 
-It returns the hexadecimal representation of the serialized secret key as a C string.
-*/
+    use super::*;
+    use std::ffi::{CStr, CString};
+    use ecies::{PublicKey, SecretKey};
+    use ecies_ffi::context::{generate_keypair, public_key_of};
+    use ecies_ffi::ecies_core::Keypair;
+    use ecies_ffi::envelope::{open_or_legacy, seal_base64};
+    use self::jni::{JNIEnv, JavaVM, NativeMethod};
+    use self::jni::objects::{GlobalRef, JByteArray, JByteBuffer, JClass, JMap, JMethodID, JObject, JString, JThrowable, JValue};
+    use self::jni::signature::{Primitive, ReturnType};
+    use self::jni::sys::{jboolean, jbyteArray, jint, jlong, jobject, jstring, JNI_ERR, JNI_FALSE, JNI_TRUE, JNI_VERSION_1_6};
+    use std::io::{BufWriter, Read};
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::path::PathBuf;
+    use std::sync::{OnceLock, RwLock};
+    use super::envelope::{seal, Header};
+    use super::error::EciesErrorCode;
+    use super::progress::Progress;
 
-#[no_mangle]
-pub unsafe extern "C" fn ecies_generate_secret_key() -> *const c_char {
-    let key_pair = generate_keypair();
-    // Ignore public key for now
-    let secret_key = key_pair.0;
+    /*
+    Java strings are read as UTF-16 (see `java_string`) and created from Rust strings by `new_string`, which encodes
+    them in the modified UTF-8 the JVM expects, so supplementary characters such as emoji and embedded NULs survive
+    both ways. Failures throw the `EciesException` subclass of their error code.
+    */
 
-    let secret_key_buffer = secret_key.serialize();
-    let secret_key_hex = hex::encode(secret_key_buffer);
+    fn string_or_throw(env: &mut JNIEnv, result: Result<String, EciesErrorCode>) -> jstring {
+        match result.and_then(|text| env.new_string(text).map_err(|_| EciesErrorCode::InvalidArgument)) {
+            Ok(text) => text.into_raw(),
+            Err(code) => {
+                throw_error(env, code);
+                std::ptr::null_mut()
+            }
+        }
+    }
 
-    let secret_key_cstring_result = CString::new(secret_key_hex);
-    let secret_key_cstr = ManuallyDrop::new(secret_key_cstring_result.unwrap());
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_generateSecretKey(mut env: JNIEnv, _: JClass) -> jstring {
+        let secret_key = generate_keypair().map(|(secret_key, _)| hex::encode(secret_key.serialize()));
+        string_or_throw(&mut env, secret_key)
+    }
 
-    let secret_key_ptr = secret_key_cstr.as_ptr();
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_derivePublicKeyFrom(mut env: JNIEnv, _: JClass, secret: JString) -> jstring {
+        let result = (|| {
+            let (secret_key, _) = keys::parse_secret_key_text(&java_string(&mut env, &secret)?)?;
+            Ok(hex::encode(public_key_of(&secret_key).serialize_compressed()))
+        })();
 
-    secret_key_ptr
-}
+        string_or_throw(&mut env, result)
+    }
 
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptMessage(mut env: JNIEnv, _: JClass, pubkey: JString, message: JString) -> jstring {
+        let result = (|| {
+            let (public_key, _) = keys::parse_public_key_text(&java_string(&mut env, &pubkey)?)?;
+            let message = java_string(&mut env, &message)?;
 
-/*
-Generates a public key from the given secret key
-It takes a secret key as a C string and returns the corresponding public key as a C string. 
-Steps: 
-     - Convert the secret key from a C string to a Rust string, 
-     - Decode the hexadecimal representation of the secret key, and then 
-     - Generate the public key from the secret key.
-*/
+            seal_base64(&public_key, message.as_bytes())?.into_string().map_err(|_| EciesErrorCode::InvalidUtf8)
+        })();
 
-#[no_mangle]
-pub unsafe extern "C" fn ecies_public_key_from(secret_key_ptr: *const c_char) -> *const c_char {
-    let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
-    let secret_key_str_result = secret_key_cstr.to_str();
-    let secret_key_str = secret_key_str_result.unwrap();
-    let secret_key_string = secret_key_str.to_string();
-    let secret_key_buffer = hex::decode(secret_key_string).unwrap();
+        string_or_throw(&mut env, result)
+    }
 
-    let secret_key = SecretKey::parse_slice(&secret_key_buffer[..]).unwrap();
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptMessage(mut env: JNIEnv, _: JClass, secret: JString, message: JString) -> jstring {
+        let result = (|| {
+            let (secret_key, _) = keys::parse_secret_key_text(&java_string(&mut env, &secret)?)?;
+            let ciphertext = armor::decode_text(java_string(&mut env, &message)?.as_bytes())?;
+            let (_, decrypted) = open_or_legacy(&secret_key, &ciphertext)?;
 
-    let public_key = PublicKey::from_secret_key(&secret_key);
+            String::from_utf8(decrypted).map_err(|_| EciesErrorCode::InvalidUtf8)
+        })();
 
-    let public_key_buffer = public_key.serialize_compressed();
-    let public_key_hex = hex::encode(public_key_buffer);
+        string_or_throw(&mut env, result)
+    }
 
-    let public_key_cstring_result = CString::new(public_key_hex);
-    // ManuallyDrop is useful when the ownership of the underlying resource is transferred to code outside of Rust
-    let public_key_cstr = ManuallyDrop::new(public_key_cstring_result.unwrap());
+    fn java_bytes(env: &JNIEnv, array: &JByteArray) -> Result<Vec<u8>, EciesErrorCode> {
+        env.convert_byte_array(array).map_err(|_| EciesErrorCode::NullPointer)
+    }
 
-    let public_key_ptr = public_key_cstr.as_ptr();
+    // The `EciesException` subclass thrown for each error code, in the package of `JNI_CLASS`
+    fn exception_class(code: EciesErrorCode) -> &'static str {
+        match code {
+            EciesErrorCode::InvalidSecretKey
+            | EciesErrorCode::InvalidPublicKey
+            | EciesErrorCode::InvalidKeyLength
+            | EciesErrorCode::InvalidCurvePoint => "InvalidKeyException",
+            EciesErrorCode::DecryptionFailed | EciesErrorCode::MacFailed => "MacMismatchException",
+            EciesErrorCode::InvalidUtf8
+            | EciesErrorCode::InvalidHex
+            | EciesErrorCode::InvalidBase64
+            | EciesErrorCode::InvalidEnvelope
+            | EciesErrorCode::InvalidArmor
+            | EciesErrorCode::UnsupportedVersion
+            | EciesErrorCode::UnsupportedSuite
+            | EciesErrorCode::NonCanonicalEnvelope
+            | EciesErrorCode::InvalidAttestation => "FormatException",
+            EciesErrorCode::InvalidSignature | EciesErrorCode::MissingSignature => "SignatureException",
+            _ => "EciesException",
+        }
+    }
 
+    // Constructs the exception for `code`, through the classes cached by `JNI_OnLoad` when called from a worker thread
+    fn new_exception<'local>(env: &mut JNIEnv<'local>, code: EciesErrorCode) -> self::jni::errors::Result<JObject<'local>> {
+        let class_name = exception_class(code);
+        let cached = cached_exception(class_name);
+        let name = env.new_string(code.to_string())?;
+        let arguments = [JValue::Int(code as i32), JValue::Object(&name)];
+        match cached {
+            Some((class, constructor)) => {
+                let arguments = arguments.map(|argument| argument.as_jni());
+                unsafe { env.new_object_unchecked(<&JClass>::from(class.as_obj()), constructor, &arguments) }
+            }
+            None => env.new_object(jni_class(class_name), "(ILjava/lang/String;)V", &arguments),
+        }
+    }
 
-    public_key_ptr
-}
+    // Leaves an exception for `code` pending, to be thrown once the native method returns
+    fn throw_error(env: &mut JNIEnv, code: EciesErrorCode) {
+        // A Java exception raised while converting arguments or results is already pending and takes precedence
+        if env.exception_check().unwrap_or(false) {
+            return;
+        }
+        match new_exception(env, code) {
+            Ok(exception) => {
+                let _ = env.throw(JThrowable::from(exception));
+            }
+            Err(_) if !env.exception_check().unwrap_or(false) => {
+                let _ = env.throw_new("java/lang/IllegalStateException", code.to_string());
+            }
+            Err(_) => {}
+        }
+    }
 
-/*
-Encrypts a message using the provided public key.
-It takes a public key and a message as C strings and returns the encrypted message as a base64-encoded C string. 
-Steps:
-     - Convert the public key from a C string to a Rust string, 
-     - Decode the hexadecimal representation of the public key, 
-     - Encrypt the the message using ecies encryption
-*/
+    fn byte_array_or_throw(env: &mut JNIEnv, result: Result<Vec<u8>, EciesErrorCode>) -> jbyteArray {
+        match result.and_then(|bytes| env.byte_array_from_slice(&bytes).map_err(|_| EciesErrorCode::InvalidArgument)) {
+            Ok(array) => array.into_raw(),
+            Err(code) => {
+                throw_error(env, code);
+                std::ptr::null_mut()
+            }
+        }
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn ecies_encrypt(public_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
-    let public_key_cstr = unsafe { CStr::from_ptr(public_key_ptr) };
-    let public_key_str_result = public_key_cstr.to_str();
-    let public_key_str = public_key_str_result.unwrap();
-    let public_key_string = public_key_str.to_string();
-    let public_key_buffer = hex::decode(public_key_string).unwrap();
+    /*
+    Java: `static native byte[] encryptBytes(byte[] pubkey, byte[] message)`
+    Encrypts a binary message into a binary envelope, the base64-decoded output of `encryptMessage`, without the
+    String, base64 and hex conversions. The public key may use any layout accepted by `ecies_convert_public_key`.
+    Failures throw the `EciesException` subclass of their error code.
+    */
 
-    let public_key_result = PublicKey::parse_slice(&public_key_buffer[..], None);
-    let public_key = public_key_result.unwrap();
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptBytes(mut env: JNIEnv, _: JClass, pubkey: JByteArray, message: JByteArray) -> jbyteArray {
+        let result = (|| {
+            let public_key = keys::parse_public_key(&java_bytes(&env, &pubkey)?)?;
+            let message = java_bytes(&env, &message)?;
 
-    let serialized_public_key_buffer = public_key.serialize_compressed();
+            seal(&public_key, &Header::default(), &message, None)
+        })();
 
-    let message_cstr = unsafe { CStr::from_ptr(message_ptr) };
-    let message_buffer = message_cstr.to_bytes();
-    
-    let encrypted_result = encrypt(&serialized_public_key_buffer, message_buffer);
-    let encrypted = encrypted_result.unwrap();
-    let encrypted_buffer = &encrypted[..];
-    let encoded = base64::encode(encrypted_buffer);
+        byte_array_or_throw(&mut env, result)
+    }
 
-    let encrypted_message_cstring = ManuallyDrop::new(CString::new(encoded).unwrap());
-    let encrypted_message_cstr = encrypted_message_cstring.as_c_str().to_str().unwrap();
+    /*
+    Java: `static native byte[] decryptBytes(byte[] secret, byte[] ciphertext)`
+    Decrypts a binary envelope, or a legacy ciphertext, with a 32-byte secret key. Failures throw like `encryptBytes`.
+    */
 
-    let encrypted_message_ptr = encrypted_message_cstr.as_ptr();
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptBytes(mut env: JNIEnv, _: JClass, secret: JByteArray, ciphertext: JByteArray) -> jbyteArray {
+        let result = (|| {
+            let mut secret = java_bytes(&env, &secret)?;
+            let secret_key = keys::parse_secret_key(&secret);
+            keys::wipe(&mut secret);
+            let (_, message) = open_or_legacy(&secret_key?, &java_bytes(&env, &ciphertext)?)?;
 
-    encrypted_message_ptr as *const c_char
-}
+            Ok(message)
+        })();
 
+        byte_array_or_throw(&mut env, result)
+    }
 
-/*
-Decrypts a message using the provided secret key.
-It takes a secret key and a message as C string and returns the decrypted message as a C string. 
-Steps:
-     - Convert the private key and encrypted message from C strings to Rust strings 
-     - Decode the hexadecimal representation of the private key, 
-     - Decrypt the message using ecies decryption
-*/
+    // What a `long` key handle points to; secret key handles also carry their public key, so they can encrypt.
+    // `freeKey`, and so `Ecies.close()`, clears the secret key as the keypair is dropped
+    enum KeyHandle {
+        Secret(Keypair),
+        Public(PublicKey),
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn ecies_decrypt(secret_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
-    let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
-    let secret_key_str_result = secret_key_cstr.to_str();
-    let secret_key_str = secret_key_str_result.unwrap();
-    let secret_key_string = secret_key_str.to_string();
-    let secret_key_buffer = hex::decode(secret_key_string).unwrap();
+    impl KeyHandle {
+        fn public_key(&self) -> &PublicKey {
+            match self {
+                KeyHandle::Secret(keypair) => keypair.public_key(),
+                KeyHandle::Public(public_key) => public_key,
+            }
+        }
 
-    let secret_key_result = SecretKey::parse_slice(&secret_key_buffer[..]);
-    let secret_key = secret_key_result.unwrap();
+        fn secret_key(&self) -> Result<&SecretKey, EciesErrorCode> {
+            match self {
+                KeyHandle::Secret(keypair) => Ok(keypair.secret_key()),
+                KeyHandle::Public(_) => Err(EciesErrorCode::InvalidSecretKey),
+            }
+        }
+    }
 
-    let serialized_secret_key_buffer = secret_key.serialize();
+    fn handle_into_long(handle: KeyHandle) -> jlong {
+        Box::into_raw(Box::new(handle)) as jlong
+    }
 
-    let message_cstr = unsafe { CStr::from_ptr(message_ptr) };
-    let message_buffer = message_cstr.to_bytes();
+    unsafe fn handle_from_long<'a>(handle: jlong) -> Result<&'a KeyHandle, EciesErrorCode> {
+        (handle as *const KeyHandle).as_ref().ok_or(EciesErrorCode::NullPointer)
+    }
 
-    let message_decode_result = base64::decode(message_buffer);
-    let message_vec = message_decode_result.unwrap();
-    
-    let decrypted_result = decrypt(&serialized_secret_key_buffer, &message_vec[..]);
-    let decrypted = decrypted_result.unwrap();
+    /*
+    Java: `static native long parseSecretKey(String secret)`
+    Parses a secret key once (any text encoding accepted by `decryptMessage`) into a native handle for
+    `decryptWithHandle` and `encryptWithHandle`, so hot paths skip the hex decoding and validation on every message.
+    Throws `InvalidKeyException` or `FormatException` on failure. Release the handle with `freeKey`.
+    */
 
-    let decrypted_message_cstring = ManuallyDrop::new(CString::new(decrypted).unwrap());
-    let decrypted_message_cstr = decrypted_message_cstring.as_c_str().to_str().unwrap();
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_parseSecretKey(mut env: JNIEnv, _: JClass, secret: JString) -> jlong {
+        let result = (|| {
+            let (secret_key, _) = keys::parse_secret_key_text(&java_string(&mut env, &secret)?)?;
+            Ok::<_, EciesErrorCode>(KeyHandle::Secret(Keypair::from_secret_key(secret_key)))
+        })();
 
-    let decrypted_message_ptr = decrypted_message_cstr.as_ptr();
+        handle_or_throw(&mut env, result)
+    }
 
-    decrypted_message_ptr as *const c_char
-}
+    /*
+    Java: `static native long parsePublicKey(String pubkey)`
+    Parses a public key once into a native handle for `encryptWithHandle`, throwing like `parseSecretKey`. Release
+    the handle with `freeKey`.
+    */
 
-/// Expose the JNI interface for android below
-#[cfg(target_os="android")]
-#[allow(non_snake_case)]
-pub mod android {
-    extern crate jni;
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_parsePublicKey(mut env: JNIEnv, _: JClass, pubkey: JString) -> jlong {
+        let result = (|| {
+            let (public_key, _) = keys::parse_public_key_text(&java_string(&mut env, &pubkey)?)?;
+            Ok::<_, EciesErrorCode>(KeyHandle::Public(public_key))
+        })();
 
-    use super::*;
-    use self::jni::JNIEnv;
-    use self::jni::objects::{JClass, JString};
-    use self::jni::sys::jstring;
+        handle_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native byte[] encryptWithHandle(long keyHandle, byte[] message)`
+    Works like `encryptBytes` with the public key of a handle from `parsePublicKey` or `parseSecretKey`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptWithHandle(mut env: JNIEnv, _: JClass, key_handle: jlong, message: JByteArray) -> jbyteArray {
+        let result = (|| {
+            let handle = handle_from_long(key_handle)?;
+            seal(handle.public_key(), &Header::default(), &java_bytes(&env, &message)?, None)
+        })();
+
+        byte_array_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native byte[] decryptWithHandle(long keyHandle, byte[] ciphertext)`
+    Works like `decryptBytes` with a handle from `parseSecretKey`; public key handles throw `InvalidKeyException`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptWithHandle(mut env: JNIEnv, _: JClass, key_handle: jlong, ciphertext: JByteArray) -> jbyteArray {
+        let result = (|| {
+            let secret_key = handle_from_long(key_handle)?.secret_key()?;
+            let (_, message) = open_or_legacy(secret_key, &java_bytes(&env, &ciphertext)?)?;
+
+            Ok(message)
+        })();
+
+        byte_array_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native byte[] encryptSignedWithHandle(long keyHandle, byte[] pubkey, byte[] message)`
+    Encrypts a message for `pubkey` into a binary envelope signed with the secret key of a handle from
+    `parseSecretKey`, which recipients verify as the sender. Backs `Ecies.encryptTo`. Failures throw like
+    `encryptBytes`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptSignedWithHandle(mut env: JNIEnv, _: JClass, key_handle: jlong, pubkey: JByteArray, message: JByteArray) -> jbyteArray {
+        let result = (|| {
+            let secret_key = handle_from_long(key_handle)?.secret_key()?;
+            let public_key = keys::parse_public_key(&java_bytes(&env, &pubkey)?)?;
+
+            seal(&public_key, &Header::default(), &java_bytes(&env, &message)?, Some(secret_key))
+        })();
+
+        byte_array_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native void freeKey(long keyHandle)`
+    Releases a handle from `parseSecretKey` or `parsePublicKey`. Passing 0 is a no-op; a handle must not be used
+    after it is freed, nor freed twice.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_freeKey(_: JNIEnv, _: JClass, key_handle: jlong) {
+        if key_handle != 0 {
+            drop(Box::from_raw(key_handle as *mut KeyHandle));
+        }
+    }
+
+    // The bytes between the position and the limit of a direct ByteBuffer, in native memory
+    struct DirectRegion {
+        data: *mut u8,
+        position: usize,
+        remaining: usize,
+    }
+
+    impl DirectRegion {
+        fn of(env: &mut JNIEnv, buffer: &JByteBuffer) -> Result<DirectRegion, EciesErrorCode> {
+            let address = env.get_direct_buffer_address(buffer).map_err(|_| EciesErrorCode::InvalidArgument)?;
+            let position = DirectRegion::index(env, buffer, "position")?;
+            let limit = DirectRegion::index(env, buffer, "limit")?;
+
+            Ok(DirectRegion { data: unsafe { address.add(position) }, position, remaining: limit - position })
+        }
+
+        // `position()` or `limit()`
+        fn index(env: &mut JNIEnv, buffer: &JByteBuffer, method: &str) -> Result<usize, EciesErrorCode> {
+            env.call_method(buffer, method, "()I", &[])
+                .and_then(|value| value.i())
+                .ok()
+                .and_then(|value| usize::try_from(value).ok())
+                .ok_or(EciesErrorCode::InvalidArgument)
+        }
+
+        fn overlaps(&self, other: &DirectRegion) -> bool {
+            let (start, end) = (self.data as usize, self.data as usize + self.remaining);
+            let (other_start, other_end) = (other.data as usize, other.data as usize + other.remaining);
+            start < other_end && other_start < end
+        }
+
+        // Moves the position of `buffer` past the `count` bytes read or written
+        fn advance(&self, env: &mut JNIEnv, buffer: &JByteBuffer, count: usize) -> Result<(), EciesErrorCode> {
+            let position = i32::try_from(self.position + count).map_err(|_| EciesErrorCode::InvalidArgument)?;
+            env.call_method(buffer, "position", "(I)Ljava/nio/Buffer;", &[JValue::Int(position)])
+                .map(|_| ())
+                .map_err(|_| EciesErrorCode::InvalidArgument)
+        }
+    }
+
+    // Byte count, or -1 with an exception pending
+    fn length_or_throw(env: &mut JNIEnv, result: Result<usize, EciesErrorCode>) -> jint {
+        match result.and_then(|count| jint::try_from(count).map_err(|_| EciesErrorCode::InvalidArgument)) {
+            Ok(count) => count,
+            Err(code) => {
+                throw_error(env, code);
+                -1
+            }
+        }
+    }
+
+    fn handle_or_throw(env: &mut JNIEnv, result: Result<KeyHandle, EciesErrorCode>) -> jlong {
+        match result {
+            Ok(handle) => handle_into_long(handle),
+            Err(code) => {
+                throw_error(env, code);
+                0
+            }
+        }
+    }
+
+    /*
+    Java: `static native int encryptedLength(int messageLength)`
+    Size of the binary envelope `encryptDirect` writes for a message of `messageLength` bytes.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptedLength(mut env: JNIEnv, _: JClass, message_length: jint) -> jint {
+        let result = usize::try_from(message_length).map(envelope::sealed_length).map_err(|_| EciesErrorCode::InvalidArgument);
+        length_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native int encryptDirect(byte[] pubkey, ByteBuffer input, ByteBuffer output)`
+    Encrypts the remaining bytes of the direct `input` buffer into a binary envelope written straight into the
+    direct `output` buffer at its position, without copying either through the JVM heap. On success both positions
+    advance past the bytes read and written, and the envelope length is returned. Failures throw like
+    `encryptBytes`, with `BufferTooSmall` when `output` has fewer than `encryptedLength` bytes remaining and
+    `InvalidArgument` when the two regions overlap, and leave both buffers as they were.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptDirect(mut env: JNIEnv, _: JClass, pubkey: JByteArray, input: JByteBuffer, output: JByteBuffer) -> jint {
+        let result = (|| {
+            let public_key = keys::parse_public_key(&java_bytes(&env, &pubkey)?)?;
+            let (source, destination) = (DirectRegion::of(&mut env, &input)?, DirectRegion::of(&mut env, &output)?);
+            let envelope_length = envelope::sealed_length(source.remaining);
+            if source.overlaps(&destination) {
+                return Err(EciesErrorCode::InvalidArgument);
+            }
+            if destination.remaining < envelope_length {
+                return Err(EciesErrorCode::BufferTooSmall);
+            }
+
+            let message = std::slice::from_raw_parts(source.data, source.remaining);
+            let envelope = std::slice::from_raw_parts_mut(destination.data, envelope_length);
+            envelope::seal_into(&public_key, message, envelope)?;
+
+            source.advance(&mut env, &input, source.remaining)?;
+            destination.advance(&mut env, &output, envelope_length)?;
+            Ok(envelope_length)
+        })();
+
+        length_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native int decryptDirect(byte[] secret, ByteBuffer input, ByteBuffer output)`
+    Decrypts the binary envelope, or legacy ciphertext, held in the remaining bytes of the direct `input` buffer
+    into the direct `output` buffer at its position. The message is never longer than the envelope, so `output`
+    always has room when it has as many bytes remaining as `input`. Positions, the return value and failures work
+    like `encryptDirect`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptDirect(mut env: JNIEnv, _: JClass, secret: JByteArray, input: JByteBuffer, output: JByteBuffer) -> jint {
+        let result = (|| {
+            let mut secret = java_bytes(&env, &secret)?;
+            let secret_key = keys::parse_secret_key(&secret);
+            keys::wipe(&mut secret);
+            let (source, destination) = (DirectRegion::of(&mut env, &input)?, DirectRegion::of(&mut env, &output)?);
+            if source.overlaps(&destination) {
+                return Err(EciesErrorCode::InvalidArgument);
+            }
+
+            let ciphertext = std::slice::from_raw_parts(source.data, source.remaining);
+            let (_, mut message) = open_or_legacy(&secret_key?, ciphertext)?;
+            if destination.remaining < message.len() {
+                keys::wipe(&mut message);
+                return Err(EciesErrorCode::BufferTooSmall);
+            }
+            std::ptr::copy_nonoverlapping(message.as_ptr(), destination.data, message.len());
+            keys::wipe(&mut message);
+
+            source.advance(&mut env, &input, source.remaining)?;
+            destination.advance(&mut env, &output, message.len())?;
+            Ok(message.len())
+        })();
+
+        length_or_throw(&mut env, result)
+    }
+
+    // Values put into the java.util.HashMap returned to Java: strings, or nested maps for the metadata
+    enum MapValue {
+        Text(String),
+        Map(Vec<(String, MapValue)>),
+    }
+
+    fn map_or_throw(env: &mut JNIEnv, result: Result<Vec<(String, MapValue)>, EciesErrorCode>) -> jobject {
+        match result.and_then(|entries| new_hash_map(env, entries).map_err(|_| EciesErrorCode::InvalidArgument)) {
+            Ok(map) => map.into_raw(),
+            Err(code) => {
+                throw_error(env, code);
+                std::ptr::null_mut()
+            }
+        }
+    }
+
+    fn new_hash_map<'local>(env: &mut JNIEnv<'local>, entries: Vec<(String, MapValue)>) -> self::jni::errors::Result<JObject<'local>> {
+        let object = env.new_object("java/util/HashMap", "()V", &[])?;
+        {
+            let map = JMap::from_env(env, &object)?;
+            for (key, value) in entries {
+                let key = env.new_string(key)?;
+                let value = match value {
+                    MapValue::Text(text) => JObject::from(env.new_string(text)?),
+                    MapValue::Map(entries) => new_hash_map(env, entries)?,
+                };
+                map.put(env, &key, &value)?;
+            }
+        }
+        Ok(object)
+    }
+
+    // Flattens what `ecies_inspect` reports into map entries; absent values are left out
+    fn inspection_entries(inspection: &inspect::Inspection) -> Vec<(String, MapValue)> {
+        let text = MapValue::Text;
+        let mut entries = vec![
+            ("format".to_string(), text(inspection.format.to_string())),
+            ("suite".to_string(), text(inspection.suite.to_string())),
+            ("suiteName".to_string(), text(inspection.suite_name.to_string())),
+            ("payloadLength".to_string(), text(inspection.payload_length.to_string())),
+            ("hasAad".to_string(), text(inspection.has_aad.to_string())),
+            ("signed".to_string(), text(inspection.signed.to_string())),
+        ];
+        if let Some(version) = inspection.version {
+            entries.push(("version".to_string(), text(version.to_string())));
+        }
+        if let Some(ephemeral_public_key) = &inspection.ephemeral_public_key {
+            entries.push(("ephemeralPublicKey".to_string(), text(ephemeral_public_key.clone())));
+        }
+        if let Some(header) = &inspection.header {
+            let optional = [
+                ("createdAt", header.created_at.map(|value| value.to_string())),
+                ("ttl", header.ttl.map(|value| value.to_string())),
+                ("messageId", header.message_id.clone()),
+                ("sender", header.sender.clone()),
+                ("keyId", header.key_id.clone()),
+            ];
+            entries.extend(optional.into_iter().filter_map(|(key, value)| value.map(|value| (key.to_string(), text(value)))));
+            entries.push(("expired".to_string(), text(header.expired.to_string())));
+            entries.push(("compressed".to_string(), text(header.compressed.to_string())));
+
+            let metadata = header.metadata.iter().map(|(key, value)| (key.clone(), text(value.clone()))).collect();
+            entries.push(("metadata".to_string(), MapValue::Map(metadata)));
+        }
+        entries
+    }
+
+    fn inspect_message(env: &mut JNIEnv, message: &JString) -> Result<Vec<(String, MapValue)>, error::EciesErrorCode> {
+        let ciphertext = armor::decode_text(java_string(env, message)?.as_bytes())?;
+
+        Ok(inspection_entries(&inspect::inspect(&ciphertext)?))
+    }
+
+    /*
+    Java: `static native Map<String, Object> inspectMessage(String message)`
+    Returns what `ecies_inspect` reports as a map of strings, with the metadata as a nested map. Throws an
+    `EciesException` when the message cannot be parsed.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_inspectMessage(mut env: JNIEnv, _: JClass, message: JString) -> jobject {
+        let result = inspect_message(&mut env, &message);
+        map_or_throw(&mut env, result)
+    }
+
+    fn decrypt_message(env: &mut JNIEnv, secret: &JString, message: &JString) -> Result<Vec<(String, MapValue)>, error::EciesErrorCode> {
+        let (secret_key, _) = keys::parse_secret_key_text(&java_string(env, secret)?)?;
+        let ciphertext = armor::decode_text(java_string(env, message)?.as_bytes())?;
+
+        let mut entries = inspection_entries(&inspect::inspect(&ciphertext)?);
+        let (_, decrypted) = open_or_legacy(&secret_key, &ciphertext)?;
+        let plaintext = String::from_utf8(decrypted).map_err(|_| error::EciesErrorCode::InvalidUtf8)?;
+        entries.push(("plaintext".to_string(), MapValue::Text(plaintext)));
+        Ok(entries)
+    }
+
+    /*
+    Java: `static native Map<String, Object> decryptMessageToMap(String secret, String message)`
+    Decrypts the message and returns the plaintext under "plaintext", along with the entries of `inspectMessage`.
+    Failures throw an `EciesException`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptMessageToMap(mut env: JNIEnv, _: JClass, secret: JString, message: JString) -> jobject {
+        let result = decrypt_message(&mut env, &secret, &message);
+        map_or_throw(&mut env, result)
+    }
+
+    #[cfg(feature = "keystore")]
+    fn key_attestation_entries(env: &mut JNIEnv, certificate: &JByteArray) -> Result<Vec<(String, MapValue)>, EciesErrorCode> {
+        let attestation = attestation::parse_key_attestation(&java_bytes(env, certificate)?)?;
+
+        let text = MapValue::Text;
+        let mut entries = vec![
+            ("attestationVersion".to_string(), text(attestation.attestation_version.to_string())),
+            ("attestationSecurityLevel".to_string(), text(attestation.attestation_security_level.to_string())),
+            ("keymintVersion".to_string(), text(attestation.keymint_version.to_string())),
+            ("keymintSecurityLevel".to_string(), text(attestation.keymint_security_level.to_string())),
+            ("challenge".to_string(), text(attestation.challenge)),
+        ];
+        if let Some(root_of_trust) = attestation.root_of_trust {
+            entries.push(("verifiedBootKey".to_string(), text(root_of_trust.verified_boot_key)));
+            entries.push(("deviceLocked".to_string(), text(root_of_trust.device_locked.to_string())));
+            entries.push(("verifiedBootState".to_string(), text(root_of_trust.verified_boot_state.to_string())));
+        }
+        Ok(entries)
+    }
+
+    /*
+    Java: `static native Map<String, Object> parseKeyAttestation(byte[] certificate)`
+    Parses the key attestation extension of an encoded certificate, typically
+    `keyStore.getCertificateChain(alias)[0].getEncoded()`, into a map of strings: the attestation and KeyMint
+    versions and security levels ("software", "trusted_environment" or "strongbox"), the hex challenge, and the
+    verified boot key, device locked flag and verified boot state when the root of trust is present.
+    The chain is not verified. Failures throw an `EciesException`, a `FormatException` when the extension is missing
+    or malformed. Only in libraries built with the `keystore` feature.
+    */
+
+    #[cfg(feature = "keystore")]
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_parseKeyAttestation(mut env: JNIEnv, _: JClass, certificate: JByteArray) -> jobject {
+        let result = key_attestation_entries(&mut env, &certificate);
+        map_or_throw(&mut env, result)
+    }
+
+    // True, or false with an exception pending
+    fn status_or_throw(env: &mut JNIEnv, result: Result<(), EciesErrorCode>) -> jboolean {
+        match result {
+            Ok(()) => JNI_TRUE,
+            Err(code) => {
+                throw_error(env, code);
+                JNI_FALSE
+            }
+        }
+    }
+
+    /*
+    Reads a Java string through its UTF-16 code units with `GetStringRegion`. `GetStringUTFChars`, behind
+    `env.get_string`, yields modified UTF-8 instead, which encodes supplementary characters as two surrogates and NUL
+    as two bytes, none of which are valid UTF-8 for the rest of the library.
+    */
+    fn java_string(env: &mut JNIEnv, string: &JString) -> Result<String, EciesErrorCode> {
+        if string.is_null() {
+            return Err(EciesErrorCode::NullPointer);
+        }
+        let raw = env.get_raw();
+        let mut units = unsafe {
+            let functions = &**raw;
+            let length_of = functions.GetStringLength.ok_or(EciesErrorCode::InvalidArgument)?;
+            let region_of = functions.GetStringRegion.ok_or(EciesErrorCode::InvalidArgument)?;
+
+            let length = length_of(raw, string.as_raw());
+            let mut units = vec![0u16; usize::try_from(length).map_err(|_| EciesErrorCode::InvalidArgument)?];
+            region_of(raw, string.as_raw(), 0, length, units.as_mut_ptr());
+            units
+        };
+        let text = wide::string_from_utf16_units(&units);
+        // Keys pass through here too
+        units.fill(0);
+
+        text
+    }
+
+    // Raw descriptor of a ParcelFileDescriptor, which keeps ownership of it
+    fn parcel_fd(env: &mut JNIEnv, descriptor: &JObject) -> Result<c_int, EciesErrorCode> {
+        env.call_method(descriptor, "getFd", "()I", &[])
+            .and_then(|value| value.i())
+            .map_err(|_| EciesErrorCode::InvalidArgument)
+    }
+
+    /*
+    Java: `static native boolean encryptFileDescriptor(String pubkey, ParcelFileDescriptor input, ParcelFileDescriptor output)`
+    Streams the input descriptor into the output one as an `ecies_encrypt_fd` stream, e.g. for SAF documents opened
+    through a ContentResolver. Both descriptors stay open. Failures throw an `EciesException`.
+    */
 
     #[no_mangle]
-    pub unsafe extern fn Java_io_metamask_ecies_Ecies_generateSecretKey(env: JNIEnv, _: JClass) -> jstring {
-        let secret_key_ptr = ecies_generate_secret_key();
-        let secret_key_cstr = CStr::from_ptr(secret_key_ptr).to_str().unwrap();
-        let result = env.new_string(secret_key_cstr).unwrap();
-        result.into_inner()
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptFileDescriptor(mut env: JNIEnv, _: JClass, pubkey: JString, input: JObject, output: JObject) -> jboolean {
+        let result = (|| {
+            let (public_key, _) = keys::parse_public_key_text(&java_string(&mut env, &pubkey)?)?;
+            let (fd_in, fd_out) = (parcel_fd(&mut env, &input)?, parcel_fd(&mut env, &output)?);
+
+            file::encrypt_fd(&public_key, fd_in, fd_out, &mut Progress::none())
+        })();
+
+        status_or_throw(&mut env, result)
+    }
+
+    /*
+    Java: `static native boolean decryptFileDescriptor(String secret, ParcelFileDescriptor input, ParcelFileDescriptor output)`
+    Decrypts the stream read from the input descriptor into the output one. Failures throw an `EciesException`, in
+    which case the output must be discarded.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptFileDescriptor(mut env: JNIEnv, _: JClass, secret: JString, input: JObject, output: JObject) -> jboolean {
+        let result = (|| {
+            let (secret_key, _) = keys::parse_secret_key_text(&java_string(&mut env, &secret)?)?;
+            let (fd_in, fd_out) = (parcel_fd(&mut env, &input)?, parcel_fd(&mut env, &output)?);
+
+            file::decrypt_fd(secret_key, fd_in, fd_out, &mut Progress::none())
+        })();
+
+        status_or_throw(&mut env, result)
+    }
+
+    // The subset of the NDK asset API needed to stream APK assets
+    #[repr(C)]
+    pub struct AAssetManager {
+        _private: [u8; 0],
+    }
+
+    #[repr(C)]
+    pub struct AAsset {
+        _private: [u8; 0],
+    }
+
+    const AASSET_MODE_STREAMING: c_int = 2;
+
+    #[link(name = "android")]
+    extern "C" {
+        fn AAssetManager_fromJava(env: *mut self::jni::sys::JNIEnv, asset_manager: jobject) -> *mut AAssetManager;
+        fn AAssetManager_open(manager: *mut AAssetManager, filename: *const c_char, mode: c_int) -> *mut AAsset;
+        fn AAsset_read(asset: *mut AAsset, buffer: *mut c_void, count: usize) -> c_int;
+        fn AAsset_close(asset: *mut AAsset);
+    }
+
+    struct AssetReader {
+        asset: *mut AAsset,
+    }
+
+    impl AssetReader {
+        unsafe fn open(env: &mut JNIEnv, assets: &JObject, name: &JString) -> Result<AssetReader, EciesErrorCode> {
+            let name = CString::new(java_string(env, name)?).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+            let manager = AAssetManager_fromJava(env.get_raw(), assets.as_raw());
+            if manager.is_null() {
+                return Err(EciesErrorCode::NullPointer);
+            }
+            let asset = AAssetManager_open(manager, name.as_ptr(), AASSET_MODE_STREAMING);
+            if asset.is_null() {
+                return Err(EciesErrorCode::IoFailed);
+            }
+            Ok(AssetReader { asset })
+        }
+    }
+
+    impl Read for AssetReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = unsafe { AAsset_read(self.asset, buf.as_mut_ptr() as *mut c_void, buf.len()) };
+            usize::try_from(read).map_err(|_| std::io::Error::other("AAsset_read failed"))
+        }
+    }
+
+    impl Drop for AssetReader {
+        fn drop(&mut self) {
+            unsafe { AAsset_close(self.asset) };
+        }
     }
 
+    /*
+    Java: `static native boolean encryptAsset(String pubkey, AssetManager assets, String name, ParcelFileDescriptor output)`
+    Encrypts an APK asset into the output descriptor without copying it through Java byte arrays.
+    Failures throw an `EciesException`.
+    */
+
     #[no_mangle]
-    pub unsafe extern fn Java_io_metamask_ecies_Ecies_derivePublicKeyFrom(env: JNIEnv, _: JClass, secret: JString) -> jstring {
-        let public_key_ptr = ecies_public_key_from(env.get_string(secret).expect("Invalid private key format").as_ptr());
-        // Retake pointer so that we can use it below and allow memory to be freed when it goes out of scope.
-        let public_key_cstr = CStr::from_ptr(public_key_ptr).to_str().unwrap();
-        let result = env.new_string(public_key_cstr).unwrap();
-        result.into_inner()
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptAsset(mut env: JNIEnv, _: JClass, pubkey: JString, assets: JObject, name: JString, output: JObject) -> jboolean {
+        let result = (|| {
+            let (public_key, _) = keys::parse_public_key_text(&java_string(&mut env, &pubkey)?)?;
+            let reader = AssetReader::open(&mut env, &assets, &name)?;
+            let output = file::borrow_fd(parcel_fd(&mut env, &output)?)?;
+
+            stream::encrypt_io(&public_key, reader, BufWriter::new(&*output), &mut Progress::none())
+        })();
+
+        status_or_throw(&mut env, result)
     }
 
+    /*
+    Java: `static native boolean decryptAsset(String secret, AssetManager assets, String name, ParcelFileDescriptor output)`
+    Decrypts an encrypted stream shipped as an APK asset into the output descriptor. Failures throw an
+    `EciesException`, in which case the output must be discarded.
+    */
+
     #[no_mangle]
-    pub unsafe extern fn Java_io_metamask_ecies_Ecies_encryptMessage(env: JNIEnv, _: JClass, pubkey: JString, message: JString) -> jstring {
-        let cipher_text_ptr = ecies_encrypt(env.get_string(pubkey).expect("Invalid public key format").as_ptr(), env.get_string(message).expect("Invalid message format").as_ptr());
-        // Retake pointer so that we can use it below and allow memory to be freed when it goes out of scope.
-        let cipher_text_cstr = CStr::from_ptr(cipher_text_ptr).to_str().unwrap();
-        let result = env.new_string(cipher_text_cstr).unwrap();
-        result.into_inner()
-    } 
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptAsset(mut env: JNIEnv, _: JClass, secret: JString, assets: JObject, name: JString, output: JObject) -> jboolean {
+        let result = (|| {
+            let (secret_key, _) = keys::parse_secret_key_text(&java_string(&mut env, &secret)?)?;
+            let reader = AssetReader::open(&mut env, &assets, &name)?;
+            let output = file::borrow_fd(parcel_fd(&mut env, &output)?)?;
+
+            stream::decrypt_io(secret_key, reader, BufWriter::new(&*output), &mut Progress::none())
+        })();
+
+        status_or_throw(&mut env, result)
+    }
+
+    // Set by `JNI_OnLoad`, or on first use, so worker threads can call back into Java
+    static JAVA_VM: OnceLock<JavaVM> = OnceLock::new();
+
+    fn cache_java_vm(env: &JNIEnv) -> Result<(), EciesErrorCode> {
+        if JAVA_VM.get().is_none() {
+            let vm = env.get_java_vm().map_err(|_| EciesErrorCode::InvalidArgument)?;
+            let _ = JAVA_VM.set(vm);
+        }
+        Ok(())
+    }
+
+    // How a queued job reports to the Java object it was given
+    #[derive(Clone, Copy)]
+    enum Listener {
+        // `EciesCallback`: `onSuccess(String)` or `onError(int)`
+        Callback,
+        // `EciesContinuation`: `resume(String)` or `resumeWithException(EciesException)`
+        Continuation,
+    }
+
+    fn deliver(env: &mut JNIEnv, listener: Listener, target: &JObject, result: Result<&str, EciesErrorCode>) {
+        let delivered = match listener {
+            Listener::Callback => call_callback(env, target, result),
+            Listener::Continuation => resume_continuation(env, target, result),
+        };
+        // An exception thrown by the listener must not outlive it on a worker thread
+        if delivered.is_err() || env.exception_check().unwrap_or(false) {
+            let _ = env.exception_clear();
+        }
+    }
+
+    fn call_callback(env: &mut JNIEnv, callback: &JObject, result: Result<&str, EciesErrorCode>) -> self::jni::errors::Result<()> {
+        let cached = cached_callback_methods();
+        let void = ReturnType::Primitive(Primitive::Void);
+        let called = match (result, cached) {
+            (Ok(text), Some((on_success, _))) => env.new_string(text).and_then(|text| unsafe {
+                env.call_method_unchecked(callback, on_success, void, &[JValue::Object(&text).as_jni()])
+            }),
+            (Ok(text), None) => env.new_string(text).and_then(|text| {
+                env.call_method(callback, "onSuccess", "(Ljava/lang/String;)V", &[JValue::Object(&text)])
+            }),
+            (Err(code), Some((_, on_error))) => unsafe {
+                env.call_method_unchecked(callback, on_error, void, &[JValue::Int(code as i32).as_jni()])
+            },
+            (Err(code), None) => env.call_method(callback, "onError", "(I)V", &[JValue::Int(code as i32)]),
+        };
+        called.map(|_| ())
+    }
+
+    fn resume_continuation(env: &mut JNIEnv, continuation: &JObject, result: Result<&str, EciesErrorCode>) -> self::jni::errors::Result<()> {
+        let cached = cached_continuation_methods();
+        let void = ReturnType::Primitive(Primitive::Void);
+        let resumed = match result {
+            Ok(text) => {
+                let text = env.new_string(text)?;
+                match cached {
+                    Some((resume, _)) => unsafe {
+                        env.call_method_unchecked(continuation, resume, void, &[JValue::Object(&text).as_jni()])
+                    },
+                    None => env.call_method(continuation, "resume", "(Ljava/lang/String;)V", &[JValue::Object(&text)]),
+                }
+            }
+            Err(code) => {
+                let exception = new_exception(env, code)?;
+                match cached {
+                    Some((_, resume_with_exception)) => unsafe {
+                        env.call_method_unchecked(continuation, resume_with_exception, void, &[JValue::Object(&exception).as_jni()])
+                    },
+                    None => {
+                        let signature = format!("(L{};)V", jni_class("EciesException"));
+                        env.call_method(continuation, "resumeWithException", signature, &[JValue::Object(&exception)])
+                    }
+                }
+            }
+        };
+        resumed.map(|_| ())
+    }
+
+    // Completion callback of the job queue; `context` is the boxed listener and global reference to its target
+    unsafe extern "C" fn complete_async(context: *mut c_void, _job_id: u64, result_ptr: *const c_char, error: EciesErrorCode) {
+        let pending = Box::from_raw(context as *mut (Listener, GlobalRef));
+        if let Some(Ok(mut env)) = JAVA_VM.get().map(JavaVM::attach_current_thread_as_daemon) {
+            let result = match result_ptr.is_null() {
+                true => Err(error),
+                false => CStr::from_ptr(result_ptr).to_str().map_err(|_| EciesErrorCode::InvalidUtf8),
+            };
+            deliver(&mut env, pending.0, pending.1.as_obj(), result);
+        }
+        buffer::ecies_string_free(result_ptr);
+    }
+
+    // `ecies_submit_encrypt_bytes` or `ecies_submit_decrypt_bytes`: the message is passed with its length, as Java
+    // strings may contain NUL characters
+    type Submit =
+        unsafe extern "C" fn(*const c_char, *const u8, usize, jobs::EciesJobCallback, *mut c_void, *mut EciesErrorCode) -> u64;
+
+    unsafe fn submit_async(env: &mut JNIEnv, submit: Submit, key: &JString, message: &JString, listener: Listener, target: &JObject) {
+        let result = (|| {
+            cache_java_vm(env)?;
+            let key = CString::new(java_string(env, key)?).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+            let message = java_string(env, message)?;
+            let target = env.new_global_ref(target).map_err(|_| EciesErrorCode::InvalidArgument)?;
+
+            let context = Box::into_raw(Box::new((listener, target))) as *mut c_void;
+            let mut error = EciesErrorCode::Success;
+            if submit(key.as_ptr(), message.as_ptr(), message.len(), Some(complete_async), context, &mut error) == 0 {
+                drop(Box::from_raw(context as *mut (Listener, GlobalRef)));
+                return Err(error);
+            }
+            Ok(())
+        })();
+
+        if let Err(code) = result {
+            deliver(env, listener, target, Err(code));
+        }
+    }
+
+    /*
+    Java: `static native void encryptAsync(String pubkey, String message, EciesCallback callback)`
+    Encrypts like `encryptMessage` on a native worker thread, then calls `callback.onSuccess(ciphertext)` or
+    `callback.onError(code)` with an `EciesErrorCode` value. Callbacks run on the worker thread, except for errors
+    detected before the work is queued, which are reported right away on the calling thread.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptAsync(mut env: JNIEnv, _: JClass, pubkey: JString, message: JString, callback: JObject) {
+        submit_async(&mut env, jobs::ecies_submit_encrypt_bytes, &pubkey, &message, Listener::Callback, &callback);
+    }
+
+    /*
+    Java: `static native void decryptAsync(String secret, String message, EciesCallback callback)`
+    Decrypts like `decryptMessage` on a native worker thread, reporting to the callback like `encryptAsync`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptAsync(mut env: JNIEnv, _: JClass, secret: JString, message: JString, callback: JObject) {
+        submit_async(&mut env, jobs::ecies_submit_decrypt_bytes, &secret, &message, Listener::Callback, &callback);
+    }
+
+    /*
+    Java: `static native void encryptWithContinuation(String pubkey, String message, EciesContinuation continuation)`
+    Encrypts like `encryptAsync`, then calls `continuation.resume(ciphertext)`, or
+    `continuation.resumeWithException(exception)` with the `EciesException` subclass `encryptMessage` would throw.
+    Backs the `suspend` functions of `EciesCoroutines.kt`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_encryptWithContinuation(mut env: JNIEnv, _: JClass, pubkey: JString, message: JString, continuation: JObject) {
+        submit_async(&mut env, jobs::ecies_submit_encrypt_bytes, &pubkey, &message, Listener::Continuation, &continuation);
+    }
+
+    /*
+    Java: `static native void decryptWithContinuation(String secret, String message, EciesContinuation continuation)`
+    Decrypts like `decryptAsync`, resuming the continuation like `encryptWithContinuation`.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_decryptWithContinuation(mut env: JNIEnv, _: JClass, secret: JString, message: JString, continuation: JObject) {
+        submit_async(&mut env, jobs::ecies_submit_decrypt_bytes, &secret, &message, Listener::Continuation, &continuation);
+    }
+
+    /*
+    Java: `static native void serveAgent(int fd, String keyDirectory)`
+    Serves agent requests (see ios/src/agent.rs) on a connected socket on a new native thread, until the client
+    closes it. Called by `EciesKeyService` in its own process with the descriptor detached from the
+    ParcelFileDescriptor the app sent, whose ownership it takes. Keys are loaded from and generated into
+    `keyDirectory`, or kept in memory only when it is null. Throws when the directory cannot be read.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_serveAgent(mut env: JNIEnv, _: JClass, fd: jint, key_directory: JString) {
+        let result = (|| {
+            let mut stream = agent::stream_from_fd(fd)?;
+            let directory = if key_directory.is_null() {
+                None
+            } else {
+                Some(PathBuf::from(java_string(&mut env, &key_directory)?))
+            };
+            let keyring = agent::shared_keyring(directory.as_deref())?;
+
+            let serve = move || {
+                let _ = keyring.serve(&mut stream);
+            };
+            std::thread::Builder::new().name("ecies-agent".to_string()).spawn(serve).map_err(|_| EciesErrorCode::IoFailed)?;
+            Ok(())
+        })();
+
+        if let Err(code) = result {
+            throw_error(&mut env, code);
+        }
+    }
+
+    /*
+    Java: `static native long agentConnect(int fd)`
+    Wraps the app end of the socketpair whose other end was handed to `serveAgent`, taking ownership of the
+    descriptor, into a native agent handle for the `agent*` methods below. Backs `EciesAgent.connect`. Release the
+    handle with `agentClose`.
+    */
 
     #[no_mangle]
-    pub unsafe extern fn Java_io_metamask_ecies_Ecies_decryptMessage(env: JNIEnv, _: JClass, secret: JString, message: JString) -> jstring {
-        let decrypted_text_ptr = ecies_decrypt(env.get_string(secret).expect("Invalid private key format").as_ptr(), env.get_string(message).expect("Invalid message format").as_ptr());
-        // Retake pointer so that we can use it below and allow memory to be freed when it goes out of scope.
-        let decrypted_text_cstr = CStr::from_ptr(decrypted_text_ptr).to_str().unwrap();
-        let output = env.new_string(decrypted_text_cstr).unwrap();
-        output.into_inner()
-    }                 
-}
\ No newline at end of file
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentConnect(mut env: JNIEnv, _: JClass, fd: jint) -> jlong {
+        match agent::stream_from_fd(fd) {
+            Ok(stream) => Box::into_raw(Box::new(agent::EciesAgent::from_stream(stream))) as jlong,
+            Err(code) => {
+                throw_error(&mut env, code);
+                0
+            }
+        }
+    }
+
+    unsafe fn agent_from_long<'a>(agent: jlong) -> Result<&'a mut agent::EciesAgent, EciesErrorCode> {
+        (agent as *mut agent::EciesAgent).as_mut().ok_or(EciesErrorCode::NullPointer)
+    }
+
+    // Sends the request built from the Java arguments to the agent of a handle from `agentConnect`
+    unsafe fn agent_call<F>(env: &mut JNIEnv, agent: jlong, request: F) -> jbyteArray
+    where
+        F: FnOnce(&mut JNIEnv) -> Result<agent::Request, EciesErrorCode>,
+    {
+        let result = request(env).and_then(|request| agent_from_long(agent)?.call(&request));
+
+        byte_array_or_throw(env, result)
+    }
+
+    /*
+    Java: `static native byte[] agentPublicKey(long agent, String keyName)`
+    The compressed public key of the named key held by the helper process. Throws an `EciesException`
+    (`UnknownKey`) when it holds no such key, and `IoFailed` once the helper process is gone.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentPublicKey(mut env: JNIEnv, _: JClass, agent: jlong, key_name: JString) -> jbyteArray {
+        agent_call(&mut env, agent, |env| Ok(agent::Request::PublicKey { key: java_string(env, &key_name)? }))
+    }
+
+    /*
+    Java: `static native byte[] agentGenerateKey(long agent, String keyName)`
+    Generates a key in the helper process, saved in its key directory, and returns the compressed public key. The
+    secret key never enters the app process. Throws an `EciesException` (`InvalidArgument`) when the name is taken.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentGenerateKey(mut env: JNIEnv, _: JClass, agent: jlong, key_name: JString) -> jbyteArray {
+        agent_call(&mut env, agent, |env| Ok(agent::Request::Generate { key: java_string(env, &key_name)? }))
+    }
+
+    /*
+    Java: `static native byte[] agentEncrypt(long agent, String keyName, byte[] pubkey, byte[] message)`
+    Works like `encryptSignedWithHandle`, signing in the helper process with the named key.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentEncrypt(mut env: JNIEnv, _: JClass, agent: jlong, key_name: JString, pubkey: JByteArray, message: JByteArray) -> jbyteArray {
+        agent_call(&mut env, agent, |env| {
+            Ok(agent::Request::Encrypt {
+                key: java_string(env, &key_name)?,
+                recipient: java_bytes(env, &pubkey)?,
+                message: java_bytes(env, &message)?,
+            })
+        })
+    }
+
+    /*
+    Java: `static native byte[] agentDecrypt(long agent, String keyName, byte[] ciphertext)`
+    Works like `decryptWithHandle`, decrypting in the helper process with the named key.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentDecrypt(mut env: JNIEnv, _: JClass, agent: jlong, key_name: JString, ciphertext: JByteArray) -> jbyteArray {
+        agent_call(&mut env, agent, |env| {
+            Ok(agent::Request::Decrypt { key: java_string(env, &key_name)?, ciphertext: java_bytes(env, &ciphertext)? })
+        })
+    }
+
+    /*
+    Java: `static native byte[] agentSign(long agent, String keyName, byte[] message)`
+    Signs the SHA-256 digest of the message in the helper process with the named key, returning the 64-byte compact
+    ECDSA signature followed by the recovery ID.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentSign(mut env: JNIEnv, _: JClass, agent: jlong, key_name: JString, message: JByteArray) -> jbyteArray {
+        agent_call(&mut env, agent, |env| {
+            Ok(agent::Request::Sign { key: java_string(env, &key_name)?, message: java_bytes(env, &message)? })
+        })
+    }
+
+    /*
+    Java: `static native void agentClose(long agent)`
+    Closes the connection of a handle from `agentConnect`, which ends the service thread serving it. Passing 0 is
+    a no-op; a handle must not be used after it is closed, nor closed twice.
+    */
+
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_agentClose(_: JNIEnv, _: JClass, agent: jlong) {
+        if agent != 0 {
+            drop(Box::from_raw(agent as *mut agent::EciesAgent));
+        }
+    }
+
+    /*
+    Java: `static native long sqliteAddSecretKey(String secret)`
+    Registers a secret key (any text encoding accepted by `decryptMessage`) for the `ecies_decrypt` SQL function of
+    the `sqlite` build and returns the handle queries bind in its place. Throws `InvalidKeyException` or
+    `FormatException` on failure. Release the key with `sqliteRemoveSecretKey`.
+    */
+
+    #[cfg(feature = "sqlite")]
+    #[no_mangle]
+    pub unsafe extern "system" fn Java_io_metamask_ecies_Ecies_sqliteAddSecretKey(mut env: JNIEnv, _: JClass, secret: JString) -> jlong {
+        let result = java_string(&mut env, &secret)
+            .and_then(|secret| keys::parse_secret_key_text(&secret))
+            .and_then(|(secret_key, _)| sqlite_bindings::add_secret_key(secret_key));
+        match result {
+            Ok(handle) => handle,
+            Err(code) => {
+                throw_error(&mut env, code);
+                0
+            }
+        }
+    }
+
+    /*
+    Java: `static native void sqliteRemoveSecretKey(long handle)`
+    Releases a key from `sqliteAddSecretKey`; later `ecies_decrypt` calls with the handle fail.
+    */
+
+    #[cfg(feature = "sqlite")]
+    #[no_mangle]
+    pub extern "system" fn Java_io_metamask_ecies_Ecies_sqliteRemoveSecretKey(_: JNIEnv, _: JClass, handle: jlong) {
+        sqlite_bindings::ecies_sqlite_remove_secret_key(handle);
+    }
+
+    /*
+    Binary name of the Java class `JNI_OnLoad` registers the natives on, `io/metamask/ecies/Ecies` unless the
+    `ECIES_JNI_CLASS` environment variable is set at build time, e.g. `ECIES_JNI_CLASS=com/example/crypto/Ecies`.
+    `EciesCallback`, `EciesContinuation` and the exception classes are looked up in the same package, so apps copy `android/java` into
+    their own package and keep the crate unchanged. The exported `Java_io_metamask_ecies_Ecies_*` symbols only bind
+    to the default class.
+    */
+    const JNI_CLASS: &str = match option_env!("ECIES_JNI_CLASS") {
+        Some(class) => class,
+        None => "io/metamask/ecies/Ecies",
+    };
+
+    // Binary name of a class in the package of `JNI_CLASS`
+    fn jni_class(simple_name: &str) -> String {
+        match JNI_CLASS.rsplit_once('/') {
+            Some((package, _)) => format!("{package}/{simple_name}"),
+            None => simple_name.to_string(),
+        }
+    }
+
+    // Classes and method IDs looked up once by `JNI_OnLoad`, whose thread resolves classes with the app class loader
+    // (worker threads attached later only see system classes), and dropped by `JNI_OnUnload`
+    struct JniCache {
+        callback_on_success: JMethodID,
+        callback_on_error: JMethodID,
+        continuation_resume: JMethodID,
+        continuation_resume_with_exception: JMethodID,
+        exceptions: Vec<(&'static str, GlobalRef, JMethodID)>,
+    }
+
+    static JNI_CACHE: RwLock<Option<JniCache>> = RwLock::new(None);
+
+    const EXCEPTION_CLASSES: [&str; 5] = [
+        "EciesException",
+        "InvalidKeyException",
+        "MacMismatchException",
+        "FormatException",
+        "SignatureException",
+    ];
+
+    fn cached_callback_methods() -> Option<(JMethodID, JMethodID)> {
+        let cache = JNI_CACHE.read().ok()?;
+        cache.as_ref().map(|cache| (cache.callback_on_success, cache.callback_on_error))
+    }
+
+    fn cached_continuation_methods() -> Option<(JMethodID, JMethodID)> {
+        let cache = JNI_CACHE.read().ok()?;
+        cache.as_ref().map(|cache| (cache.continuation_resume, cache.continuation_resume_with_exception))
+    }
+
+    fn cached_exception(class_name: &str) -> Option<(GlobalRef, JMethodID)> {
+        let cache = JNI_CACHE.read().ok()?;
+        let (_, class, constructor) = cache.as_ref()?.exceptions.iter().find(|(name, _, _)| *name == class_name)?;
+        Some((class.clone(), *constructor))
+    }
+
+    fn load_cache(env: &mut JNIEnv) -> self::jni::errors::Result<JniCache> {
+        let callback = env.find_class(jni_class("EciesCallback"))?;
+        let callback_on_success = env.get_method_id(&callback, "onSuccess", "(Ljava/lang/String;)V")?;
+        let callback_on_error = env.get_method_id(&callback, "onError", "(I)V")?;
+        let continuation = env.find_class(jni_class("EciesContinuation"))?;
+        let continuation_resume = env.get_method_id(&continuation, "resume", "(Ljava/lang/String;)V")?;
+        let exception_signature = format!("(L{};)V", jni_class("EciesException"));
+        let continuation_resume_with_exception = env.get_method_id(&continuation, "resumeWithException", exception_signature)?;
+
+        let mut exceptions = Vec::with_capacity(EXCEPTION_CLASSES.len());
+        for name in EXCEPTION_CLASSES {
+            let class = env.find_class(jni_class(name))?;
+            let constructor = env.get_method_id(&class, "<init>", "(ILjava/lang/String;)V")?;
+            exceptions.push((name, env.new_global_ref(class)?, constructor));
+        }
+        Ok(JniCache {
+            callback_on_success,
+            callback_on_error,
+            continuation_resume,
+            continuation_resume_with_exception,
+            exceptions,
+        })
+    }
+
+    // Every native method of the Java class, for `RegisterNatives`
+    fn native_methods() -> Vec<NativeMethod> {
+        const STRING: &str = "Ljava/lang/String;";
+        const DESCRIPTOR: &str = "Landroid/os/ParcelFileDescriptor;";
+        const BUFFER: &str = "Ljava/nio/ByteBuffer;";
+        let callback = format!("L{};", jni_class("EciesCallback"));
+        let continuation = format!("L{};", jni_class("EciesContinuation"));
+        let method = |name: &str, signature: String, function: *mut c_void| NativeMethod {
+            name: name.into(),
+            sig: signature.into(),
+            fn_ptr: function,
+        };
+
+        #[allow(unused_mut)]
+        let mut methods = vec![
+            method("generateSecretKey", format!("(){STRING}"), Java_io_metamask_ecies_Ecies_generateSecretKey as *mut c_void),
+            method("derivePublicKeyFrom", format!("({STRING}){STRING}"), Java_io_metamask_ecies_Ecies_derivePublicKeyFrom as *mut c_void),
+            method("encryptMessage", format!("({STRING}{STRING}){STRING}"), Java_io_metamask_ecies_Ecies_encryptMessage as *mut c_void),
+            method("decryptMessage", format!("({STRING}{STRING}){STRING}"), Java_io_metamask_ecies_Ecies_decryptMessage as *mut c_void),
+            method("encryptBytes", "([B[B)[B".to_string(), Java_io_metamask_ecies_Ecies_encryptBytes as *mut c_void),
+            method("decryptBytes", "([B[B)[B".to_string(), Java_io_metamask_ecies_Ecies_decryptBytes as *mut c_void),
+            method("parseSecretKey", format!("({STRING})J"), Java_io_metamask_ecies_Ecies_parseSecretKey as *mut c_void),
+            method("parsePublicKey", format!("({STRING})J"), Java_io_metamask_ecies_Ecies_parsePublicKey as *mut c_void),
+            method("encryptWithHandle", "(J[B)[B".to_string(), Java_io_metamask_ecies_Ecies_encryptWithHandle as *mut c_void),
+            method("decryptWithHandle", "(J[B)[B".to_string(), Java_io_metamask_ecies_Ecies_decryptWithHandle as *mut c_void),
+            method("encryptSignedWithHandle", "(J[B[B)[B".to_string(), Java_io_metamask_ecies_Ecies_encryptSignedWithHandle as *mut c_void),
+            method("freeKey", "(J)V".to_string(), Java_io_metamask_ecies_Ecies_freeKey as *mut c_void),
+            method("encryptedLength", "(I)I".to_string(), Java_io_metamask_ecies_Ecies_encryptedLength as *mut c_void),
+            method("encryptDirect", format!("([B{BUFFER}{BUFFER})I"), Java_io_metamask_ecies_Ecies_encryptDirect as *mut c_void),
+            method("decryptDirect", format!("([B{BUFFER}{BUFFER})I"), Java_io_metamask_ecies_Ecies_decryptDirect as *mut c_void),
+            method("inspectMessage", format!("({STRING})Ljava/util/Map;"), Java_io_metamask_ecies_Ecies_inspectMessage as *mut c_void),
+            method("decryptMessageToMap", format!("({STRING}{STRING})Ljava/util/Map;"), Java_io_metamask_ecies_Ecies_decryptMessageToMap as *mut c_void),
+            method("encryptFileDescriptor", format!("({STRING}{DESCRIPTOR}{DESCRIPTOR})Z"), Java_io_metamask_ecies_Ecies_encryptFileDescriptor as *mut c_void),
+            method("decryptFileDescriptor", format!("({STRING}{DESCRIPTOR}{DESCRIPTOR})Z"), Java_io_metamask_ecies_Ecies_decryptFileDescriptor as *mut c_void),
+            method("encryptAsset", format!("({STRING}Landroid/content/res/AssetManager;{STRING}{DESCRIPTOR})Z"), Java_io_metamask_ecies_Ecies_encryptAsset as *mut c_void),
+            method("decryptAsset", format!("({STRING}Landroid/content/res/AssetManager;{STRING}{DESCRIPTOR})Z"), Java_io_metamask_ecies_Ecies_decryptAsset as *mut c_void),
+            method("encryptAsync", format!("({STRING}{STRING}{callback})V"), Java_io_metamask_ecies_Ecies_encryptAsync as *mut c_void),
+            method("decryptAsync", format!("({STRING}{STRING}{callback})V"), Java_io_metamask_ecies_Ecies_decryptAsync as *mut c_void),
+            method("encryptWithContinuation", format!("({STRING}{STRING}{continuation})V"), Java_io_metamask_ecies_Ecies_encryptWithContinuation as *mut c_void),
+            method("decryptWithContinuation", format!("({STRING}{STRING}{continuation})V"), Java_io_metamask_ecies_Ecies_decryptWithContinuation as *mut c_void),
+            method("serveAgent", format!("(I{STRING})V"), Java_io_metamask_ecies_Ecies_serveAgent as *mut c_void),
+            method("agentConnect", "(I)J".to_string(), Java_io_metamask_ecies_Ecies_agentConnect as *mut c_void),
+            method("agentPublicKey", format!("(J{STRING})[B"), Java_io_metamask_ecies_Ecies_agentPublicKey as *mut c_void),
+            method("agentGenerateKey", format!("(J{STRING})[B"), Java_io_metamask_ecies_Ecies_agentGenerateKey as *mut c_void),
+            method("agentEncrypt", format!("(J{STRING}[B[B)[B"), Java_io_metamask_ecies_Ecies_agentEncrypt as *mut c_void),
+            method("agentDecrypt", format!("(J{STRING}[B)[B"), Java_io_metamask_ecies_Ecies_agentDecrypt as *mut c_void),
+            method("agentSign", format!("(J{STRING}[B)[B"), Java_io_metamask_ecies_Ecies_agentSign as *mut c_void),
+            method("agentClose", "(J)V".to_string(), Java_io_metamask_ecies_Ecies_agentClose as *mut c_void),
+        ];
+        #[cfg(feature = "keystore")]
+        methods.push(method("parseKeyAttestation", "([B)Ljava/util/Map;".to_string(), Java_io_metamask_ecies_Ecies_parseKeyAttestation as *mut c_void));
+        #[cfg(feature = "sqlite")]
+        methods.extend([
+            method("sqliteAddSecretKey", format!("({STRING})J"), Java_io_metamask_ecies_Ecies_sqliteAddSecretKey as *mut c_void),
+            method("sqliteRemoveSecretKey", "(J)V".to_string(), Java_io_metamask_ecies_Ecies_sqliteRemoveSecretKey as *mut c_void),
+        ]);
+
+        methods
+    }
+
+    fn register_natives(env: &mut JNIEnv, class_name: &str) -> self::jni::errors::Result<()> {
+        let class = env.find_class(class_name)?;
+        env.register_native_methods(&class, &native_methods())
+    }
+
+    /*
+    Called by the JVM when `System.loadLibrary("ecies")` loads the library. Caches the JavaVM, the callback and
+    exception classes with their method IDs, and registers the natives of `JNI_CLASS`, so calls skip the symbol
+    lookup. Missing classes are not fatal: the natives still resolve through their exported names, and
+    callbacks and exceptions fall back to lookups by name.
+    */
+
+    #[no_mangle]
+    pub extern "system" fn JNI_OnLoad(vm: JavaVM, _: *mut c_void) -> jint {
+        let Ok(mut env) = vm.get_env() else {
+            return JNI_ERR;
+        };
+        let cache = load_cache(&mut env);
+        let registered = register_natives(&mut env, JNI_CLASS);
+        // A failed lookup leaves a NoClassDefFoundError pending, which must not escape `System.loadLibrary`
+        if cache.is_err() || registered.is_err() {
+            let _ = env.exception_clear();
+        }
+
+        if let (Ok(cache), Ok(mut slot)) = (cache, JNI_CACHE.write()) {
+            *slot = Some(cache);
+        }
+        let _ = JAVA_VM.set(vm);
+        JNI_VERSION_1_6
+    }
+
+    /*
+    Called by the JVM when the class loader holding the library is collected. Drops the cached global references.
+    */
+
+    #[no_mangle]
+    pub extern "system" fn JNI_OnUnload(_: JavaVM, _: *mut c_void) {
+        if let Ok(mut slot) = JNI_CACHE.write() {
+            *slot = None;
+        }
+    }
+}
@@ -1,3 +1,6 @@
+// Like the C API of `ios/`, the functions are `unsafe` for the raw pointers they take
+#![allow(clippy::missing_safety_doc)]
+
 use std::mem::ManuallyDrop;
 use std::os::raw::c_char;
 use std::ffi::{CStr, CString};
@@ -33,9 +36,7 @@ pub unsafe extern "C" fn ecies_generate_secret_key() -> *const c_char {
     let secret_key_cstring_result = CString::new(secret_key_hex);
     let secret_key_cstr = ManuallyDrop::new(secret_key_cstring_result.unwrap());
 
-    let secret_key_ptr = secret_key_cstr.as_ptr();
-
-    secret_key_ptr
+    secret_key_cstr.as_ptr()
 }
 
 
@@ -67,10 +68,7 @@ pub unsafe extern "C" fn ecies_public_key_from(secret_key_ptr: *const c_char) ->
     // ManuallyDrop is useful when the ownership of the underlying resource is transferred to code outside of Rust
     let public_key_cstr = ManuallyDrop::new(public_key_cstring_result.unwrap());
 
-    let public_key_ptr = public_key_cstr.as_ptr();
-
-
-    public_key_ptr
+    public_key_cstr.as_ptr()
 }
 
 /*
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ecies_core::armor::{armor, decode_text};
+use ecies_core::encoding::{encode_base64, EciesBase64Flavor};
+use ecies_core::envelope::{is_envelope, now, seal, Compression};
+use ecies_core::keys::{encode_key_text, serialize_public_key, EciesKeyEncoding, EciesPublicKeyFormat};
+use ecies_core::{parse_public_key_from_text, EciesErrorCode, Header, Keypair};
+use ecies_ffi::inspect::inspect;
+
+/*
+Command-line front end of `ecies-core`, for reproducing the ciphertexts of the mobile apps and debugging interop
+issues from a shell, without a device.
+
+Keys and ciphertexts use the text forms of the C API: keys are hex or base64 in any layout, `encrypt` writes the
+base64 text of `ecies_encrypt` unless asked for armor or the binary envelope, and `decrypt` and `inspect` accept
+all three. Input comes from `--in` or stdin and output goes to `--out` or stdout; diagnostics go to stderr, and
+failures exit with status 1 and the `EciesErrorCode` name:
+
+    ecies-cli keygen --out recipient.key
+    ecies-cli pubkey --key-file recipient.key
+    echo hello | ecies-cli encrypt --to 02a1... --ttl 3600
+    ecies-cli decrypt --key-file recipient.key --in message.txt
+    ecies-cli inspect --in message.txt
+*/
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "ecies-cli", version, about = "Encrypts, decrypts and inspects ECIES envelopes")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a secret key, written as hex
+    Keygen {
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Derives the public key of a secret key
+    Pubkey {
+        #[command(flatten)]
+        key: SecretKeyInput,
+        #[arg(long, value_enum, default_value_t = Format::Compressed)]
+        format: Format,
+        #[arg(long, value_enum, default_value_t = Encoding::Hex)]
+        encoding: Encoding,
+    },
+    /// Encrypts a message to a public key
+    Encrypt {
+        /// Recipient public key, hex or base64
+        #[arg(long)]
+        to: String,
+        #[arg(long, value_enum, default_value_t = Form::Base64)]
+        form: Form,
+        /// Seconds until the message expires
+        #[arg(long)]
+        ttl: Option<u64>,
+        /// Header metadata entry, repeatable
+        #[arg(long = "metadata", value_name = "KEY=VALUE", value_parser = parse_metadata)]
+        metadata: Vec<(String, String)>,
+        /// Identifier of the recipient key, for key rotation
+        #[arg(long)]
+        key_id: Option<String>,
+        /// Compresses the message with zstd before encrypting it
+        #[arg(long)]
+        compress: bool,
+        /// File with the secret key of the sender, to sign the envelope
+        #[arg(long, value_name = "FILE")]
+        sign_key_file: Option<PathBuf>,
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Decrypts a base64, armored or binary ciphertext
+    Decrypt {
+        #[command(flatten)]
+        key: SecretKeyInput,
+        /// Fails unless the envelope is signed
+        #[arg(long)]
+        require_signature: bool,
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Prints what can be read from a ciphertext without the secret key, as JSON
+    Inspect {
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+#[derive(Args)]
+#[group(multiple = false)]
+struct SecretKeyInput {
+    /// Secret key, hex or base64
+    #[arg(long)]
+    key: Option<String>,
+    /// File with the secret key, e.g. the output of `keygen`. Without `--key` or `--key-file` it is read from stdin
+    #[arg(long, value_name = "FILE")]
+    key_file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Input {
+    /// Input file, stdin when absent or `-`
+    #[arg(long = "in", value_name = "FILE")]
+    path: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Output {
+    /// Output file, stdout when absent or `-`
+    #[arg(long = "out", value_name = "FILE")]
+    path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Compressed,
+    Uncompressed,
+    Ethereum,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+// Forms of the ciphertext written by `encrypt`
+#[derive(Clone, Copy, ValueEnum)]
+enum Form {
+    Base64,
+    Armor,
+    Binary,
+}
+
+fn parse_metadata(entry: &str) -> std::result::Result<(String, String), String> {
+    entry
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{entry}`"))
+}
+
+// The file to use, if any, rather than stdin or stdout
+fn file(path: &Option<PathBuf>) -> Option<&Path> {
+    path.as_deref().filter(|path| path.as_os_str() != "-")
+}
+
+impl Input {
+    fn read(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match file(&self.path) {
+            Some(path) => {
+                bytes = fs::read(path).map_err(|error| format!("{}: {error}", path.display()))?;
+            }
+            None => {
+                io::stdin().read_to_end(&mut bytes)?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl Output {
+    fn write(&self, bytes: &[u8]) -> Result<()> {
+        match file(&self.path) {
+            Some(path) => {
+                fs::write(path, bytes).map_err(|error| format!("{}: {error}", path.display()))?;
+            }
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(bytes)?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Key files may end with a newline, as written by `keygen`
+fn read_key_file(path: &Path) -> Result<String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    Ok(text.trim().to_string())
+}
+
+impl SecretKeyInput {
+    fn keypair(&self) -> Result<Keypair> {
+        let text = match (&self.key, &self.key_file) {
+            (Some(key), _) => key.trim().to_string(),
+            (None, Some(path)) => read_key_file(path)?,
+            (None, None) => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                text.trim().to_string()
+            }
+        };
+        Ok(Keypair::from_text(&text)?)
+    }
+}
+
+// Binary envelopes and legacy ciphertexts, which start with an uncompressed public key, are taken as they are;
+// anything else is decoded as base64 or armor text
+fn ciphertext_bytes(input: Vec<u8>) -> Result<Vec<u8>> {
+    if is_envelope(&input) || input.first() == Some(&0x04) {
+        return Ok(input);
+    }
+    Ok(decode_text(&input)?)
+}
+
+fn keygen(output: &Output) -> Result<()> {
+    let keypair = Keypair::generate()?;
+    let secret_key = keypair.secret_key().serialize();
+
+    output.write(format!("{}\n", hex::encode(secret_key)).as_bytes())
+}
+
+fn pubkey(key: &SecretKeyInput, format: Format, encoding: Encoding) -> Result<()> {
+    let format = match format {
+        Format::Compressed => EciesPublicKeyFormat::Compressed,
+        Format::Uncompressed => EciesPublicKeyFormat::Uncompressed,
+        Format::Ethereum => EciesPublicKeyFormat::Ethereum,
+    };
+    let encoding = match encoding {
+        Encoding::Hex => EciesKeyEncoding::Hex,
+        Encoding::Base64 => EciesKeyEncoding::Base64,
+    };
+    let public_key = serialize_public_key(key.keypair()?.public_key(), format);
+
+    println!("{}", encode_key_text(&public_key, encoding));
+    Ok(())
+}
+
+fn decrypt(key: &SecretKeyInput, require_signature: bool, input: &Input, output: &Output) -> Result<()> {
+    if key.key.is_none() && key.key_file.is_none() && file(&input.path).is_none() {
+        return Err("the secret key and the ciphertext cannot both come from stdin".into());
+    }
+    let keypair = key.keypair()?;
+    let ciphertext = ciphertext_bytes(input.read()?)?;
+    let (header, message) = ecies_core::decrypt_with_header(keypair.secret_key(), &ciphertext)?;
+
+    match &header.sender {
+        Some(sender) => eprintln!("signed by {}", hex::encode(sender.serialize_compressed())),
+        None if require_signature => return Err(EciesErrorCode::MissingSignature.into()),
+        None => {}
+    }
+    if header.is_expired(now()) {
+        eprintln!("warning: the message has expired");
+    }
+
+    output.write(&message)
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Keygen { output } => keygen(&output),
+        Command::Pubkey { key, format, encoding } => pubkey(&key, format, encoding),
+        Command::Encrypt { to, form, ttl, metadata, key_id, compress, sign_key_file, input, output } => {
+            let public_key = parse_public_key_from_text(to.trim())?;
+            let sender = sign_key_file.as_deref().map(read_key_file).transpose()?;
+            let sender = sender.as_deref().map(Keypair::from_text).transpose()?;
+
+            let header = Header {
+                created_at: ttl.map(|_| now()),
+                ttl,
+                compression: compress.then_some(Compression::Zstd),
+                metadata: metadata.into_iter().collect::<BTreeMap<_, _>>(),
+                key_id,
+                ..Header::default()
+            };
+            let message = input.read()?;
+            let envelope = seal(&public_key, &header, &message, sender.as_ref().map(Keypair::secret_key))?;
+
+            match form {
+                Form::Base64 => {
+                    let text = encode_base64(&envelope, EciesBase64Flavor::Standard);
+                    output.write(format!("{text}\n").as_bytes())
+                }
+                Form::Armor => output.write(armor(&envelope).as_bytes()),
+                Form::Binary => output.write(&envelope),
+            }
+        }
+        Command::Decrypt { key, require_signature, input, output } => decrypt(&key, require_signature, &input, &output),
+        Command::Inspect { input } => {
+            let inspection = inspect(&ciphertext_bytes(input.read()?)?)?;
+            println!("{}", serde_json::to_string_pretty(&inspection)?);
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("ecies-cli: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
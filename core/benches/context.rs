@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libsecp256k1::curve::{ECMultContext, ECMultGenContext};
+use libsecp256k1::{PublicKey, SecretKey};
+use rand::thread_rng;
+
+/*
+Compares the curve operations of one small-message encryption (ephemeral key generation and ECDH) when the
+secp256k1 tables are set up for every call against the shared tables built once, as `context.rs` does.
+*/
+
+fn encrypt_point_ops(recipient: &PublicKey, ecmult: &ECMultContext, ecmult_gen: &ECMultGenContext) -> PublicKey {
+    let ephemeral_secret_key = SecretKey::random(&mut thread_rng());
+    let _ephemeral_public_key = PublicKey::from_secret_key_with_context(&ephemeral_secret_key, ecmult_gen);
+
+    let mut shared_point = *recipient;
+    shared_point.tweak_mul_assign_with_context(&ephemeral_secret_key, ecmult).unwrap();
+    shared_point
+}
+
+fn bench_context(c: &mut Criterion) {
+    let recipient = PublicKey::from_secret_key(&SecretKey::random(&mut thread_rng()));
+
+    c.bench_function("per-call context", |b| {
+        b.iter(|| {
+            let ecmult = ECMultContext::new_boxed();
+            let ecmult_gen = ECMultGenContext::new_boxed();
+            encrypt_point_ops(black_box(&recipient), &ecmult, &ecmult_gen)
+        })
+    });
+
+    let ecmult = ECMultContext::new_boxed();
+    let ecmult_gen = ECMultGenContext::new_boxed();
+    c.bench_function("shared context", |b| {
+        b.iter(|| encrypt_point_ops(black_box(&recipient), &ecmult, &ecmult_gen))
+    });
+}
+
+criterion_group!(benches, bench_context);
+criterion_main!(benches);
@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/*
+Compares the scalar `base64` and `hex` crates with the SIMD encoders enabled by the `simd` feature, over
+ciphertext-sized payloads. Run on the target device (e.g. aarch64) with `cargo bench --features simd --bench encoding`.
+*/
+
+const SIZES: [usize; 3] = [1024, 64 * 1024, 1024 * 1024];
+
+fn bench_base64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64");
+    for size in SIZES {
+        let bytes = vec![0xa5u8; size];
+        let encoded = base64::encode(&bytes);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("encode scalar", size), &bytes, |b, bytes| {
+            b.iter(|| base64::encode(black_box(bytes)))
+        });
+        group.bench_with_input(BenchmarkId::new("encode simd", size), &bytes, |b, bytes| {
+            b.iter(|| base64_simd::STANDARD.encode_to_string(black_box(bytes)))
+        });
+        group.bench_with_input(BenchmarkId::new("decode scalar", size), &encoded, |b, encoded| {
+            b.iter(|| base64::decode(black_box(encoded)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode simd", size), &encoded, |b, encoded| {
+            b.iter(|| base64_simd::STANDARD.decode_to_vec(black_box(encoded)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_hex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex");
+    for size in SIZES {
+        let bytes = vec![0xa5u8; size];
+        let encoded = hex::encode(&bytes);
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("encode scalar", size), &bytes, |b, bytes| {
+            b.iter(|| hex::encode(black_box(bytes)))
+        });
+        group.bench_with_input(BenchmarkId::new("encode simd", size), &bytes, |b, bytes| {
+            b.iter(|| hex_simd::encode_to_string(black_box(bytes), hex_simd::AsciiCase::Lower))
+        });
+        group.bench_with_input(BenchmarkId::new("decode scalar", size), &encoded, |b, encoded| {
+            b.iter(|| hex::decode(black_box(encoded)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode simd", size), &encoded, |b, encoded| {
+            b.iter(|| hex_simd::decode_to_vec(black_box(encoded)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_base64, bench_hex);
+criterion_main!(benches);
@@ -0,0 +1,107 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::encoding::decode_base64;
+use crate::error::EciesErrorCode;
+
+/*
+PGP-style ASCII armor (RFC 4880, section 6) for envelopes, so ciphertexts can be pasted into email and tickets:
+
+    -----BEGIN ECIES MESSAGE-----
+
+    base64 body, wrapped at 64 columns
+    =CRC24 checksum (base64)
+    -----END ECIES MESSAGE-----
+
+Dearmoring ignores all whitespace inside the body, so line re-wrapping, indentation and CRLF line endings survive.
+*/
+
+const BEGIN_LINE: &str = "-----BEGIN ECIES MESSAGE-----";
+const END_LINE: &str = "-----END ECIES MESSAGE-----";
+const LINE_LENGTH: usize = 64;
+
+const CRC24_INIT: u32 = 0xB704CE;
+const CRC24_POLY: u32 = 0x1864CFB;
+
+fn crc24(bytes: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for byte in bytes {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+pub fn armor(bytes: &[u8]) -> String {
+    let body = base64::encode(bytes);
+    let checksum = base64::encode(&crc24(bytes).to_be_bytes()[1..]);
+
+    let mut output = String::with_capacity(body.len() + body.len() / LINE_LENGTH + 128);
+    output.push_str(BEGIN_LINE);
+    output.push_str("\n\n");
+    for line in body.as_bytes().chunks(LINE_LENGTH) {
+        // base64 output is ASCII, so every chunk is valid UTF-8
+        output.push_str(core::str::from_utf8(line).unwrap_or_default());
+        output.push('\n');
+    }
+    output.push('=');
+    output.push_str(&checksum);
+    output.push('\n');
+    output.push_str(END_LINE);
+    output.push('\n');
+    output
+}
+
+pub fn is_armored(text: &[u8]) -> bool {
+    text.trim_ascii_start().starts_with(BEGIN_LINE.as_bytes())
+}
+
+pub fn dearmor(text: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    let text = text.trim();
+    let inner = text
+        .strip_prefix(BEGIN_LINE)
+        .and_then(|rest| rest.trim_end().strip_suffix(END_LINE))
+        .ok_or(EciesErrorCode::InvalidArmor)?;
+
+    // Armor headers ("Key: value" lines) are separated from the body by a blank line
+    let mut lines: Vec<&str> = inner.lines().map(str::trim).collect();
+    if let Some(blank) = lines.iter().position(|line| line.is_empty()) {
+        if lines[..blank].iter().all(|line| line.contains(": ")) {
+            lines.drain(..blank);
+        }
+    }
+
+    let mut body: String = lines.concat().chars().filter(|character| !character.is_whitespace()).collect();
+    let checksum = match body.rfind('=') {
+        // Padding is at most two '=' at the end, while the checksum marker is followed by 4 characters
+        Some(index) if body.len() - index == 5 => {
+            let checksum = body.split_off(index);
+            Some(base64::decode(&checksum[1..]).map_err(|_| EciesErrorCode::InvalidArmor)?)
+        }
+        _ => None,
+    };
+
+    let bytes = base64::decode(&body).map_err(|_| EciesErrorCode::InvalidBase64)?;
+    if let Some(checksum) = checksum {
+        if checksum[..] != crc24(&bytes).to_be_bytes()[1..] {
+            return Err(EciesErrorCode::InvalidArmor);
+        }
+    }
+
+    Ok(bytes)
+}
+
+// Decodes the text form of a ciphertext accepted by the decrypt functions: armored, or base64 of any flavor
+pub fn decode_text(text: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    if is_armored(text) {
+        let text = core::str::from_utf8(text).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+        return dearmor(text);
+    }
+
+    decode_base64(text)
+}
@@ -0,0 +1,111 @@
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::error::EciesErrorCode;
+use crate::features::sha256;
+use crate::metrics::{count, Started};
+
+/*
+The audit hook, reporting every key generation, encryption and decryption of the library to the host, for audit
+trails. Events carry no secret: the operation, the SHA-256 fingerprint of the public key involved (the recipient
+for encryption, the public key of the secret key for decryption), the input and output lengths and the result,
+so failures are reported as well.
+
+Ephemeral keys are not reported as key generations, and an operation reports a single event however many
+primitives it runs: an envelope is one encryption, a stream one encryption once it is finished. Opening an
+envelope is one decryption carrying the final result, whether a malformed header, a bad sender signature, the
+payload or a decryption option (expiry, replay, a required signature) rejected it. Nothing is computed when no hook
+is installed.
+
+The hook runs on the thread of the operation, before its result is returned, and must not call back into the
+library.
+*/
+
+pub const ECIES_AUDIT_FINGERPRINT_LENGTH: usize = 32;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesAuditOperation {
+    KeyGeneration = 0,
+    Encryption = 1,
+    Decryption = 2,
+}
+
+/*
+An audited operation.
+    - key_fingerprint: SHA-256 of the public key in its usual encoding (compressed SEC 1 for secp256k1 keys),
+      all zeros when `has_key_fingerprint` is false, e.g. for messages to several age recipients
+    - input_length: length of the message for encryption, of the ciphertext for decryption, 0 for key generation
+    - output_length: length of the ciphertext or message produced, 0 for key generation and on failure
+    - result: `Success` or the error code of the failure
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EciesAuditEvent {
+    pub operation: EciesAuditOperation,
+    pub key_fingerprint: [u8; ECIES_AUDIT_FINGERPRINT_LENGTH],
+    pub has_key_fingerprint: bool,
+    pub input_length: usize,
+    pub output_length: usize,
+    pub result: EciesErrorCode,
+}
+
+pub type AuditHook = fn(&EciesAuditEvent);
+
+static AUDIT_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+// Installs the hook receiving every event, or removes it
+pub fn set_audit_hook(hook: Option<AuditHook>) {
+    let hook = hook.map_or(core::ptr::null_mut(), |hook| hook as *mut ());
+    AUDIT_HOOK.store(hook, Ordering::Release);
+}
+
+fn audit_hook() -> Option<AuditHook> {
+    let hook = AUDIT_HOOK.load(Ordering::Acquire);
+    // Only `set_audit_hook` stores into `AUDIT_HOOK`, always an `AuditHook` or null
+    (!hook.is_null()).then(|| unsafe { core::mem::transmute::<*mut (), AuditHook>(hook) })
+}
+
+// The output length or error code of an operation returning bytes, for `Operation::record`
+pub fn outcome<T: AsRef<[u8]>>(result: &Result<T, EciesErrorCode>) -> Result<usize, EciesErrorCode> {
+    result.as_ref().map(|output| output.as_ref().len()).map_err(|code| *code)
+}
+
+// An operation being carried out, started before its first step so its latency counts in the metrics
+#[derive(Debug, Clone, Copy)]
+pub struct Operation {
+    operation: EciesAuditOperation,
+    started: Started,
+}
+
+pub fn begin(operation: EciesAuditOperation) -> Operation {
+    Operation { operation, started: Started::now() }
+}
+
+impl Operation {
+    // Counts the operation in the metrics and reports it to the hook, if one is installed. `public_key` is only
+    // called then, since it may have to be derived from a secret key; an empty key reports no fingerprint
+    pub fn record<K: AsRef<[u8]>>(
+        &self,
+        public_key: impl FnOnce() -> K,
+        input_length: usize,
+        output_length: Result<usize, EciesErrorCode>,
+    ) {
+        count(self.operation, self.started, input_length, output_length);
+        let Some(hook) = audit_hook() else {
+            return;
+        };
+        let public_key = public_key();
+        let public_key = public_key.as_ref();
+        let has_key_fingerprint = !public_key.is_empty();
+
+        hook(&EciesAuditEvent {
+            operation: self.operation,
+            key_fingerprint: if has_key_fingerprint { sha256(public_key) } else { [0; ECIES_AUDIT_FINGERPRINT_LENGTH] },
+            has_key_fingerprint,
+            input_length,
+            output_length: output_length.unwrap_or(0),
+            result: output_length.err().unwrap_or(EciesErrorCode::Success),
+        });
+    }
+}
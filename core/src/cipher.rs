@@ -0,0 +1,105 @@
+use alloc::{vec, vec::Vec};
+use hkdf::Hkdf;
+use libsecp256k1::{PublicKey, SecretKey};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::context::{ecdh_point, ephemeral_keypair, public_key_of};
+use crate::error::EciesErrorCode;
+use crate::health;
+use crate::random::rng;
+use crate::symmetric::{aes_256_gcm_decrypt, aes_256_gcm_encrypt};
+
+/*
+The ECIES construction used by the ecies crate, extended with AES-GCM associated data.
+
+Output layout (identical to `ecies::encrypt` when the associated data is empty):
+    ephemeral public key (65 bytes) | nonce (16 bytes) | tag (16 bytes) | encrypted message
+
+The associated data is authenticated but not encrypted, which lets envelope headers stay readable
+while any modification of them makes decryption fail.
+*/
+
+pub const PUBLIC_KEY_LENGTH: usize = 65;
+pub const NONCE_LENGTH: usize = 16;
+pub const TAG_LENGTH: usize = 16;
+pub const OVERHEAD_LENGTH: usize = PUBLIC_KEY_LENGTH + NONCE_LENGTH + TAG_LENGTH;
+
+// HKDF-SHA256 over the uncompressed ephemeral public key and shared point, as done by the ecies crate
+fn derive_aes_key(ephemeral_public_key: &PublicKey, shared_point: &PublicKey) -> Result<[u8; 32], EciesErrorCode> {
+    let mut master = Vec::with_capacity(PUBLIC_KEY_LENGTH * 2);
+    master.extend_from_slice(&ephemeral_public_key.serialize());
+    master.extend_from_slice(&shared_point.serialize());
+
+    let hkdf = Hkdf::<Sha256>::new(None, &master);
+    let mut aes_key = [0u8; 32];
+    hkdf.expand(&[], &mut aes_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    Ok(aes_key)
+}
+
+pub fn encrypt_with_aad(public_key: &PublicKey, message: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut output = vec![0u8; OVERHEAD_LENGTH + message.len()];
+    encrypt_with_aad_into(public_key, message, aad, &mut output)?;
+
+    Ok(output)
+}
+
+// Like `encrypt_with_aad`, writing the payload into `output`, which must hold exactly
+// `OVERHEAD_LENGTH + message.len()` bytes, so callers can place it inside a larger allocation
+pub fn encrypt_with_aad_into(public_key: &PublicKey, message: &[u8], aad: &[u8], output: &mut [u8]) -> Result<(), EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_payload(public_key, message, aad, output);
+    let output_length = result.map(|()| output.len());
+    operation.record(|| public_key.serialize_compressed(), message.len(), output_length);
+
+    result
+}
+
+fn seal_payload(public_key: &PublicKey, message: &[u8], aad: &[u8], output: &mut [u8]) -> Result<(), EciesErrorCode> {
+    if output.len() != OVERHEAD_LENGTH + message.len() {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+
+    let shared_point = ecdh_point(public_key, &ephemeral_secret_key)?;
+    let aes_key = derive_aes_key(&ephemeral_public_key, &shared_point)?;
+
+    let (ephemeral_public_key_bytes, rest) = output.split_at_mut(PUBLIC_KEY_LENGTH);
+    let (nonce, rest) = rest.split_at_mut(NONCE_LENGTH);
+    let (tag, encrypted) = rest.split_at_mut(TAG_LENGTH);
+    ephemeral_public_key_bytes.copy_from_slice(&ephemeral_public_key.serialize());
+    rng()?.fill_bytes(nonce);
+    health::check()?;
+
+    aes_256_gcm_encrypt(&aes_key, nonce, aad, message, encrypted, tag)
+}
+
+pub fn decrypt_with_aad(secret_key: &SecretKey, payload: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_payload(secret_key, payload, aad);
+    let public_key = || public_key_of(secret_key).serialize_compressed();
+    operation.record(public_key, payload.len(), outcome(&result));
+
+    result
+}
+
+// `decrypt_with_aad` without an audit event, for operations reporting a single event of their own
+pub fn open_payload(secret_key: &SecretKey, payload: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    if payload.len() < OVERHEAD_LENGTH {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+
+    let ephemeral_public_key = PublicKey::parse_slice(&payload[..PUBLIC_KEY_LENGTH], None)
+        .map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+
+    let shared_point = ecdh_point(&ephemeral_public_key, secret_key)?;
+    let aes_key = derive_aes_key(&ephemeral_public_key, &shared_point)?;
+
+    let nonce = &payload[PUBLIC_KEY_LENGTH..PUBLIC_KEY_LENGTH + NONCE_LENGTH];
+    let tag = &payload[PUBLIC_KEY_LENGTH + NONCE_LENGTH..OVERHEAD_LENGTH];
+    let encrypted = &payload[OVERHEAD_LENGTH..];
+
+    aes_256_gcm_decrypt(&aes_key, nonce, aad, encrypted, tag)
+}
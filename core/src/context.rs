@@ -0,0 +1,122 @@
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use libsecp256k1::curve::{ECMultContext, ECMultGenContext};
+use libsecp256k1::{sign_with_context, verify_with_context, Message, RecoveryId, Signature};
+use libsecp256k1::{PublicKey, SecretKey};
+use rand::RngCore;
+
+use crate::audit::{begin, EciesAuditOperation};
+use crate::error::EciesErrorCode;
+use crate::features::cpu_features;
+use crate::health;
+use crate::keys::wipe;
+use crate::policy::allow_unapproved;
+use crate::random::rng;
+
+/*
+The secp256k1 multiplication tables, built once and shared by every curve operation of the library instead of
+being set up per call. They are built lazily on first use, or eagerly by `ecies_init` so that the first
+encryption does not pay for them.
+
+Without std there is no `OnceLock` to build them in, so `no_std` builds use the tables libsecp256k1 precomputes
+at compile time, which live in the binary instead of on the heap.
+*/
+
+#[cfg(feature = "std")]
+struct Contexts {
+    ecmult: Box<ECMultContext>,
+    ecmult_gen: Box<ECMultGenContext>,
+}
+
+#[cfg(feature = "std")]
+static CONTEXTS: OnceLock<Contexts> = OnceLock::new();
+
+#[cfg(feature = "std")]
+fn contexts() -> &'static Contexts {
+    CONTEXTS.get_or_init(|| Contexts {
+        ecmult: ECMultContext::new_boxed(),
+        ecmult_gen: ECMultGenContext::new_boxed(),
+    })
+}
+
+#[cfg(feature = "std")]
+fn ecmult() -> &'static ECMultContext {
+    &contexts().ecmult
+}
+
+#[cfg(feature = "std")]
+fn ecmult_gen() -> &'static ECMultGenContext {
+    &contexts().ecmult_gen
+}
+
+#[cfg(not(feature = "std"))]
+fn ecmult() -> &'static ECMultContext {
+    &libsecp256k1::ECMULT_CONTEXT
+}
+
+#[cfg(not(feature = "std"))]
+fn ecmult_gen() -> &'static ECMultGenContext {
+    &libsecp256k1::ECMULT_GEN_CONTEXT
+}
+
+// Same as `ecies::utils::generate_keypair`, over the shared tables. Reported to the audit hook as a key generation
+pub fn generate_keypair() -> Result<(SecretKey, PublicKey), EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::KeyGeneration);
+    let keypair = ephemeral_keypair();
+    match &keypair {
+        Ok((_, public_key)) => operation.record(|| public_key.serialize_compressed(), 0, Ok(0)),
+        Err(code) => operation.record(|| [0u8; 0], 0, Err(*code)),
+    }
+
+    keypair
+}
+
+// The key of a single encryption, which is not reported to the audit hook. Fails with `RngFailure` once the
+// random number generator failed its health tests
+pub fn ephemeral_keypair() -> Result<(SecretKey, PublicKey), EciesErrorCode> {
+    let mut rng = rng()?;
+    let mut bytes = [0u8; 32];
+    // Like `SecretKey::random`, with the health tests checked on every draw: a stuck source would never yield a
+    // valid scalar
+    let secret_key = loop {
+        rng.fill_bytes(&mut bytes);
+        let secret_key = SecretKey::parse(&bytes);
+        wipe(&mut bytes);
+        health::check()?;
+        if let Ok(secret_key) = secret_key {
+            break secret_key;
+        }
+    };
+    let public_key = public_key_of(&secret_key);
+
+    Ok((secret_key, public_key))
+}
+
+pub fn public_key_of(secret_key: &SecretKey) -> PublicKey {
+    PublicKey::from_secret_key_with_context(secret_key, ecmult_gen())
+}
+
+// The ECDH shared point `secret_key * public_key`, behind every secp256k1 key agreement of the library. Fails with
+// `PolicyViolation` under the FIPS policy, secp256k1 not being an approved curve
+pub fn ecdh_point(public_key: &PublicKey, secret_key: &SecretKey) -> Result<PublicKey, EciesErrorCode> {
+    allow_unapproved()?;
+    let mut shared_point = *public_key;
+    shared_point.tweak_mul_assign_with_context(secret_key, ecmult()).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+
+    Ok(shared_point)
+}
+
+pub fn sign(message: &Message, secret_key: &SecretKey) -> (Signature, RecoveryId) {
+    sign_with_context(message, secret_key, ecmult_gen())
+}
+
+pub fn verify(message: &Message, signature: &Signature, public_key: &PublicKey) -> bool {
+    verify_with_context(message, signature, public_key, ecmult())
+}
+
+// Builds the tables and detects the CPU features now rather than on first use, see `ecies_init`
+pub fn init() {
+    ecmult();
+    ecmult_gen();
+    cpu_features();
+}
@@ -0,0 +1,229 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::EciesErrorCode;
+
+/*
+Text encodings for ciphertext output: base64 in several flavors, lowercase hex for legacy database columns
+and log pipelines, and base45 for QR codes.
+
+All base64 flavors share the standard alphabet except for two characters, so they are produced by adjusting
+standard base64 output. Decoding accepts any flavor, with or without padding, and ignores whitespace.
+
+With the `simd` feature, base64 and hex transcoding go through `base64-simd` and `hex-simd`, which select NEON,
+SSE or AVX2 code at runtime. Output is identical either way; `cargo bench --features simd --bench encoding`
+compares the two.
+
+Hex needs the `hex` feature and base64 the `base64` feature, both enabled by default. Base45 is always available.
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesBase64Flavor {
+    // RFC 4648 section 4, with '=' padding
+    Standard = 0,
+    StandardNoPadding = 1,
+    // RFC 4648 section 5: '-' and '_' instead of '+' and '/', safe in URLs and file names
+    UrlSafe = 2,
+    UrlSafeNoPadding = 3,
+}
+
+#[cfg(feature = "base64")]
+impl EciesBase64Flavor {
+    fn is_url_safe(&self) -> bool {
+        matches!(self, EciesBase64Flavor::UrlSafe | EciesBase64Flavor::UrlSafeNoPadding)
+    }
+
+    fn is_padded(&self) -> bool {
+        matches!(self, EciesBase64Flavor::Standard | EciesBase64Flavor::UrlSafe)
+    }
+}
+
+#[cfg(all(feature = "base64", not(feature = "simd")))]
+fn encode_standard_base64(bytes: &[u8]) -> String {
+    base64::encode(bytes)
+}
+
+#[cfg(feature = "simd")]
+fn encode_standard_base64(bytes: &[u8]) -> String {
+    base64_simd::STANDARD.encode_to_string(bytes)
+}
+
+#[cfg(all(feature = "base64", not(feature = "simd")))]
+fn decode_standard_base64(text: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    base64::decode(text).map_err(|_| EciesErrorCode::InvalidBase64)
+}
+
+#[cfg(feature = "simd")]
+fn decode_standard_base64(text: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    base64_simd::STANDARD.decode_to_vec(text).map_err(|_| EciesErrorCode::InvalidBase64)
+}
+
+#[cfg(all(feature = "hex", not(feature = "simd")))]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+#[cfg(feature = "simd")]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    hex_simd::encode_to_string(bytes, hex_simd::AsciiCase::Lower)
+}
+
+// Upper and lower case are both accepted
+#[cfg(all(feature = "hex", not(feature = "simd")))]
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    hex::decode(text).map_err(|_| EciesErrorCode::InvalidHex)
+}
+
+#[cfg(feature = "simd")]
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    hex_simd::decode_to_vec(text).map_err(|_| EciesErrorCode::InvalidHex)
+}
+
+#[cfg(feature = "base64")]
+pub fn encode_base64(bytes: &[u8], flavor: EciesBase64Flavor) -> String {
+    let mut encoded = encode_standard_base64(bytes);
+    if flavor.is_url_safe() {
+        encoded = encoded.replace('+', "-").replace('/', "_");
+    }
+    if !flavor.is_padded() {
+        encoded.truncate(encoded.trim_end_matches('=').len());
+    }
+    encoded
+}
+
+#[cfg(all(feature = "base64", not(feature = "simd")))]
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Length of the padded base64 encoding of `length` bytes
+#[cfg(feature = "base64")]
+pub fn base64_length(length: usize) -> usize {
+    length.div_ceil(3) * 4
+}
+
+// Encodes `buffer[raw_start..]` as padded standard base64 into the whole buffer, which must be
+// `base64_length(buffer.len() - raw_start)` bytes long. Each group of four output bytes is written after its three
+// input bytes are read and never reaches input not yet read, so the input can share the output's allocation
+#[cfg(all(feature = "base64", not(feature = "simd")))]
+pub fn encode_base64_in_place(buffer: &mut [u8], raw_start: usize) -> Result<(), EciesErrorCode> {
+    if buffer.len() != base64_length(buffer.len().saturating_sub(raw_start)) {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    let (mut input, mut output) = (raw_start, 0);
+    while input < buffer.len() {
+        let remaining = buffer.len() - input;
+        let first = buffer[input];
+        let second = if remaining > 1 { buffer[input + 1] } else { 0 };
+        let third = if remaining > 2 { buffer[input + 2] } else { 0 };
+
+        buffer[output] = BASE64_ALPHABET[(first >> 2) as usize];
+        buffer[output + 1] = BASE64_ALPHABET[(((first & 0x03) << 4) | (second >> 4)) as usize];
+        buffer[output + 2] = match remaining > 1 {
+            true => BASE64_ALPHABET[(((second & 0x0f) << 2) | (third >> 6)) as usize],
+            false => b'=',
+        };
+        buffer[output + 3] = match remaining > 2 {
+            true => BASE64_ALPHABET[(third & 0x3f) as usize],
+            false => b'=',
+        };
+        input += 3;
+        output += 4;
+    }
+    Ok(())
+}
+
+// Block size of the SIMD in-place encoder, a multiple of three so that only the last block is padded
+#[cfg(feature = "simd")]
+const IN_PLACE_BLOCK: usize = 3 * 1024;
+
+// Same contract as the scalar version. Each block is copied out before being encoded over the buffer: after reading
+// up to byte `m` of the input, output reaches `4 * m / 3`, which stays behind the unread input since `raw_start`
+// is at least a third of the input length
+#[cfg(feature = "simd")]
+pub fn encode_base64_in_place(buffer: &mut [u8], raw_start: usize) -> Result<(), EciesErrorCode> {
+    if buffer.len() != base64_length(buffer.len().saturating_sub(raw_start)) {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    let mut block = [0u8; IN_PLACE_BLOCK];
+    let (mut input, mut output) = (raw_start, 0);
+    while input < buffer.len() {
+        let length = (buffer.len() - input).min(IN_PLACE_BLOCK);
+        let encoded_length = base64_length(length);
+        block[..length].copy_from_slice(&buffer[input..input + length]);
+
+        let out = base64_simd::Out::from_slice(&mut buffer[output..output + encoded_length]);
+        base64_simd::STANDARD.encode(&block[..length], out);
+        input += length;
+        output += encoded_length;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "base64")]
+pub fn decode_base64(text: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut standard: Vec<u8> = text
+        .iter()
+        .filter(|byte| !byte.is_ascii_whitespace() && **byte != b'=')
+        .map(|byte| match byte {
+            b'-' => b'+',
+            b'_' => b'/',
+            other => *other,
+        })
+        .collect();
+    while !standard.len().is_multiple_of(4) {
+        standard.push(b'=');
+    }
+
+    decode_standard_base64(&standard)
+}
+
+/*
+Base45 (RFC 9285) uses only characters of the QR alphanumeric mode, which packs 45 symbols into 5.5 bits each.
+Two bytes become three characters, so a QR code holds noticeably more ciphertext than with base64 in byte mode.
+*/
+
+const BASE45_ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+pub fn encode_base45(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(2) * 3);
+    for pair in bytes.chunks(2) {
+        // A pair becomes three digits, the odd byte at the end two
+        let mut value = pair.iter().fold(0, |value, byte| value * 256 + *byte as usize);
+        let digits = pair.len() + 1;
+        for _ in 0..digits {
+            encoded.push(BASE45_ALPHABET[value % 45] as char);
+            value /= 45;
+        }
+    }
+    encoded
+}
+
+pub fn decode_base45(text: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    let digits = text
+        .bytes()
+        .map(|character| BASE45_ALPHABET.iter().position(|symbol| *symbol == character))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or(EciesErrorCode::InvalidBase45)?;
+
+    let mut decoded = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        match group {
+            [c, d, e] => {
+                let value = c + d * 45 + e * 45 * 45;
+                if value > u16::MAX as usize {
+                    return Err(EciesErrorCode::InvalidBase45);
+                }
+                decoded.extend_from_slice(&(value as u16).to_be_bytes());
+            }
+            [c, d] => {
+                let value = c + d * 45;
+                if value > u8::MAX as usize {
+                    return Err(EciesErrorCode::InvalidBase45);
+                }
+                decoded.push(value as u8);
+            }
+            _ => return Err(EciesErrorCode::InvalidBase45),
+        }
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,553 @@
+use alloc::collections::BTreeMap;
+#[cfg(feature = "base64")]
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use libsecp256k1::{Message, PublicKey, SecretKey, Signature};
+
+use crate::audit::{begin, EciesAuditOperation, Operation};
+use crate::cipher::{encrypt_with_aad, encrypt_with_aad_into, open_payload, OVERHEAD_LENGTH};
+use crate::context::{public_key_of, sign, verify};
+use crate::sec1::{self, Sec1Scheme};
+#[cfg(feature = "base64")]
+use crate::encoding::{base64_length, encode_base64_in_place};
+use crate::error::EciesErrorCode;
+use crate::features::sha256;
+use crate::id::{generate_uuid, UUID_LENGTH};
+
+/*
+Envelope format
+
+    magic "ECIE" (4 bytes) | version (1 byte) | suite (1 byte) | header length (2 bytes, big endian) | header
+    | payload | signature?
+
+The suite identifies the algorithms used for the payload, so new curves or AEADs can be introduced without
+breaking existing ciphertexts.
+
+The header is a list of fields, each encoded as tag (1 byte) | length (2 bytes, big endian) | value.
+Everything before the payload is passed to AES-GCM as associated data: it can be read without the secret key,
+but tampering with it makes decryption fail.
+The payload is the ciphertext of the suite: the ecies ciphertext produced by `cipher::encrypt_with_aad` by default,
+or the SEC 1 / ISO 18033-2 construction of `sec1` for interop with smartcards and HSMs.
+When the header names a sender public key, a 64-byte compact secp256k1 signature by that sender over the SHA-256
+of every preceding envelope byte is appended after the payload.
+*/
+
+pub const MAGIC: &[u8; 4] = b"ECIE";
+pub const VERSION: u8 = 1;
+
+const PREFIX_LENGTH: usize = MAGIC.len() + 1 + 1 + 2;
+
+const FIELD_CREATED_AT: u8 = 0x01;
+const FIELD_TTL: u8 = 0x02;
+const FIELD_MESSAGE_ID: u8 = 0x03;
+const FIELD_COMPRESSION: u8 = 0x04;
+const FIELD_SENDER: u8 = 0x05;
+const FIELD_METADATA: u8 = 0x06;
+const FIELD_KEY_ID: u8 = 0x07;
+
+pub const MESSAGE_ID_LENGTH: usize = UUID_LENGTH;
+pub const SIGNATURE_LENGTH: usize = 64;
+
+#[cfg(feature = "std")]
+const ZSTD_LEVEL: i32 = 3;
+// Largest message a compressed envelope may expand to, so a few kilobytes cannot inflate into gigabytes
+pub const MAX_DECOMPRESSED_LENGTH: usize = 64 * 1024 * 1024;
+
+// Algorithms used to produce the payload
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suite {
+    // secp256k1 ECDH, HKDF-SHA256 and AES-256-GCM with a 16-byte nonce, as implemented by the ecies crate
+    Secp256k1HkdfSha256Aes256Gcm = 1,
+    // SEC 1 / ISO 18033-2 ECIES on secp256k1: X9.63 KDF with SHA-256, AES-256-CBC and HMAC-SHA256
+    Secp256k1Kdf2Sha256Aes256CbcHmacSha256 = 2,
+    // SEC 1 / ISO 18033-2 ECIES on secp256k1: X9.63 KDF with SHA-256, XOR encryption and HMAC-SHA256
+    Secp256k1Kdf2Sha256XorHmacSha256 = 3,
+}
+
+impl Suite {
+    pub fn from_byte(byte: u8) -> Result<Suite, EciesErrorCode> {
+        match byte {
+            1 => Ok(Suite::Secp256k1HkdfSha256Aes256Gcm),
+            2 => Ok(Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256),
+            3 => Ok(Suite::Secp256k1Kdf2Sha256XorHmacSha256),
+            _ => Err(EciesErrorCode::UnsupportedSuite),
+        }
+    }
+
+    // Identifier used by text formats such as the JSON ciphertext
+    pub fn name(&self) -> &'static str {
+        match self {
+            Suite::Secp256k1HkdfSha256Aes256Gcm => "secp256k1-hkdf-sha256-aes-256-gcm",
+            Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 => "secp256k1-kdf2-sha256-aes-256-cbc-hmac-sha256",
+            Suite::Secp256k1Kdf2Sha256XorHmacSha256 => "secp256k1-kdf2-sha256-xor-hmac-sha256",
+        }
+    }
+
+    pub fn encrypt(&self, public_key: &PublicKey, message: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        match self {
+            Suite::Secp256k1HkdfSha256Aes256Gcm => encrypt_with_aad(public_key, message, aad),
+            Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 => {
+                sec1::encrypt_with_aad(Sec1Scheme::Aes256Cbc, public_key, message, aad)
+            }
+            Suite::Secp256k1Kdf2Sha256XorHmacSha256 => sec1::encrypt_with_aad(Sec1Scheme::Xor, public_key, message, aad),
+        }
+    }
+
+    // Reports no audit event: the envelope is opened as a whole, and reported once by `open`
+    pub fn decrypt(&self, secret_key: &SecretKey, payload: &[u8], aad: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        match self {
+            Suite::Secp256k1HkdfSha256Aes256Gcm => open_payload(secret_key, payload, aad),
+            Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 => {
+                sec1::open_payload(Sec1Scheme::Aes256Cbc, secret_key, payload, aad)
+            }
+            Suite::Secp256k1Kdf2Sha256XorHmacSha256 => sec1::open_payload(Sec1Scheme::Xor, secret_key, payload, aad),
+        }
+    }
+}
+
+// Compression applied to the message before encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Zstd = 1,
+}
+
+impl Compression {
+    fn from_byte(byte: u8) -> Result<Compression, EciesErrorCode> {
+        match byte {
+            1 => Ok(Compression::Zstd),
+            _ => Err(EciesErrorCode::InvalidEnvelope),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn compress(&self, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        match self {
+            Compression::Zstd => zstd::encode_all(message, ZSTD_LEVEL).map_err(|_| EciesErrorCode::CompressionFailed),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn decompress(&self, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        match self {
+            Compression::Zstd => {
+                let decoder = zstd::stream::read::Decoder::new(message).map_err(|_| EciesErrorCode::CompressionFailed)?;
+                let mut decompressed = Vec::new();
+                decoder
+                    .take(MAX_DECOMPRESSED_LENGTH as u64 + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|_| EciesErrorCode::CompressionFailed)?;
+                if decompressed.len() > MAX_DECOMPRESSED_LENGTH {
+                    return Err(EciesErrorCode::CompressionFailed);
+                }
+                Ok(decompressed)
+            }
+        }
+    }
+
+    // zstd is a C library built against std, so `no_std` builds can neither produce nor open compressed envelopes
+    #[cfg(not(feature = "std"))]
+    pub fn compress(&self, _message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        Err(EciesErrorCode::CompressionFailed)
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn decompress(&self, _message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        Err(EciesErrorCode::CompressionFailed)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    // Seconds since the Unix epoch
+    pub created_at: Option<u64>,
+    // Seconds after `created_at` when the message expires
+    pub ttl: Option<u64>,
+    // Random identifier used by hosts to detect replayed messages
+    pub message_id: Option<[u8; MESSAGE_ID_LENGTH]>,
+    pub compression: Option<Compression>,
+    // Long-term public key of the sender who signed the envelope
+    pub sender: Option<PublicKey>,
+    // Caller-supplied descriptions of the content, such as a content type or file name. Authenticated but readable
+    // without the secret key
+    pub metadata: BTreeMap<String, String>,
+    // Caller-supplied identifier or version of the recipient key, for picking the secret key in a rotated set
+    pub key_id: Option<String>,
+}
+
+impl Header {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut fields = Vec::new();
+        if let Some(created_at) = self.created_at {
+            push_field(&mut fields, FIELD_CREATED_AT, &created_at.to_be_bytes());
+        }
+        if let Some(ttl) = self.ttl {
+            push_field(&mut fields, FIELD_TTL, &ttl.to_be_bytes());
+        }
+        if let Some(message_id) = &self.message_id {
+            push_field(&mut fields, FIELD_MESSAGE_ID, message_id);
+        }
+        if let Some(compression) = self.compression {
+            push_field(&mut fields, FIELD_COMPRESSION, &[compression as u8]);
+        }
+        if let Some(sender) = &self.sender {
+            push_field(&mut fields, FIELD_SENDER, &sender.serialize_compressed());
+        }
+        if !self.metadata.is_empty() {
+            push_field(&mut fields, FIELD_METADATA, &encode_metadata(&self.metadata));
+        }
+        if let Some(key_id) = &self.key_id {
+            push_field(&mut fields, FIELD_KEY_ID, key_id.as_bytes());
+        }
+        fields
+    }
+
+    pub fn decode(mut fields: &[u8]) -> Result<Header, EciesErrorCode> {
+        let mut header = Header::default();
+        while !fields.is_empty() {
+            if fields.len() < 3 {
+                return Err(EciesErrorCode::InvalidEnvelope);
+            }
+            let tag = fields[0];
+            let length = u16::from_be_bytes([fields[1], fields[2]]) as usize;
+            let value = fields.get(3..3 + length).ok_or(EciesErrorCode::InvalidEnvelope)?;
+
+            match tag {
+                FIELD_CREATED_AT => header.created_at = Some(read_u64(value)?),
+                FIELD_TTL => header.ttl = Some(read_u64(value)?),
+                FIELD_MESSAGE_ID => {
+                    let message_id = value.try_into().map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+                    header.message_id = Some(message_id);
+                }
+                // Unlike other fields, an unknown compression cannot be ignored
+                FIELD_COMPRESSION => match value {
+                    [byte] => header.compression = Some(Compression::from_byte(*byte)?),
+                    _ => return Err(EciesErrorCode::InvalidEnvelope),
+                },
+                FIELD_SENDER => {
+                    let sender = PublicKey::parse_slice(value, None).map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+                    header.sender = Some(sender);
+                }
+                FIELD_METADATA => header.metadata = decode_metadata(value)?,
+                FIELD_KEY_ID => {
+                    let key_id = String::from_utf8(value.to_vec()).map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+                    header.key_id = Some(key_id);
+                }
+                // Unknown fields are authenticated like every other header byte, so they can be skipped safely
+                _ => {}
+            }
+
+            fields = &fields[3 + length..];
+        }
+        Ok(header)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        match (self.created_at, self.ttl) {
+            (Some(created_at), Some(ttl)) => now >= created_at.saturating_add(ttl),
+            _ => false,
+        }
+    }
+}
+
+fn push_field(fields: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    fields.push(tag);
+    fields.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    fields.extend_from_slice(value);
+}
+
+// Entries are encoded as key length (2 bytes, big endian) | key | value length (2 bytes, big endian) | value,
+// in key order
+fn encode_metadata(metadata: &BTreeMap<String, String>) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for (key, value) in metadata {
+        for string in [key, value] {
+            encoded.extend_from_slice(&(string.len() as u16).to_be_bytes());
+            encoded.extend_from_slice(string.as_bytes());
+        }
+    }
+    encoded
+}
+
+fn read_metadata_string(encoded: &mut &[u8]) -> Result<String, EciesErrorCode> {
+    let length_bytes = encoded.get(..2).ok_or(EciesErrorCode::InvalidEnvelope)?;
+    let length = u16::from_be_bytes([length_bytes[0], length_bytes[1]]) as usize;
+    let string = encoded.get(2..2 + length).ok_or(EciesErrorCode::InvalidEnvelope)?;
+    let string = String::from_utf8(string.to_vec()).map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+
+    *encoded = &encoded[2 + length..];
+    Ok(string)
+}
+
+fn decode_metadata(mut encoded: &[u8]) -> Result<BTreeMap<String, String>, EciesErrorCode> {
+    let mut metadata = BTreeMap::new();
+    while !encoded.is_empty() {
+        let key = read_metadata_string(&mut encoded)?;
+        let value = read_metadata_string(&mut encoded)?;
+        metadata.insert(key, value);
+    }
+    Ok(metadata)
+}
+
+fn read_u64(value: &[u8]) -> Result<u64, EciesErrorCode> {
+    let bytes: [u8; 8] = value.try_into().map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+// A version 4 UUID, failing with `RngFailure` once the random number generator failed its health tests
+pub fn generate_message_id() -> Result<[u8; MESSAGE_ID_LENGTH], EciesErrorCode> {
+    generate_uuid()
+}
+
+// Without std there is no clock: firmware passes its own time to `Header::is_expired`
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+// `SystemTime::now` panics on wasm32-unknown-unknown, the clock comes from JavaScript instead
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+pub fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+// Returns true when the bytes start with the envelope magic. Legacy ciphertexts start with the 0x04 prefix of an
+// uncompressed public key, so they can never match
+pub fn is_envelope(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub struct ParsedEnvelope<'a> {
+    pub version: u8,
+    pub suite: Suite,
+    // Magic, version and header: the associated data of the payload
+    pub authenticated: &'a [u8],
+    pub header: Header,
+    pub payload: &'a [u8],
+    // Every byte covered by the signature, and the signature itself
+    pub signed: &'a [u8],
+    pub signature: Option<&'a [u8]>,
+}
+
+impl ParsedEnvelope<'_> {
+    // The encoded header fields, without the prefix
+    pub fn header_bytes(&self) -> &[u8] {
+        &self.authenticated[PREFIX_LENGTH..]
+    }
+}
+
+// Splits an envelope into its parts without decrypting it
+pub fn parse(envelope: &[u8]) -> Result<ParsedEnvelope<'_>, EciesErrorCode> {
+    if envelope.len() < PREFIX_LENGTH || !is_envelope(envelope) {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+    let version = envelope[MAGIC.len()];
+    if version != VERSION {
+        return Err(EciesErrorCode::UnsupportedVersion);
+    }
+    let suite = Suite::from_byte(envelope[MAGIC.len() + 1])?;
+
+    let header_length = u16::from_be_bytes([envelope[MAGIC.len() + 2], envelope[MAGIC.len() + 3]]) as usize;
+    let header_end = PREFIX_LENGTH + header_length;
+    let header_bytes = envelope.get(PREFIX_LENGTH..header_end).ok_or(EciesErrorCode::InvalidEnvelope)?;
+    let header = Header::decode(header_bytes)?;
+
+    let (signed, signature) = match header.sender {
+        Some(_) => {
+            let signature_start = envelope.len().checked_sub(SIGNATURE_LENGTH).filter(|start| *start >= header_end)
+                .ok_or(EciesErrorCode::InvalidEnvelope)?;
+            (&envelope[..signature_start], Some(&envelope[signature_start..]))
+        }
+        None => (envelope, None),
+    };
+
+    Ok(ParsedEnvelope {
+        version,
+        suite,
+        authenticated: &envelope[..header_end],
+        header,
+        payload: &signed[header_end..],
+        signed,
+        signature,
+    })
+}
+
+fn signing_digest(signed: &[u8]) -> Message {
+    Message::parse(&sha256(signed))
+}
+
+// Checks the sender signature of a parsed envelope, if it has one
+pub fn verify_signature(parsed: &ParsedEnvelope) -> Result<(), EciesErrorCode> {
+    if let (Some(sender), Some(signature)) = (&parsed.header.sender, parsed.signature) {
+        let signature = Signature::parse_standard_slice(signature).map_err(|_| EciesErrorCode::InvalidSignature)?;
+        if !verify(&signing_digest(parsed.signed), &signature, sender) {
+            return Err(EciesErrorCode::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+// Builds the binary envelope from its parts, the inverse of `parse`
+pub fn assemble(
+    version: u8,
+    suite: Suite,
+    header_bytes: &[u8],
+    payload: &[u8],
+    signature: Option<&[u8]>,
+) -> Result<Vec<u8>, EciesErrorCode> {
+    if header_bytes.len() > u16::MAX as usize {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+
+    let signature = signature.unwrap_or(&[]);
+    let mut envelope = Vec::with_capacity(PREFIX_LENGTH + header_bytes.len() + payload.len() + signature.len());
+    envelope.extend_from_slice(MAGIC);
+    envelope.push(version);
+    envelope.push(suite as u8);
+    envelope.extend_from_slice(&(header_bytes.len() as u16).to_be_bytes());
+    envelope.extend_from_slice(header_bytes);
+    envelope.extend_from_slice(payload);
+    envelope.extend_from_slice(signature);
+
+    Ok(envelope)
+}
+
+// Encrypts a message into an envelope, signing it when a sender secret key is given
+pub fn seal(
+    public_key: &PublicKey,
+    header: &Header,
+    message: &[u8],
+    sender_secret_key: Option<&SecretKey>,
+) -> Result<Vec<u8>, EciesErrorCode> {
+    seal_with_suite(Suite::Secp256k1HkdfSha256Aes256Gcm, public_key, header, message, sender_secret_key)
+}
+
+pub fn seal_with_suite(
+    suite: Suite,
+    public_key: &PublicKey,
+    header: &Header,
+    message: &[u8],
+    sender_secret_key: Option<&SecretKey>,
+) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut header = header.clone();
+    header.sender = sender_secret_key.map(public_key_of);
+
+    let header_bytes = header.encode();
+    let mut envelope = assemble(VERSION, suite, &header_bytes, &[], None)?;
+
+    let payload = match header.compression {
+        Some(compression) => suite.encrypt(public_key, &compression.compress(message)?, &envelope)?,
+        None => suite.encrypt(public_key, message, &envelope)?,
+    };
+    envelope.extend_from_slice(&payload);
+
+    if let Some(sender_secret_key) = sender_secret_key {
+        let (signature, _) = sign(&signing_digest(&envelope), sender_secret_key);
+        envelope.extend_from_slice(&signature.serialize());
+    }
+
+    Ok(envelope)
+}
+
+// Length of the envelope `seal_into` writes for a message of `message_length` bytes
+pub fn sealed_length(message_length: usize) -> usize {
+    PREFIX_LENGTH + OVERHEAD_LENGTH + message_length
+}
+
+// `seal` with an empty header, written into `envelope`, which must be exactly `sealed_length` bytes
+pub fn seal_into(public_key: &PublicKey, message: &[u8], envelope: &mut [u8]) -> Result<(), EciesErrorCode> {
+    if envelope.len() != sealed_length(message.len()) {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    let (prefix, payload) = envelope.split_at_mut(PREFIX_LENGTH);
+
+    let suite = Suite::Secp256k1HkdfSha256Aes256Gcm;
+    prefix[..MAGIC.len()].copy_from_slice(MAGIC);
+    prefix[MAGIC.len()] = VERSION;
+    prefix[MAGIC.len() + 1] = suite as u8;
+    prefix[MAGIC.len() + 2..].copy_from_slice(&0u16.to_be_bytes());
+
+    encrypt_with_aad_into(public_key, message, prefix, payload)
+}
+
+/*
+`seal` with an empty header, base64-encoded into a C string, the output of `ecies_encrypt`.
+
+The output size is known up front, so everything happens in one allocation sized for the C string: the binary
+envelope is written at its end, encrypted in place, then base64-encoded in place towards the front, and the
+allocation becomes the C string without being copied.
+*/
+#[cfg(feature = "base64")]
+pub fn seal_base64(public_key: &PublicKey, message: &[u8]) -> Result<CString, EciesErrorCode> {
+    let envelope_length = sealed_length(message.len());
+    let encoded_length = base64_length(envelope_length);
+
+    let mut buffer = Vec::with_capacity(encoded_length + 1);
+    buffer.resize(encoded_length, 0);
+    let envelope_start = encoded_length - envelope_length;
+    seal_into(public_key, message, &mut buffer[envelope_start..])?;
+
+    encode_base64_in_place(&mut buffer, envelope_start)?;
+    // The spare byte of capacity takes the terminator
+    CString::new(buffer).map_err(|_| EciesErrorCode::InvalidUtf8)
+}
+
+// Decrypts an envelope, verifying the sender signature first when there is one
+pub fn open(secret_key: &SecretKey, envelope: &[u8]) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_envelope(secret_key, envelope);
+    record_open(&operation, secret_key, envelope, &result);
+
+    result
+}
+
+fn open_envelope(secret_key: &SecretKey, envelope: &[u8]) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    let parsed = parse(envelope)?;
+    verify_signature(&parsed)?;
+
+    let mut message = parsed.suite.decrypt(secret_key, parsed.payload, parsed.authenticated)?;
+    if let Some(compression) = parsed.header.compression {
+        message = compression.decompress(&message)?;
+    }
+
+    Ok((parsed.header, message))
+}
+
+// Decrypts either an envelope or a legacy ciphertext, i.e. the raw ecies payload produced before envelopes existed
+pub fn open_or_legacy(secret_key: &SecretKey, bytes: &[u8]) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    open_or_legacy_checked(secret_key, bytes, |_| Ok(()))
+}
+
+// `open_or_legacy` that also fails when `check` rejects the authenticated header, e.g. an expired envelope. The
+// attempt is reported once, with the result of the check, whatever step rejected it
+pub fn open_or_legacy_checked(
+    secret_key: &SecretKey,
+    bytes: &[u8],
+    check: impl FnOnce(&Header) -> Result<(), EciesErrorCode>,
+) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = match is_envelope(bytes) {
+        true => open_envelope(secret_key, bytes),
+        false => open_payload(secret_key, bytes, &[]).map(|message| (Header::default(), message)),
+    };
+    let result = result.and_then(|(header, message)| check(&header).map(|()| (header, message)));
+    record_open(&operation, secret_key, bytes, &result);
+
+    result
+}
+
+fn record_open(
+    operation: &Operation,
+    secret_key: &SecretKey,
+    ciphertext: &[u8],
+    result: &Result<(Header, Vec<u8>), EciesErrorCode>,
+) {
+    let public_key = || public_key_of(secret_key).serialize_compressed();
+    let message_length = result.as_ref().map(|(_, message)| message.len()).map_err(|code| *code);
+    operation.record(public_key, ciphertext.len(), message_length);
+}
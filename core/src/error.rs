@@ -0,0 +1,143 @@
+/*
+Error codes reported by the status-returning FFI functions.
+
+Functions that can fail take an optional `error_ptr` out-parameter. On failure they return a null pointer
+and, when `error_ptr` is not null, write one of these codes into it. On success `Success` is written. The
+functions laid out for other runtimes (`ecies_net_*`, `ecies_wasi_*`) return the code instead.
+
+The values are frozen: they are emitted into ecies.h, mirrored by the wrappers in every language and carried
+across processes by the key agent, so a released code never changes its number or meaning. New codes are
+appended, and callers should treat codes they do not know like a generic failure. Broad codes predate the finer
+ones after them: a key of the wrong length is `InvalidKeyLength` rather than `InvalidSecretKey` or
+`InvalidPublicKey`, a public key whose point is not on the curve `InvalidCurvePoint`, and a ciphertext failing
+its authentication tag `MacFailed` rather than `DecryptionFailed`, which remains for the other decryption
+failures such as bad padding.
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
+pub enum EciesErrorCode {
+    Success = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidHex = 3,
+    InvalidBase64 = 4,
+    InvalidSecretKey = 5,
+    InvalidPublicKey = 6,
+    InvalidEnvelope = 7,
+    EncryptionFailed = 8,
+    DecryptionFailed = 9,
+    Expired = 10,
+    Replayed = 11,
+    MissingMessageId = 12,
+    CompressionFailed = 13,
+    InvalidSignature = 14,
+    MissingSignature = 15,
+    UnsupportedVersion = 16,
+    UnsupportedSuite = 17,
+    InvalidJson = 18,
+    InvalidCbor = 19,
+    InvalidProtobuf = 20,
+    InvalidMsgpack = 21,
+    InvalidArmor = 22,
+    InvalidBase45 = 23,
+    InvalidUr = 24,
+    IncompleteUr = 25,
+    InvalidArgument = 26,
+    InvalidChunk = 27,
+    IncompleteChunks = 28,
+    InvalidJwe = 29,
+    InvalidCose = 30,
+    InvalidAge = 31,
+    InvalidAgeKey = 32,
+    InvalidCertificate = 33,
+    UnsupportedCurve = 34,
+    InvalidSshKey = 35,
+    InvalidOpenPgp = 36,
+    NonCanonicalEnvelope = 37,
+    InvalidStream = 38,
+    TruncatedStream = 39,
+    IoFailed = 40,
+    Cancelled = 41,
+    InvalidUtf16 = 42,
+    InvalidAttestation = 43,
+    UnknownKey = 44,
+    InvalidKeyLength = 45,
+    InvalidCurvePoint = 46,
+    MacFailed = 47,
+    BufferTooSmall = 48,
+    RngFailure = 49,
+    PolicyViolation = 50,
+}
+
+impl EciesErrorCode {
+    pub(crate) const ALL: [EciesErrorCode; 51] = [
+        EciesErrorCode::Success,
+        EciesErrorCode::NullPointer,
+        EciesErrorCode::InvalidUtf8,
+        EciesErrorCode::InvalidHex,
+        EciesErrorCode::InvalidBase64,
+        EciesErrorCode::InvalidSecretKey,
+        EciesErrorCode::InvalidPublicKey,
+        EciesErrorCode::InvalidEnvelope,
+        EciesErrorCode::EncryptionFailed,
+        EciesErrorCode::DecryptionFailed,
+        EciesErrorCode::Expired,
+        EciesErrorCode::Replayed,
+        EciesErrorCode::MissingMessageId,
+        EciesErrorCode::CompressionFailed,
+        EciesErrorCode::InvalidSignature,
+        EciesErrorCode::MissingSignature,
+        EciesErrorCode::UnsupportedVersion,
+        EciesErrorCode::UnsupportedSuite,
+        EciesErrorCode::InvalidJson,
+        EciesErrorCode::InvalidCbor,
+        EciesErrorCode::InvalidProtobuf,
+        EciesErrorCode::InvalidMsgpack,
+        EciesErrorCode::InvalidArmor,
+        EciesErrorCode::InvalidBase45,
+        EciesErrorCode::InvalidUr,
+        EciesErrorCode::IncompleteUr,
+        EciesErrorCode::InvalidArgument,
+        EciesErrorCode::InvalidChunk,
+        EciesErrorCode::IncompleteChunks,
+        EciesErrorCode::InvalidJwe,
+        EciesErrorCode::InvalidCose,
+        EciesErrorCode::InvalidAge,
+        EciesErrorCode::InvalidAgeKey,
+        EciesErrorCode::InvalidCertificate,
+        EciesErrorCode::UnsupportedCurve,
+        EciesErrorCode::InvalidSshKey,
+        EciesErrorCode::InvalidOpenPgp,
+        EciesErrorCode::NonCanonicalEnvelope,
+        EciesErrorCode::InvalidStream,
+        EciesErrorCode::TruncatedStream,
+        EciesErrorCode::IoFailed,
+        EciesErrorCode::Cancelled,
+        EciesErrorCode::InvalidUtf16,
+        EciesErrorCode::InvalidAttestation,
+        EciesErrorCode::UnknownKey,
+        EciesErrorCode::InvalidKeyLength,
+        EciesErrorCode::InvalidCurvePoint,
+        EciesErrorCode::MacFailed,
+        EciesErrorCode::BufferTooSmall,
+        EciesErrorCode::RngFailure,
+        EciesErrorCode::PolicyViolation,
+    ];
+
+    // The variant with the given numeric value, for codes that crossed a process boundary
+    pub fn from_code(code: u32) -> Option<EciesErrorCode> {
+        EciesErrorCode::ALL.iter().copied().find(|variant| *variant as u32 == code)
+    }
+}
+
+// The variant name, also the message of the exceptions thrown by the generated bindings
+impl core::fmt::Display for EciesErrorCode {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(formatter, "{:?}", self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EciesErrorCode {}
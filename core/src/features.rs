@@ -0,0 +1,69 @@
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use sha2::{Digest, Sha256};
+
+/*
+CPU crypto extensions detected at runtime: AES-NI with PCLMULQDQ and the SHA extensions on x86, the ARMv8 AES
+(with PMULL) and SHA2 extensions on aarch64.
+
+AES-GCM goes through OpenSSL outside wasm32, whose assembly selects the AES-NI or ARMv8 code paths when the CPU has them
+and a constant-time software implementation otherwise. SHA-256 over whole messages goes through `sha256`, which
+uses OpenSSL's accelerated assembly when the CPU has SHA extensions and the portable implementation otherwise.
+
+Detection needs std, so `no_std` builds report no extensions and always run the portable code.
+*/
+
+#[derive(Debug, Clone, Copy)]
+pub struct CpuFeatures {
+    pub hw_aes: bool,
+    pub hw_sha: bool,
+}
+
+#[cfg(feature = "std")]
+static CPU_FEATURES: OnceLock<CpuFeatures> = OnceLock::new();
+
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn detect() -> CpuFeatures {
+    CpuFeatures {
+        hw_aes: std::arch::is_x86_feature_detected!("aes") && std::arch::is_x86_feature_detected!("pclmulqdq"),
+        hw_sha: std::arch::is_x86_feature_detected!("sha"),
+    }
+}
+
+// The `aes` feature covers both AES and PMULL
+#[cfg(all(feature = "std", target_arch = "aarch64"))]
+fn detect() -> CpuFeatures {
+    CpuFeatures {
+        hw_aes: std::arch::is_aarch64_feature_detected!("aes"),
+        hw_sha: std::arch::is_aarch64_feature_detected!("sha2"),
+    }
+}
+
+#[cfg(all(feature = "std", not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))))]
+fn detect() -> CpuFeatures {
+    CpuFeatures { hw_aes: false, hw_sha: false }
+}
+
+#[cfg(feature = "std")]
+pub fn cpu_features() -> CpuFeatures {
+    *CPU_FEATURES.get_or_init(detect)
+}
+
+#[cfg(not(feature = "std"))]
+pub fn cpu_features() -> CpuFeatures {
+    CpuFeatures { hw_aes: false, hw_sha: false }
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    match cpu_features().hw_sha {
+        true => openssl::sha::sha256(data),
+        false => Sha256::digest(data).into(),
+    }
+}
+
+// There is no OpenSSL on wasm32 or without std, see `symmetric.rs`
+#[cfg(any(not(feature = "std"), target_arch = "wasm32"))]
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
@@ -0,0 +1,176 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::error::EciesErrorCode;
+
+/*
+Health tests of NIST SP 800-90B section 4.4 on the bytes of the random number generator, so a broken source (a
+stuck hardware TRNG, a host `fill_bytes` returning zeros) stops key generation and encryption instead of
+producing predictable keys.
+
+Each byte is a sample, assumed to carry full entropy, and the cutoffs give a false alarm probability of 2^-40 per
+sample:
+    - repetition count: 6 identical bytes in a row fail
+    - adaptive proportion: 19 or more occurrences of the first byte of a 512-byte window fail
+A startup test runs both over 1024 bytes drawn and discarded before the first output, then the tests run
+continuously over every byte read from the entropy source: the seeds of the thread-local generator, or every byte
+handed out when the source is read directly (see `random.rs`).
+
+A failure is latched for the life of the process: `random::rng()`, and with it every key and nonce draw, returns
+`RngFailure` from then on, starting with the draw whose seed failed. The state is shared by every thread without
+a lock; concurrent draws may overwrite each other's progress, which can only delay a detection, never cause a
+false one.
+*/
+
+const REPETITION_CUTOFF: u32 = 6;
+const PROPORTION_WINDOW: u32 = 512;
+const PROPORTION_CUTOFF: u32 = 19;
+pub const STARTUP_SAMPLES: usize = 1024;
+
+// The progress of both tests through a source, carried from one batch of samples to the next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct State {
+    last: u8,
+    run: u32,
+    first: u8,
+    seen: u32,
+    count: u32,
+}
+
+impl State {
+    // Last byte << 16 | run length
+    fn repetition(self) -> u32 {
+        ((self.last as u32) << 16) | self.run
+    }
+
+    // First byte of the window << 20 | bytes seen in the window << 10 | occurrences of the first byte
+    fn proportion(self) -> u32 {
+        ((self.first as u32) << 20) | (self.seen << 10) | self.count
+    }
+
+    fn from_parts(repetition: u32, proportion: u32) -> State {
+        State {
+            last: (repetition >> 16) as u8,
+            run: repetition & 0xFFFF,
+            first: (proportion >> 20) as u8,
+            seen: (proportion >> 10) & 0x3FF,
+            count: proportion & 0x3FF,
+        }
+    }
+}
+
+static REPETITION: AtomicU32 = AtomicU32::new(0);
+static PROPORTION: AtomicU32 = AtomicU32::new(0);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+// Whether the startup test still has to run, before the first bytes are handed out
+pub fn needs_startup() -> bool {
+    !STARTED.load(Ordering::Acquire)
+}
+
+pub fn startup(samples: &[u8]) {
+    observe(samples);
+    STARTED.store(true, Ordering::Release);
+}
+
+// Runs both tests over the next samples of the process source, latching a failure
+pub fn observe(samples: &[u8]) {
+    let mut state = State::from_parts(REPETITION.load(Ordering::Relaxed), PROPORTION.load(Ordering::Relaxed));
+    let passed = test(&mut state, samples);
+
+    REPETITION.store(state.repetition(), Ordering::Relaxed);
+    PROPORTION.store(state.proportion(), Ordering::Relaxed);
+    if !passed {
+        FAILED.store(true, Ordering::Release);
+    }
+}
+
+// Runs both tests over the next samples of a source, returning false if either failed on them
+pub fn test(state: &mut State, samples: &[u8]) -> bool {
+    let mut failed = false;
+
+    for &sample in samples {
+        state.run = if state.run > 0 && sample == state.last { state.run + 1 } else { 1 };
+        state.last = sample;
+        failed |= state.run >= REPETITION_CUTOFF;
+        state.run = state.run.min(REPETITION_CUTOFF);
+
+        if state.seen == 0 {
+            (state.first, state.count) = (sample, 1);
+        } else if sample == state.first {
+            state.count += 1;
+            failed |= state.count >= PROPORTION_CUTOFF;
+            state.count = state.count.min(PROPORTION_CUTOFF);
+        }
+        state.seen = (state.seen + 1) % PROPORTION_WINDOW;
+    }
+
+    !failed
+}
+
+// Fails with `RngFailure` once a test has failed
+pub fn check() -> Result<(), EciesErrorCode> {
+    match FAILED.load(Ordering::Acquire) {
+        true => Err(EciesErrorCode::RngFailure),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Xorshift output, standing in for a healthy source
+    fn healthy_samples(length: usize) -> alloc::vec::Vec<u8> {
+        let mut x = 0x2545_F491_4F6C_DD1Du64;
+        (0..length)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                (x >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn passes_healthy_output() {
+        let mut state = State::default();
+        assert!(test(&mut state, &healthy_samples(STARTUP_SAMPLES)));
+        assert!(test(&mut state, &healthy_samples(64 * 1024)));
+    }
+
+    #[test]
+    fn fails_repeated_bytes() {
+        let mut samples = healthy_samples(100);
+        samples[40..45].fill(0xAB);
+        assert!(test(&mut State::default(), &samples));
+
+        samples[40..46].fill(0xAB);
+        assert!(!test(&mut State::default(), &samples));
+
+        // Runs continue across batches
+        let mut state = State::default();
+        assert!(test(&mut state, &[1, 7, 7, 7]));
+        assert!(!test(&mut state, &[7, 7, 7]));
+    }
+
+    #[test]
+    fn fails_overrepresented_bytes() {
+        // The first byte of the window, spread out so no run is long enough for the repetition test
+        let mut samples = healthy_samples(PROPORTION_WINDOW as usize);
+        let first = samples[0];
+        for index in (0..samples.len()).step_by(20).skip(1).take(PROPORTION_CUTOFF as usize - 2) {
+            samples[index] = first;
+        }
+        let count = |samples: &[u8]| samples.iter().filter(|&&sample| sample == first).count();
+        assert!(count(&samples) < PROPORTION_CUTOFF as usize);
+        assert!(test(&mut State::default(), &samples));
+
+        for index in (0..samples.len()).step_by(20).skip(1).take(PROPORTION_CUTOFF as usize) {
+            samples[index] = first;
+        }
+        assert!(count(&samples) >= PROPORTION_CUTOFF as usize);
+        assert!(!test(&mut State::default(), &samples));
+    }
+}
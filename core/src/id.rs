@@ -0,0 +1,54 @@
+use alloc::string::String;
+use core::fmt::Write;
+use libsecp256k1::PublicKey;
+
+use crate::error::EciesErrorCode;
+use crate::features::sha256;
+use crate::random::random_bytes;
+
+/*
+Identifiers labelling keys and ciphertexts, formatted identically on every platform instead of by each host's
+UUID library.
+
+    - UUIDs: random version 4 UUIDs (RFC 9562), 16 bytes, written in lowercase hex as
+      `xxxxxxxx-xxxx-4xxx-yxxx-xxxxxxxxxxxx`. Envelope message IDs are UUIDs.
+    - Key IDs: the first 8 bytes of the SHA-256 of the compressed public key, as 16 lowercase hex characters. They
+      are derived rather than drawn, so every platform labels a key with the same ID, and match the start of the
+      key fingerprint reported to the audit hook. Hosts without a naming scheme of their own pass them as the
+      `key_id` of envelopes.
+*/
+
+pub const UUID_LENGTH: usize = 16;
+pub const KEY_ID_LENGTH: usize = 8;
+
+// Fails with `RngFailure` once the random number generator failed its health tests
+pub fn generate_uuid() -> Result<[u8; UUID_LENGTH], EciesErrorCode> {
+    let mut uuid = [0u8; UUID_LENGTH];
+    random_bytes(&mut uuid)?;
+    uuid[6] = (uuid[6] & 0x0F) | 0x40;
+    uuid[8] = (uuid[8] & 0x3F) | 0x80;
+
+    Ok(uuid)
+}
+
+// Any 16 bytes in the hyphenated form, so message IDs of older envelopes display like the new ones
+pub fn format_uuid(uuid: &[u8; UUID_LENGTH]) -> String {
+    let mut text = String::with_capacity(2 * UUID_LENGTH + 4);
+    for (index, byte) in uuid.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            text.push('-');
+        }
+        let _ = write!(text, "{:02x}", byte);
+    }
+    text
+}
+
+pub fn key_id(public_key: &PublicKey) -> String {
+    let fingerprint = sha256(&public_key.serialize_compressed());
+
+    let mut text = String::with_capacity(2 * KEY_ID_LENGTH);
+    for byte in &fingerprint[..KEY_ID_LENGTH] {
+        let _ = write!(text, "{:02x}", byte);
+    }
+    text
+}
@@ -0,0 +1,157 @@
+use alloc::vec::Vec;
+#[cfg(all(feature = "hex", feature = "base64"))]
+use alloc::string::String;
+#[cfg(all(feature = "hex", feature = "base64"))]
+use core::ops::Deref;
+use core::sync::atomic::{compiler_fence, Ordering};
+use libsecp256k1::{PublicKey, SecretKey};
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+use crate::encoding::{decode_base64, encode_base64, EciesBase64Flavor};
+use crate::error::EciesErrorCode;
+
+/*
+Public key representations, so hosts no longer need their own conversion code.
+
+Layouts:
+    - compressed: 33 bytes, 0x02 or 0x03 followed by x
+    - uncompressed: 65 bytes, 0x04 followed by x and y, the layout taken by the encryption functions
+    - ethereum: 64 bytes, x and y without prefix
+Each can be written as hex or standard base64. On input the layout is recognised from the length and the encoding
+from the characters: hex (with or without `0x`) or base64 of any flavor.
+
+Every function taking a key goes through `decode_key_text`, so secret and public keys can be passed as hex or
+base64 alike. Keys are decoded straight into a stack buffer and parsed from there, without intermediate copies.
+The text forms need both the `hex` and `base64` features; binary keys are always accepted.
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesPublicKeyFormat {
+    Compressed = 0,
+    Uncompressed = 1,
+    Ethereum = 2,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesKeyEncoding {
+    Hex = 0,
+    Base64 = 1,
+}
+
+// Longest key layout, an uncompressed public key
+#[cfg(all(feature = "hex", feature = "base64"))]
+const MAX_KEY_LENGTH: usize = 65;
+
+/*
+Decoded key bytes, kept on the stack and wiped when dropped so that secret keys leave no copies behind on the heap.
+Input longer than any key layout is recorded as `MAX_KEY_LENGTH + 1` zero bytes, a length every parser rejects.
+*/
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub struct KeyBytes {
+    bytes: [u8; MAX_KEY_LENGTH + 1],
+    len: usize,
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+impl Deref for KeyBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+impl Drop for KeyBytes {
+    fn drop(&mut self) {
+        wipe(&mut self.bytes);
+    }
+}
+
+// Zeroes key material in a way the compiler cannot elide
+pub fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub fn decode_key_text(text: &str) -> Result<(KeyBytes, EciesKeyEncoding), EciesErrorCode> {
+    let text = text.trim();
+    let hex_text = text.strip_prefix("0x").unwrap_or(text);
+    let mut key = KeyBytes { bytes: [0u8; MAX_KEY_LENGTH + 1], len: 0 };
+    if !hex_text.is_empty() && hex_text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        if !hex_text.len().is_multiple_of(2) {
+            return Err(EciesErrorCode::InvalidHex);
+        }
+        key.len = (hex_text.len() / 2).min(MAX_KEY_LENGTH + 1);
+        if key.len <= MAX_KEY_LENGTH {
+            hex::decode_to_slice(hex_text, &mut key.bytes[..key.len]).map_err(|_| EciesErrorCode::InvalidHex)?;
+        }
+        return Ok((key, EciesKeyEncoding::Hex));
+    }
+
+    let mut decoded = decode_base64(text.as_bytes())?;
+    key.len = decoded.len().min(MAX_KEY_LENGTH + 1);
+    if key.len <= MAX_KEY_LENGTH {
+        key.bytes[..key.len].copy_from_slice(&decoded);
+    }
+    wipe(&mut decoded);
+
+    Ok((key, EciesKeyEncoding::Base64))
+}
+
+// Keys with a known layout whose coordinates do not solve the curve equation fail with `InvalidCurvePoint`
+pub fn parse_public_key(bytes: &[u8]) -> Result<PublicKey, EciesErrorCode> {
+    let parsed = match (bytes.len(), bytes.first()) {
+        (64, _) => {
+            let mut uncompressed = [0x04; 65];
+            uncompressed[1..].copy_from_slice(bytes);
+            PublicKey::parse(&uncompressed)
+        }
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04)) => PublicKey::parse_slice(bytes, None),
+        (33 | 65, _) => return Err(EciesErrorCode::InvalidPublicKey),
+        _ => return Err(EciesErrorCode::InvalidKeyLength),
+    };
+    parsed.map_err(|_| EciesErrorCode::InvalidCurvePoint)
+}
+
+// Secret keys are 32 bytes; zero and values past the curve order are `InvalidSecretKey`
+pub fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey, EciesErrorCode> {
+    if bytes.len() != 32 {
+        return Err(EciesErrorCode::InvalidKeyLength);
+    }
+    SecretKey::parse_slice(bytes).map_err(|_| EciesErrorCode::InvalidSecretKey)
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub fn parse_public_key_text(text: &str) -> Result<(PublicKey, EciesKeyEncoding), EciesErrorCode> {
+    let (bytes, encoding) = decode_key_text(text)?;
+    Ok((parse_public_key(&bytes)?, encoding))
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub fn parse_secret_key_text(text: &str) -> Result<(SecretKey, EciesKeyEncoding), EciesErrorCode> {
+    let (bytes, encoding) = decode_key_text(text)?;
+    Ok((parse_secret_key(&bytes)?, encoding))
+}
+
+pub fn serialize_public_key(public_key: &PublicKey, format: EciesPublicKeyFormat) -> Vec<u8> {
+    match format {
+        EciesPublicKeyFormat::Compressed => public_key.serialize_compressed().to_vec(),
+        EciesPublicKeyFormat::Uncompressed => public_key.serialize().to_vec(),
+        EciesPublicKeyFormat::Ethereum => public_key.serialize()[1..].to_vec(),
+    }
+}
+
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub fn encode_key_text(bytes: &[u8], encoding: EciesKeyEncoding) -> String {
+    match encoding {
+        EciesKeyEncoding::Hex => hex::encode(bytes),
+        EciesKeyEncoding::Base64 => encode_base64(bytes, EciesBase64Flavor::Standard),
+    }
+}
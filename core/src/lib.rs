@@ -0,0 +1,311 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use libsecp256k1::{PublicKey, SecretKey};
+
+pub mod error;
+pub mod random;
+pub mod health;
+pub mod audit;
+pub mod metrics;
+pub mod policy;
+pub mod context;
+pub mod features;
+pub mod symmetric;
+pub mod cipher;
+pub mod sec1;
+pub mod envelope;
+#[cfg(feature = "base64")]
+pub mod armor;
+pub mod encoding;
+pub mod keys;
+pub mod id;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("ecies_core");
+
+pub use crate::envelope::Header;
+pub use crate::error::EciesErrorCode;
+
+use alloc::vec::Vec;
+#[cfg(feature = "base64")]
+use alloc::string::String;
+
+#[cfg(feature = "base64")]
+use crate::armor::decode_text;
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, open_or_legacy_checked, seal};
+#[cfg(feature = "base64")]
+use crate::envelope::seal_base64;
+#[cfg(all(feature = "hex", feature = "base64"))]
+use crate::keys::{parse_public_key_text, parse_secret_key_text};
+
+/*
+The envelope format and its cryptography in plain Rust, without FFI: the C API (`ios/`), the JNI layer
+(`android/`) and the wasm module (`wasm/`) are thin crates on top of this one.
+
+The functions below are its safe API, for Rust services and tools that depend on the crate directly instead of
+going through C strings and error pointers. The modules hold the building blocks they share with the bindings.
+
+Ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`, except for `encrypt_to_string`
+and `decrypt_text`, which use the text forms. Decryption also accepts legacy ciphertexts. Errors are the
+`EciesErrorCode` values of the C API, which implement `std::error::Error`.
+
+Without the default `std` feature the crate is `no_std` and needs only `alloc`, so the same envelope code runs
+on firmware. The text forms then depend on the `hex` and `base64` features, and randomness comes from the
+generator installed with `random::set_fill_bytes` unless the `rng` feature is enabled.
+*/
+
+pub type Result<T> = core::result::Result<T, EciesErrorCode>;
+
+// A secret key with its public key. The secret key is cleared when the keypair is dropped
+pub struct Keypair {
+    secret_key: SecretKey,
+    public_key: PublicKey,
+}
+
+impl Keypair {
+    // Fails with `RngFailure` once the random number generator failed its health tests
+    pub fn generate() -> Result<Keypair> {
+        let (secret_key, public_key) = generate_keypair()?;
+        Ok(Keypair { secret_key, public_key })
+    }
+
+    pub fn from_secret_key(secret_key: SecretKey) -> Keypair {
+        let public_key = public_key_of(&secret_key);
+        Keypair { secret_key, public_key }
+    }
+
+    // 32 raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Keypair> {
+        parse_secret_key(bytes).map(Keypair::from_secret_key)
+    }
+
+    // Hexadecimal or base64, as accepted by `ecies_public_key_from`
+    #[cfg(all(feature = "hex", feature = "base64"))]
+    pub fn from_text(text: &str) -> Result<Keypair> {
+        parse_secret_key_text(text).map(|(secret_key, _)| Keypair::from_secret_key(secret_key))
+    }
+
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.secret_key
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        decrypt(&self.secret_key, ciphertext)
+    }
+
+    // Encrypts to `public_key` an envelope signed with this keypair, as `ecies_encrypt_signed`
+    pub fn encrypt_signed(&self, public_key: &PublicKey, message: &[u8]) -> Result<Vec<u8>> {
+        seal(public_key, &Header::default(), message, Some(&self.secret_key))
+    }
+}
+
+impl Drop for Keypair {
+    fn drop(&mut self) {
+        // The scalar is stored inline, without heap allocations, so its bytes can be cleared in place
+        let bytes = &mut self.secret_key as *mut SecretKey as *mut u8;
+        keys::wipe(unsafe { core::slice::from_raw_parts_mut(bytes, core::mem::size_of::<SecretKey>()) });
+    }
+}
+
+// Leaves the secret key out of logs
+impl core::fmt::Debug for Keypair {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature = "hex")]
+        let public_key = hex::encode(self.public_key.serialize_compressed());
+        #[cfg(not(feature = "hex"))]
+        let public_key = self.public_key.serialize_compressed();
+        formatter.debug_struct("Keypair").field("public_key", &public_key).finish_non_exhaustive()
+    }
+}
+
+pub fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey> {
+    keys::parse_secret_key(bytes)
+}
+
+// Compressed, uncompressed or raw 64-byte public keys
+pub fn parse_public_key(bytes: &[u8]) -> Result<PublicKey> {
+    keys::parse_public_key(bytes)
+}
+
+// Hexadecimal or base64 public keys, as accepted by `ecies_encrypt`
+#[cfg(all(feature = "hex", feature = "base64"))]
+pub fn parse_public_key_from_text(text: &str) -> Result<PublicKey> {
+    parse_public_key_text(text).map(|(public_key, _)| public_key)
+}
+
+// Encrypts a message into a binary envelope
+pub fn encrypt(public_key: &PublicKey, message: &[u8]) -> Result<Vec<u8>> {
+    seal(public_key, &Header::default(), message, None)
+}
+
+// Encrypts a message into a binary envelope with the given header fields, e.g. a TTL, metadata or compression
+pub fn encrypt_with_header(public_key: &PublicKey, header: &Header, message: &[u8]) -> Result<Vec<u8>> {
+    seal(public_key, header, message, None)
+}
+
+// Encrypts a message into the base64 text returned by `ecies_encrypt`
+#[cfg(feature = "base64")]
+pub fn encrypt_to_string(public_key: &PublicKey, message: &[u8]) -> Result<String> {
+    seal_base64(public_key, message)?.into_string().map_err(|_| EciesErrorCode::InvalidUtf8)
+}
+
+// Decrypts a binary envelope or a legacy ciphertext, verifying the sender signature when there is one
+pub fn decrypt(secret_key: &SecretKey, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    open_or_legacy(secret_key, ciphertext).map(|(_, message)| message)
+}
+
+// `decrypt` returning the authenticated header as well, for the TTL, sender or metadata of the envelope
+pub fn decrypt_with_header(secret_key: &SecretKey, ciphertext: &[u8]) -> Result<(Header, Vec<u8>)> {
+    open_or_legacy(secret_key, ciphertext)
+}
+
+// Decrypts the base64 or armored text of `ecies_encrypt`
+#[cfg(feature = "base64")]
+pub fn decrypt_text(secret_key: &SecretKey, ciphertext: &str) -> Result<Vec<u8>> {
+    decrypt(secret_key, &decode_text(ciphertext.as_bytes())?)
+}
+
+// Decrypts a signed envelope, returning the verified public key of the sender with the message
+pub fn decrypt_signed(secret_key: &SecretKey, ciphertext: &[u8]) -> Result<(PublicKey, Vec<u8>)> {
+    let require_signature = |header: &Header| header.sender.map(|_| ()).ok_or(EciesErrorCode::MissingSignature);
+    let (header, message) = open_or_legacy_checked(secret_key, ciphertext, require_signature)?;
+    let sender = header.sender.ok_or(EciesErrorCode::MissingSignature)?;
+
+    Ok((sender, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_binary_envelopes() {
+        let keypair = Keypair::generate().unwrap();
+        let ciphertext = encrypt(keypair.public_key(), b"hello").unwrap();
+
+        assert_eq!(keypair.decrypt(&ciphertext).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn round_trips_text_envelopes() {
+        let keypair = Keypair::generate().unwrap();
+        let ciphertext = encrypt_to_string(keypair.public_key(), "🔐 sealed".as_bytes()).unwrap();
+
+        assert_eq!(decrypt_text(keypair.secret_key(), &ciphertext).unwrap(), "🔐 sealed".as_bytes());
+    }
+
+    #[test]
+    fn verifies_signed_envelopes() {
+        let sender = Keypair::generate().unwrap();
+        let recipient = Keypair::generate().unwrap();
+        let ciphertext = sender.encrypt_signed(recipient.public_key(), b"hello").unwrap();
+
+        let (public_key, message) = decrypt_signed(recipient.secret_key(), &ciphertext).unwrap();
+        assert_eq!(public_key, *sender.public_key());
+        assert_eq!(message, b"hello");
+    }
+
+    #[test]
+    fn rejects_unsigned_envelopes_as_signed() {
+        let keypair = Keypair::generate().unwrap();
+        let ciphertext = encrypt(keypair.public_key(), b"hello").unwrap();
+
+        assert_eq!(decrypt_signed(keypair.secret_key(), &ciphertext).unwrap_err(), EciesErrorCode::MissingSignature);
+    }
+
+    #[test]
+    fn rejects_other_secret_keys() {
+        let ciphertext = encrypt(Keypair::generate().unwrap().public_key(), b"hello").unwrap();
+
+        assert_eq!(Keypair::generate().unwrap().decrypt(&ciphertext).unwrap_err(), EciesErrorCode::MacFailed);
+    }
+
+    #[test]
+    fn rejects_tampered_envelopes() {
+        let keypair = Keypair::generate().unwrap();
+        let mut ciphertext = encrypt(keypair.public_key(), b"hello").unwrap();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+
+        assert!(keypair.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn authenticates_header_fields() {
+        let keypair = Keypair::generate().unwrap();
+        let mut header = Header { ttl: Some(60), key_id: Some("2024-01".to_string()), ..Header::default() };
+        header.metadata.insert("content-type".to_string(), "text/plain".to_string());
+        let ciphertext = encrypt_with_header(keypair.public_key(), &header, b"hello").unwrap();
+
+        let (opened, message) = decrypt_with_header(keypair.secret_key(), &ciphertext).unwrap();
+        assert_eq!(opened.ttl, Some(60));
+        assert_eq!(opened.key_id.as_deref(), Some("2024-01"));
+        assert_eq!(opened.metadata, header.metadata);
+        assert_eq!(message, b"hello");
+    }
+
+    #[test]
+    fn restores_keypairs_from_secret_keys() {
+        let keypair = Keypair::generate().unwrap();
+        let restored = Keypair::from_bytes(&keypair.secret_key().serialize()).unwrap();
+
+        assert_eq!(restored.public_key(), keypair.public_key());
+        assert_eq!(Keypair::from_bytes(&[0; 32]).unwrap_err(), EciesErrorCode::InvalidSecretKey);
+        assert_eq!(Keypair::from_bytes(&[1; 31]).unwrap_err(), EciesErrorCode::InvalidKeyLength);
+    }
+
+    #[test]
+    fn reports_public_key_failures() {
+        let public_key = Keypair::generate().unwrap().public_key().serialize_compressed();
+        let mut off_curve = [0xff; 33];
+        off_curve[0] = 0x02;
+
+        assert_eq!(parse_public_key(&public_key[..32]).unwrap_err(), EciesErrorCode::InvalidKeyLength);
+        assert_eq!(parse_public_key(&off_curve).unwrap_err(), EciesErrorCode::InvalidCurvePoint);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rejects_decompression_bombs() {
+        use crate::envelope::{Compression, MAX_DECOMPRESSED_LENGTH};
+
+        let at_limit = zstd::encode_all(&vec![0u8; MAX_DECOMPRESSED_LENGTH][..], 3).unwrap();
+        assert_eq!(Compression::Zstd.decompress(&at_limit).unwrap().len(), MAX_DECOMPRESSED_LENGTH);
+
+        let bomb = zstd::encode_all(&vec![0u8; MAX_DECOMPRESSED_LENGTH + 1][..], 3).unwrap();
+        assert!(bomb.len() < 64 * 1024);
+        assert_eq!(Compression::Zstd.decompress(&bomb).unwrap_err(), EciesErrorCode::CompressionFailed);
+    }
+
+    #[test]
+    fn formats_uuids_and_key_ids() {
+        let uuid = id::format_uuid(&id::generate_uuid().unwrap());
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+
+        let keypair = Keypair::generate().unwrap();
+        let key_id = id::key_id(keypair.public_key());
+        assert_eq!(key_id.len(), 2 * id::KEY_ID_LENGTH);
+        // The same ID whichever encoding the key was parsed from
+        let uncompressed = parse_public_key(&keypair.public_key().serialize()).unwrap();
+        assert_eq!(id::key_id(&uncompressed), key_id);
+    }
+
+    #[test]
+    fn keeps_error_codes_frozen() {
+        assert_eq!(EciesErrorCode::from_code(9), Some(EciesErrorCode::DecryptionFailed));
+        assert_eq!(EciesErrorCode::from_code(44), Some(EciesErrorCode::UnknownKey));
+        assert_eq!(EciesErrorCode::from_code(48), Some(EciesErrorCode::BufferTooSmall));
+        assert_eq!(EciesErrorCode::from_code(49), Some(EciesErrorCode::RngFailure));
+        assert_eq!(EciesErrorCode::from_code(50), Some(EciesErrorCode::PolicyViolation));
+        assert_eq!(EciesErrorCode::from_code(51), None);
+    }
+}
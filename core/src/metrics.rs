@@ -0,0 +1,135 @@
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+
+use crate::audit::EciesAuditOperation;
+use crate::error::EciesErrorCode;
+
+/*
+Counters of the operations reported to the audit hook, kept whether or not a hook is installed, so apps can send
+crypto health telemetry (volumes, failure rates, latency) without instrumenting their call sites.
+
+Every key generation, encryption and decryption counts once, with its input and output lengths, its duration and
+its error code on failure. Streams count from their creation to their last chunk. The counters are global to the
+process and cumulative until `reset_metrics`.
+
+Builds without std keep no counters and `metrics` is unavailable. On wasm32-unknown-unknown, which has no
+monotonic clock without JavaScript, latencies are zero.
+*/
+
+// When an operation started, for its latency
+#[derive(Debug, Clone, Copy)]
+pub struct Started {
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    instant: std::time::Instant,
+}
+
+impl Started {
+    pub fn now() -> Started {
+        Started {
+            #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+            instant: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn elapsed(&self) -> Duration {
+        self.instant.elapsed()
+    }
+
+    #[cfg(not(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown")))))]
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationMetrics {
+    pub count: u64,
+    pub failures: u64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    // Time spent in the operations, failed ones included
+    pub latency: Duration,
+}
+
+impl OperationMetrics {
+    const EMPTY: OperationMetrics =
+        OperationMetrics { count: 0, failures: 0, input_bytes: 0, output_bytes: 0, latency: Duration::ZERO };
+
+    pub fn average_latency(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => Duration::from_nanos((self.latency.as_nanos() / count as u128) as u64),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics {
+    pub key_generation: OperationMetrics,
+    pub encryption: OperationMetrics,
+    pub decryption: OperationMetrics,
+    // Failures of all operations by error code, in code order. Codes that never occurred are left out
+    pub failures: Vec<(EciesErrorCode, u64)>,
+}
+
+#[cfg(feature = "std")]
+struct Counters {
+    // Indexed by `EciesAuditOperation`
+    operations: [OperationMetrics; 3],
+    // Indexed like `EciesErrorCode::ALL`
+    failures: [u64; EciesErrorCode::ALL.len()],
+}
+
+#[cfg(feature = "std")]
+static COUNTERS: Mutex<Counters> = Mutex::new(Counters {
+    operations: [OperationMetrics::EMPTY; 3],
+    failures: [0; EciesErrorCode::ALL.len()],
+});
+
+// Called by `audit::Operation::record` for every operation
+#[cfg(feature = "std")]
+pub fn count(
+    operation: EciesAuditOperation,
+    started: Started,
+    input_length: usize,
+    result: Result<usize, EciesErrorCode>,
+) {
+    let latency = started.elapsed();
+    let mut counters = COUNTERS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let metrics = &mut counters.operations[operation as usize];
+    metrics.count += 1;
+    metrics.input_bytes += input_length as u64;
+    metrics.output_bytes += result.unwrap_or(0) as u64;
+    metrics.latency += latency;
+    if let Err(code) = result {
+        metrics.failures += 1;
+        if let Some(index) = EciesErrorCode::ALL.iter().position(|variant| *variant == code) {
+            counters.failures[index] += 1;
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub fn count(_: EciesAuditOperation, _: Started, _: usize, _: Result<usize, EciesErrorCode>) {}
+
+#[cfg(feature = "std")]
+pub fn metrics() -> Metrics {
+    let counters = COUNTERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let [key_generation, encryption, decryption] = counters.operations;
+    let failures = EciesErrorCode::ALL.iter().zip(counters.failures).filter(|(_, count)| *count > 0);
+
+    Metrics { key_generation, encryption, decryption, failures: failures.map(|(code, count)| (*code, count)).collect() }
+}
+
+#[cfg(feature = "std")]
+pub fn reset_metrics() {
+    let mut counters = COUNTERS.lock().unwrap_or_else(PoisonError::into_inner);
+    counters.operations = [OperationMetrics::EMPTY; 3];
+    counters.failures = [0; EciesErrorCode::ALL.len()];
+}
@@ -0,0 +1,53 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::EciesErrorCode;
+
+/*
+The algorithm policy of the process, so one build serves consumer apps and regulated deployments alike.
+
+Under `Fips`, everything outside the FIPS 140-3 approved set fails with `PolicyViolation` before any key is used:
+    - secp256k1 key agreement, which SP 800-186 does not approve, and with it every secp256k1 ECIES variant:
+      envelopes of all suites, the legacy format, streams, JWE, COSE and OpenPGP
+    - X25519 with XSalsa20-Poly1305 (libsodium sealed boxes, eth-sig-util payloads) and with ChaCha20-Poly1305
+      (age)
+    - the XOR scheme of SEC 1
+Regulated deployments encrypt to P-256 keys instead (`p256.rs` in the C API), whose construction only uses
+approved primitives: P-256 ECDH, HKDF-SHA256 and AES-256-GCM. Validation also needs OpenSSL built with its FIPS
+provider, which runs them.
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesPolicy {
+    Unrestricted = 0,
+    Fips = 1,
+}
+
+impl EciesPolicy {
+    // The variant with the given numeric value, for policies passed across the FFI as integers
+    pub fn from_code(code: u32) -> Option<EciesPolicy> {
+        match code {
+            0 => Some(EciesPolicy::Unrestricted),
+            1 => Some(EciesPolicy::Fips),
+            _ => None,
+        }
+    }
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(EciesPolicy::Unrestricted as u8);
+
+pub fn set_policy(policy: EciesPolicy) {
+    POLICY.store(policy as u8, Ordering::Release);
+}
+
+pub fn policy() -> EciesPolicy {
+    EciesPolicy::from_code(POLICY.load(Ordering::Acquire) as u32).unwrap_or(EciesPolicy::Unrestricted)
+}
+
+// Called before an algorithm outside the approved set is used, failing under `Fips`
+pub fn allow_unapproved() -> Result<(), EciesErrorCode> {
+    match policy() {
+        EciesPolicy::Fips => Err(EciesErrorCode::PolicyViolation),
+        EciesPolicy::Unrestricted => Ok(()),
+    }
+}
@@ -0,0 +1,214 @@
+use rand::{CryptoRng, RngCore};
+
+use crate::error::EciesErrorCode;
+use crate::health;
+
+/*
+The random number generator behind every key, nonce and message ID of the library.
+
+By default this is a ChaCha12 generator (rand's `StdRng`) kept in thread-local storage, seeded from the operating
+system generator (getrandom) and reseeded from it after every 64 KiB. With the `os-rng` feature every call reads
+the operating system generator directly and the library keeps no thread-local state, for hosts such as Go whose
+goroutines move between OS threads.
+
+Without the `rng` feature, e.g. in `no_std` firmware, there is no operating system to read from: the host installs
+a function filling buffers from its own source, typically a hardware TRNG, with `set_fill_bytes` before the first
+key is generated. Generating randomness before one is installed panics rather than returning predictable bytes.
+
+The health tests of `health.rs` run over the entropy source, never over the output of the ChaCha generator, which
+would look healthy whatever its seed: the operating system generator or the installed function. Every draw starts
+with `rng()`, which fails with `RngFailure` once the tests failed, after reseeding, so no byte derived from a
+failing seed is handed out. Sources read directly can only be tested as their bytes are handed out; callers
+check `health::check()` again after drawing a key or nonce from them.
+*/
+
+#[cfg(feature = "rng")]
+fn entropy() -> impl RngCore + CryptoRng {
+    rand::rngs::OsRng
+}
+
+#[cfg(not(feature = "rng"))]
+use installed::source as entropy;
+#[cfg(not(feature = "rng"))]
+pub use installed::{set_fill_bytes, FillBytes};
+
+#[cfg(all(feature = "rng", not(feature = "os-rng")))]
+use seeded::generator;
+
+#[cfg(any(feature = "os-rng", not(feature = "rng")))]
+fn generator() -> Result<impl RngCore + CryptoRng, EciesErrorCode> {
+    Ok(HealthTested(entropy()))
+}
+
+// Fails with `RngFailure` once the health tests failed
+pub fn rng() -> Result<impl RngCore + CryptoRng, EciesErrorCode> {
+    if health::needs_startup() {
+        let mut samples = [0u8; health::STARTUP_SAMPLES];
+        entropy().fill_bytes(&mut samples);
+        health::startup(&samples);
+    }
+    let generator = generator()?;
+    health::check()?;
+
+    Ok(generator)
+}
+
+// Fills `dest` for the host's own salts, nonces and tokens. It fails with `RngFailure`, leaving `dest` zeroed,
+// once the health tests failed, including on the bytes just drawn
+pub fn random_bytes(dest: &mut [u8]) -> Result<(), EciesErrorCode> {
+    rng()?.fill_bytes(dest);
+
+    let result = health::check();
+    if result.is_err() {
+        dest.fill(0);
+    }
+    result
+}
+
+// Runs the continuous health tests over the bytes drawn from the entropy source
+struct HealthTested<R>(R);
+
+impl<R: RngCore> RngCore for HealthTested<R> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+        health::observe(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)?;
+        health::observe(dest);
+        Ok(())
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for HealthTested<R> {}
+
+#[cfg(not(feature = "rng"))]
+mod installed {
+    use core::sync::atomic::{AtomicPtr, Ordering};
+    use rand::{CryptoRng, RngCore};
+
+    // Fills the whole buffer with cryptographically secure random bytes
+    pub type FillBytes = fn(&mut [u8]);
+
+    static FILL_BYTES: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+    pub fn set_fill_bytes(fill_bytes: FillBytes) {
+        FILL_BYTES.store(fill_bytes as *mut (), Ordering::Release);
+    }
+
+    struct InstalledRng(FillBytes);
+
+    impl RngCore for InstalledRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0u8; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            (self.0)(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for InstalledRng {}
+
+    pub fn source() -> impl RngCore + CryptoRng {
+        let fill_bytes = FILL_BYTES.load(Ordering::Acquire);
+        assert!(!fill_bytes.is_null(), "no random number generator installed, see `random::set_fill_bytes`");
+
+        // Only `set_fill_bytes` stores into `FILL_BYTES`, always a `FillBytes`
+        InstalledRng(unsafe { core::mem::transmute::<*mut (), FillBytes>(fill_bytes) })
+    }
+}
+
+#[cfg(all(feature = "rng", not(feature = "os-rng")))]
+mod seeded {
+    use core::cell::RefCell;
+    use core::marker::PhantomData;
+    use rand::rngs::StdRng;
+    use rand::{CryptoRng, RngCore, SeedableRng};
+
+    use super::{entropy, HealthTested};
+    use crate::error::EciesErrorCode;
+
+    const RESEED_THRESHOLD: usize = 64 * 1024;
+
+    struct Seeded {
+        rng: StdRng,
+        drawn: usize,
+    }
+
+    thread_local! {
+        static GENERATOR: RefCell<Option<Seeded>> = const { RefCell::new(None) };
+    }
+
+    // The generator of the current thread. It is reseeded here rather than while it draws, so `rng()` checks the
+    // health of every seed before any byte derived from it is used
+    pub fn generator() -> Result<ThreadGenerator, EciesErrorCode> {
+        GENERATOR.with(|generator| {
+            let mut generator = generator.borrow_mut();
+            if generator.as_ref().is_none_or(|seeded| seeded.drawn >= RESEED_THRESHOLD) {
+                let rng = StdRng::from_rng(HealthTested(entropy())).map_err(|_| EciesErrorCode::RngFailure)?;
+                *generator = Some(Seeded { rng, drawn: 0 });
+            }
+            Ok(ThreadGenerator(PhantomData))
+        })
+    }
+
+    // A handle on the generator of the thread that called `generator`, which is seeded
+    pub struct ThreadGenerator(PhantomData<*const ()>);
+
+    impl RngCore for ThreadGenerator {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0u8; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            GENERATOR.with(|generator| {
+                let mut generator = generator.borrow_mut();
+                let seeded = generator.as_mut().expect("seeded by `generator`");
+                seeded.rng.fill_bytes(dest);
+                seeded.drawn = seeded.drawn.saturating_add(dest.len());
+            })
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for ThreadGenerator {}
+}
@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use libsecp256k1::{PublicKey, SecretKey};
+use sha2::{Digest, Sha256};
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::cipher::PUBLIC_KEY_LENGTH;
+use crate::context::{ecdh_point, ephemeral_keypair, public_key_of};
+use crate::error::EciesErrorCode;
+use crate::policy::allow_unapproved;
+use crate::symmetric::{aes_256_cbc_decrypt, aes_256_cbc_encrypt};
+
+/*
+The classical ECIES of SEC 1 v2 (section 5.1) and ISO 18033-2, as implemented by smartcard and HSM vendors.
+
+    ephemeral public key (65 bytes) | encrypted message | HMAC-SHA256 tag (32 bytes)
+
+The encryption and MAC keys come from the ANSI X9.63 KDF (KDF2 in ISO 18033-2) with SHA-256 over the x coordinate
+of the shared point, with empty SharedInfo1. The tag covers the encrypted message followed by SharedInfo2, which
+carries the associated data, e.g. the envelope header.
+Two symmetric schemes are supported: AES-256-CBC with a zero IV and PKCS#7 padding, and the XOR scheme, where the
+KDF output is used as a one-time pad as long as the message.
+*/
+
+pub const MAC_LENGTH: usize = 32;
+
+const AES_KEY_LENGTH: usize = 32;
+const MAC_KEY_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sec1Scheme {
+    Aes256Cbc,
+    Xor,
+}
+
+// The XOR scheme is not an approved mode of encryption, refused under the FIPS policy
+fn check_policy(scheme: Sec1Scheme) -> Result<(), EciesErrorCode> {
+    match scheme {
+        Sec1Scheme::Aes256Cbc => Ok(()),
+        Sec1Scheme::Xor => allow_unapproved(),
+    }
+}
+
+fn kdf2(shared_secret: &[u8], length: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(length + 32);
+    let mut counter: u32 = 1;
+    while output.len() < length {
+        let mut hasher = Sha256::new();
+        hasher.update(shared_secret);
+        hasher.update(counter.to_be_bytes());
+        output.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    output.truncate(length);
+    output
+}
+
+fn shared_secret(public_key: &PublicKey, secret_key: &SecretKey) -> Result<Vec<u8>, EciesErrorCode> {
+    let shared_point = ecdh_point(public_key, secret_key)?;
+    Ok(shared_point.serialize()[1..33].to_vec())
+}
+
+fn mac(mac_key: &[u8], encrypted: &[u8], shared_info: &[u8]) -> Result<Hmac<Sha256>, EciesErrorCode> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(mac_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    mac.update(encrypted);
+    mac.update(shared_info);
+    Ok(mac)
+}
+
+fn encryption_key_length(scheme: Sec1Scheme, message_length: usize) -> usize {
+    match scheme {
+        Sec1Scheme::Aes256Cbc => AES_KEY_LENGTH,
+        Sec1Scheme::Xor => message_length,
+    }
+}
+
+pub fn encrypt_with_aad(
+    scheme: Sec1Scheme,
+    public_key: &PublicKey,
+    message: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_payload(scheme, public_key, message, aad);
+    operation.record(|| public_key.serialize_compressed(), message.len(), outcome(&result));
+
+    result
+}
+
+fn seal_payload(
+    scheme: Sec1Scheme,
+    public_key: &PublicKey,
+    message: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, EciesErrorCode> {
+    check_policy(scheme)?;
+    let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+    let shared_secret = shared_secret(public_key, &ephemeral_secret_key)?;
+
+    let encryption_key_length = encryption_key_length(scheme, message.len());
+    let keys = kdf2(&shared_secret, encryption_key_length + MAC_KEY_LENGTH);
+    let (encryption_key, mac_key) = keys.split_at(encryption_key_length);
+
+    let encrypted = match scheme {
+        Sec1Scheme::Aes256Cbc => aes_256_cbc_encrypt(encryption_key, message)?,
+        Sec1Scheme::Xor => message.iter().zip(encryption_key).map(|(byte, pad)| byte ^ pad).collect(),
+    };
+    let tag = mac(mac_key, &encrypted, aad)?.finalize().into_bytes();
+
+    let mut output = Vec::with_capacity(PUBLIC_KEY_LENGTH + encrypted.len() + MAC_LENGTH);
+    output.extend_from_slice(&ephemeral_public_key.serialize());
+    output.extend_from_slice(&encrypted);
+    output.extend_from_slice(&tag);
+
+    Ok(output)
+}
+
+pub fn decrypt_with_aad(
+    scheme: Sec1Scheme,
+    secret_key: &SecretKey,
+    payload: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_payload(scheme, secret_key, payload, aad);
+    let public_key = || public_key_of(secret_key).serialize_compressed();
+    operation.record(public_key, payload.len(), outcome(&result));
+
+    result
+}
+
+// `decrypt_with_aad` without an audit event, for operations reporting a single event of their own
+pub fn open_payload(
+    scheme: Sec1Scheme,
+    secret_key: &SecretKey,
+    payload: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, EciesErrorCode> {
+    check_policy(scheme)?;
+    if payload.len() < PUBLIC_KEY_LENGTH + MAC_LENGTH {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+
+    let ephemeral_public_key = PublicKey::parse_slice(&payload[..PUBLIC_KEY_LENGTH], None)
+        .map_err(|_| EciesErrorCode::InvalidEnvelope)?;
+    let shared_secret = shared_secret(&ephemeral_public_key, secret_key)?;
+
+    let encrypted = &payload[PUBLIC_KEY_LENGTH..payload.len() - MAC_LENGTH];
+    let tag = &payload[payload.len() - MAC_LENGTH..];
+
+    let encryption_key_length = encryption_key_length(scheme, encrypted.len());
+    let keys = kdf2(&shared_secret, encryption_key_length + MAC_KEY_LENGTH);
+    let (encryption_key, mac_key) = keys.split_at(encryption_key_length);
+
+    mac(mac_key, encrypted, aad)?.verify_slice(tag).map_err(|_| EciesErrorCode::MacFailed)?;
+
+    match scheme {
+        Sec1Scheme::Aes256Cbc => aes_256_cbc_decrypt(encryption_key, encrypted),
+        Sec1Scheme::Xor => Ok(encrypted.iter().zip(encryption_key).map(|(byte, pad)| byte ^ pad).collect()),
+    }
+}
@@ -0,0 +1,126 @@
+/*
+The AES primitives behind the suites: AES-256-GCM with a 16-byte nonce for `cipher.rs`, AES-256-CBC with a zero IV
+and PKCS#7 padding for `sec1.rs`.
+
+Native builds use OpenSSL. OpenSSL does not build for wasm32 and needs std, so wasm32 and `no_std` builds use the
+RustCrypto implementations instead, which produce the same bytes.
+*/
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod imp {
+    use openssl::symm::{decrypt, decrypt_aead, encrypt, Cipher, Crypter, Mode};
+
+    use crate::error::EciesErrorCode;
+
+    pub fn aes_256_gcm_encrypt(
+        key: &[u8; 32],
+        nonce: &[u8],
+        aad: &[u8],
+        message: &[u8],
+        encrypted: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), EciesErrorCode> {
+        let mut crypter = Crypter::new(Cipher::aes_256_gcm(), Mode::Encrypt, key, Some(nonce))
+            .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        crypter.aad_update(aad).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+        // GCM is a stream mode: the output is exactly as long as the message, which `Crypter` still requires to have
+        // a spare byte of room for. Encrypting the last byte separately keeps the writes within `encrypted`
+        let split = message.len().saturating_sub(1);
+        let mut written = crypter.update(&message[..split], encrypted).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        let mut last = [0u8; 2];
+        if split < message.len() {
+            crypter.update(&message[split..], &mut last).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+            encrypted[written] = last[0];
+            written += 1;
+        }
+        crypter.finalize(&mut last).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        crypter.get_tag(tag).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        debug_assert_eq!(written, message.len());
+
+        Ok(())
+    }
+
+    pub fn aes_256_gcm_decrypt(
+        key: &[u8; 32],
+        nonce: &[u8],
+        aad: &[u8],
+        encrypted: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, EciesErrorCode> {
+        decrypt_aead(Cipher::aes_256_gcm(), key, Some(nonce), aad, encrypted, tag)
+            .map_err(|_| EciesErrorCode::MacFailed)
+    }
+
+    pub fn aes_256_cbc_encrypt(key: &[u8], message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        encrypt(Cipher::aes_256_cbc(), key, Some(&[0u8; 16]), message).map_err(|_| EciesErrorCode::EncryptionFailed)
+    }
+
+    pub fn aes_256_cbc_decrypt(key: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        decrypt(Cipher::aes_256_cbc(), key, Some(&[0u8; 16]), encrypted).map_err(|_| EciesErrorCode::DecryptionFailed)
+    }
+}
+
+#[cfg(any(not(feature = "std"), target_arch = "wasm32"))]
+mod imp {
+    use alloc::vec::Vec;
+    use aes::Aes256;
+    use aes_gcm::aead::consts::U16;
+    use aes_gcm::aead::{AeadInPlace, KeyInit};
+    use aes_gcm::{AesGcm, Nonce, Tag};
+    use cbc::cipher::block_padding::Pkcs7;
+    use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+
+    use crate::error::EciesErrorCode;
+
+    // The ecies payload uses a 16-byte nonce rather than the usual 12
+    type Aes256Gcm16 = AesGcm<Aes256, U16>;
+
+    pub fn aes_256_gcm_encrypt(
+        key: &[u8; 32],
+        nonce: &[u8],
+        aad: &[u8],
+        message: &[u8],
+        encrypted: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), EciesErrorCode> {
+        let cipher = Aes256Gcm16::new(key.into());
+        encrypted.copy_from_slice(message);
+        let computed = cipher
+            .encrypt_in_place_detached(Nonce::from_slice(nonce), aad, encrypted)
+            .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        tag.copy_from_slice(&computed);
+
+        Ok(())
+    }
+
+    pub fn aes_256_gcm_decrypt(
+        key: &[u8; 32],
+        nonce: &[u8],
+        aad: &[u8],
+        encrypted: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, EciesErrorCode> {
+        let cipher = Aes256Gcm16::new(key.into());
+        let mut message = encrypted.to_vec();
+        cipher
+            .decrypt_in_place_detached(Nonce::from_slice(nonce), aad, &mut message, Tag::from_slice(tag))
+            .map_err(|_| EciesErrorCode::MacFailed)?;
+
+        Ok(message)
+    }
+
+    pub fn aes_256_cbc_encrypt(key: &[u8], message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        let cipher = cbc::Encryptor::<Aes256>::new_from_slices(key, &[0u8; 16])
+            .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        Ok(cipher.encrypt_padded_vec_mut::<Pkcs7>(message))
+    }
+
+    pub fn aes_256_cbc_decrypt(key: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        let cipher = cbc::Decryptor::<Aes256>::new_from_slices(key, &[0u8; 16])
+            .map_err(|_| EciesErrorCode::DecryptionFailed)?;
+        cipher.decrypt_padded_vec_mut::<Pkcs7>(encrypted).map_err(|_| EciesErrorCode::DecryptionFailed)
+    }
+}
+
+pub use imp::{aes_256_cbc_decrypt, aes_256_cbc_encrypt, aes_256_gcm_decrypt, aes_256_gcm_encrypt};
@@ -0,0 +1,193 @@
+// C# binding over the `ecies_net_*` exports of ecies.h, see `dotnet.rs`. Apart from the SafeHandle, which
+// the marshaller handles natively, every signature is blittable and needs no marshalling attributes.
+// Requires AllowUnsafeBlocks.
+//
+//     var keypair = Ecies.GenerateKeypair();
+//     byte[] ciphertext = Ecies.Encrypt(keypair.PublicKey, Encoding.UTF8.GetBytes("hello"));
+//     byte[] message = Ecies.Decrypt(keypair.SecretKey, ciphertext);
+
+using System;
+using System.Runtime.InteropServices;
+
+namespace EciesNet
+{
+    // Values of `EciesErrorCode` in ecies.h; only the ones this layer reports are named
+    public enum EciesErrorCode
+    {
+        Success = 0,
+        NullPointer = 1,
+        InvalidSecretKey = 5,
+        InvalidPublicKey = 6,
+        InvalidEnvelope = 7,
+        EncryptionFailed = 8,
+        DecryptionFailed = 9,
+        CompressionFailed = 13,
+        InvalidSignature = 14,
+        UnsupportedVersion = 16,
+        UnsupportedSuite = 17,
+        InvalidArgument = 26,
+        InvalidKeyLength = 45,
+        InvalidCurvePoint = 46,
+        MacFailed = 47,
+        BufferTooSmall = 48,
+        RngFailure = 49,
+        PolicyViolation = 50,
+    }
+
+    public sealed class EciesException : Exception
+    {
+        public EciesErrorCode Code { get; }
+
+        public EciesException(EciesErrorCode code) : base(code.ToString())
+        {
+            Code = code;
+        }
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe struct EciesNetKeypair
+    {
+        public fixed byte SecretKey[32];
+        public fixed byte PublicKey[33];
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    internal unsafe struct EciesNetPublicKey
+    {
+        public fixed byte Bytes[33];
+    }
+
+    // Owns an `EciesNetBytes` handle, released by `ecies_net_bytes_free` even if the caller forgets to dispose it
+    internal sealed class EciesBytesHandle : SafeHandle
+    {
+        public EciesBytesHandle() : base(IntPtr.Zero, true)
+        {
+        }
+
+        public override bool IsInvalid => handle == IntPtr.Zero;
+
+        protected override bool ReleaseHandle()
+        {
+            Native.ecies_net_bytes_free(handle);
+            return true;
+        }
+
+        public unsafe byte[] ToArray()
+        {
+            var bytes = new byte[(int)Native.ecies_net_bytes_length(this)];
+            fixed (byte* destination = bytes)
+            {
+                Ecies.Check(Native.ecies_net_bytes_copy(this, destination, (nuint)bytes.Length));
+            }
+            return bytes;
+        }
+    }
+
+    internal static unsafe class Native
+    {
+        private const string Library = "ecies";
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern EciesErrorCode ecies_net_generate_keypair(EciesNetKeypair* keypair);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern EciesErrorCode ecies_net_public_key_from(
+            byte* secretKey, nuint secretKeyLength, EciesNetPublicKey* publicKey);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern EciesErrorCode ecies_net_encrypt(
+            byte* publicKey, nuint publicKeyLength, byte* message, nuint messageLength, out EciesBytesHandle bytes);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern EciesErrorCode ecies_net_decrypt(
+            byte* secretKey, nuint secretKeyLength, byte* ciphertext, nuint ciphertextLength, out EciesBytesHandle bytes);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern nuint ecies_net_bytes_length(EciesBytesHandle bytes);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern EciesErrorCode ecies_net_bytes_copy(
+            EciesBytesHandle bytes, byte* destination, nuint destinationLength);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern void ecies_net_bytes_free(IntPtr bytes);
+    }
+
+    public readonly struct Keypair
+    {
+        public byte[] SecretKey { get; }
+        public byte[] PublicKey { get; }
+
+        public Keypair(byte[] secretKey, byte[] publicKey)
+        {
+            SecretKey = secretKey;
+            PublicKey = publicKey;
+        }
+    }
+
+    public static unsafe class Ecies
+    {
+        // An empty array pins to a null pointer, which the library rejects
+        private static readonly byte[] Empty = new byte[1];
+
+        internal static void Check(EciesErrorCode code)
+        {
+            if (code != EciesErrorCode.Success)
+            {
+                throw new EciesException(code);
+            }
+        }
+
+        private static byte[] Pinnable(byte[] bytes) => bytes.Length > 0 ? bytes : Empty;
+
+        public static Keypair GenerateKeypair()
+        {
+            EciesNetKeypair keypair;
+            Check(Native.ecies_net_generate_keypair(&keypair));
+            var secretKey = new ReadOnlySpan<byte>(keypair.SecretKey, 32).ToArray();
+            var publicKey = new ReadOnlySpan<byte>(keypair.PublicKey, 33).ToArray();
+            new Span<byte>(keypair.SecretKey, 32).Clear();
+            return new Keypair(secretKey, publicKey);
+        }
+
+        public static byte[] PublicKeyFrom(byte[] secretKey)
+        {
+            EciesNetPublicKey publicKey;
+            fixed (byte* secretKeyPtr = Pinnable(secretKey))
+            {
+                Check(Native.ecies_net_public_key_from(secretKeyPtr, (nuint)secretKey.Length, &publicKey));
+            }
+            return new ReadOnlySpan<byte>(publicKey.Bytes, 33).ToArray();
+        }
+
+        public static byte[] Encrypt(byte[] publicKey, byte[] message)
+        {
+            EciesBytesHandle bytes;
+            fixed (byte* publicKeyPtr = Pinnable(publicKey))
+            fixed (byte* messagePtr = Pinnable(message))
+            {
+                Check(Native.ecies_net_encrypt(
+                    publicKeyPtr, (nuint)publicKey.Length, messagePtr, (nuint)message.Length, out bytes));
+            }
+            using (bytes)
+            {
+                return bytes.ToArray();
+            }
+        }
+
+        public static byte[] Decrypt(byte[] secretKey, byte[] ciphertext)
+        {
+            EciesBytesHandle bytes;
+            fixed (byte* secretKeyPtr = Pinnable(secretKey))
+            fixed (byte* ciphertextPtr = Pinnable(ciphertext))
+            {
+                Check(Native.ecies_net_decrypt(
+                    secretKeyPtr, (nuint)secretKey.Length, ciphertextPtr, (nuint)ciphertext.Length, out bytes));
+            }
+            using (bytes)
+            {
+                return bytes.ToArray();
+            }
+        }
+    }
+}
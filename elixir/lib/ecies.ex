@@ -0,0 +1,25 @@
+defmodule Ecies do
+  @moduledoc """
+  ECIES envelopes from the native library, loaded as a NIF built from `android/` with the `nif` feature.
+
+  Keys, messages and ciphertexts are binaries. Ciphertexts are binary envelopes: decode the base64 text produced
+  by the mobile apps with `Base.decode64!/1` first. Functions return `{:ok, result}` or `{:error, reason}`, where
+  `reason` is the snake_case name of the `EciesErrorCode`, such as `:decryption_failed`.
+
+  `encrypt/2` and `decrypt/2` run on dirty CPU schedulers.
+  """
+
+  use Rustler, otp_app: :ecies, crate: "ecies", path: "../android", features: ["nif"]
+
+  @spec generate_keypair() :: {:ok, {secret_key :: binary, public_key :: binary}} | {:error, atom}
+  def generate_keypair, do: :erlang.nif_error(:nif_not_loaded)
+
+  @spec public_key_from(binary) :: {:ok, binary} | {:error, atom}
+  def public_key_from(_secret_key), do: :erlang.nif_error(:nif_not_loaded)
+
+  @spec encrypt(binary, binary) :: {:ok, binary} | {:error, atom}
+  def encrypt(_public_key, _message), do: :erlang.nif_error(:nif_not_loaded)
+
+  @spec decrypt(binary, binary) :: {:ok, binary} | {:error, atom}
+  def decrypt(_secret_key, _ciphertext), do: :erlang.nif_error(:nif_not_loaded)
+end
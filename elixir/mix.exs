@@ -0,0 +1,22 @@
+defmodule Ecies.MixProject do
+  use Mix.Project
+
+  def project do
+    [
+      app: :ecies,
+      version: "0.1.0",
+      elixir: "~> 1.14",
+      deps: deps()
+    ]
+  end
+
+  def application do
+    [extra_applications: []]
+  end
+
+  defp deps do
+    [
+      {:rustler, "~> 0.30.0", runtime: false}
+    ]
+  end
+end
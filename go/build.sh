@@ -0,0 +1,13 @@
+#!/bin/bash
+
+# ASSUMPTIONS
+# 1. Cargo package manager is already installed on your computer
+# 2. A C toolchain is installed for cgo (CGO_ENABLED=1)
+
+# This builds target/go/libecies.a in the workspace root, the static library the Go package in this directory
+# links against.
+# The `go` profile aborts on panic instead of unwinding into Go, and `os-rng` keeps no thread-local state,
+# since goroutines move between OS threads
+
+cd ../ios
+cargo build --profile go --features os-rng
@@ -0,0 +1,123 @@
+// Package ecies links the static library built by build.sh, so Go services can decrypt and verify envelopes
+// produced by the mobile apps in-process.
+//
+// Keys are hexadecimal strings, as returned by the C API. Ciphertexts are base64 envelopes, the output of
+// `ecies_encrypt`. Call Init once at startup: the library then initializes nothing lazily, and holds no
+// per-thread state, so every function is safe to call from any goroutine.
+package ecies
+
+/*
+#cgo CFLAGS: -I${SRCDIR}/../ios/include
+#cgo LDFLAGS: -L${SRCDIR}/../target/go -lecies -lm -ldl -lpthread
+#include <stdlib.h>
+#include "ecies.h"
+*/
+import "C"
+
+import (
+	"fmt"
+	"unsafe"
+)
+
+// Error carries the EciesErrorCode of a failed call, see ecies.h.
+type Error struct {
+	Code int
+}
+
+func (e Error) Error() string {
+	return fmt.Sprintf("EciesErrorCode %d", e.Code)
+}
+
+func check(code C.enum_EciesErrorCode) error {
+	if code != C.Success {
+		return Error{Code: int(code)}
+	}
+	return nil
+}
+
+// takeString copies and releases a string returned by the library.
+func takeString(ptr *C.char) string {
+	defer C.ecies_string_free(ptr)
+	return C.GoString(ptr)
+}
+
+// Init builds the secp256k1 tables and detects the CPU features, see `ecies_init`.
+func Init() {
+	C.ecies_init()
+}
+
+// Keypair holds a hexadecimal secret key and its compressed public key.
+type Keypair struct {
+	SecretKey string
+	PublicKey string
+}
+
+func GenerateKeypair() (Keypair, error) {
+	secretKey := takeString(C.ecies_generate_secret_key())
+	publicKey, err := PublicKeyFrom(secretKey)
+	if err != nil {
+		return Keypair{}, err
+	}
+	return Keypair{SecretKey: secretKey, PublicKey: publicKey}, nil
+}
+
+func PublicKeyFrom(secretKey string) (string, error) {
+	cSecretKey := C.CString(secretKey)
+	defer C.free(unsafe.Pointer(cSecretKey))
+
+	var code C.enum_EciesErrorCode
+	publicKey := C.ecies_derive_public_key(cSecretKey, &code)
+	if err := check(code); err != nil {
+		return "", err
+	}
+	return takeString(publicKey), nil
+}
+
+// Encrypt seals a message into a base64 envelope for publicKey.
+func Encrypt(publicKey string, message []byte) (string, error) {
+	cPublicKey := C.CString(publicKey)
+	defer C.free(unsafe.Pointer(cPublicKey))
+	cMessage := C.CBytes(message)
+	defer C.free(cMessage)
+
+	var code C.enum_EciesErrorCode
+	envelope := C.ecies_encrypt_bytes(cPublicKey, (*C.uint8_t)(cMessage), C.uintptr_t(len(message)), nil, &code)
+	if err := check(code); err != nil {
+		return "", err
+	}
+	return takeString(envelope), nil
+}
+
+// Decrypt opens a base64 envelope, signed or not.
+func Decrypt(secretKey string, envelope string) ([]byte, error) {
+	cSecretKey := C.CString(secretKey)
+	defer C.free(unsafe.Pointer(cSecretKey))
+	cEnvelope := C.CString(envelope)
+	defer C.free(unsafe.Pointer(cEnvelope))
+
+	var code C.enum_EciesErrorCode
+	buffer := C.ecies_decrypt_bytes(cSecretKey, cEnvelope, nil, &code)
+	if err := check(code); err != nil {
+		return nil, err
+	}
+	defer C.ecies_buffer_free(buffer)
+	return C.GoBytes(unsafe.Pointer(buffer.data), C.int(buffer.length)), nil
+}
+
+// DecryptSigned opens a signed envelope, verifying its signature, and returns the message with the
+// hexadecimal public key of the sender. Unsigned envelopes fail with MissingSignature. The message is
+// returned as a C string by the library, so it ends at the first NUL byte.
+func DecryptSigned(secretKey string, envelope string) (message string, sender string, err error) {
+	cSecretKey := C.CString(secretKey)
+	defer C.free(unsafe.Pointer(cSecretKey))
+	cEnvelope := C.CString(envelope)
+	defer C.free(unsafe.Pointer(cEnvelope))
+
+	var code C.enum_EciesErrorCode
+	var cSender *C.char
+	cMessage := C.ecies_decrypt_signed(cSecretKey, cEnvelope, nil, &cSender, &code)
+	if err := check(code); err != nil {
+		return "", "", err
+	}
+	return takeString(cMessage), takeString(cSender), nil
+}
@@ -0,0 +1,26 @@
+package ecies_test
+
+import (
+	"fmt"
+
+	ecies "github.com/elefantel/ecies-rust-ffi/go"
+)
+
+func Example() {
+	ecies.Init()
+
+	keypair, err := ecies.GenerateKeypair()
+	if err != nil {
+		panic(err)
+	}
+	envelope, err := ecies.Encrypt(keypair.PublicKey, []byte("hello"))
+	if err != nil {
+		panic(err)
+	}
+	message, err := ecies.Decrypt(keypair.SecretKey, envelope)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Println(string(message))
+	// Output: hello
+}
@@ -0,0 +1,3 @@
+module github.com/elefantel/ecies-rust-ffi/go
+
+go 1.21
@@ -0,0 +1,21 @@
+use std::env;
+use std::path::PathBuf;
+
+// Generates the C header from the exported functions and types into OUT_DIR, where the header test compares it
+// with the committed include/ecies.h, so a stale header fails `cargo test`. The committed copy is regenerated
+// explicitly, see cbindgen.toml; the build never writes into the source tree.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=../core/src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("Invalid cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(out_dir.join("ecies.h"));
+}
@@ -6,14 +6,9 @@
 
 # This generates an .xcframework that can be embedded in your app as a crypto module
 
-# Install `cbindgen`
-# cbindgen (C Bindings Generator) is a tool that generates C header files from Rust source code
-# It is designed to make it easier to create FFI (Foreign Function Interface) bindings between Rust and C/C++ code
-cargo install --force cbindgen
-
-# Generates a C header file named ecies.h
-# The --lang c flag specifies that the generated header file should be in C syntax
-cbindgen --lang c --output include/ecies.h
+# Regenerate the C header include/ecies.h from the exported Rust functions (see cbindgen.toml)
+# cbindgen can be installed with `cargo install cbindgen`
+cbindgen --config cbindgen.toml --crate ecies-ffi-c --output include/ecies.h
 
 # Create a `module.modulemap` file
 # Used by the Clang compiler to define our module i.e collection of related header files
@@ -31,12 +26,12 @@ endof
 # First install target platform
 rustup target add aarch64-apple-ios
 # Then build for target platform
-cargo build --release --target aarch64-apple-ios
+cargo build --release --features uniffi --target aarch64-apple-ios
 
 # Build for Apple iOS Simulator on macs with x86 Intel microprocessor chip
 
 rustup target add x86_64-apple-ios
-cargo build --release --target x86_64-apple-ios
+cargo build --release --features uniffi --target x86_64-apple-ios
 
 # Build for Apple iOS Simulator running on macs with Apple Silicon chip 
 # NOTE - this command currently (December 2022) fails with the error:
@@ -51,14 +46,22 @@ rustup component add rust-src --toolchain nightly-aarch64-apple-darwin
 
 # Finally, build for the simulator from source using build-std - Cargo's experimental feature that allows us to rebuild the standard library locally
 rustup target add aarch64-apple-ios-sim
-cargo +nightly build -Z build-std --target aarch64-apple-ios-sim --release
+cargo +nightly build -Z build-std --features uniffi --target aarch64-apple-ios-sim --release
+
+# Generate the Swift bindings wrapped by swift/Ecies.swift: `ecies.swift` and `ecies_core.swift` (which declares
+# `EciesErrorCode`) for the app target, and the `eciesFFI` and `ecies_coreFFI` C headers they call, which ships in the xcframework next to ecies.h (see include/module.modulemap)
+cargo run --features uniffi --bin uniffi-bindgen generate \
+  --library ../target/aarch64-apple-ios/release/libecies.a \
+  --language swift \
+  --out-dir ./swift/generated
+cp ./swift/generated/eciesFFI.h ./swift/generated/ecies_coreFFI.h ./include/
 
 # Generate an xcframework named `Ecies`, with support for aarch64-apple-ios (devices) and aarch64-apple-ios-sim (M1 simulators). 
 # Unfortunately we can't have both aarch64-apple-ios-sim and x86_64-apple-ios as xcodebuild quips "Both 'ios-x86_64-simulator' and 'ios-arm64-simulator' represent two equivalent library definitions."
 xcodebuild -create-xcframework \
-  -library ./target/aarch64-apple-ios/release/libecies.a \
+  -library ../target/aarch64-apple-ios/release/libecies.a \
   -headers ./include/ \
-  -library ./target/aarch64-apple-ios-sim/release/libecies.a \
+  -library ../target/aarch64-apple-ios-sim/release/libecies.a \
   -headers ./include/ \
   -output Ecies.xcframework
 
@@ -1,12 +1,1631 @@
+/* Generated by cbindgen from the Rust sources (see cbindgen.toml), do not edit by hand */
+
 #include <stdarg.h>
 #include <stdbool.h>
 #include <stdint.h>
 #include <stdlib.h>
+#if defined(_WIN32) && !defined(ECIES_STATIC)
+#define ECIES_API __declspec(dllimport)
+#else
+#define ECIES_API
+#endif
+
+#define ECIES_NET_SECRET_KEY_LENGTH 32
+
+#define ECIES_NET_PUBLIC_KEY_LENGTH 33
+
+#define ECIES_AUDIT_FINGERPRINT_LENGTH 32
+
+typedef enum EciesAuditOperation {
+  KeyGeneration = 0,
+  Encryption = 1,
+  Decryption = 2,
+} EciesAuditOperation;
+
+typedef enum EciesBase64Flavor {
+  Standard = 0,
+  StandardNoPadding = 1,
+  UrlSafe = 2,
+  UrlSafeNoPadding = 3,
+} EciesBase64Flavor;
+
+typedef enum EciesCoseStructure {
+  Encrypt0 = 0,
+  Encrypt = 1,
+} EciesCoseStructure;
+
+typedef enum EciesCurve {
+  Secp256k1 = 0,
+  P256 = 1,
+  X25519 = 2,
+} EciesCurve;
+
+typedef enum EciesErrorCode {
+  Success = 0,
+  NullPointer = 1,
+  InvalidUtf8 = 2,
+  InvalidHex = 3,
+  InvalidBase64 = 4,
+  InvalidSecretKey = 5,
+  InvalidPublicKey = 6,
+  InvalidEnvelope = 7,
+  EncryptionFailed = 8,
+  DecryptionFailed = 9,
+  Expired = 10,
+  Replayed = 11,
+  MissingMessageId = 12,
+  CompressionFailed = 13,
+  InvalidSignature = 14,
+  MissingSignature = 15,
+  UnsupportedVersion = 16,
+  UnsupportedSuite = 17,
+  InvalidJson = 18,
+  InvalidCbor = 19,
+  InvalidProtobuf = 20,
+  InvalidMsgpack = 21,
+  InvalidArmor = 22,
+  InvalidBase45 = 23,
+  InvalidUr = 24,
+  IncompleteUr = 25,
+  InvalidArgument = 26,
+  InvalidChunk = 27,
+  IncompleteChunks = 28,
+  InvalidJwe = 29,
+  InvalidCose = 30,
+  InvalidAge = 31,
+  InvalidAgeKey = 32,
+  InvalidCertificate = 33,
+  UnsupportedCurve = 34,
+  InvalidSshKey = 35,
+  InvalidOpenPgp = 36,
+  NonCanonicalEnvelope = 37,
+  InvalidStream = 38,
+  TruncatedStream = 39,
+  IoFailed = 40,
+  Cancelled = 41,
+  InvalidUtf16 = 42,
+  InvalidAttestation = 43,
+  UnknownKey = 44,
+  InvalidKeyLength = 45,
+  InvalidCurvePoint = 46,
+  MacFailed = 47,
+  BufferTooSmall = 48,
+  RngFailure = 49,
+  PolicyViolation = 50,
+} EciesErrorCode;
+
+typedef enum EciesJobStatus {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+  UnknownJob = 3,
+} EciesJobStatus;
+
+typedef enum EciesJweAlgorithm {
+  EcdhEs = 0,
+  EcdhEsA256Kw = 1,
+} EciesJweAlgorithm;
+
+typedef enum EciesKeyEncoding {
+  Hex = 0,
+  Base64 = 1,
+} EciesKeyEncoding;
+
+typedef enum EciesPublicKeyFormat {
+  Compressed = 0,
+  Uncompressed = 1,
+  Ethereum = 2,
+} EciesPublicKeyFormat;
+
+typedef struct EciesAgent EciesAgent;
+
+typedef struct EciesCancelToken EciesCancelToken;
+
+typedef struct EciesDecryptor EciesDecryptor;
+
+typedef struct EciesEncryptor EciesEncryptor;
+
+typedef struct EciesNetBytes EciesNetBytes;
+
+typedef struct EciesUrDecoder EciesUrDecoder;
+
+typedef struct EciesUrEncoder EciesUrEncoder;
+
+typedef struct EciesBuffer {
+  uint8_t *data;
+  uintptr_t length;
+} EciesBuffer;
+
+typedef struct EciesAuditEvent {
+  enum EciesAuditOperation operation;
+  uint8_t key_fingerprint[ECIES_AUDIT_FINGERPRINT_LENGTH];
+  bool has_key_fingerprint;
+  uintptr_t input_length;
+  uintptr_t output_length;
+  enum EciesErrorCode result;
+} EciesAuditEvent;
+
+typedef void (*EciesAuditCallback)(void *context, const struct EciesAuditEvent *event);
+
+typedef struct EciesEncryptOptions {
+  bool embed_timestamp;
+  uint64_t ttl_seconds;
+  bool embed_message_id;
+  bool compress;
+  const char *sender_secret_key_ptr;
+  enum EciesBase64Flavor base64_flavor;
+  uint8_t suite;
+  const char *metadata_json_ptr;
+  const char *key_id_ptr;
+} EciesEncryptOptions;
+
+typedef bool (*EciesSeenBeforeCallback)(void *context, const uint8_t *message_id_ptr, uintptr_t message_id_length);
+
+typedef struct EciesDecryptOptions {
+  bool reject_expired;
+  EciesSeenBeforeCallback seen_before;
+  void *seen_before_context;
+  bool require_signature;
+} EciesDecryptOptions;
+
+typedef struct EciesComponents {
+  struct EciesBuffer header;
+  struct EciesBuffer ephemeral_public_key;
+  struct EciesBuffer nonce;
+  struct EciesBuffer body;
+  struct EciesBuffer tag;
+  struct EciesBuffer signature;
+} EciesComponents;
 
+typedef void (*EciesProgressCallback)(void *context, uint64_t processed, uint64_t total);
+
+typedef struct EciesProgressOptions {
+  EciesProgressCallback callback;
+  void *callback_context;
+  const struct EciesCancelToken *cancel_token;
+} EciesProgressOptions;
+
+typedef void (*EciesCompletionCallback)(void *context, enum EciesErrorCode error);
+
+typedef bool (*EciesWriteCallback)(void *context, const uint8_t *data_ptr, uintptr_t data_length);
+
+typedef intptr_t (*EciesReadCallback)(void *context, uint8_t *buffer_ptr, uintptr_t buffer_length);
+
+typedef void (*EciesJobCallback)(void *context, uint64_t job_id, const char *result_ptr, enum EciesErrorCode error);
+
+typedef struct EciesNetKeypair {
+  uint8_t secret_key[ECIES_NET_SECRET_KEY_LENGTH];
+  uint8_t public_key[ECIES_NET_PUBLIC_KEY_LENGTH];
+} EciesNetKeypair;
+
+typedef struct EciesNetPublicKey {
+  uint8_t bytes[ECIES_NET_PUBLIC_KEY_LENGTH];
+} EciesNetPublicKey;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Generates a new secret key using the libsecp256k1 library  It returns the hexadecimal representation of the serialized secret key as a C string.
+ */
+ECIES_API
 const char *ecies_generate_secret_key(void);
 
+/*
+ Generates a public key from the given secret key It takes a secret key as a C string and returns the corresponding public key as a C string.  Steps:       - Convert the secret key from a C string to a Rust string,       - Decode the hexadecimal (or base64) representation of the secret key, and then       - Generate the public key from the secret key. Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
+ */
+ECIES_API
 const char *ecies_public_key_from(const char *secret_key_ptr);
 
-const char *ecies_encrypt(const char *public_key_ptr, const char *message_ptr);
+/*
+ Encrypts a message using the provided public key. It takes a public key and a message as C strings and returns the encrypted message as a base64-encoded envelope C string.  Steps:      - Convert the public key from a C string to a Rust string,       - Decode the hexadecimal (or base64) representation of the public key,       - Encrypt the the message using ecies encryption into a versioned envelope (see `envelope.rs`), encoded in the same buffer Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
+ */
+ECIES_API
+const char *ecies_encrypt(const char *public_key_ptr,
+                          const char *message_ptr);
+
+/*
+ Decrypts a message using the provided secret key. It takes a secret key and a message as C string and returns the decrypted message as a C string.  Steps:      - Convert the private key and encrypted message from C strings to Rust strings       - Decode the hexadecimal (or base64) representation of the private key,       - Decrypt the message using ecies decryption. Both envelopes and legacy ciphertexts (raw base64 ecies payloads) are accepted, base64-encoded or ASCII-armored Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
+ */
+ECIES_API
+const char *ecies_decrypt(const char *secret_key_ptr,
+                          const char *message_ptr);
+
+/*
+ Fills the `length` bytes at `out` with cryptographically secure random bytes. It fails with `RngFailure`, leaving the buffer zeroed, once the random number generator failed its health tests; a zero `length` only checks them.
+ */
+ECIES_API
+bool ecies_random_bytes(uint8_t *out,
+                        uintptr_t length,
+                        enum EciesErrorCode *error_ptr);
+
+/*
+ Releases a buffer returned by this library. Passing a null buffer is a no-op.
+ */
+ECIES_API void ecies_buffer_free(struct EciesBuffer buffer);
+
+/*
+ Releases a C string returned by this library. Passing null is a no-op.
+ */
+ECIES_API void ecies_string_free(const char *string_ptr);
+
+/*
+ Releases an array of C strings returned by this library, together with every string in it.
+ */
+ECIES_API void ecies_string_array_free(const char **strings_ptr, uintptr_t count);
+
+/*
+ Builds the shared secp256k1 tables and detects the CPU features up front. Calling it is optional and repeated calls are no-ops; hosts call it at startup to move the one-time setup off the latency of their first operation, and hosts that want no lazy initialization at all (see the `go` profile) call it before anything else.
+ */
+ECIES_API
+void ecies_init(void);
+
+/*
+ Installs the audit callback, called with `context` and the event of every key generation, encryption and decryption, or removes it when `callback` is null. The callback must not call back into the library, and `context` must stay valid until the callback is replaced or removed.
+ */
+ECIES_API
+void ecies_set_audit_hook(EciesAuditCallback callback,
+                          void *context);
+
+/*
+ Returns the counters of the key generations, encryptions and decryptions since the library was loaded or `ecies_reset_metrics` was called, as a JSON C string to release with `ecies_string_free`.
+ */
+ECIES_API
+const char *ecies_get_metrics(enum EciesErrorCode *error_ptr);
+
+/*
+ Sets every counter back to zero, e.g. after each telemetry upload.
+ */
+ECIES_API void ecies_reset_metrics(void);
+
+/*
+ Sets the algorithm policy of the process from its `EciesPolicy` value, or fails with `InvalidArgument` for any other value, returning whether it was set. Under `Fips`, the algorithms outside the FIPS 140-3 approved set (secp256k1 key agreement, X25519, XSalsa20, ChaCha20 and the XOR scheme of SEC 1) fail with `PolicyViolation` from the next call on, leaving the P-256 functions.
+ */
+ECIES_API
+bool ecies_set_policy(uint32_t policy,
+                      enum EciesErrorCode *error_ptr);
+
+/*
+ Reports whether a feature is available in this build on this device:     - "hw-aes": AES-GCM runs on the CPU's AES instructions     - "hw-sha": SHA-256 runs on the CPU's SHA instructions     - "simd": base64 and hex use SIMD encoders (the `simd` build feature) Unknown names, and null, report false.
+ */
+ECIES_API
+bool ecies_has_feature(const char *feature_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key. It takes a public key and a message as C strings, plus optional encryption options, and returns the envelope as a base64-encoded C string, or null on failure with the reason written into `error_ptr`. Steps:      - Parse the hexadecimal public key,      - Build the header from the options,      - Encrypt the message, authenticating the header as associated data
+ */
+ECIES_API
+const char *ecies_encrypt_with_options(const char *public_key_ptr,
+                                       const char *message_ptr,
+                                       const struct EciesEncryptOptions *options_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a binary message into an envelope using the provided public key. Works like `ecies_encrypt_with_options`, for messages given as `message_length` bytes that may contain NUL bytes, such as `NSData` or byte array contents.
+ */
+ECIES_API
+const char *ecies_encrypt_bytes(const char *public_key_ptr,
+                                const uint8_t *message_ptr,
+                                uintptr_t message_length,
+                                const struct EciesEncryptOptions *options_ptr,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an envelope using the provided secret key. It takes a secret key and a base64-encoded (or ASCII-armored) envelope as C strings, plus optional decryption options, and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`. Steps:      - Parse the hexadecimal secret key and decode the base64 envelope,      - Verify the sender signature, if any, and decrypt the payload, verifying the header,      - Reject the message if it has expired and the options ask for it,      - Ask the host whether the message ID was seen before. This happens after decryption so only authenticated        IDs ever reach the host's registry
+ */
+ECIES_API
+const char *ecies_decrypt_with_options(const char *secret_key_ptr,
+                                       const char *message_ptr,
+                                       const struct EciesDecryptOptions *options_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an envelope into a binary message using the provided secret key. Works like `ecies_decrypt_with_options`, returning the message as a buffer to release with `ecies_buffer_free`, so messages containing NUL bytes come back whole.
+ */
+ECIES_API
+struct EciesBuffer ecies_decrypt_bytes(const char *secret_key_ptr,
+                                       const char *message_ptr,
+                                       const struct EciesDecryptOptions *options_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a signed envelope using the provided secret key. Works like `ecies_decrypt_with_options`, but always requires a valid sender signature and writes the verified sender public key (hexadecimal, compressed) into `sender_public_key_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_signed(const char *secret_key_ptr,
+                                 const char *message_ptr,
+                                 const struct EciesDecryptOptions *options_ptr,
+                                 const char **sender_public_key_ptr,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an envelope using the provided secret key. Works like `ecies_decrypt_with_options`, and writes the metadata stored at encryption into `metadata_ptr` as a JSON object C string, empty when the envelope has none.
+ */
+ECIES_API
+const char *ecies_decrypt_with_metadata(const char *secret_key_ptr,
+                                        const char *message_ptr,
+                                        const struct EciesDecryptOptions *options_ptr,
+                                        const char **metadata_ptr,
+                                        enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message using the provided public key. It takes a public key and a message as C strings and returns the ciphertext as an eth-sig-util style JSON C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_encrypt_json(const char *public_key_ptr,
+                               const char *message_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an eth-sig-util style JSON ciphertext using the provided secret key. It takes a secret key and the JSON as C strings and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_json(const char *secret_key_ptr,
+                               const char *json_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts the named fields of a JSON document using the provided public key. It takes a public key, the JSON document and an array of `path_count` JSON pointers as C strings, and returns the document with each named value replaced by its envelope, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_encrypt_json_fields(const char *public_key_ptr,
+                                      const char *json_ptr,
+                                      const char *const *paths_ptr,
+                                      uintptr_t path_count,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the named fields of a JSON document produced by `ecies_encrypt_json_fields`. It takes a secret key, the JSON document and an array of `path_count` JSON pointers as C strings, and returns the document with the original values restored, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_json_fields(const char *secret_key_ptr,
+                                      const char *json_ptr,
+                                      const char *const *paths_ptr,
+                                      uintptr_t path_count,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an eth-sig-util `encrypt()` payload using a MetaMask-format encryption private key. It takes the hex private key and the JSON (or its `0x`-prefixed hex encoding) as C strings and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_eth_sig_util(const char *private_key_ptr,
+                                       const char *json_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the encryption public key of a MetaMask-format encryption private key, base64 encoded like the result of `eth_getEncryptionPublicKey`, for dapps that encrypt to this account.
+ */
+ECIES_API
+const char *ecies_eth_encryption_public_key(const char *private_key_ptr,
+                                            enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns its CBOR encoding. Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_cbor(const char *public_key_ptr,
+                                      const char *message_ptr,
+                                      const struct EciesEncryptOptions *options_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a CBOR-encoded envelope using the provided secret key. Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_cbor(const char *secret_key_ptr,
+                               const uint8_t *cbor_ptr,
+                               uintptr_t cbor_length,
+                               const struct EciesDecryptOptions *options_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns its protobuf encoding. Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_protobuf(const char *public_key_ptr,
+                                          const char *message_ptr,
+                                          const struct EciesEncryptOptions *options_ptr,
+                                          enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a protobuf-encoded envelope using the provided secret key. Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_protobuf(const char *secret_key_ptr,
+                                   const uint8_t *protobuf_ptr,
+                                   uintptr_t protobuf_length,
+                                   const struct EciesDecryptOptions *options_ptr,
+                                   enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns its MessagePack encoding. Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_msgpack(const char *public_key_ptr,
+                                         const char *message_ptr,
+                                         const struct EciesEncryptOptions *options_ptr,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a MessagePack-encoded envelope using the provided secret key. Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_msgpack(const char *secret_key_ptr,
+                                  const uint8_t *msgpack_ptr,
+                                  uintptr_t msgpack_length,
+                                  const struct EciesDecryptOptions *options_ptr,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns it ASCII-armored. Takes the same options as `ecies_encrypt_with_options`. Armored ciphertexts are accepted by `ecies_decrypt` and `ecies_decrypt_with_options`.
+ */
+ECIES_API
+const char *ecies_encrypt_armored(const char *public_key_ptr,
+                                  const char *message_ptr,
+                                  const struct EciesEncryptOptions *options_ptr,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns it as a lowercase hex C string. Takes the same options as `ecies_encrypt_with_options`; `base64_flavor` is ignored.
+ */
+ECIES_API
+const char *ecies_encrypt_hex(const char *public_key_ptr,
+                              const char *message_ptr,
+                              const struct EciesEncryptOptions *options_ptr,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a hex-encoded envelope (or legacy ciphertext) using the provided secret key. Takes the same options as `ecies_decrypt_with_options`. Upper and lower case hex are both accepted.
+ */
+ECIES_API
+const char *ecies_decrypt_hex(const char *secret_key_ptr,
+                              const char *message_ptr,
+                              const struct EciesDecryptOptions *options_ptr,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into an envelope using the provided public key and returns it as a base45 C string, ready to be placed in an alphanumeric-mode QR code. Takes the same options as `ecies_encrypt_with_options`.
+ */
+ECIES_API
+const char *ecies_encrypt_base45(const char *public_key_ptr,
+                                 const char *message_ptr,
+                                 const struct EciesEncryptOptions *options_ptr,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a base45-encoded envelope using the provided secret key. Takes the same options as `ecies_decrypt_with_options`.
+ */
+ECIES_API
+const char *ecies_decrypt_base45(const char *secret_key_ptr,
+                                 const char *message_ptr,
+                                 const struct EciesDecryptOptions *options_ptr,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Converts a public key between representations. It takes a public key in any supported layout and encoding, and returns it in the requested layout and encoding, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_convert_public_key(const char *public_key_ptr,
+                                     enum EciesPublicKeyFormat format,
+                                     enum EciesKeyEncoding encoding,
+                                     enum EciesErrorCode *error_ptr);
+
+/*
+ Derives the public key of a secret key, like `ecies_public_key_from`. Returns the compressed public key as a hex C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_derive_public_key(const char *secret_key_ptr,
+                                    enum EciesErrorCode *error_ptr);
+
+/*
+ Detects the encoding of a secret or public key, as the key-accepting functions do. It writes `Hex` or `Base64` into `encoding_ptr` and returns true when the key decodes to a valid secret key (32 bytes) or public key (33, 64 or 65 bytes), false otherwise with the reason written into `error_ptr`.
+ */
+ECIES_API
+bool ecies_detect_key_encoding(const char *key_ptr,
+                               enum EciesKeyEncoding *encoding_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Generates a random version 4 UUID, returned in its hyphenated lowercase form, e.g. `3f2b8c1e-9a4d-4e7f-b5c6-0d1e2f3a4b5c`. It fails with `RngFailure` once the random number generator failed its health tests.
+ */
+ECIES_API
+const char *ecies_generate_uuid(enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the key ID of a public key (hex or base64, compressed or uncompressed): 16 lowercase hex characters derived from its SHA-256, the same for the key on every platform.
+ */
+ECIES_API
+const char *ecies_key_id(const char *public_key_ptr,
+                         enum EciesErrorCode *error_ptr);
+
+/*
+ Creates an encoder for the given bytes, split into fragments of at most `max_fragment_length` bytes. Returns null on failure. Release with `ecies_ur_encoder_free`.
+ */
+ECIES_API
+struct EciesUrEncoder *ecies_ur_encoder_new(const uint8_t *data_ptr,
+                                            uintptr_t data_length,
+                                            uintptr_t max_fragment_length,
+                                            enum EciesErrorCode *error_ptr);
+
+/*
+ Number of parts needed to carry the data without fountain coding, useful for progress indicators
+ */
+ECIES_API uintptr_t ecies_ur_encoder_fragment_count(const struct EciesUrEncoder *encoder_ptr);
+
+/*
+ Returns the next UR part as a C string, to be shown as the next QR frame.
+ */
+ECIES_API const char *ecies_ur_encoder_next_part(struct EciesUrEncoder *encoder_ptr, enum EciesErrorCode *error_ptr);
+
+ECIES_API void ecies_ur_encoder_free(struct EciesUrEncoder *encoder_ptr);
+
+/*
+ Creates a decoder that accumulates scanned UR parts. Release with `ecies_ur_decoder_free`.
+ */
+ECIES_API struct EciesUrDecoder *ecies_ur_decoder_new(void);
+
+/*
+ Feeds one scanned part to the decoder. Parts may arrive in any order and duplicates are ignored. Returns true once the data is complete.
+ */
+ECIES_API
+bool ecies_ur_decoder_receive(struct EciesUrDecoder *decoder_ptr,
+                              const char *part_ptr,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the reassembled data once `ecies_ur_decoder_receive` has reported completion. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_ur_decoder_message(const struct EciesUrDecoder *decoder_ptr,
+                                            enum EciesErrorCode *error_ptr);
+
+ECIES_API void ecies_ur_decoder_free(struct EciesUrDecoder *decoder_ptr);
+
+/*
+ Splits a ciphertext string (e.g. the output of `ecies_encrypt`) into chunks of at most `max_chunk_length` characters. Returns an array of `*chunk_count_ptr` C strings, to be released with `ecies_string_array_free`, or null on failure.
+ */
+ECIES_API
+const char **ecies_split_envelope(const char *ciphertext_ptr,
+                                  uintptr_t max_chunk_length,
+                                  uintptr_t *chunk_count_ptr,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Reassembles the ciphertext string from its chunks, given in any order. Fails with `EciesErrorCode::InvalidChunk` on a corrupted or foreign chunk, and `EciesErrorCode::IncompleteChunks` while chunks are still missing.
+ */
+ECIES_API
+const char *ecies_join_chunks(const char *const *chunks_ptr,
+                              uintptr_t chunk_count,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message for the provided public key as a compact JWE C string, using the given key management algorithm. Returns null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_encrypt_jwe(const char *public_key_ptr,
+                              const char *message_ptr,
+                              enum EciesJweAlgorithm algorithm,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a compact JWE C string (ECDH-ES or ECDH-ES+A256KW with A256GCM on secp256k1) using the provided secret key. Returns the decrypted message as a C string, or null on failure.
+ */
+ECIES_API
+const char *ecies_decrypt_jwe(const char *secret_key_ptr,
+                              const char *jwe_ptr,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message for the provided public key as a tagged COSE_Encrypt or COSE_Encrypt0 message. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_cose(const char *public_key_ptr,
+                                      const char *message_ptr,
+                                      enum EciesCoseStructure structure,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a tagged COSE_Encrypt or COSE_Encrypt0 message produced with ECDH-ES + HKDF-256 and A256GCM. Returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_decrypt_cose(const char *secret_key_ptr,
+                               const uint8_t *cose_ptr,
+                               uintptr_t cose_length,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Generates a new age identity. It returns the `AGE-SECRET-KEY-1...` string, the matching recipient can be derived from it with `ecies_age_recipient_from`.
+ */
+ECIES_API
+const char *ecies_age_generate_identity(enum EciesErrorCode *error_ptr);
+
+/*
+ Derives the `age1...` recipient of an `AGE-SECRET-KEY-1...` identity, or the ssh-ed25519 public key line of an OpenSSH private key.
+ */
+ECIES_API
+const char *ecies_age_recipient_from(const char *identity_ptr,
+                                     enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts data into a binary age file. It takes:     - the recipients, one or more `age1...` strings separated by whitespace or commas, or ssh-ed25519 public key       lines, one per line     - the data to encrypt and its length The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_age(const char *recipients_ptr,
+                                     const uint8_t *data_ptr,
+                                     uintptr_t data_length,
+                                     enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a binary age file with an `AGE-SECRET-KEY-1...` identity or an unencrypted OpenSSH ed25519 private key. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_decrypt_age(const char *identity_ptr,
+                                     const uint8_t *age_ptr,
+                                     uintptr_t age_length,
+                                     enum EciesErrorCode *error_ptr);
+
+/*
+ Generates a new X25519 secret key for sealed boxes, returned as hex.
+ */
+ECIES_API const char *ecies_sealed_box_generate_secret_key(enum EciesErrorCode *error_ptr);
+
+/*
+ Derives the hex X25519 public key of a hex sealed box secret key.
+ */
+ECIES_API const char *ecies_sealed_box_public_key_from(const char *secret_key_ptr, enum EciesErrorCode *error_ptr);
+
+/*
+ Seals data for a hex X25519 public key, like `crypto_box_seal`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_sealed_box_seal(const char *public_key_ptr,
+                                         const uint8_t *data_ptr,
+                                         uintptr_t data_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Opens a sealed box with a hex X25519 secret key, like `crypto_box_seal_open`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_sealed_box_open(const char *secret_key_ptr,
+                                         const uint8_t *sealed_ptr,
+                                         uintptr_t sealed_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Describes a ciphertext without decrypting it. It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns its metadata as a JSON C string, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_inspect(const char *ciphertext_ptr,
+                          enum EciesErrorCode *error_ptr);
+
+/*
+ Splits a ciphertext into its components. It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns its parts. On failure every buffer is null and the reason is written into `error_ptr`. The result must be released with `ecies_components_free`.
+ */
+ECIES_API
+struct EciesComponents ecies_split_components(const char *ciphertext_ptr,
+                                              enum EciesErrorCode *error_ptr);
+
+/*
+ Reassembles a ciphertext from its components, the reverse of `ecies_split_components`. Empty or null `header` and `signature` buffers produce a legacy ciphertext. It returns the ciphertext as a base64 C string, as returned by `ecies_encrypt`, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char *ecies_join_components(const struct EciesComponents *components_ptr,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Releases the buffers of components returned by `ecies_split_components`.
+ */
+ECIES_API void ecies_components_free(struct EciesComponents components);
+
+/*
+ Returns the canonical binary form of a ciphertext given in any text form accepted by `ecies_decrypt`. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_canonicalize(const char *ciphertext_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the SHA-256 digest of the canonical form of a ciphertext, as a hexadecimal C string, or null on failure with the reason written into `error_ptr`. Equal envelopes have equal digests whatever text form they came in.
+ */
+ECIES_API
+const char *ecies_envelope_digest(const char *ciphertext_ptr,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Migrates a ciphertext to another format version. It takes:     - the base64 or armored ciphertext, as accepted by `ecies_decrypt`     - the version it is expected to have (0 for legacy ciphertexts) and the version to produce (1)     - the hexadecimal secret key able to decrypt it     - optionally the hexadecimal public key to encrypt it for instead, null to keep the same recipient It returns the migrated ciphertext as a base64 C string, or null on failure with the reason written into `error_ptr`. A ciphertext of another version than `from_version` fails with `UnsupportedVersion`.
+ */
+ECIES_API
+const char *ecies_migrate(const char *ciphertext_ptr,
+                          uint8_t from_version,
+                          uint8_t to_version,
+                          const char *secret_key_ptr,
+                          const char *new_public_key_ptr,
+                          enum EciesErrorCode *error_ptr);
+
+/*
+ Migrates a list of ciphertexts, e.g. a page of database rows, parsing the keys only once. It takes the same arguments as `ecies_migrate`, with an array of `count` ciphertexts, and returns an array of `count` migrated ciphertexts to release with `ecies_string_array_free`. A ciphertext that fails to migrate does not stop the others: its entry is null, and when `error_codes_ptr` points to an array of `count` codes, the reason is written at its index. `error_ptr` only reports failures of the whole call, such as invalid keys.
+ */
+ECIES_API
+const char **ecies_migrate_all(const char *const *ciphertexts_ptr,
+                               uintptr_t count,
+                               uint8_t from_version,
+                               uint8_t to_version,
+                               const char *secret_key_ptr,
+                               const char *new_public_key_ptr,
+                               enum EciesErrorCode *error_codes_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Generates a new P-256 secret key, returned as hex.
+ */
+ECIES_API const char *ecies_p256_generate_secret_key(enum EciesErrorCode *error_ptr);
+
+/*
+ Derives the public key of a hex (or base64) P-256 secret key, returned as a hex uncompressed point like the P-256 keys of `ecies_public_key_from_x509` and `ecies_public_key_from_ssh`.
+ */
+ECIES_API
+const char *ecies_p256_public_key_from(const char *secret_key_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts data for a hex (or base64) P-256 public key, compressed or uncompressed. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_p256_encrypt(const char *public_key_ptr,
+                                      const uint8_t *data_ptr,
+                                      uintptr_t data_length,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the output of `ecies_p256_encrypt` with a hex (or base64) P-256 secret key. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_p256_decrypt(const char *secret_key_ptr,
+                                      const uint8_t *data_ptr,
+                                      uintptr_t data_length,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Extracts the public key of an X.509 certificate or a SubjectPublicKeyInfo. It takes the DER or PEM bytes and their length, and returns the key as a hexadecimal uncompressed point, writing its curve into `curve_ptr` when not null. Keys on other curves, or of other types, fail with `UnsupportedCurve`.
+ */
+ECIES_API
+const char *ecies_public_key_from_x509(const uint8_t *data_ptr,
+                                       uintptr_t data_length,
+                                       enum EciesCurve *curve_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Parses the Android key attestation extension of a certificate. It takes the DER or PEM bytes of the leaf certificate of an attested Keystore key and their length, and returns a JSON C string with the attestation and KeyMint versions and security levels (`software`, `trusted_environment` or `strongbox`), the hex attestation challenge, and the root of trust (verified boot key, device locked flag and verified boot state) when present. Certificates without the extension fail with `InvalidAttestation`. The certificate chain is not verified.
+ */
+ECIES_API
+const char *ecies_parse_key_attestation(const uint8_t *data_ptr,
+                                        uintptr_t data_length,
+                                        enum EciesErrorCode *error_ptr);
+
+/*
+ Reads the public key of an OpenSSH public key line, such as a line of `~/.ssh/id_ed25519.pub`. It returns the key as hex and writes its curve into `curve_ptr` when not null:     - ecdsa-sha2-nistp256 keys as an uncompressed P-256 point (`P256`), usable with `ecies_p256_encrypt`     - ssh-ed25519 keys converted to X25519 (`X25519`), usable with `ecies_sealed_box_seal`
+ */
+ECIES_API
+const char *ecies_public_key_from_ssh(const char *line_ptr,
+                                      enum EciesCurve *curve_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Converts an unencrypted OpenSSH ed25519 private key into the hex X25519 secret key matching `ecies_public_key_from_ssh`, usable with `ecies_sealed_box_open`.
+ */
+ECIES_API
+const char *ecies_secret_key_from_ssh(const char *private_key_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts data into an OpenPGP message. It takes:     - the recipient's OpenPGP public key, binary or armored, with a secp256k1 ECDH key or subkey     - the data to encrypt and its length It returns the binary message, as written by `gpg --encrypt`, to release with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_openpgp(const uint8_t *public_key_ptr,
+                                         uintptr_t public_key_length,
+                                         const uint8_t *data_ptr,
+                                         uintptr_t data_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an OpenPGP message. It takes:     - the recipient's unprotected OpenPGP secret key, binary or armored, with a secp256k1 ECDH key or subkey,       e.g. exported with `gpg --export-secret-subkeys` after removing the passphrase     - the binary or armored message and its length It returns the literal data, to release with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_decrypt_openpgp(const uint8_t *secret_key_ptr,
+                                         uintptr_t secret_key_length,
+                                         const uint8_t *message_ptr,
+                                         uintptr_t message_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Creates a cancellation token. Release it with `ecies_cancel_token_free` once no operation uses it anymore.
+ */
+ECIES_API struct EciesCancelToken *ecies_cancel_token_new(void);
+
+/*
+ Cancels the operations using the token. Safe to call from any thread, and more than once.
+ */
+ECIES_API void ecies_cancel(const struct EciesCancelToken *cancel_token_ptr);
+
+ECIES_API void ecies_cancel_token_free(struct EciesCancelToken *cancel_token_ptr);
+
+/*
+ Starts encrypting a stream for the given public key. Returns null on failure. Feed the message with `ecies_encrypt_update`, end it with `ecies_encrypt_final`, and release the handle with `ecies_encryptor_free`.
+ */
+ECIES_API
+struct EciesEncryptor *ecies_encrypt_init(const char *public_key_ptr,
+                                          enum EciesErrorCode *error_ptr);
+
+/*
+ Like `ecies_encrypt_init`, with chunks of `chunk_size` bytes instead of the default 64 KiB, between 1 byte and 1 MiB. Larger chunks lower the per-chunk overhead of 25 bytes; decryptors buffer up to one chunk.
+ */
+ECIES_API
+struct EciesEncryptor *ecies_encrypt_init_with_chunk_size(const char *public_key_ptr,
+                                                          uintptr_t chunk_size,
+                                                          enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts the next part of the message. Parts may have any size. Returns the stream bytes that became ready, which may be empty, to be written out in order. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_update(struct EciesEncryptor *encryptor_ptr,
+                                        const uint8_t *data_ptr,
+                                        uintptr_t data_length,
+                                        enum EciesErrorCode *error_ptr);
+
+/*
+ Ends the message, returning the last stream bytes. The encryptor accepts no further data afterwards. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_encrypt_final(struct EciesEncryptor *encryptor_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Releases an encryptor returned by `ecies_encrypt_init` or `ecies_encrypt_init_with_chunk_size`, clearing its stream key. The handle must not be used afterwards; null is ignored. Releasing an encryptor before `ecies_encrypt_final` abandons the stream.
+ */
+ECIES_API
+void ecies_encryptor_free(struct EciesEncryptor *encryptor_ptr);
+
+/*
+ Starts decrypting a stream produced by `ecies_encrypt_init` with the given secret key. Returns null on failure. Feed the stream with `ecies_decrypt_update`, check its end with `ecies_decrypt_final`, and release the handle with `ecies_decryptor_free`.
+ */
+ECIES_API
+struct EciesDecryptor *ecies_decrypt_init(const char *secret_key_ptr,
+                                          enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the next part of the stream. Parts may have any size. Returns the plaintext of every chunk completed and authenticated so far, which may be empty. After a failure the decryptor rejects further input. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_decrypt_update(struct EciesDecryptor *decryptor_ptr,
+                                        const uint8_t *data_ptr,
+                                        uintptr_t data_length,
+                                        enum EciesErrorCode *error_ptr);
+
+/*
+ Ends the stream, returning false with `TruncatedStream` if it stopped before its last chunk. Plaintext already returned by `ecies_decrypt_update` must not be trusted as complete until this returns true.
+ */
+ECIES_API
+bool ecies_decrypt_final(struct EciesDecryptor *decryptor_ptr,
+                         enum EciesErrorCode *error_ptr);
+
+/*
+ Releases a decryptor returned by `ecies_decrypt_init`, clearing its secret key and stream key. The handle must not be used afterwards; null is ignored.
+ */
+ECIES_API
+void ecies_decryptor_free(struct EciesDecryptor *decryptor_ptr);
+
+/*
+ Encrypts the file at `in_path` for the given public key into `out_path`, as a stream `ecies_decrypt_init` can read. Returns false on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+bool ecies_encrypt_file(const char *public_key_ptr,
+                        const char *in_path_ptr,
+                        const char *out_path_ptr,
+                        enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the stream in the file at `in_path` with the given secret key into `out_path`. Returns false on failure with the reason written into `error_ptr`, in which case `out_path` is removed.
+ */
+ECIES_API
+bool ecies_decrypt_file(const char *secret_key_ptr,
+                        const char *in_path_ptr,
+                        const char *out_path_ptr,
+                        enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts the file at `in_path` like `ecies_encrypt_file`, through memory mappings of the input and of the preallocated output. Intended for very large local files; the output is removed on failure.
+ */
+ECIES_API
+bool ecies_encrypt_file_mapped(const char *public_key_ptr,
+                               const char *in_path_ptr,
+                               const char *out_path_ptr,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts the file at `in_path` like `ecies_encrypt_file_mapped`, sealing chunks in parallel across all cores. The output is the same stream, assembled in order.
+ */
+ECIES_API
+bool ecies_encrypt_file_parallel(const char *public_key_ptr,
+                                 const char *in_path_ptr,
+                                 const char *out_path_ptr,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts everything readable from `fd_in` for the given public key into `fd_out`, e.g. descriptors from a ParcelFileDescriptor or a pipe. Both stay open and owned by the caller. Returns false on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+bool ecies_encrypt_fd(const char *public_key_ptr,
+                      int fd_in,
+                      int fd_out,
+                      enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the stream readable from `fd_in` with the given secret key into `fd_out`. Both stay open and owned by the caller. Returns false on failure with the reason written into `error_ptr`; plaintext written before the failure must be discarded.
+ */
+ECIES_API
+bool ecies_decrypt_fd(const char *secret_key_ptr,
+                      int fd_in,
+                      int fd_out,
+                      enum EciesErrorCode *error_ptr);
+
+/*
+ Variants of the file and descriptor functions above reporting progress through `options_ptr`, and failing with `Cancelled` once its cancel token is cancelled. Null options behave like the plain functions.
+ */
+ECIES_API
+bool ecies_encrypt_file_with_progress(const char *public_key_ptr,
+                                      const char *in_path_ptr,
+                                      const char *out_path_ptr,
+                                      const struct EciesProgressOptions *options_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+ECIES_API
+bool ecies_decrypt_file_with_progress(const char *secret_key_ptr,
+                                      const char *in_path_ptr,
+                                      const char *out_path_ptr,
+                                      const struct EciesProgressOptions *options_ptr,
+                                      enum EciesErrorCode *error_ptr);
+
+ECIES_API
+bool ecies_encrypt_file_mapped_with_progress(const char *public_key_ptr,
+                                             const char *in_path_ptr,
+                                             const char *out_path_ptr,
+                                             const struct EciesProgressOptions *options_ptr,
+                                             enum EciesErrorCode *error_ptr);
+
+ECIES_API
+bool ecies_encrypt_fd_with_progress(const char *public_key_ptr,
+                                    int fd_in,
+                                    int fd_out,
+                                    const struct EciesProgressOptions *options_ptr,
+                                    enum EciesErrorCode *error_ptr);
+
+ECIES_API
+bool ecies_decrypt_fd_with_progress(const char *secret_key_ptr,
+                                    int fd_in,
+                                    int fd_out,
+                                    const struct EciesProgressOptions *options_ptr,
+                                    enum EciesErrorCode *error_ptr);
+
+ECIES_API
+bool ecies_encrypt_file_parallel_with_progress(const char *public_key_ptr,
+                                               const char *in_path_ptr,
+                                               const char *out_path_ptr,
+                                               const struct EciesProgressOptions *options_ptr,
+                                               enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a file like `ecies_encrypt_file_with_progress`, on a background thread. Returns true once the operation has started, and `completion` later receives its outcome; returns false, without calling `completion`, when the arguments are invalid or no thread could be started. `options_ptr` may be null.
+ */
+ECIES_API
+bool ecies_encrypt_file_async(const char *public_key_ptr,
+                              const char *in_path_ptr,
+                              const char *out_path_ptr,
+                              const struct EciesProgressOptions *options_ptr,
+                              EciesCompletionCallback completion,
+                              void *completion_context,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a file like `ecies_decrypt_file_with_progress`, on a background thread, reporting like `ecies_encrypt_file_async`.
+ */
+ECIES_API
+bool ecies_decrypt_file_async(const char *secret_key_ptr,
+                              const char *in_path_ptr,
+                              const char *out_path_ptr,
+                              const struct EciesProgressOptions *options_ptr,
+                              EciesCompletionCallback completion,
+                              void *completion_context,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a file like `ecies_encrypt_file_parallel_with_progress`, on a background thread, reporting like `ecies_encrypt_file_async`. The chunks are still sealed on all cores.
+ */
+ECIES_API
+bool ecies_encrypt_file_parallel_async(const char *public_key_ptr,
+                                       const char *in_path_ptr,
+                                       const char *out_path_ptr,
+                                       const struct EciesProgressOptions *options_ptr,
+                                       EciesCompletionCallback completion,
+                                       void *completion_context,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts data for the given public key as a chunked stream, handing each frame to `write_callback` instead of returning a buffer. Returns false on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+bool ecies_encrypt_to_callback(const char *public_key_ptr,
+                               const uint8_t *data_ptr,
+                               uintptr_t data_length,
+                               EciesWriteCallback write_callback,
+                               void *write_context,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a chunked stream with the given secret key, handing the plaintext of each authenticated chunk to `write_callback`. Returns false on failure with the reason written into `error_ptr`; plaintext already handed over must then be discarded.
+ */
+ECIES_API
+bool ecies_decrypt_to_callback(const char *secret_key_ptr,
+                               const uint8_t *stream_ptr,
+                               uintptr_t stream_length,
+                               EciesWriteCallback write_callback,
+                               void *write_context,
+                               enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts the input pulled from `read_callback` until it reports the end, handing each frame of the chunked stream to `write_callback`. Memory use is bounded by a chunk whatever the input size. Returns false on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+bool ecies_encrypt_from_callback(const char *public_key_ptr,
+                                 EciesReadCallback read_callback,
+                                 void *read_context,
+                                 EciesWriteCallback write_callback,
+                                 void *write_context,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts the chunked stream pulled from `read_callback`, handing the plaintext of each authenticated chunk to `write_callback`. Returns false on failure with the reason written into `error_ptr`, including `TruncatedStream` when the input ends early; plaintext already handed over must then be discarded.
+ */
+ECIES_API
+bool ecies_decrypt_from_callback(const char *secret_key_ptr,
+                                 EciesReadCallback read_callback,
+                                 void *read_context,
+                                 EciesWriteCallback write_callback,
+                                 void *write_context,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Queues the encryption of a message for the given public key, like `ecies_encrypt`. Returns the job ID, or 0 on failure with the reason written into `error_ptr`. `callback` may be null to collect the result with `ecies_job_poll` instead.
+ */
+ECIES_API
+uint64_t ecies_submit_encrypt(const char *public_key_ptr,
+                              const char *message_ptr,
+                              EciesJobCallback callback,
+                              void *callback_context,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Queues the decryption of a message with the given secret key, like `ecies_decrypt`. Returns the job ID, or 0 on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+uint64_t ecies_submit_decrypt(const char *secret_key_ptr,
+                              const char *message_ptr,
+                              EciesJobCallback callback,
+                              void *callback_context,
+                              enum EciesErrorCode *error_ptr);
+
+/*
+ Queues the encryption of a message given as `message_length` bytes, which may contain NUL bytes, like `ecies_encrypt_bytes`. Otherwise works like `ecies_submit_encrypt`.
+ */
+ECIES_API
+uint64_t ecies_submit_encrypt_bytes(const char *public_key_ptr,
+                                    const uint8_t *message_ptr,
+                                    uintptr_t message_length,
+                                    EciesJobCallback callback,
+                                    void *callback_context,
+                                    enum EciesErrorCode *error_ptr);
+
+/*
+ Queues the decryption of a ciphertext given as `message_length` bytes of base64 or armored text. Otherwise works like `ecies_submit_decrypt`.
+ */
+ECIES_API
+uint64_t ecies_submit_decrypt_bytes(const char *secret_key_ptr,
+                                    const uint8_t *message_ptr,
+                                    uintptr_t message_length,
+                                    EciesJobCallback callback,
+                                    void *callback_context,
+                                    enum EciesErrorCode *error_ptr);
+
+/*
+ Checks on a job submitted without a completion callback.     - Pending: still queued or running     - Completed: the result C string is written into `result_ptr`, owned by the caller, or discarded when       `result_ptr` is null     - Failed: the job's error code is written into `error_ptr`     - UnknownJob: no such job, or its outcome was already collected Completed and failed jobs are forgotten once reported.
+ */
+ECIES_API
+enum EciesJobStatus ecies_job_poll(uint64_t job_id,
+                                   const char **result_ptr,
+                                   enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts an array of `count` messages for the given public key, like `ecies_encrypt` on each. Returns an array of `count` ciphertexts in the same order, to release with `ecies_string_array_free`, or null on failure with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char **ecies_encrypt_batch(const char *public_key_ptr,
+                                 const char *const *messages_ptr,
+                                 uintptr_t count,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts an array of `count` messages for each of `public_key_count` recipients. Returns an array of `count * public_key_count` ciphertexts, grouped by message: the ciphertext of message `i` for recipient `j` is at index `i * public_key_count + j`. Release it with `ecies_string_array_free`.
+ */
+ECIES_API
+const char **ecies_encrypt_batch_multi(const char *const *public_keys_ptr,
+                                       uintptr_t public_key_count,
+                                       const char *const *messages_ptr,
+                                       uintptr_t count,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts an array of `count` ciphertexts with the given secret key, like `ecies_decrypt` on each. A failing item does not fail the batch: its entry in the returned array is null and, when `statuses_ptr` points to an array of `count` error codes, its code is written at the same index (`Success` for the items that decrypted). The returned array is released with `ecies_string_array_free`. Null is only returned when the secret key or the array itself is invalid, with the reason written into `error_ptr`.
+ */
+ECIES_API
+const char **ecies_decrypt_batch(const char *secret_key_ptr,
+                                 const char *const *ciphertexts_ptr,
+                                 uintptr_t count,
+                                 enum EciesErrorCode *statuses_ptr,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Generates the compressed hex public key of a secret key, like `ecies_derive_public_key`.
+ */
+ECIES_API
+const uint16_t *ecies_public_key_from_w(const uint16_t *secret_key_ptr,
+                                        uintptr_t secret_key_length,
+                                        enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into a base64 envelope, like `ecies_encrypt`.
+ */
+ECIES_API
+const uint16_t *ecies_encrypt_w(const uint16_t *public_key_ptr,
+                                uintptr_t public_key_length,
+                                const uint16_t *message_ptr,
+                                uintptr_t message_length,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a base64 or armored envelope (or legacy ciphertext), like `ecies_decrypt`. The decrypted message must be UTF-8 text, otherwise `InvalidUtf8` is reported.
+ */
+ECIES_API
+const uint16_t *ecies_decrypt_w(const uint16_t *secret_key_ptr,
+                                uintptr_t secret_key_length,
+                                const uint16_t *message_ptr,
+                                uintptr_t message_length,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Releases a UTF-16 string returned by the `_w` functions. Passing null is a no-op.
+ */
+ECIES_API void ecies_wide_string_free(const uint16_t *string_ptr);
+
+/*
+ Generates a keypair into caller-provided memory: 32 bytes at `secret_key_out` and 33 bytes (compressed) at `public_key_out`. Returns false on failure.
+ */
+ECIES_API
+bool ecies_script_generate_keypair(uint8_t *secret_key_out,
+                                   uint8_t *public_key_out,
+                                   enum EciesErrorCode *error_ptr);
+
+/*
+ Writes the 33-byte compressed public key of a 32-byte secret key to `public_key_out`. Returns false on failure.
+ */
+ECIES_API
+bool ecies_script_public_key_from(const uint8_t *secret_key_ptr,
+                                  uintptr_t secret_key_length,
+                                  uint8_t *public_key_out,
+                                  enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into a binary envelope. Returns null on failure; release the result with `ecies_script_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer *ecies_script_encrypt(const uint8_t *public_key_ptr,
+                                         uintptr_t public_key_length,
+                                         const uint8_t *message_ptr,
+                                         uintptr_t message_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a binary envelope, or a legacy ciphertext. Returns null on failure; release the result with `ecies_script_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer *ecies_script_decrypt(const uint8_t *secret_key_ptr,
+                                         uintptr_t secret_key_length,
+                                         const uint8_t *ciphertext_ptr,
+                                         uintptr_t ciphertext_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the address of the bytes of a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions, null for null.
+ */
+ECIES_API
+const uint8_t *ecies_script_buffer_data(const struct EciesBuffer *buffer);
+
+/*
+ Returns the length of a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions, 0 for null.
+ */
+ECIES_API uintptr_t ecies_script_buffer_length(const struct EciesBuffer *buffer);
+
+/*
+ Releases a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions. Passing null is a no-op.
+ */
+ECIES_API void ecies_script_buffer_free(struct EciesBuffer *buffer);
+
+/*
+ Builds encryption options from their fields (see `EciesEncryptOptions`), copying the strings, which may be null. The result is accepted wherever `const EciesEncryptOptions *` is; release it with `ecies_jna_encrypt_options_free`.
+ */
+ECIES_API
+struct EciesEncryptOptions *ecies_jna_encrypt_options_new(bool embed_timestamp,
+                                                          uint64_t ttl_seconds,
+                                                          bool embed_message_id,
+                                                          bool compress,
+                                                          const char *sender_secret_key_ptr,
+                                                          enum EciesBase64Flavor base64_flavor,
+                                                          uint8_t suite,
+                                                          const char *metadata_json_ptr,
+                                                          const char *key_id_ptr);
+
+/*
+ Releases options returned by `ecies_jna_encrypt_options_new`, clearing the copied strings, among which the sender secret key. Passing null is a no-op.
+ */
+ECIES_API
+void ecies_jna_encrypt_options_free(struct EciesEncryptOptions *options_ptr);
+
+/*
+ Builds decryption options from their fields (see `EciesDecryptOptions`). `seen_before` may be null. The result is accepted wherever `const EciesDecryptOptions *` is; release it with `ecies_jna_decrypt_options_free`.
+ */
+ECIES_API
+struct EciesDecryptOptions *ecies_jna_decrypt_options_new(bool reject_expired,
+                                                          EciesSeenBeforeCallback seen_before,
+                                                          void *seen_before_context,
+                                                          bool require_signature);
+
+/*
+ Releases options returned by `ecies_jna_decrypt_options_new`. Passing null is a no-op.
+ */
+ECIES_API void ecies_jna_decrypt_options_free(struct EciesDecryptOptions *options_ptr);
+
+/*
+ Builds progress options from their fields (see `EciesProgressOptions`), any of which may be null. The result is accepted wherever `const EciesProgressOptions *` is; release it with `ecies_jna_progress_options_free`.
+ */
+ECIES_API
+struct EciesProgressOptions *ecies_jna_progress_options_new(EciesProgressCallback callback,
+                                                            void *callback_context,
+                                                            const struct EciesCancelToken *cancel_token);
+
+/*
+ Releases options returned by `ecies_jna_progress_options_new`. Passing null is a no-op.
+ */
+ECIES_API void ecies_jna_progress_options_free(struct EciesProgressOptions *options_ptr);
+
+/*
+ Returns one component of a ciphertext, in the order of the fields of `EciesComponents`: 0 for the header, then the ephemeral public key, the nonce, the body, the tag and 5 for the signature. Other indexes fail with `InvalidArgument`. Returns null on failure; release the result with `ecies_script_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer *ecies_jna_split_component(const char *ciphertext_ptr,
+                                              uint32_t index,
+                                              enum EciesErrorCode *error_ptr);
+
+/*
+ Variant of `ecies_join_components` taking each component as a pointer and a length. Null or empty header and signature pointers produce a legacy ciphertext.
+ */
+ECIES_API
+const char *ecies_jna_join_components(const uint8_t *header_ptr,
+                                      uintptr_t header_length,
+                                      const uint8_t *ephemeral_public_key_ptr,
+                                      uintptr_t ephemeral_public_key_length,
+                                      const uint8_t *nonce_ptr,
+                                      uintptr_t nonce_length,
+                                      const uint8_t *body_ptr,
+                                      uintptr_t body_length,
+                                      const uint8_t *tag_ptr,
+                                      uintptr_t tag_length,
+                                      const uint8_t *signature_ptr,
+                                      uintptr_t signature_length,
+                                      enum EciesErrorCode *error_ptr);
+
+/*
+ Variant of `ecies_decrypt_bytes` returning the message boxed, null on failure; release it with `ecies_script_buffer_free`. The same holds for the other `ecies_jna_*` variants of calls returning `EciesBuffer`.
+ */
+ECIES_API
+struct EciesBuffer *ecies_jna_decrypt_bytes(const char *secret_key_ptr,
+                                            const char *message_ptr,
+                                            const struct EciesDecryptOptions *options_ptr,
+                                            enum EciesErrorCode *error_ptr);
+
+ECIES_API struct EciesBuffer *ecies_jna_canonicalize(const char *ciphertext_ptr, enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_update(struct EciesEncryptor *encryptor_ptr,
+                                             const uint8_t *data_ptr,
+                                             uintptr_t data_length,
+                                             enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_final(struct EciesEncryptor *encryptor_ptr,
+                                            enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_decrypt_update(struct EciesDecryptor *decryptor_ptr,
+                                             const uint8_t *data_ptr,
+                                             uintptr_t data_length,
+                                             enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_cbor(const char *public_key_ptr,
+                                           const char *message_ptr,
+                                           const struct EciesEncryptOptions *options_ptr,
+                                           enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_protobuf(const char *public_key_ptr,
+                                               const char *message_ptr,
+                                               const struct EciesEncryptOptions *options_ptr,
+                                               enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_msgpack(const char *public_key_ptr,
+                                              const char *message_ptr,
+                                              const struct EciesEncryptOptions *options_ptr,
+                                              enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_ur_decoder_message(const struct EciesUrDecoder *decoder_ptr,
+                                                 enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_cose(const char *public_key_ptr,
+                                           const char *message_ptr,
+                                           enum EciesCoseStructure structure,
+                                           enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_age(const char *recipients_ptr,
+                                          const uint8_t *data_ptr,
+                                          uintptr_t data_length,
+                                          enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_decrypt_age(const char *identity_ptr,
+                                          const uint8_t *age_ptr,
+                                          uintptr_t age_length,
+                                          enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_sealed_box_seal(const char *public_key_ptr,
+                                              const uint8_t *data_ptr,
+                                              uintptr_t data_length,
+                                              enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_sealed_box_open(const char *secret_key_ptr,
+                                              const uint8_t *sealed_ptr,
+                                              uintptr_t sealed_length,
+                                              enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_encrypt_openpgp(const uint8_t *public_key_ptr,
+                                              uintptr_t public_key_length,
+                                              const uint8_t *data_ptr,
+                                              uintptr_t data_length,
+                                              enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_decrypt_openpgp(const uint8_t *secret_key_ptr,
+                                              uintptr_t secret_key_length,
+                                              const uint8_t *message_ptr,
+                                              uintptr_t message_length,
+                                              enum EciesErrorCode *error_ptr);
+
+ECIES_API
+struct EciesBuffer *ecies_jna_agent_sign(struct EciesAgent *agent_ptr,
+                                         const char *key_name_ptr,
+                                         const uint8_t *message_ptr,
+                                         uintptr_t message_length,
+                                         enum EciesErrorCode *error_ptr);
+
+/*
+ Generates a keypair into `keypair_out`.
+ */
+ECIES_API enum EciesErrorCode ecies_net_generate_keypair(struct EciesNetKeypair *keypair_out);
+
+/*
+ Writes the compressed public key of a 32-byte secret key into `public_key_out`.
+ */
+ECIES_API
+enum EciesErrorCode ecies_net_public_key_from(const uint8_t *secret_key_ptr,
+                                              uintptr_t secret_key_length,
+                                              struct EciesNetPublicKey *public_key_out);
+
+/*
+ Encrypts a message into a binary envelope, stored in a new handle at `bytes_out`, which stays untouched on failure. The public key may use any layout accepted by `ecies_convert_public_key`, as raw bytes.
+ */
+ECIES_API
+enum EciesErrorCode ecies_net_encrypt(const uint8_t *public_key_ptr,
+                                      uintptr_t public_key_length,
+                                      const uint8_t *message_ptr,
+                                      uintptr_t message_length,
+                                      struct EciesNetBytes **bytes_out);
+
+/*
+ Decrypts a binary envelope, or a legacy ciphertext, into a new handle at `bytes_out`, which stays untouched on failure.
+ */
+ECIES_API
+enum EciesErrorCode ecies_net_decrypt(const uint8_t *secret_key_ptr,
+                                      uintptr_t secret_key_length,
+                                      const uint8_t *ciphertext_ptr,
+                                      uintptr_t ciphertext_length,
+                                      struct EciesNetBytes **bytes_out);
+
+/*
+ Returns the length of the bytes held by a handle, 0 for null.
+ */
+ECIES_API uintptr_t ecies_net_bytes_length(const struct EciesNetBytes *bytes);
+
+/*
+ Copies the bytes held by a handle into `destination`, which must have room for `ecies_net_bytes_length` bytes, or fails with `BufferTooSmall`.
+ */
+ECIES_API
+enum EciesErrorCode ecies_net_bytes_copy(const struct EciesNetBytes *bytes,
+                                         uint8_t *destination,
+                                         uintptr_t destination_length);
+
+/*
+ Releases a handle returned by `ecies_net_encrypt` or `ecies_net_decrypt`, clearing its bytes. Passing null is a no-op. This is the `ReleaseHandle` of the `SafeHandle` holding it.
+ */
+ECIES_API
+void ecies_net_bytes_free(struct EciesNetBytes *bytes);
+
+/*
+ Allocates `length` zeroed bytes, to be released with `ecies_dart_free`. The pointer is never null, even for a length of 0, so empty inputs can be passed like any other.
+ */
+ECIES_API
+uint8_t *ecies_dart_alloc(uintptr_t length);
+
+/*
+ Clears and releases memory from `ecies_dart_alloc` or returned by the `ecies_dart_*` functions. `length` must be the allocated length. Passing null is a no-op.
+ */
+ECIES_API
+void ecies_dart_free(uint8_t *data,
+                     uintptr_t length);
+
+/*
+ Generates a keypair into caller-provided memory: 32 bytes at `secret_key_out` and 33 bytes (compressed) at `public_key_out`. Returns false on failure.
+ */
+ECIES_API
+bool ecies_dart_generate_keypair(uint8_t *secret_key_out,
+                                 uint8_t *public_key_out,
+                                 enum EciesErrorCode *error_ptr);
+
+/*
+ Writes the 33-byte compressed public key of a 32-byte secret key to `public_key_out`. Returns false on failure.
+ */
+ECIES_API
+bool ecies_dart_public_key_from(const uint8_t *secret_key_ptr,
+                                uintptr_t secret_key_length,
+                                uint8_t *public_key_out,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message into a binary envelope, writing its length to `envelope_length_out`. Returns null on failure; release the result with `ecies_dart_free`.
+ */
+ECIES_API
+uint8_t *ecies_dart_encrypt(const uint8_t *public_key_ptr,
+                            uintptr_t public_key_length,
+                            const uint8_t *message_ptr,
+                            uintptr_t message_length,
+                            uintptr_t *envelope_length_out,
+                            enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a binary envelope, or a legacy ciphertext, writing the message length to `message_length_out`. Returns null on failure; release the result with `ecies_dart_free`.
+ */
+ECIES_API
+uint8_t *ecies_dart_decrypt(const uint8_t *secret_key_ptr,
+                            uintptr_t secret_key_length,
+                            const uint8_t *ciphertext_ptr,
+                            uintptr_t ciphertext_length,
+                            uintptr_t *message_length_out,
+                            enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the `EciesErrorCode` of the last `ecies_generate_secret_key`, `ecies_public_key_from`, `ecies_encrypt` or `ecies_decrypt` call on the calling thread, `Success` when it succeeded. It is how builds with the `no-panic` feature learn why one of them returned null.
+ */
+ECIES_API
+enum EciesErrorCode ecies_last_error(void);
+
+/*
+ Connects to the agent listening on the Unix domain socket at the given path. Returns null on failure, with `IoFailed` when no agent accepts the connection. Use the handle from one thread at a time and release it with `ecies_agent_free`.
+ */
+ECIES_API
+struct EciesAgent *ecies_agent_connect(const char *socket_path_ptr,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Wraps one end of a connected socket, e.g. from `socketpair`, whose other end is served by a helper process with `ecies_agent_serve_fd`. Takes ownership of the descriptor, which `ecies_agent_free` closes. Returns null on failure. Use the handle from one thread at a time.
+ */
+ECIES_API
+struct EciesAgent *ecies_agent_from_fd(int fd,
+                                       enum EciesErrorCode *error_ptr);
+
+/*
+ Serves agent requests on a connected socket until the client closes it, blocking the calling thread, for helper processes holding the keys of an app. Takes ownership of the descriptor. Keys are loaded from and generated into `key_directory_ptr`, created if needed, or kept in memory only when it is null; connections served with the same directory share their keys. Returns false on failure.
+ */
+ECIES_API
+bool ecies_agent_serve_fd(int fd,
+                          const char *key_directory_ptr,
+                          enum EciesErrorCode *error_ptr);
+
+/*
+ Returns the public key of the named agent key, hexadecimal and compressed, as a C string, or null on failure with `UnknownKey` when the agent holds no key with that name.
+ */
+ECIES_API
+const char *ecies_agent_public_key(struct EciesAgent *agent_ptr,
+                                   const char *key_name_ptr,
+                                   enum EciesErrorCode *error_ptr);
+
+/*
+ Creates a new key in the agent under the given name, written to its key directory when it has one, and returns its public key, hexadecimal and compressed, as a C string. Returns null on failure, with `InvalidArgument` when the name is taken or is not made of ASCII letters, digits, `-`, `_` and `.`.
+ */
+ECIES_API
+const char *ecies_agent_generate_key(struct EciesAgent *agent_ptr,
+                                     const char *key_name_ptr,
+                                     enum EciesErrorCode *error_ptr);
+
+/*
+ Encrypts a message for the given public key in the agent, signed with the named agent key. It takes the recipient public key (hexadecimal or base64) and the message as C strings and returns the base64 envelope as a C string, as `ecies_encrypt_signed`, or null on failure.
+ */
+ECIES_API
+const char *ecies_agent_encrypt(struct EciesAgent *agent_ptr,
+                                const char *key_name_ptr,
+                                const char *public_key_ptr,
+                                const char *message_ptr,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Decrypts a ciphertext in the agent with the named agent key. It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns the message as a C string, or null on failure. Sender signatures are verified when present.
+ */
+ECIES_API
+const char *ecies_agent_decrypt(struct EciesAgent *agent_ptr,
+                                const char *key_name_ptr,
+                                const char *ciphertext_ptr,
+                                enum EciesErrorCode *error_ptr);
+
+/*
+ Signs a message in the agent with the named agent key: the 64-byte compact ECDSA signature of its SHA-256 digest followed by the 1-byte recovery ID. The returned buffer must be released with `ecies_buffer_free`.
+ */
+ECIES_API
+struct EciesBuffer ecies_agent_sign(struct EciesAgent *agent_ptr,
+                                    const char *key_name_ptr,
+                                    const uint8_t *message_ptr,
+                                    uintptr_t message_length,
+                                    enum EciesErrorCode *error_ptr);
+
+ECIES_API void ecies_agent_free(struct EciesAgent *agent_ptr);
+
+/*
+ Registers a secret key (hexadecimal or base64 C string) for the `ecies_decrypt` SQL function and returns its handle, a random positive integer to bind as the first argument of `ecies_decrypt`, or 0 on failure with the reason written into `error_ptr`. The key stays in native memory until `ecies_sqlite_remove_secret_key`.
+ */
+ECIES_API
+int64_t ecies_sqlite_add_secret_key(const char *secret_key_ptr,
+                                    enum EciesErrorCode *error_ptr);
+
+/*
+ Releases a secret key registered with `ecies_sqlite_add_secret_key`, which clears it once no query still uses it. Later calls to `ecies_decrypt` with the handle fail with `InvalidSecretKey`. Unknown handles are ignored.
+ */
+ECIES_API
+void ecies_sqlite_remove_secret_key(int64_t handle);
 
-const char *ecies_decrypt(const char *secret_key_ptr, const char *message_ptr);
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
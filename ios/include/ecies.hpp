@@ -0,0 +1,166 @@
+// C++17 interface over the C API in ecies.h, for the NDK and other C++ consumers.
+//
+// Strings and buffers returned by the library are owned by RAII handles and released with ecies_string_free /
+// ecies_buffer_free, and every failure is thrown as ecies::Error carrying its EciesErrorCode.
+//
+//     auto secret_key = ecies::SecretKey::generate();
+//     auto ciphertext = secret_key.public_key().encrypt("hello");
+//     std::string message = secret_key.decrypt_string(ciphertext);
+
+#pragma once
+
+#include <cstdint>
+#include <memory>
+#include <stdexcept>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+extern "C" {
+#include "ecies.h"
+}
+
+namespace ecies {
+
+class Error : public std::runtime_error {
+public:
+    explicit Error(EciesErrorCode code)
+        : std::runtime_error("ecies operation failed with error code " + std::to_string(static_cast<int>(code))),
+          code_(code) {}
+
+    EciesErrorCode code() const noexcept { return code_; }
+
+private:
+    EciesErrorCode code_;
+};
+
+namespace detail {
+
+struct StringDeleter {
+    void operator()(const char *string) const noexcept { ecies_string_free(string); }
+};
+
+// A C string returned by the library
+using OwnedString = std::unique_ptr<const char, StringDeleter>;
+
+// A buffer returned by the library
+class OwnedBuffer {
+public:
+    explicit OwnedBuffer(EciesBuffer buffer) noexcept : buffer_(buffer) {}
+    OwnedBuffer(const OwnedBuffer &) = delete;
+    OwnedBuffer &operator=(const OwnedBuffer &) = delete;
+    ~OwnedBuffer() { ecies_buffer_free(buffer_); }
+
+    std::vector<std::uint8_t> to_vector() const { return {buffer_.data, buffer_.data + buffer_.length}; }
+
+private:
+    EciesBuffer buffer_;
+};
+
+inline std::string take_string(const char *string, EciesErrorCode code) {
+    if (string == nullptr) {
+        throw Error(code);
+    }
+    OwnedString owned(string);
+    return std::string(owned.get());
+}
+
+inline std::vector<std::uint8_t> take_buffer(EciesBuffer buffer, EciesErrorCode code) {
+    if (buffer.data == nullptr) {
+        throw Error(code);
+    }
+    return OwnedBuffer(buffer).to_vector();
+}
+
+// Overwrites key text before its memory is released
+inline void wipe(std::string &text) noexcept {
+    volatile char *data = text.data();
+    for (std::size_t index = 0; index < text.size(); ++index) {
+        data[index] = 0;
+    }
+}
+
+}  // namespace detail
+
+// A base64 envelope, as produced by PublicKey::encrypt or received from another client
+class Ciphertext {
+public:
+    explicit Ciphertext(std::string text) : text_(std::move(text)) {}
+
+    const std::string &str() const noexcept { return text_; }
+    const char *c_str() const noexcept { return text_.c_str(); }
+
+private:
+    std::string text_;
+};
+
+class PublicKey {
+public:
+    // Any layout and encoding accepted by the C API; throws ecies::Error if the key is invalid
+    explicit PublicKey(std::string text) : text_(std::move(text)) {
+        EciesErrorCode code = Success;
+        detail::take_string(ecies_convert_public_key(text_.c_str(), Compressed, Hex, &code), code);
+    }
+
+    Ciphertext encrypt(const std::uint8_t *data, std::size_t length) const {
+        static const std::uint8_t empty[1] = {0};
+        EciesErrorCode code = Success;
+        const char *ciphertext = ecies_encrypt_bytes(text_.c_str(), length > 0 ? data : empty, length, nullptr, &code);
+        return Ciphertext(detail::take_string(ciphertext, code));
+    }
+
+    Ciphertext encrypt(const std::vector<std::uint8_t> &message) const { return encrypt(message.data(), message.size()); }
+
+    Ciphertext encrypt(std::string_view message) const {
+        return encrypt(reinterpret_cast<const std::uint8_t *>(message.data()), message.size());
+    }
+
+    const std::string &str() const noexcept { return text_; }
+
+private:
+    std::string text_;
+};
+
+class SecretKey {
+public:
+    // Hex or base64; throws ecies::Error if the key is invalid
+    explicit SecretKey(std::string text) : text_(std::move(text)), public_key_(derive_public_key(text_)) {}
+
+    SecretKey(const SecretKey &) = default;
+    SecretKey(SecretKey &&) = default;
+    SecretKey &operator=(const SecretKey &) = default;
+    SecretKey &operator=(SecretKey &&) = default;
+    ~SecretKey() { detail::wipe(text_); }
+
+    static SecretKey generate() {
+        std::string text = detail::take_string(ecies_generate_secret_key(), Success);
+        SecretKey secret_key(text);
+        detail::wipe(text);
+        return secret_key;
+    }
+
+    const PublicKey &public_key() const noexcept { return public_key_; }
+
+    std::vector<std::uint8_t> decrypt(const Ciphertext &ciphertext) const {
+        EciesErrorCode code = Success;
+        EciesBuffer buffer = ecies_decrypt_bytes(text_.c_str(), ciphertext.c_str(), nullptr, &code);
+        return detail::take_buffer(buffer, code);
+    }
+
+    std::string decrypt_string(const Ciphertext &ciphertext) const {
+        std::vector<std::uint8_t> message = decrypt(ciphertext);
+        return std::string(message.begin(), message.end());
+    }
+
+private:
+    static PublicKey derive_public_key(const std::string &text) {
+        EciesErrorCode code = Success;
+        return PublicKey(detail::take_string(ecies_derive_public_key(text.c_str(), &code), code));
+    }
+
+    std::string text_;
+    PublicKey public_key_;
+};
+
+}  // namespace ecies
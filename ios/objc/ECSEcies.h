@@ -0,0 +1,39 @@
+// Objective-C interface over the C API in ecies.h, for the parts of the iOS app that cannot use the Swift
+// wrapper. Failures are reported through NSError out-parameters in ECSEciesErrorDomain, whose codes are the
+// EciesErrorCode values. Add ECSEcies.h and ECSEcies.m to the target linking the Ecies xcframework.
+
+#import <Foundation/Foundation.h>
+
+NS_ASSUME_NONNULL_BEGIN
+
+extern NSErrorDomain const ECSEciesErrorDomain;
+
+@interface ECSKeypair : NSObject
+
+// Hex secret key and compressed hex public key, as accepted by every ECSEcies method
+@property (nonatomic, copy, readonly) NSString *secretKey;
+@property (nonatomic, copy, readonly) NSString *publicKey;
+
+- (instancetype)init NS_UNAVAILABLE;
+
+@end
+
+@interface ECSEcies : NSObject
+
++ (ECSKeypair *)generateKeypair;
+
++ (nullable NSString *)publicKeyFromSecretKey:(NSString *)secretKey error:(NSError **)error;
+
+// Returns the base64 envelope of the message
++ (nullable NSString *)encryptData:(NSData *)message publicKey:(NSString *)publicKey error:(NSError **)error;
+
++ (nullable NSString *)encryptString:(NSString *)message publicKey:(NSString *)publicKey error:(NSError **)error;
+
+// Accepts base64 or ASCII-armored envelopes and legacy ciphertexts, like ecies_decrypt
++ (nullable NSData *)decryptData:(NSString *)ciphertext secretKey:(NSString *)secretKey error:(NSError **)error;
+
++ (nullable NSString *)decryptString:(NSString *)ciphertext secretKey:(NSString *)secretKey error:(NSError **)error;
+
+@end
+
+NS_ASSUME_NONNULL_END
@@ -0,0 +1,99 @@
+#import "ECSEcies.h"
+#import "ecies.h"
+
+NSErrorDomain const ECSEciesErrorDomain = @"io.metamask.ecies";
+
+static BOOL ECSFail(EciesErrorCode code, NSError **error) {
+    if (error != NULL) {
+        NSString *description = [NSString stringWithFormat:@"ecies operation failed with error code %d", (int)code];
+        *error = [NSError errorWithDomain:ECSEciesErrorDomain
+                                     code:(NSInteger)code
+                                 userInfo:@{NSLocalizedDescriptionKey: description}];
+    }
+    return NO;
+}
+
+// Takes ownership of a C string returned by the library
+static NSString *_Nullable ECSTakeString(const char *_Nullable string, EciesErrorCode code, NSError **error) {
+    if (string == NULL) {
+        ECSFail(code, error);
+        return nil;
+    }
+    NSString *result = [NSString stringWithUTF8String:string];
+    ecies_string_free(string);
+    if (result == nil) {
+        ECSFail(InvalidUtf8, error);
+    }
+    return result;
+}
+
+@interface ECSKeypair ()
+
+- (instancetype)initWithSecretKey:(NSString *)secretKey publicKey:(NSString *)publicKey;
+
+@end
+
+@implementation ECSKeypair
+
+- (instancetype)initWithSecretKey:(NSString *)secretKey publicKey:(NSString *)publicKey {
+    if ((self = [super init])) {
+        _secretKey = [secretKey copy];
+        _publicKey = [publicKey copy];
+    }
+    return self;
+}
+
+@end
+
+@implementation ECSEcies
+
++ (ECSKeypair *)generateKeypair {
+    NSString *secretKey = ECSTakeString(ecies_generate_secret_key(), Success, NULL);
+    NSString *publicKey = [self publicKeyFromSecretKey:secretKey error:NULL];
+    return [[ECSKeypair alloc] initWithSecretKey:secretKey publicKey:publicKey];
+}
+
++ (NSString *)publicKeyFromSecretKey:(NSString *)secretKey error:(NSError **)error {
+    EciesErrorCode code = Success;
+    const char *publicKey = ecies_derive_public_key(secretKey.UTF8String, &code);
+    return ECSTakeString(publicKey, code, error);
+}
+
++ (NSString *)encryptData:(NSData *)message publicKey:(NSString *)publicKey error:(NSError **)error {
+    EciesErrorCode code = Success;
+    // Empty NSData may have no bytes pointer, which the library treats as a missing argument
+    static const uint8_t empty[1] = {0};
+    const uint8_t *bytes = message.length > 0 ? message.bytes : empty;
+    const char *ciphertext = ecies_encrypt_bytes(publicKey.UTF8String, bytes, message.length, NULL, &code);
+    return ECSTakeString(ciphertext, code, error);
+}
+
++ (NSString *)encryptString:(NSString *)message publicKey:(NSString *)publicKey error:(NSError **)error {
+    return [self encryptData:[message dataUsingEncoding:NSUTF8StringEncoding] publicKey:publicKey error:error];
+}
+
++ (NSData *)decryptData:(NSString *)ciphertext secretKey:(NSString *)secretKey error:(NSError **)error {
+    EciesErrorCode code = Success;
+    EciesBuffer buffer = ecies_decrypt_bytes(secretKey.UTF8String, ciphertext.UTF8String, NULL, &code);
+    if (buffer.data == NULL) {
+        ECSFail(code, error);
+        return nil;
+    }
+    NSData *message = [NSData dataWithBytes:buffer.data length:buffer.length];
+    ecies_buffer_free(buffer);
+    return message;
+}
+
++ (NSString *)decryptString:(NSString *)ciphertext secretKey:(NSString *)secretKey error:(NSError **)error {
+    NSData *message = [self decryptData:ciphertext secretKey:secretKey error:error];
+    if (message == nil) {
+        return nil;
+    }
+    NSString *string = [[NSString alloc] initWithData:message encoding:NSUTF8StringEncoding];
+    if (string == nil) {
+        ECSFail(InvalidUtf8, error);
+    }
+    return string;
+}
+
+@end
@@ -0,0 +1,477 @@
+use std::os::raw::c_char;
+use bech32::{FromBase32, ToBase32, Variant};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::encoding::{decode_base64, encode_base64, EciesBase64Flavor};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr};
+use crate::policy::allow_unapproved;
+use crate::random::rng;
+use crate::ssh::{
+    ed25519_to_x25519_public_key, ed25519_to_x25519_secret_key, parse_ed25519_secret_key, parse_public_key_line,
+    SshPublicKey, ED25519_KEY_TYPE,
+};
+
+/*
+The age v1 file format (https://age-encryption.org/v1) with X25519 recipients, so files produced by the `age`
+CLI can be opened through this library and vice versa.
+
+    age-encryption.org/v1
+    -> X25519 <ephemeral share>
+    <wrapped file key>
+    --- <header MAC>
+    <payload nonce (16 bytes)> | <STREAM of ChaCha20-Poly1305 chunks of 64 KiB>
+
+Recipients are bech32 `age1...` strings and identities `AGE-SECRET-KEY-1...` strings, as printed by `age-keygen`.
+Like with `age -R`, ssh-ed25519 public key lines can be used as recipients, and unencrypted OpenSSH ed25519 private
+keys as identities. Their stanza is `-> ssh-ed25519 <key tag> <ephemeral share>`.
+Only the binary encoding is handled, armored files must be dearmored first.
+*/
+
+const INTRO: &str = "age-encryption.org/v1";
+const X25519_LABEL: &str = "age-encryption.org/v1/X25519";
+const SSH_ED25519_LABEL: &str = "age-encryption.org/v1/ssh-ed25519";
+const RECIPIENT_PREFIX: &str = "age";
+const IDENTITY_PREFIX: &str = "age-secret-key-";
+
+const FILE_KEY_LENGTH: usize = 16;
+const PAYLOAD_NONCE_LENGTH: usize = 16;
+const CHUNK_LENGTH: usize = 64 * 1024;
+const CHUNK_TAG_LENGTH: usize = 16;
+const COLUMNS: usize = 64;
+
+pub enum Recipient {
+    X25519(X25519PublicKey),
+    // The converted X25519 key of an ssh-ed25519 key, and the SSH key blob bound into the key derivation
+    SshEd25519 { wire: Vec<u8>, public_key: X25519PublicKey },
+}
+
+pub enum Identity {
+    X25519(StaticSecret),
+    SshEd25519 { wire: Vec<u8>, secret: StaticSecret },
+}
+
+struct Stanza {
+    arguments: Vec<String>,
+    body: Vec<u8>,
+}
+
+fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8]) -> Result<[u8; 32], EciesErrorCode> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm).expand(info, &mut key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    Ok(key)
+}
+
+// Finalized when writing the header, verified in constant time when reading it
+fn header_mac(file_key: &[u8], header: &[u8]) -> Result<Hmac<Sha256>, EciesErrorCode> {
+    let mac_key = hkdf(file_key, &[], b"header")?;
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&mac_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    mac.update(header);
+    Ok(mac)
+}
+
+fn stream_nonce(counter: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+pub fn parse_recipient(recipient: &str) -> Result<Recipient, EciesErrorCode> {
+    if recipient.starts_with(ED25519_KEY_TYPE) {
+        return match parse_public_key_line(recipient)? {
+            SshPublicKey::Ed25519 { wire, key } => {
+                let public_key = X25519PublicKey::from(ed25519_to_x25519_public_key(&key)?);
+                Ok(Recipient::SshEd25519 { wire, public_key })
+            }
+            // age has no stanza for ECDSA keys, unlike `ecies_p256_encrypt`
+            SshPublicKey::EcdsaP256 { .. } => Err(EciesErrorCode::UnsupportedCurve),
+        };
+    }
+
+    let (hrp, data, variant) = bech32::decode(recipient).map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    if hrp != RECIPIENT_PREFIX || variant != Variant::Bech32 {
+        return Err(EciesErrorCode::InvalidAgeKey);
+    }
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    Ok(Recipient::X25519(X25519PublicKey::from(bytes)))
+}
+
+pub fn parse_identity(identity: &str) -> Result<Identity, EciesErrorCode> {
+    if identity.contains("OPENSSH PRIVATE KEY") {
+        let secret_key = parse_ed25519_secret_key(identity)?;
+        let secret = StaticSecret::from(ed25519_to_x25519_secret_key(&secret_key.seed));
+        return Ok(Identity::SshEd25519 { wire: secret_key.wire, secret });
+    }
+
+    let (hrp, data, variant) = bech32::decode(identity.trim()).map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    if hrp != IDENTITY_PREFIX || variant != Variant::Bech32 {
+        return Err(EciesErrorCode::InvalidAgeKey);
+    }
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    Ok(Identity::X25519(StaticSecret::from(bytes)))
+}
+
+fn encode_recipient(identity: &Identity) -> Result<String, EciesErrorCode> {
+    match identity {
+        Identity::X25519(secret) => {
+            bech32::encode(RECIPIENT_PREFIX, X25519PublicKey::from(secret).as_bytes().to_base32(), Variant::Bech32)
+                .map_err(|_| EciesErrorCode::InvalidAgeKey)
+        }
+        Identity::SshEd25519 { wire, .. } => Ok(format!("{} {}", ED25519_KEY_TYPE, base64::encode(wire))),
+    }
+}
+
+fn encode_identity(secret: &StaticSecret) -> Result<String, EciesErrorCode> {
+    let encoded = bech32::encode(IDENTITY_PREFIX, secret.to_bytes().to_base32(), Variant::Bech32)
+        .map_err(|_| EciesErrorCode::InvalidAgeKey)?;
+    Ok(encoded.to_uppercase())
+}
+
+// First 4 bytes of the SHA-256 of the SSH key blob, letting identities skip stanzas meant for other keys
+fn ssh_key_tag(wire: &[u8]) -> String {
+    encode_base64(&Sha256::digest(wire)[..4], EciesBase64Flavor::StandardNoPadding)
+}
+
+// ssh-ed25519 stanzas mix the SSH key blob into the shared secret, so the converted X25519 key alone cannot open them
+fn ssh_tweak(wire: &[u8], shared_secret: &[u8; 32]) -> Result<[u8; 32], EciesErrorCode> {
+    let tweak = StaticSecret::from(hkdf(&[], wire, SSH_ED25519_LABEL.as_bytes())?);
+    Ok(tweak.diffie_hellman(&X25519PublicKey::from(*shared_secret)).to_bytes())
+}
+
+fn derive_wrap_key(
+    shared_secret: &[u8; 32],
+    ephemeral_share: &X25519PublicKey,
+    recipient: &X25519PublicKey,
+    label: &str,
+) -> Result<[u8; 32], EciesErrorCode> {
+    let mut salt = ephemeral_share.as_bytes().to_vec();
+    salt.extend_from_slice(recipient.as_bytes());
+    hkdf(shared_secret, &salt, label.as_bytes())
+}
+
+fn wrap_file_key(recipient: &Recipient, file_key: &[u8]) -> Result<Stanza, EciesErrorCode> {
+    let mut ephemeral_bytes = [0u8; 32];
+    rng()?.fill_bytes(&mut ephemeral_bytes);
+    let ephemeral_secret = StaticSecret::from(ephemeral_bytes);
+    let ephemeral_share = X25519PublicKey::from(&ephemeral_secret);
+    let share = encode_base64(ephemeral_share.as_bytes(), EciesBase64Flavor::StandardNoPadding);
+
+    let (wrap_key, arguments) = match recipient {
+        Recipient::X25519(public_key) => {
+            let shared_secret = ephemeral_secret.diffie_hellman(public_key).to_bytes();
+            let wrap_key = derive_wrap_key(&shared_secret, &ephemeral_share, public_key, X25519_LABEL)?;
+            (wrap_key, vec!["X25519".to_string(), share])
+        }
+        Recipient::SshEd25519 { wire, public_key } => {
+            let shared_secret = ssh_tweak(wire, &ephemeral_secret.diffie_hellman(public_key).to_bytes())?;
+            let wrap_key = derive_wrap_key(&shared_secret, &ephemeral_share, public_key, SSH_ED25519_LABEL)?;
+            (wrap_key, vec![ED25519_KEY_TYPE.to_string(), ssh_key_tag(wire), share])
+        }
+    };
+
+    let body = ChaCha20Poly1305::new(Key::from_slice(&wrap_key))
+        .encrypt(Nonce::from_slice(&[0u8; 12]), file_key)
+        .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    Ok(Stanza { arguments, body })
+}
+
+fn decode_share(share: &str) -> Result<X25519PublicKey, EciesErrorCode> {
+    let share: [u8; 32] = decode_base64(share.as_bytes())?.try_into().map_err(|_| EciesErrorCode::InvalidAge)?;
+    Ok(X25519PublicKey::from(share))
+}
+
+// Returns None when the stanza is for another recipient, the file key when it opens with the identity
+fn unwrap_file_key(identity: &Identity, stanza: &Stanza) -> Result<Option<Vec<u8>>, EciesErrorCode> {
+    let arguments: Vec<&str> = stanza.arguments.iter().map(String::as_str).collect();
+
+    let wrap_key = match (identity, &arguments[..]) {
+        (Identity::X25519(secret), ["X25519", share]) => {
+            let ephemeral_share = decode_share(share)?;
+            let shared_secret = secret.diffie_hellman(&ephemeral_share).to_bytes();
+            derive_wrap_key(&shared_secret, &ephemeral_share, &X25519PublicKey::from(secret), X25519_LABEL)?
+        }
+        (Identity::SshEd25519 { wire, secret }, [ED25519_KEY_TYPE, tag, share]) => {
+            if *tag != ssh_key_tag(wire) {
+                return Ok(None);
+            }
+            let ephemeral_share = decode_share(share)?;
+            let shared_secret = ssh_tweak(wire, &secret.diffie_hellman(&ephemeral_share).to_bytes())?;
+            derive_wrap_key(&shared_secret, &ephemeral_share, &X25519PublicKey::from(secret), SSH_ED25519_LABEL)?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&wrap_key))
+        .decrypt(Nonce::from_slice(&[0u8; 12]), &stanza.body[..])
+        .ok())
+}
+
+fn write_header(stanzas: &[Stanza]) -> String {
+    let mut header = format!("{}\n", INTRO);
+    for stanza in stanzas {
+        header.push_str(&format!("-> {}\n", stanza.arguments.join(" ")));
+        let body = encode_base64(&stanza.body, EciesBase64Flavor::StandardNoPadding);
+        let mut lines: Vec<&str> = body.as_bytes().chunks(COLUMNS).map(|line| std::str::from_utf8(line).unwrap_or_default()).collect();
+        // The body ends with the first line shorter than 64 columns, which may have to be an empty one
+        if body.len().is_multiple_of(COLUMNS) {
+            lines.push("");
+        }
+        for line in lines {
+            header.push_str(line);
+            header.push('\n');
+        }
+    }
+    header.push_str("---");
+    header
+}
+
+fn next_line<'a>(data: &'a [u8], offset: &mut usize) -> Result<&'a str, EciesErrorCode> {
+    let remaining = &data[*offset..];
+    let end = remaining.iter().position(|byte| *byte == b'\n').ok_or(EciesErrorCode::InvalidAge)?;
+    *offset += end + 1;
+    std::str::from_utf8(&remaining[..end]).map_err(|_| EciesErrorCode::InvalidAge)
+}
+
+// The stanzas, the header bytes covered by the MAC, the MAC and the payload
+type ParsedHeader<'a> = (Vec<Stanza>, &'a [u8], Vec<u8>, &'a [u8]);
+
+fn parse_header(data: &[u8]) -> Result<ParsedHeader<'_>, EciesErrorCode> {
+    let mut offset = 0;
+    if next_line(data, &mut offset)? != INTRO {
+        return Err(EciesErrorCode::InvalidAge);
+    }
+
+    let mut stanzas = Vec::new();
+    loop {
+        let line_start = offset;
+        let line = next_line(data, &mut offset)?;
+
+        if let Some(mac) = line.strip_prefix("--- ") {
+            let mac = decode_base64(mac.as_bytes())?;
+            return Ok((stanzas, &data[..line_start + 3], mac, &data[offset..]));
+        }
+
+        let arguments = line.strip_prefix("-> ").ok_or(EciesErrorCode::InvalidAge)?;
+        let mut body = String::new();
+        loop {
+            let body_line = next_line(data, &mut offset)?;
+            if body_line.len() > COLUMNS {
+                return Err(EciesErrorCode::InvalidAge);
+            }
+            body.push_str(body_line);
+            if body_line.len() < COLUMNS {
+                break;
+            }
+        }
+
+        stanzas.push(Stanza {
+            arguments: arguments.split(' ').map(str::to_string).collect(),
+            body: decode_base64(body.as_bytes())?,
+        });
+    }
+}
+
+// The key reported to the audit hook: the X25519 share, or the SSH key blob
+fn recipient_key(recipient: &Recipient) -> Vec<u8> {
+    match recipient {
+        Recipient::X25519(public_key) => public_key.as_bytes().to_vec(),
+        Recipient::SshEd25519 { wire, .. } => wire.clone(),
+    }
+}
+
+fn identity_key(identity: &Identity) -> Vec<u8> {
+    match identity {
+        Identity::X25519(secret) => X25519PublicKey::from(secret).as_bytes().to_vec(),
+        Identity::SshEd25519 { wire, .. } => wire.clone(),
+    }
+}
+
+// Messages to several recipients are reported without a key fingerprint
+pub fn encrypt(recipients: &[Recipient], message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_age(recipients, message);
+    let public_key = || match recipients {
+        [recipient] => recipient_key(recipient),
+        _ => Vec::new(),
+    };
+    operation.record(public_key, message.len(), outcome(&result));
+    result
+}
+
+fn seal_age(recipients: &[Recipient], message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    allow_unapproved()?;
+    if recipients.is_empty() {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+
+    let mut file_key = [0u8; FILE_KEY_LENGTH];
+    rng()?.fill_bytes(&mut file_key);
+    let stanzas = recipients.iter().map(|recipient| wrap_file_key(recipient, &file_key)).collect::<Result<Vec<_>, _>>()?;
+
+    let header = write_header(&stanzas);
+    let mac = header_mac(&file_key, header.as_bytes())?.finalize().into_bytes();
+    let mut output = format!("{} {}\n", header, encode_base64(&mac, EciesBase64Flavor::StandardNoPadding)).into_bytes();
+
+    let mut payload_nonce = [0u8; PAYLOAD_NONCE_LENGTH];
+    rng()?.fill_bytes(&mut payload_nonce);
+    output.extend_from_slice(&payload_nonce);
+
+    let payload_key = hkdf(&file_key, &payload_nonce, b"payload")?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&payload_key));
+
+    // An empty message is still written as one empty final chunk
+    let chunks: Vec<&[u8]> = if message.is_empty() { vec![message] } else { message.chunks(CHUNK_LENGTH).collect() };
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let nonce = stream_nonce(counter as u64, counter + 1 == chunks.len());
+        let sealed = cipher.encrypt(Nonce::from_slice(&nonce), *chunk).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        output.extend_from_slice(&sealed);
+    }
+
+    Ok(output)
+}
+
+pub fn decrypt(identity: &Identity, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_age(identity, data);
+    operation.record(|| identity_key(identity), data.len(), outcome(&result));
+    result
+}
+
+fn open_age(identity: &Identity, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    allow_unapproved()?;
+    let (stanzas, header, mac, payload) = parse_header(data)?;
+
+    let mut file_key = None;
+    for stanza in &stanzas {
+        if let Some(key) = unwrap_file_key(identity, stanza)? {
+            file_key = Some(key);
+            break;
+        }
+    }
+    let file_key = file_key.ok_or(EciesErrorCode::DecryptionFailed)?;
+    if file_key.len() != FILE_KEY_LENGTH {
+        return Err(EciesErrorCode::InvalidAge);
+    }
+    header_mac(&file_key, header)?.verify_slice(&mac).map_err(|_| EciesErrorCode::InvalidAge)?;
+
+    if payload.len() < PAYLOAD_NONCE_LENGTH {
+        return Err(EciesErrorCode::InvalidAge);
+    }
+    let (payload_nonce, stream) = payload.split_at(PAYLOAD_NONCE_LENGTH);
+    let payload_key = hkdf(&file_key, payload_nonce, b"payload")?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&payload_key));
+
+    let chunks: Vec<&[u8]> = stream.chunks(CHUNK_LENGTH + CHUNK_TAG_LENGTH).collect();
+    if chunks.is_empty() {
+        return Err(EciesErrorCode::InvalidAge);
+    }
+    let mut message = Vec::with_capacity(stream.len());
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let nonce = stream_nonce(counter as u64, counter + 1 == chunks.len());
+        let opened = cipher.decrypt(Nonce::from_slice(&nonce), *chunk).map_err(|_| EciesErrorCode::MacFailed)?;
+        message.extend_from_slice(&opened);
+    }
+
+    Ok(message)
+}
+
+/**
+Generates a new age identity. It returns the `AGE-SECRET-KEY-1...` string, the matching recipient can be
+derived from it with `ecies_age_recipient_from`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_age_generate_identity(error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        allow_unapproved()?;
+        let operation = begin(EciesAuditOperation::KeyGeneration);
+        let mut secret_bytes = [0u8; 32];
+        rng()?.fill_bytes(&mut secret_bytes);
+        let secret = StaticSecret::from(secret_bytes);
+        operation.record(|| X25519PublicKey::from(&secret).to_bytes(), 0, Ok(0));
+
+        string_into_ptr(encode_identity(&secret)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Derives the `age1...` recipient of an `AGE-SECRET-KEY-1...` identity, or the ssh-ed25519 public key line of an
+OpenSSH private key.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_age_recipient_from(identity_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        let identity = parse_identity(str_from_ptr(identity_ptr)?)?;
+
+        string_into_ptr(encode_recipient(&identity)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Encrypts data into a binary age file. It takes:
+    - the recipients, one or more `age1...` strings separated by whitespace or commas, or ssh-ed25519 public key
+      lines, one per line
+    - the data to encrypt and its length
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_age(
+    recipients_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let mut recipients = Vec::new();
+        for line in str_from_ptr(recipients_ptr)?.lines() {
+            if line.trim_start().starts_with(ED25519_KEY_TYPE) {
+                recipients.push(parse_recipient(line.trim())?);
+                continue;
+            }
+            for recipient in line.split(|c: char| c.is_whitespace() || c == ',').filter(|recipient| !recipient.is_empty()) {
+                recipients.push(parse_recipient(recipient)?);
+            }
+        }
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        encrypt(&recipients, data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a binary age file with an `AGE-SECRET-KEY-1...` identity or an unencrypted OpenSSH ed25519 private key.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_age(
+    identity_ptr: *const c_char,
+    age_ptr: *const u8,
+    age_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let identity = parse_identity(str_from_ptr(identity_ptr)?)?;
+        let age = slice_from_raw(age_ptr, age_length)?;
+
+        decrypt(&identity, age)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
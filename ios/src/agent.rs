@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::FromRawFd;
+use std::os::raw::{c_char, c_int};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use libsecp256k1::Message;
+
+use ecies_core::Keypair;
+
+use crate::armor::decode_text;
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::context::sign;
+use crate::encoding::{encode_base64, EciesBase64Flavor};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::features::sha256;
+use crate::ffi::{bytes_from_ptr, finish, finish_handle, finish_status, str_from_ptr, string_into_ptr};
+use crate::keys::{decode_key_text, parse_public_key, parse_secret_key_text, wipe};
+
+/*
+Key isolation through `ecies-agent` (see agent/src/main.rs), a daemon that holds the secret keys in its own process
+and serves requests over a Unix domain socket, so the keys never live in the app process. This module has the wire
+protocol, the serving side used by the daemon, and the client API.
+
+Every request and response is one frame: a 4-byte big-endian length, at most `MAX_FRAME_LENGTH`, then the body.
+A request body is an operation byte followed by its fields, each a 4-byte big-endian length and the bytes:
+    - 1 public key: key name. Returns the compressed public key
+    - 2 encrypt: key name, recipient public key in any layout, message. Returns a binary envelope signed with
+      the named key, as `ecies_encrypt_signed`
+    - 3 decrypt: key name, binary envelope or legacy ciphertext. Returns the message
+    - 4 sign: key name, message. Returns the 64-byte compact ECDSA signature of the SHA-256 digest of the message,
+      followed by the recovery ID
+    - 5 generate: key name. Creates a new key under that name inside the agent and returns its compressed public
+      key, failing with `InvalidArgument` when the name is taken
+A response body is a 4-byte big-endian `EciesErrorCode`: `Success` followed by the result, or the failure alone.
+Requests naming a key the agent does not hold fail with `UnknownKey`.
+
+Requests on a connection are answered in order. A client handle keeps one connection and must not be used from
+several threads at once. Anyone who can open the socket can use the keys, so access is controlled by the
+permissions of the socket file and its directory.
+
+The agent can also run as a helper process of the app, e.g. an Android service in its own process: the app
+creates a socketpair, hands one end to the helper, which serves it with `ecies_agent_serve_fd`, and talks to it
+through `ecies_agent_from_fd` on the other end. Keys are then never mapped into the app process, so a
+compromised app process can use them while it runs but cannot read them out.
+
+A keyring opened on a directory loads every `<name>.key` file in it (a hex or base64 secret key, as written by
+`ecies-cli keygen`) and writes the keys it generates there, readable by the owner only. Without a directory,
+generated keys live as long as the process.
+*/
+
+pub const MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+
+const OPERATION_PUBLIC_KEY: u8 = 1;
+const OPERATION_ENCRYPT: u8 = 2;
+const OPERATION_DECRYPT: u8 = 3;
+const OPERATION_SIGN: u8 = 4;
+const OPERATION_GENERATE: u8 = 5;
+
+const KEY_FILE_EXTENSION: &str = "key";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    PublicKey { key: String },
+    Encrypt { key: String, recipient: Vec<u8>, message: Vec<u8> },
+    Decrypt { key: String, ciphertext: Vec<u8> },
+    Sign { key: String, message: Vec<u8> },
+    Generate { key: String },
+}
+
+fn push_field(body: &mut Vec<u8>, field: &[u8]) {
+    body.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    body.extend_from_slice(field);
+}
+
+fn read_field<'a>(body: &mut &'a [u8]) -> Result<&'a [u8], EciesErrorCode> {
+    let length = body.get(..4).ok_or(EciesErrorCode::InvalidArgument)?;
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+    let end = length.checked_add(4).ok_or(EciesErrorCode::InvalidArgument)?;
+    let field = body.get(4..end).ok_or(EciesErrorCode::InvalidArgument)?;
+
+    *body = &body[end..];
+    Ok(field)
+}
+
+impl Request {
+    pub fn key(&self) -> &str {
+        match self {
+            Request::PublicKey { key }
+            | Request::Encrypt { key, .. }
+            | Request::Decrypt { key, .. }
+            | Request::Sign { key, .. }
+            | Request::Generate { key } => key,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        match self {
+            Request::PublicKey { key } => {
+                body.push(OPERATION_PUBLIC_KEY);
+                push_field(&mut body, key.as_bytes());
+            }
+            Request::Encrypt { key, recipient, message } => {
+                body.push(OPERATION_ENCRYPT);
+                push_field(&mut body, key.as_bytes());
+                push_field(&mut body, recipient);
+                push_field(&mut body, message);
+            }
+            Request::Decrypt { key, ciphertext } => {
+                body.push(OPERATION_DECRYPT);
+                push_field(&mut body, key.as_bytes());
+                push_field(&mut body, ciphertext);
+            }
+            Request::Sign { key, message } => {
+                body.push(OPERATION_SIGN);
+                push_field(&mut body, key.as_bytes());
+                push_field(&mut body, message);
+            }
+            Request::Generate { key } => {
+                body.push(OPERATION_GENERATE);
+                push_field(&mut body, key.as_bytes());
+            }
+        }
+        body
+    }
+
+    pub fn decode(body: &[u8]) -> Result<Request, EciesErrorCode> {
+        let (&operation, mut fields) = body.split_first().ok_or(EciesErrorCode::InvalidArgument)?;
+        let key = std::str::from_utf8(read_field(&mut fields)?).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+        let key = key.to_string();
+
+        let request = match operation {
+            OPERATION_PUBLIC_KEY => Request::PublicKey { key },
+            OPERATION_ENCRYPT => {
+                let recipient = read_field(&mut fields)?.to_vec();
+                Request::Encrypt { key, recipient, message: read_field(&mut fields)?.to_vec() }
+            }
+            OPERATION_DECRYPT => Request::Decrypt { key, ciphertext: read_field(&mut fields)?.to_vec() },
+            OPERATION_SIGN => Request::Sign { key, message: read_field(&mut fields)?.to_vec() },
+            OPERATION_GENERATE => Request::Generate { key },
+            _ => return Err(EciesErrorCode::InvalidArgument),
+        };
+        if !fields.is_empty() {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        Ok(request)
+    }
+}
+
+pub fn encode_response(result: &Result<Vec<u8>, EciesErrorCode>) -> Vec<u8> {
+    match result {
+        Ok(bytes) => {
+            let mut body = (EciesErrorCode::Success as u32).to_be_bytes().to_vec();
+            body.extend_from_slice(bytes);
+            body
+        }
+        Err(code) => (*code as u32).to_be_bytes().to_vec(),
+    }
+}
+
+pub fn decode_response(body: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let code = body.get(..4).ok_or(EciesErrorCode::InvalidArgument)?;
+    let code = u32::from_be_bytes([code[0], code[1], code[2], code[3]]);
+    // Codes added by a newer agent are reported as malformed responses
+    match EciesErrorCode::from_code(code).ok_or(EciesErrorCode::InvalidArgument)? {
+        EciesErrorCode::Success => Ok(body[4..].to_vec()),
+        code => Err(code),
+    }
+}
+
+pub fn write_frame(writer: &mut impl Write, body: &[u8]) -> Result<(), EciesErrorCode> {
+    if body.len() > MAX_FRAME_LENGTH {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    let mut frame = Vec::with_capacity(4 + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+
+    writer.write_all(&frame).and_then(|_| writer.flush()).map_err(|_| EciesErrorCode::IoFailed)
+}
+
+// Reads the next frame, or returns `None` when the peer closed the connection between frames
+pub fn read_frame(reader: &mut impl Read) -> Result<Option<Vec<u8>>, EciesErrorCode> {
+    let mut length = [0; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(_) => return Err(EciesErrorCode::IoFailed),
+    }
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| EciesErrorCode::IoFailed)?;
+    Ok(Some(body))
+}
+
+// The named secret keys served by an agent, and the directory generated keys are written to
+#[derive(Default)]
+pub struct Keyring {
+    keys: RwLock<HashMap<String, Keypair>>,
+    directory: Option<PathBuf>,
+}
+
+// Names become file names, so they must not reach outside the key directory
+fn check_key_name(name: &str) -> Result<(), EciesErrorCode> {
+    let valid = name.bytes().all(|byte| byte.is_ascii_alphanumeric() || b"-_.".contains(&byte));
+    if name.is_empty() || name.starts_with('.') || !valid {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    Ok(())
+}
+
+impl Keyring {
+    pub fn new() -> Keyring {
+        Keyring::default()
+    }
+
+    // Loads the key files of a directory, which is created if needed
+    pub fn open(directory: &Path) -> Result<Keyring, EciesErrorCode> {
+        fs::create_dir_all(directory).map_err(|_| EciesErrorCode::IoFailed)?;
+        let mut keyring = Keyring { keys: RwLock::default(), directory: Some(directory.to_path_buf()) };
+
+        for entry in fs::read_dir(directory).map_err(|_| EciesErrorCode::IoFailed)? {
+            let path = entry.map_err(|_| EciesErrorCode::IoFailed)?.path();
+            if path.extension().is_none_or(|extension| extension != KEY_FILE_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let mut text = fs::read(&path).map_err(|_| EciesErrorCode::IoFailed)?;
+            let secret_key = std::str::from_utf8(&text)
+                .map_err(|_| EciesErrorCode::InvalidUtf8)
+                .and_then(|text| parse_secret_key_text(text.trim()));
+            // The file contents are a copy of the secret key
+            wipe(&mut text);
+
+            let (secret_key, _) = secret_key?;
+            keyring.insert(name, Keypair::from_secret_key(secret_key));
+        }
+        Ok(keyring)
+    }
+
+    pub fn insert(&mut self, name: &str, keypair: Keypair) {
+        let keys = self.keys.get_mut().unwrap_or_else(PoisonError::into_inner);
+        keys.insert(name.to_string(), keypair);
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        keys.keys().cloned().collect()
+    }
+
+    fn generate(&self, name: &str) -> Result<Vec<u8>, EciesErrorCode> {
+        check_key_name(name)?;
+        let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        if keys.contains_key(name) {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        let keypair = Keypair::generate()?;
+
+        if let Some(directory) = &self.directory {
+            let path = directory.join(format!("{name}.{KEY_FILE_EXTENSION}"));
+            let mut text = format!("{}\n", hex::encode(keypair.secret_key().serialize())).into_bytes();
+            let written = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&text).and_then(|_| file.sync_all()));
+            wipe(&mut text);
+            written.map_err(|_| EciesErrorCode::IoFailed)?;
+        }
+
+        let public_key = keypair.public_key().serialize_compressed().to_vec();
+        keys.insert(name.to_string(), keypair);
+        Ok(public_key)
+    }
+
+    pub fn handle(&self, request: &Request) -> Result<Vec<u8>, EciesErrorCode> {
+        if let Request::Generate { key } = request {
+            return self.generate(key);
+        }
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        let keypair = keys.get(request.key()).ok_or(EciesErrorCode::UnknownKey)?;
+
+        match request {
+            // `Generate` is answered above
+            Request::PublicKey { .. } | Request::Generate { .. } => {
+                Ok(keypair.public_key().serialize_compressed().to_vec())
+            }
+            Request::Encrypt { recipient, message, .. } => {
+                let recipient = parse_public_key(recipient)?;
+                seal(&recipient, &Header::default(), message, Some(keypair.secret_key()))
+            }
+            Request::Decrypt { ciphertext, .. } => {
+                open_or_legacy(keypair.secret_key(), ciphertext).map(|(_, message)| message)
+            }
+            Request::Sign { message, .. } => {
+                let (signature, recovery_id) = sign(&Message::parse(&sha256(message)), keypair.secret_key());
+                let mut signed = signature.serialize().to_vec();
+                signed.push(recovery_id.serialize());
+                Ok(signed)
+            }
+        }
+    }
+
+    // Answers the requests of one connection until the client closes it
+    pub fn serve(&self, stream: &mut (impl Read + Write)) -> Result<(), EciesErrorCode> {
+        while let Some(body) = read_frame(stream)? {
+            let result = Request::decode(&body).and_then(|request| self.handle(&request));
+            let mut response = encode_response(&result);
+            let written = write_frame(stream, &response);
+            // Decrypted messages do not stay behind in the agent's memory
+            wipe(&mut response);
+            if let Ok(mut message) = result {
+                wipe(&mut message);
+            }
+            written?;
+        }
+        Ok(())
+    }
+}
+
+// Keyrings of the helper process, one per key directory, shared by every connection it serves
+static SHARED_KEYRINGS: Mutex<Vec<(Option<PathBuf>, Arc<Keyring>)>> = Mutex::new(Vec::new());
+
+pub fn shared_keyring(directory: Option<&Path>) -> Result<Arc<Keyring>, EciesErrorCode> {
+    let mut keyrings = SHARED_KEYRINGS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, keyring)) = keyrings.iter().find(|(existing, _)| existing.as_deref() == directory) {
+        return Ok(Arc::clone(keyring));
+    }
+    let keyring = Arc::new(match directory {
+        Some(directory) => Keyring::open(directory)?,
+        None => Keyring::new(),
+    });
+    keyrings.push((directory.map(Path::to_path_buf), Arc::clone(&keyring)));
+    Ok(keyring)
+}
+
+// Takes ownership of a connected stream socket descriptor
+pub unsafe fn stream_from_fd(fd: c_int) -> Result<UnixStream, EciesErrorCode> {
+    if fd < 0 {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    Ok(unsafe { UnixStream::from_raw_fd(fd) })
+}
+
+// A connection to an agent
+pub struct EciesAgent {
+    stream: UnixStream,
+}
+
+impl EciesAgent {
+    pub fn connect(socket_path: &str) -> Result<EciesAgent, EciesErrorCode> {
+        let stream = UnixStream::connect(socket_path).map_err(|_| EciesErrorCode::IoFailed)?;
+        Ok(EciesAgent { stream })
+    }
+
+    pub fn from_stream(stream: UnixStream) -> EciesAgent {
+        EciesAgent { stream }
+    }
+
+    pub fn call(&mut self, request: &Request) -> Result<Vec<u8>, EciesErrorCode> {
+        write_frame(&mut self.stream, &request.encode())?;
+        let body = read_frame(&mut self.stream)?.ok_or(EciesErrorCode::IoFailed)?;
+
+        decode_response(&body)
+    }
+}
+
+/**
+Connects to the agent listening on the Unix domain socket at the given path. Returns null on failure, with
+`IoFailed` when no agent accepts the connection.
+Use the handle from one thread at a time and release it with `ecies_agent_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_connect(
+    socket_path_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesAgent {
+    let result = (|| EciesAgent::connect(str_from_ptr(socket_path_ptr)?).map(Box::new))();
+
+    finish_handle(result, error_ptr)
+}
+
+unsafe fn call(agent_ptr: *mut EciesAgent, request: &Request) -> Result<Vec<u8>, EciesErrorCode> {
+    let agent = unsafe { agent_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+    agent.call(request)
+}
+
+/**
+Wraps one end of a connected socket, e.g. from `socketpair`, whose other end is served by a helper process with
+`ecies_agent_serve_fd`. Takes ownership of the descriptor, which `ecies_agent_free` closes.
+Returns null on failure. Use the handle from one thread at a time.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_from_fd(fd: c_int, error_ptr: *mut EciesErrorCode) -> *mut EciesAgent {
+    let result = stream_from_fd(fd).map(|stream| Box::new(EciesAgent::from_stream(stream)));
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Serves agent requests on a connected socket until the client closes it, blocking the calling thread, for helper
+processes holding the keys of an app. Takes ownership of the descriptor. Keys are loaded from and generated into
+`key_directory_ptr`, created if needed, or kept in memory only when it is null; connections served with the same
+directory share their keys. Returns false on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_serve_fd(
+    fd: c_int,
+    key_directory_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let mut stream = stream_from_fd(fd)?;
+        let directory = if key_directory_ptr.is_null() {
+            None
+        } else {
+            Some(Path::new(str_from_ptr(key_directory_ptr)?))
+        };
+
+        shared_keyring(directory)?.serve(&mut stream)
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Returns the public key of the named agent key, hexadecimal and compressed, as a C string, or null on failure with
+`UnknownKey` when the agent holds no key with that name.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_public_key(
+    agent_ptr: *mut EciesAgent,
+    key_name_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let request = Request::PublicKey { key: str_from_ptr(key_name_ptr)?.to_string() };
+        let public_key = call(agent_ptr, &request)?;
+
+        string_into_ptr(hex::encode(public_key))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Creates a new key in the agent under the given name, written to its key directory when it has one, and returns
+its public key, hexadecimal and compressed, as a C string. Returns null on failure, with `InvalidArgument` when
+the name is taken or is not made of ASCII letters, digits, `-`, `_` and `.`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_generate_key(
+    agent_ptr: *mut EciesAgent,
+    key_name_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let request = Request::Generate { key: str_from_ptr(key_name_ptr)?.to_string() };
+        let public_key = call(agent_ptr, &request)?;
+
+        string_into_ptr(hex::encode(public_key))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Encrypts a message for the given public key in the agent, signed with the named agent key.
+It takes the recipient public key (hexadecimal or base64) and the message as C strings and returns the base64
+envelope as a C string, as `ecies_encrypt_signed`, or null on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_encrypt(
+    agent_ptr: *mut EciesAgent,
+    key_name_ptr: *const c_char,
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let (recipient, _) = decode_key_text(str_from_ptr(public_key_ptr)?)?;
+        let request = Request::Encrypt {
+            key: str_from_ptr(key_name_ptr)?.to_string(),
+            recipient: recipient.to_vec(),
+            message: bytes_from_ptr(message_ptr)?.to_vec(),
+        };
+        let envelope = call(agent_ptr, &request)?;
+
+        string_into_ptr(encode_base64(&envelope, EciesBase64Flavor::Standard))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts a ciphertext in the agent with the named agent key.
+It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns the message
+as a C string, or null on failure. Sender signatures are verified when present.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_decrypt(
+    agent_ptr: *mut EciesAgent,
+    key_name_ptr: *const c_char,
+    ciphertext_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let request = Request::Decrypt {
+            key: str_from_ptr(key_name_ptr)?.to_string(),
+            ciphertext: decode_text(bytes_from_ptr(ciphertext_ptr)?)?,
+        };
+        let message = call(agent_ptr, &request)?;
+
+        // The message is returned as text, so it must be UTF-8 without interior NULs
+        string_into_ptr(String::from_utf8(message).map_err(|_| EciesErrorCode::InvalidUtf8)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Signs a message in the agent with the named agent key: the 64-byte compact ECDSA signature of its SHA-256
+digest followed by the 1-byte recovery ID. The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_sign(
+    agent_ptr: *mut EciesAgent,
+    key_name_ptr: *const c_char,
+    message_ptr: *const u8,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let request = Request::Sign {
+            key: str_from_ptr(key_name_ptr)?.to_string(),
+            message: slice_from_raw(message_ptr, message_length)?.to_vec(),
+        };
+
+        call(agent_ptr, &request)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_agent_free(agent_ptr: *mut EciesAgent) {
+    if !agent_ptr.is_null() {
+        drop(unsafe { Box::from_raw(agent_ptr) });
+    }
+}
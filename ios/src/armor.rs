@@ -0,0 +1,30 @@
+use std::os::raw::c_char;
+
+pub use ecies_core::armor::*;
+
+use crate::envelope::{seal_with_options, EciesEncryptOptions};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, string_into_ptr};
+
+/**
+Encrypts a message into an envelope using the provided public key and returns it ASCII-armored.
+Takes the same options as `ecies_encrypt_with_options`. Armored ciphertexts are accepted by `ecies_decrypt`
+and `ecies_decrypt_with_options`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_armored(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        string_into_ptr(armor(&envelope))
+    })();
+
+    finish(result, error_ptr)
+}
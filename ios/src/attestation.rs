@@ -0,0 +1,257 @@
+use std::os::raw::c_char;
+use openssl::x509::X509;
+use serde::Serialize;
+
+use crate::buffer::slice_from_raw;
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, string_into_ptr};
+use crate::pki::is_pem;
+
+/*
+Android key attestation: the KeyDescription extension (OID 1.3.6.1.4.1.11129.2.1.17) that Keystore adds to the leaf
+certificate of an attested key, as returned by `KeyStore.getCertificateChain(alias)[0].getEncoded()`.
+
+Only the fields needed to check hardware backing are reported: the security levels of the attestation and of the
+key (software, TEE or StrongBox), the attestation challenge, and the root of trust with the verified boot state.
+The certificate chain itself is not verified here; hosts must still check it up to the Google attestation root
+before trusting what the extension says.
+
+The extension is DER, read with the small TLV reader below rather than a full ASN.1 implementation.
+*/
+
+const KEY_ATTESTATION_OID: [u8; 10] = [0x2B, 0x06, 0x01, 0x04, 0x01, 0xD6, 0x79, 0x02, 0x01, 0x11];
+
+// Identifier class and constructed bits
+const UNIVERSAL: u8 = 0x00;
+const CONSTRUCTED: u8 = 0x20;
+const CONTEXT: u8 = 0x80;
+
+const BOOLEAN: u32 = 1;
+const INTEGER: u32 = 2;
+const OCTET_STRING: u32 = 4;
+const OBJECT_IDENTIFIER: u32 = 6;
+const ENUMERATED: u32 = 10;
+const SEQUENCE: u32 = 16;
+
+// Tags of the AuthorizationList fields read here
+const EXTENSIONS_TAG: u32 = 3;
+const ROOT_OF_TRUST_TAG: u32 = 704;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RootOfTrust {
+    pub verified_boot_key: String,
+    pub device_locked: bool,
+    pub verified_boot_state: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyAttestation {
+    pub attestation_version: i64,
+    pub attestation_security_level: &'static str,
+    pub keymint_version: i64,
+    pub keymint_security_level: &'static str,
+    pub challenge: String,
+    pub root_of_trust: Option<RootOfTrust>,
+}
+
+// One TLV element: the class and constructed bits of its identifier, its tag number and its contents
+struct Element<'a> {
+    class: u8,
+    number: u32,
+    contents: &'a [u8],
+}
+
+fn read_element<'a>(data: &mut &'a [u8]) -> Result<Element<'a>, EciesErrorCode> {
+    let invalid = EciesErrorCode::InvalidAttestation;
+    let (&identifier, mut rest) = data.split_first().ok_or(invalid)?;
+
+    // Tag numbers above 30, such as the AuthorizationList tags, follow in base 128
+    let mut number = u32::from(identifier & 0x1F);
+    if number == 0x1F {
+        number = 0;
+        loop {
+            let (&byte, tail) = rest.split_first().ok_or(invalid)?;
+            rest = tail;
+            if number > u32::MAX >> 7 {
+                return Err(invalid);
+            }
+            number = number << 7 | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+    }
+
+    let (&first, tail) = rest.split_first().ok_or(invalid)?;
+    rest = tail;
+    let length = if first & 0x80 == 0 {
+        usize::from(first)
+    } else {
+        let count = usize::from(first & 0x7F);
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(invalid);
+        }
+        let (bytes, tail) = rest.split_at(count);
+        rest = tail;
+        bytes.iter().fold(0, |length, &byte| length << 8 | usize::from(byte))
+    };
+    if rest.len() < length {
+        return Err(invalid);
+    }
+
+    let (contents, tail) = rest.split_at(length);
+    *data = tail;
+    Ok(Element { class: identifier & 0xE0, number, contents })
+}
+
+fn expect<'a>(data: &mut &'a [u8], class: u8, number: u32) -> Result<&'a [u8], EciesErrorCode> {
+    let element = read_element(data)?;
+    if element.class != class || element.number != number {
+        return Err(EciesErrorCode::InvalidAttestation);
+    }
+    Ok(element.contents)
+}
+
+fn read_integer(data: &mut &[u8], number: u32) -> Result<i64, EciesErrorCode> {
+    let contents = expect(data, UNIVERSAL, number)?;
+    if contents.is_empty() || contents.len() > 8 {
+        return Err(EciesErrorCode::InvalidAttestation);
+    }
+    // Two's complement, sign-extended from the first byte
+    let initial = if contents[0] & 0x80 != 0 { -1 } else { 0 };
+    Ok(contents.iter().fold(initial, |value, &byte| value << 8 | i64::from(byte)))
+}
+
+fn security_level(value: i64) -> Result<&'static str, EciesErrorCode> {
+    match value {
+        0 => Ok("software"),
+        1 => Ok("trusted_environment"),
+        2 => Ok("strongbox"),
+        _ => Err(EciesErrorCode::InvalidAttestation),
+    }
+}
+
+fn verified_boot_state(value: i64) -> Result<&'static str, EciesErrorCode> {
+    match value {
+        0 => Ok("verified"),
+        1 => Ok("self_signed"),
+        2 => Ok("unverified"),
+        3 => Ok("failed"),
+        _ => Err(EciesErrorCode::InvalidAttestation),
+    }
+}
+
+// The contents of the key attestation extension of a DER certificate
+fn attestation_extension(certificate: &[u8]) -> Result<&[u8], EciesErrorCode> {
+    let mut data = certificate;
+    let mut certificate = expect(&mut data, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+    let mut tbs_certificate = expect(&mut certificate, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+
+    // Extensions are the explicitly tagged [3] field, after the mandatory fields and the optional unique IDs
+    while !tbs_certificate.is_empty() {
+        let element = read_element(&mut tbs_certificate)?;
+        if element.class != CONTEXT | CONSTRUCTED || element.number != EXTENSIONS_TAG {
+            continue;
+        }
+        let mut wrapper = element.contents;
+        let mut extensions = expect(&mut wrapper, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+        while !extensions.is_empty() {
+            let mut extension = expect(&mut extensions, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+            if expect(&mut extension, UNIVERSAL, OBJECT_IDENTIFIER)? != KEY_ATTESTATION_OID {
+                continue;
+            }
+            // Skips the `critical` flag when present
+            let mut value = read_element(&mut extension)?;
+            if value.class == UNIVERSAL && value.number == BOOLEAN {
+                value = read_element(&mut extension)?;
+            }
+            if value.class != UNIVERSAL || value.number != OCTET_STRING {
+                return Err(EciesErrorCode::InvalidAttestation);
+            }
+            return Ok(value.contents);
+        }
+    }
+
+    Err(EciesErrorCode::InvalidAttestation)
+}
+
+fn find_root_of_trust(mut authorization_list: &[u8]) -> Result<Option<RootOfTrust>, EciesErrorCode> {
+    while !authorization_list.is_empty() {
+        let element = read_element(&mut authorization_list)?;
+        if element.class != CONTEXT | CONSTRUCTED || element.number != ROOT_OF_TRUST_TAG {
+            continue;
+        }
+        let mut wrapper = element.contents;
+        let mut root_of_trust = expect(&mut wrapper, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+
+        let verified_boot_key = expect(&mut root_of_trust, UNIVERSAL, OCTET_STRING)?;
+        let device_locked = expect(&mut root_of_trust, UNIVERSAL, BOOLEAN)?;
+        let state = read_integer(&mut root_of_trust, ENUMERATED)?;
+
+        return Ok(Some(RootOfTrust {
+            verified_boot_key: hex::encode(verified_boot_key),
+            device_locked: device_locked.first().is_some_and(|&byte| byte != 0),
+            verified_boot_state: verified_boot_state(state)?,
+        }));
+    }
+
+    Ok(None)
+}
+
+// Parses the attestation of a DER or PEM certificate
+pub fn parse_key_attestation(data: &[u8]) -> Result<KeyAttestation, EciesErrorCode> {
+    let certificate = if is_pem(data) { X509::from_pem(data) } else { X509::from_der(data) };
+    let der = certificate
+        .and_then(|certificate| certificate.to_der())
+        .map_err(|_| EciesErrorCode::InvalidCertificate)?;
+
+    let mut extension = attestation_extension(&der)?;
+    let mut description = expect(&mut extension, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+    let attestation_version = read_integer(&mut description, INTEGER)?;
+    let attestation_security_level = security_level(read_integer(&mut description, ENUMERATED)?)?;
+    let keymint_version = read_integer(&mut description, INTEGER)?;
+    let keymint_security_level = security_level(read_integer(&mut description, ENUMERATED)?)?;
+    let challenge = expect(&mut description, UNIVERSAL, OCTET_STRING)?;
+    expect(&mut description, UNIVERSAL, OCTET_STRING)?; // uniqueId
+    let software_enforced = expect(&mut description, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+    let hardware_enforced = expect(&mut description, UNIVERSAL | CONSTRUCTED, SEQUENCE)?;
+
+    // Hardware-backed keys report the root of trust as hardware enforced, software attestations as software enforced
+    let root_of_trust = match find_root_of_trust(hardware_enforced)? {
+        Some(root_of_trust) => Some(root_of_trust),
+        None => find_root_of_trust(software_enforced)?,
+    };
+
+    Ok(KeyAttestation {
+        attestation_version,
+        attestation_security_level,
+        keymint_version,
+        keymint_security_level,
+        challenge: hex::encode(challenge),
+        root_of_trust,
+    })
+}
+
+/**
+Parses the Android key attestation extension of a certificate.
+It takes the DER or PEM bytes of the leaf certificate of an attested Keystore key and their length, and returns a
+JSON C string with the attestation and KeyMint versions and security levels (`software`, `trusted_environment` or
+`strongbox`), the hex attestation challenge, and the root of trust (verified boot key, device locked flag and
+verified boot state) when present. Certificates without the extension fail with `InvalidAttestation`. The
+certificate chain is not verified.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_parse_key_attestation(
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let attestation = parse_key_attestation(slice_from_raw(data_ptr, data_length)?)?;
+        let json = serde_json::to_string(&attestation).map_err(|_| EciesErrorCode::InvalidJson)?;
+
+        string_into_ptr(json)
+    })();
+
+    finish(result, error_ptr)
+}
@@ -0,0 +1,47 @@
+use std::os::raw::c_void;
+use std::sync::{Mutex, PoisonError};
+
+pub use ecies_core::audit::*;
+
+/*
+The audit hook of `ecies_core::audit` for C hosts: the callback receives an `EciesAuditEvent` for every key
+generation, encryption and decryption, including the failed ones, so enterprise deployments can feed their audit
+trail (a tamper-evident log, a SIEM, ...) without wrapping every call.
+
+Events are reported by the C API and by every binding built on this library alike. The event pointer is only
+valid during the call, and the callback runs on the thread of the operation, possibly several at once.
+*/
+
+pub type EciesAuditCallback = Option<unsafe extern "C" fn(context: *mut c_void, event: *const EciesAuditEvent)>;
+
+// The host context only travels to the threads of the operations to be handed back to the host
+#[derive(Clone, Copy)]
+struct Host {
+    callback: EciesAuditCallback,
+    context: *mut c_void,
+}
+
+unsafe impl Send for Host {}
+
+static HOST: Mutex<Option<Host>> = Mutex::new(None);
+
+// Called outside of the lock, so the callback may take its time without holding back other threads
+fn forward(event: &EciesAuditEvent) {
+    let host = *HOST.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(Host { callback: Some(callback), context }) = host {
+        unsafe { callback(context, event) }
+    }
+}
+
+/**
+Installs the audit callback, called with `context` and the event of every key generation, encryption and
+decryption, or removes it when `callback` is null. The callback must not call back into the library, and
+`context` must stay valid until the callback is replaced or removed.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_set_audit_hook(callback: EciesAuditCallback, context: *mut c_void) {
+    let mut host = HOST.lock().unwrap_or_else(PoisonError::into_inner);
+    *host = callback.is_some().then_some(Host { callback, context });
+
+    set_audit_hook(host.is_some().then_some(forward as AuditHook));
+}
@@ -0,0 +1,136 @@
+use std::os::raw::c_char;
+use ecies::{PublicKey, SecretKey};
+
+use crate::armor::decode_text;
+use crate::buffer::string_array_into_ptr;
+use crate::envelope::{open_or_legacy, seal_base64};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish_array, public_key_from_ptr, secret_key_from_ptr, string_into_ptr, write_error};
+
+/*
+Batch operations, amortizing the cost of crossing the FFI (and JNI) boundary when syncing hundreds of small records.
+Keys are parsed once per call and every item produces the same output as the single-item function.
+*/
+
+fn encrypt_all(public_keys: &[PublicKey], messages: &[&[u8]]) -> Result<Vec<String>, EciesErrorCode> {
+    let mut ciphertexts = Vec::with_capacity(messages.len() * public_keys.len());
+    for message in messages {
+        for public_key in public_keys {
+            let ciphertext = seal_base64(public_key, message)?;
+            ciphertexts.push(ciphertext.into_string().map_err(|_| EciesErrorCode::InvalidUtf8)?);
+        }
+    }
+    Ok(ciphertexts)
+}
+
+// Same steps as `ecies_decrypt`, for one item of a batch
+unsafe fn decrypt_ptr(secret_key: &SecretKey, ciphertext_ptr: *const c_char) -> Result<*const c_char, EciesErrorCode> {
+    let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+    let (_, decrypted) = open_or_legacy(secret_key, &ciphertext)?;
+
+    string_into_ptr(decrypted)
+}
+
+// Messages are read as raw bytes, like the message of `ecies_encrypt`
+unsafe fn messages_from_ptr<'a>(
+    messages_ptr: *const *const c_char,
+    count: usize,
+) -> Result<Vec<&'a [u8]>, EciesErrorCode> {
+    if messages_ptr.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let ptrs = unsafe { std::slice::from_raw_parts(messages_ptr, count) };
+    ptrs.iter().map(|ptr| bytes_from_ptr(*ptr)).collect()
+}
+
+/**
+Encrypts an array of `count` messages for the given public key, like `ecies_encrypt` on each.
+Returns an array of `count` ciphertexts in the same order, to release with `ecies_string_array_free`, or null on
+failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_batch(
+    public_key_ptr: *const c_char,
+    messages_ptr: *const *const c_char,
+    count: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let messages = messages_from_ptr(messages_ptr, count)?;
+
+        string_array_into_ptr(encrypt_all(&[public_key], &messages)?).map(|(ptrs, _)| ptrs)
+    })();
+
+    finish_array(result, error_ptr)
+}
+
+/**
+Encrypts an array of `count` messages for each of `public_key_count` recipients.
+Returns an array of `count * public_key_count` ciphertexts, grouped by message: the ciphertext of message `i` for
+recipient `j` is at index `i * public_key_count + j`. Release it with `ecies_string_array_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_batch_multi(
+    public_keys_ptr: *const *const c_char,
+    public_key_count: usize,
+    messages_ptr: *const *const c_char,
+    count: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut *const c_char {
+    let result = (|| {
+        if public_keys_ptr.is_null() {
+            return Err(EciesErrorCode::NullPointer);
+        }
+        let public_key_ptrs = unsafe { std::slice::from_raw_parts(public_keys_ptr, public_key_count) };
+        let public_keys = public_key_ptrs
+            .iter()
+            .map(|ptr| public_key_from_ptr(*ptr))
+            .collect::<Result<Vec<_>, _>>()?;
+        let messages = messages_from_ptr(messages_ptr, count)?;
+
+        string_array_into_ptr(encrypt_all(&public_keys, &messages)?).map(|(ptrs, _)| ptrs)
+    })();
+
+    finish_array(result, error_ptr)
+}
+
+/**
+Decrypts an array of `count` ciphertexts with the given secret key, like `ecies_decrypt` on each.
+A failing item does not fail the batch: its entry in the returned array is null and, when `statuses_ptr` points to
+an array of `count` error codes, its code is written at the same index (`Success` for the items that decrypted).
+The returned array is released with `ecies_string_array_free`. Null is only returned when the secret key or the
+array itself is invalid, with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_batch(
+    secret_key_ptr: *const c_char,
+    ciphertexts_ptr: *const *const c_char,
+    count: usize,
+    statuses_ptr: *mut EciesErrorCode,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut *const c_char {
+    let result = (|| {
+        if ciphertexts_ptr.is_null() {
+            return Err(EciesErrorCode::NullPointer);
+        }
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let ciphertexts = unsafe { std::slice::from_raw_parts(ciphertexts_ptr, count) };
+
+        let decrypted: Vec<*const c_char> = ciphertexts
+            .iter()
+            .enumerate()
+            .map(|(index, ciphertext_ptr)| {
+                let result = decrypt_ptr(&secret_key, *ciphertext_ptr);
+                if !statuses_ptr.is_null() {
+                    write_error(unsafe { statuses_ptr.add(index) }, result.err().unwrap_or(EciesErrorCode::Success));
+                }
+                result.unwrap_or(std::ptr::null())
+            })
+            .collect();
+
+        Ok(Box::into_raw(decrypted.into_boxed_slice()) as *mut *const c_char)
+    })();
+
+    finish_array(result, error_ptr)
+}
@@ -0,0 +1,148 @@
+use std::os::raw::c_char;
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr, write_error};
+
+/*
+Uniform Resources (bc-ur) with fountain-coded multipart fragments, for moving large ciphertexts and key backups
+between air-gapped devices through animated QR codes.
+
+The encoder produces an endless sequence of `ur:ecies-envelope/...` parts: the first `fragment_count` parts carry the
+data as-is, later ones are fountain-coded mixes, so the receiver can finish from any sufficiently large subset of
+scanned frames. Binary envelopes such as the output of `ecies_encrypt_cbor` are a natural payload.
+*/
+
+const UR_TYPE: &str = "ecies-envelope";
+
+pub struct EciesUrEncoder {
+    encoder: ur::Encoder,
+}
+
+pub struct EciesUrDecoder {
+    decoder: ur::Decoder,
+}
+
+/**
+Creates an encoder for the given bytes, split into fragments of at most `max_fragment_length` bytes.
+Returns null on failure. Release with `ecies_ur_encoder_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_encoder_new(
+    data_ptr: *const u8,
+    data_length: usize,
+    max_fragment_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesUrEncoder {
+    let result = (|| {
+        let data = slice_from_raw(data_ptr, data_length)?;
+        let encoder = ur::Encoder::new(data, max_fragment_length, UR_TYPE).map_err(|_| EciesErrorCode::InvalidUr)?;
+        Ok(Box::new(EciesUrEncoder { encoder }))
+    })();
+
+    match result {
+        Ok(encoder) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            Box::into_raw(encoder)
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Number of parts needed to carry the data without fountain coding, useful for progress indicators
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_encoder_fragment_count(encoder_ptr: *const EciesUrEncoder) -> usize {
+    match unsafe { encoder_ptr.as_ref() } {
+        Some(encoder) => encoder.encoder.fragment_count(),
+        None => 0,
+    }
+}
+
+/**
+Returns the next UR part as a C string, to be shown as the next QR frame.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_encoder_next_part(
+    encoder_ptr: *mut EciesUrEncoder,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let encoder = unsafe { encoder_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+        let part = encoder.encoder.next_part().map_err(|_| EciesErrorCode::InvalidUr)?;
+        string_into_ptr(part)
+    })();
+
+    finish(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_encoder_free(encoder_ptr: *mut EciesUrEncoder) {
+    if !encoder_ptr.is_null() {
+        drop(unsafe { Box::from_raw(encoder_ptr) });
+    }
+}
+
+/**
+Creates a decoder that accumulates scanned UR parts. Release with `ecies_ur_decoder_free`.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_ur_decoder_new() -> *mut EciesUrDecoder {
+    Box::into_raw(Box::new(EciesUrDecoder { decoder: ur::Decoder::default() }))
+}
+
+/**
+Feeds one scanned part to the decoder. Parts may arrive in any order and duplicates are ignored.
+Returns true once the data is complete.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_decoder_receive(
+    decoder_ptr: *mut EciesUrDecoder,
+    part_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let decoder = unsafe { decoder_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+        let part = str_from_ptr(part_ptr)?;
+        decoder.decoder.receive(&part.to_lowercase()).map_err(|_| EciesErrorCode::InvalidUr)?;
+        Ok(decoder.decoder.complete())
+    })();
+
+    match result {
+        Ok(complete) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            complete
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            false
+        }
+    }
+}
+
+/**
+Returns the reassembled data once `ecies_ur_decoder_receive` has reported completion.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_decoder_message(
+    decoder_ptr: *const EciesUrDecoder,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let decoder = unsafe { decoder_ptr.as_ref() }.ok_or(EciesErrorCode::NullPointer)?;
+        let message = decoder.decoder.message().map_err(|_| EciesErrorCode::InvalidUr)?;
+        message.ok_or(EciesErrorCode::IncompleteUr)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_ur_decoder_free(decoder_ptr: *mut EciesUrDecoder) {
+    if !decoder_ptr.is_null() {
+        drop(unsafe { Box::from_raw(decoder_ptr) });
+    }
+}
@@ -0,0 +1,111 @@
+use std::os::raw::c_char;
+use std::ffi::CString;
+
+use crate::error::EciesErrorCode;
+use crate::ffi::string_into_ptr;
+
+/*
+Memory returned across the FFI boundary, and the functions releasing it.
+
+A byte buffer returned across the FFI boundary, for outputs that are binary rather than C strings.
+Ownership is transferred to the caller, who must release it with `ecies_buffer_free`.
+A failed call returns a buffer whose `data` is null.
+*/
+
+#[repr(C)]
+pub struct EciesBuffer {
+    pub data: *mut u8,
+    pub length: usize,
+}
+
+impl EciesBuffer {
+    pub(crate) fn from_vec(bytes: Vec<u8>) -> EciesBuffer {
+        let boxed = bytes.into_boxed_slice();
+        let length = boxed.len();
+        let data = Box::into_raw(boxed) as *mut u8;
+
+        EciesBuffer { data, length }
+    }
+
+    pub(crate) fn null() -> EciesBuffer {
+        EciesBuffer { data: std::ptr::null_mut(), length: 0 }
+    }
+}
+
+pub(crate) unsafe fn slice_from_raw<'a>(data: *const u8, length: usize) -> Result<&'a [u8], EciesErrorCode> {
+    if data.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    Ok(unsafe { std::slice::from_raw_parts(data, length) })
+}
+
+// Unwraps an FFI result into a buffer, reporting the error code and returning a null buffer on failure
+pub(crate) unsafe fn finish_buffer(result: Result<Vec<u8>, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> EciesBuffer {
+    match result {
+        Ok(bytes) => {
+            crate::ffi::write_error(error_ptr, EciesErrorCode::Success);
+            EciesBuffer::from_vec(bytes)
+        }
+        Err(code) => {
+            crate::ffi::write_error(error_ptr, code);
+            EciesBuffer::null()
+        }
+    }
+}
+
+/**
+Releases a buffer returned by this library. Passing a null buffer is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_buffer_free(buffer: EciesBuffer) {
+    if buffer.data.is_null() {
+        return;
+    }
+    let slice = std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.length);
+    drop(unsafe { Box::from_raw(slice) });
+}
+
+// Transfers a list of strings to the caller as an array of C strings, returning the array and its length
+pub(crate) fn string_array_into_ptr(strings: Vec<String>) -> Result<(*mut *const c_char, usize), EciesErrorCode> {
+    let mut ptrs = Vec::with_capacity(strings.len());
+    for string in strings {
+        match string_into_ptr(string) {
+            Ok(ptr) => ptrs.push(ptr),
+            // The strings transferred so far are released, the caller never sees them
+            Err(code) => {
+                for ptr in ptrs {
+                    unsafe { ecies_string_free(ptr) };
+                }
+                return Err(code);
+            }
+        }
+    }
+    let boxed = ptrs.into_boxed_slice();
+    let count = boxed.len();
+
+    Ok((Box::into_raw(boxed) as *mut *const c_char, count))
+}
+
+/**
+Releases a C string returned by this library. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_string_free(string_ptr: *const c_char) {
+    if !string_ptr.is_null() {
+        drop(unsafe { CString::from_raw(string_ptr as *mut c_char) });
+    }
+}
+
+/**
+Releases an array of C strings returned by this library, together with every string in it.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_string_array_free(strings_ptr: *mut *const c_char, count: usize) {
+    if strings_ptr.is_null() {
+        return;
+    }
+    let strings = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(strings_ptr, count)) };
+    for string_ptr in strings.iter() {
+        ecies_string_free(*string_ptr);
+    }
+}
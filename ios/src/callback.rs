@@ -0,0 +1,207 @@
+use std::io::{Read, Write};
+use std::os::raw::{c_char, c_void};
+use ecies::{PublicKey, SecretKey};
+
+use crate::audit::{begin, EciesAuditOperation};
+use crate::buffer::slice_from_raw;
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish_status, public_key_from_ptr, secret_key_from_ptr};
+use crate::progress::Progress;
+use crate::stream::{decrypt_io, encrypt_io, frame_size, message_chunks, stream_size, EciesEncryptor};
+
+/*
+Host callbacks as output sinks and input sources, so ciphertext and plaintext can go straight into sockets or
+OutputStreams instead of a result buffer allocated by this library, and be pulled from network streams or content
+providers without staging them in memory.
+
+The write callback receives each piece of output in order, e.g. every frame of an encrypted stream, and returns
+false to abort the operation, which then fails with `EciesErrorCode::IoFailed`. The bytes are only valid during
+the call. Output uses the chunked stream format of `stream.rs`, so the host never holds more than a frame at once.
+
+The read callback fills the buffer it is given with up to `buffer_length` bytes and returns how many it wrote,
+0 at the end of the input, or a negative value on failure, which fails the operation with `IoFailed`.
+*/
+
+pub type EciesWriteCallback =
+    Option<unsafe extern "C" fn(context: *mut c_void, data_ptr: *const u8, data_length: usize) -> bool>;
+
+pub type EciesReadCallback =
+    Option<unsafe extern "C" fn(context: *mut c_void, buffer_ptr: *mut u8, buffer_length: usize) -> isize>;
+
+pub struct CallbackWriter {
+    callback: EciesWriteCallback,
+    context: *mut c_void,
+}
+
+impl CallbackWriter {
+    pub fn new(callback: EciesWriteCallback, context: *mut c_void) -> Result<CallbackWriter, EciesErrorCode> {
+        callback.ok_or(EciesErrorCode::NullPointer)?;
+        Ok(CallbackWriter { callback, context })
+    }
+
+    pub fn emit(&mut self, bytes: &[u8]) -> Result<(), EciesErrorCode> {
+        match self.callback.map(|callback| unsafe { callback(self.context, bytes.as_ptr(), bytes.len()) }) {
+            Some(true) => Ok(()),
+            _ => Err(EciesErrorCode::IoFailed),
+        }
+    }
+}
+
+impl Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.emit(buf).map_err(|_| std::io::Error::other("write callback failed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct CallbackReader {
+    callback: EciesReadCallback,
+    context: *mut c_void,
+}
+
+impl CallbackReader {
+    pub fn new(callback: EciesReadCallback, context: *mut c_void) -> Result<CallbackReader, EciesErrorCode> {
+        callback.ok_or(EciesErrorCode::NullPointer)?;
+        Ok(CallbackReader { callback, context })
+    }
+}
+
+impl Read for CallbackReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.callback.map_or(-1, |callback| unsafe { callback(self.context, buf.as_mut_ptr(), buf.len()) });
+        match usize::try_from(read) {
+            Ok(read) if read <= buf.len() => Ok(read),
+            _ => Err(std::io::Error::other("read callback failed")),
+        }
+    }
+}
+
+// Encrypts a message frame by frame into the sink, reusing a single frame buffer
+pub fn encrypt_to_writer(public_key: &PublicKey, message: &[u8], writer: &mut CallbackWriter) -> Result<(), EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = emit_frames(public_key, message, writer);
+    operation.record(|| public_key.serialize_compressed(), message.len(), result);
+    result.map(|_| ())
+}
+
+// Returns the length of the stream, which is reported here rather than by the encryptor
+fn emit_frames(public_key: &PublicKey, message: &[u8], writer: &mut CallbackWriter) -> Result<usize, EciesErrorCode> {
+    let mut encryptor = EciesEncryptor::new(public_key)?;
+    writer.emit(encryptor.header())?;
+
+    let chunks = message_chunks(message, encryptor.chunk_size());
+    let mut frame = Vec::with_capacity(frame_size(encryptor.chunk_size()));
+    for (index, chunk) in chunks.iter().enumerate() {
+        frame.resize(frame_size(chunk.len()), 0);
+        encryptor.seal_chunk_into(chunk, index + 1 == chunks.len(), &mut frame)?;
+        writer.emit(&frame)?;
+    }
+    Ok(stream_size(message.len(), encryptor.chunk_size()))
+}
+
+pub fn decrypt_to_writer(secret_key: SecretKey, stream: &[u8], writer: &mut CallbackWriter) -> Result<(), EciesErrorCode> {
+    decrypt_io(secret_key, stream, writer, &mut Progress::none())
+}
+
+/**
+Encrypts data for the given public key as a chunked stream, handing each frame to `write_callback` instead of
+returning a buffer. Returns false on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_to_callback(
+    public_key_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    write_callback: EciesWriteCallback,
+    write_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+        let mut writer = CallbackWriter::new(write_callback, write_context)?;
+
+        encrypt_to_writer(&public_key, data, &mut writer)
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Decrypts a chunked stream with the given secret key, handing the plaintext of each authenticated chunk to
+`write_callback`. Returns false on failure with the reason written into `error_ptr`; plaintext already handed over
+must then be discarded.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_to_callback(
+    secret_key_ptr: *const c_char,
+    stream_ptr: *const u8,
+    stream_length: usize,
+    write_callback: EciesWriteCallback,
+    write_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let stream = slice_from_raw(stream_ptr, stream_length)?;
+        let mut writer = CallbackWriter::new(write_callback, write_context)?;
+
+        decrypt_to_writer(secret_key, stream, &mut writer)
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts the input pulled from `read_callback` until it reports the end, handing each frame of the chunked stream
+to `write_callback`. Memory use is bounded by a chunk whatever the input size. Returns false on failure with the
+reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_from_callback(
+    public_key_ptr: *const c_char,
+    read_callback: EciesReadCallback,
+    read_context: *mut c_void,
+    write_callback: EciesWriteCallback,
+    write_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let reader = CallbackReader::new(read_callback, read_context)?;
+        let writer = CallbackWriter::new(write_callback, write_context)?;
+
+        encrypt_io(&public_key, reader, writer, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Decrypts the chunked stream pulled from `read_callback`, handing the plaintext of each authenticated chunk to
+`write_callback`. Returns false on failure with the reason written into `error_ptr`, including `TruncatedStream`
+when the input ends early; plaintext already handed over must then be discarded.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_from_callback(
+    secret_key_ptr: *const c_char,
+    read_callback: EciesReadCallback,
+    read_context: *mut c_void,
+    write_callback: EciesWriteCallback,
+    write_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let reader = CallbackReader::new(read_callback, read_context)?;
+        let writer = CallbackWriter::new(write_callback, write_context)?;
+
+        decrypt_io(secret_key, reader, writer, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
@@ -0,0 +1,71 @@
+use std::os::raw::c_char;
+
+use crate::armor::decode_text;
+use crate::buffer::{finish_buffer, EciesBuffer};
+use crate::envelope::{is_envelope, parse};
+use crate::error::EciesErrorCode;
+use crate::features::sha256;
+use crate::ffi::{bytes_from_ptr, finish, string_into_ptr};
+
+/*
+Canonical serialization of an envelope, so external systems can sign or audit ciphertexts without ambiguity.
+
+The same envelope can travel as standard or URL-safe base64, with or without padding, armored, wrapped in
+whitespace, and so on. Its canonical form is the binary envelope itself:
+
+    magic | version | suite | header length | header | payload | signature?
+
+where the header must be exactly what `Header::encode` writes for its fields: known fields only, in ascending tag
+order, each in its fixed-size encoding (compressed sender key, metadata entries in key order). Every envelope
+produced by this library is canonical. One that is not, e.g. with unknown or reordered fields, is rejected with
+`NonCanonicalEnvelope` rather than rewritten, since its header is authenticated as written.
+Legacy ciphertexts have a single binary form, which is their canonical form.
+
+The envelope digest is the SHA-256 of the canonical form.
+*/
+
+pub fn canonical_bytes(ciphertext: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    if !is_envelope(ciphertext) {
+        return Ok(ciphertext.to_vec());
+    }
+
+    let parsed = parse(ciphertext)?;
+    if parsed.header.encode() != parsed.header_bytes() {
+        return Err(EciesErrorCode::NonCanonicalEnvelope);
+    }
+    Ok(ciphertext.to_vec())
+}
+
+pub fn digest(ciphertext: &[u8]) -> Result<[u8; 32], EciesErrorCode> {
+    Ok(sha256(&canonical_bytes(ciphertext)?))
+}
+
+/**
+Returns the canonical binary form of a ciphertext given in any text form accepted by `ecies_decrypt`.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_canonicalize(ciphertext_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> EciesBuffer {
+    let result = (|| {
+        let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+
+        canonical_bytes(&ciphertext)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Returns the SHA-256 digest of the canonical form of a ciphertext, as a hexadecimal C string, or null on failure
+with the reason written into `error_ptr`. Equal envelopes have equal digests whatever text form they came in.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_envelope_digest(ciphertext_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+
+        string_into_ptr(hex::encode(digest(&ciphertext)?))
+    })();
+
+    finish(result, error_ptr)
+}
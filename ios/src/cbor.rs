@@ -0,0 +1,215 @@
+use std::os::raw::c_char;
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::envelope::{
+    assemble, open_with_options, parse, seal_with_options, EciesDecryptOptions, EciesEncryptOptions, Suite,
+};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, string_into_ptr};
+
+/*
+CBOR encoding of the envelope (RFC 8949), for bandwidth-sensitive transports such as BLE and NFC.
+
+    {
+        1: version (unsigned),
+        2: suite (unsigned),
+        3: header (bytes, the encoded header fields),
+        4: payload (bytes),
+        5: signature (bytes, signed envelopes only)
+    }
+
+Encoding is deterministic: definite lengths, shortest-form integers and map keys in ascending order.
+The decoder rejects anything else, so every envelope has exactly one CBOR form.
+*/
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_MAP: u8 = 5;
+
+const KEY_VERSION: u64 = 1;
+const KEY_SUITE: u64 = 2;
+const KEY_HEADER: u64 = 3;
+const KEY_PAYLOAD: u64 = 4;
+const KEY_SIGNATURE: u64 = 5;
+
+fn write_head(output: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        output.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        output.push(major | 24);
+        output.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        output.push(major | 25);
+        output.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        output.push(major | 26);
+        output.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        output.push(major | 27);
+        output.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_unsigned(output: &mut Vec<u8>, key: u64, value: u64) {
+    write_head(output, MAJOR_UNSIGNED, key);
+    write_head(output, MAJOR_UNSIGNED, value);
+}
+
+fn write_bytes(output: &mut Vec<u8>, key: u64, value: &[u8]) {
+    write_head(output, MAJOR_UNSIGNED, key);
+    write_head(output, MAJOR_BYTES, value.len() as u64);
+    output.extend_from_slice(value);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], EciesErrorCode> {
+        if self.bytes.len() < length {
+            return Err(EciesErrorCode::InvalidCbor);
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], EciesErrorCode> {
+        self.take(N)?.try_into().map_err(|_| EciesErrorCode::InvalidCbor)
+    }
+
+    fn read_head(&mut self, expected_major: u8) -> Result<u64, EciesErrorCode> {
+        let initial = self.take(1)?[0];
+        if initial >> 5 != expected_major {
+            return Err(EciesErrorCode::InvalidCbor);
+        }
+
+        let (value, minimum) = match initial & 0x1f {
+            additional @ 0..=23 => (additional as u64, 0),
+            24 => (self.take(1)?[0] as u64, 24),
+            25 => (u16::from_be_bytes(self.take_array::<2>()?) as u64, u8::MAX as u64 + 1),
+            26 => (u32::from_be_bytes(self.take_array::<4>()?) as u64, u16::MAX as u64 + 1),
+            27 => (u64::from_be_bytes(self.take_array::<8>()?), u32::MAX as u64 + 1),
+            _ => return Err(EciesErrorCode::InvalidCbor),
+        };
+        // Deterministic encoding requires the shortest form
+        if value < minimum {
+            return Err(EciesErrorCode::InvalidCbor);
+        }
+        Ok(value)
+    }
+
+    // Keys must appear in ascending order, which also rules out duplicates
+    fn read_key(&mut self, expected: u64) -> Result<(), EciesErrorCode> {
+        match self.read_head(MAJOR_UNSIGNED)? {
+            key if key == expected => Ok(()),
+            _ => Err(EciesErrorCode::InvalidCbor),
+        }
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], EciesErrorCode> {
+        let length = self.read_head(MAJOR_BYTES)?;
+        let length = usize::try_from(length).map_err(|_| EciesErrorCode::InvalidCbor)?;
+        self.take(length)
+    }
+}
+
+// Converts a binary envelope into its CBOR form
+pub fn encode(envelope: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let parsed = parse(envelope)?;
+
+    let mut output = Vec::with_capacity(envelope.len() + 16);
+    write_head(&mut output, MAJOR_MAP, if parsed.signature.is_some() { 5 } else { 4 });
+    write_unsigned(&mut output, KEY_VERSION, parsed.version as u64);
+    write_unsigned(&mut output, KEY_SUITE, parsed.suite as u64);
+    write_bytes(&mut output, KEY_HEADER, parsed.header_bytes());
+    write_bytes(&mut output, KEY_PAYLOAD, parsed.payload);
+    if let Some(signature) = parsed.signature {
+        write_bytes(&mut output, KEY_SIGNATURE, signature);
+    }
+
+    Ok(output)
+}
+
+// Converts the CBOR form back into the binary envelope
+pub fn decode(cbor: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut reader = Reader { bytes: cbor };
+
+    let entries = reader.read_head(MAJOR_MAP)?;
+    if entries != 4 && entries != 5 {
+        return Err(EciesErrorCode::InvalidCbor);
+    }
+
+    reader.read_key(KEY_VERSION)?;
+    let version = reader.read_head(MAJOR_UNSIGNED)?;
+    let version = u8::try_from(version).map_err(|_| EciesErrorCode::InvalidCbor)?;
+    reader.read_key(KEY_SUITE)?;
+    let suite = reader.read_head(MAJOR_UNSIGNED)?;
+    let suite = Suite::from_byte(u8::try_from(suite).map_err(|_| EciesErrorCode::UnsupportedSuite)?)?;
+    reader.read_key(KEY_HEADER)?;
+    let header_bytes = reader.read_bytes()?;
+    reader.read_key(KEY_PAYLOAD)?;
+    let payload = reader.read_bytes()?;
+    let signature = match entries {
+        5 => {
+            reader.read_key(KEY_SIGNATURE)?;
+            Some(reader.read_bytes()?)
+        }
+        _ => None,
+    };
+
+    if !reader.bytes.is_empty() {
+        return Err(EciesErrorCode::InvalidCbor);
+    }
+
+    assemble(version, suite, header_bytes, payload, signature)
+}
+
+/**
+Encrypts a message into an envelope using the provided public key and returns its CBOR encoding.
+Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_cbor(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        encode(&envelope)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a CBOR-encoded envelope using the provided secret key.
+Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_cbor(
+    secret_key_ptr: *const c_char,
+    cbor_ptr: *const u8,
+    cbor_length: usize,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let cbor = slice_from_raw(cbor_ptr, cbor_length)?;
+
+        let envelope = decode(cbor)?;
+        let (_, decrypted) = open_with_options(&secret_key, &envelope, options_ptr)?;
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
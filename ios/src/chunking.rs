@@ -0,0 +1,177 @@
+use std::os::raw::c_char;
+use rand::RngCore;
+
+use crate::buffer::string_array_into_ptr;
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_array_from_ptr, str_from_ptr, string_into_ptr, write_error};
+use crate::random::rng;
+
+/*
+Multipart chunking for size-limited transports such as SMS or BLE characteristics.
+
+Each chunk is a self-describing line of text:
+
+    ecc1:<group>:<index>:<count>:<checksum>:<data>
+
+    - group: 8 hex characters shared by all chunks of one ciphertext, so interleaved messages can be told apart
+    - index: position of the chunk, starting at 0, and count: total number of chunks
+    - checksum: CRC-32 (8 hex characters) of "<group>:<index>:<count>:<data>"
+    - data: the next slice of the ciphertext string
+
+Chunks can arrive in any order; joining them checks every checksum and that none is missing.
+*/
+
+const PREFIX: &str = "ecc1";
+const GROUP_LENGTH: usize = 8;
+const CHECKSUM_LENGTH: usize = 8;
+
+fn checksum(group: &str, index: usize, count: usize, data: &str) -> String {
+    let checked = format!("{}:{}:{}:{}", group, index, count, data);
+    format!("{:08x}", crc32fast::hash(checked.as_bytes()))
+}
+
+// Length of everything but the data in the largest chunk of a split into `count` chunks
+fn overhead(count: usize) -> usize {
+    let digits = count.to_string().len();
+    PREFIX.len() + GROUP_LENGTH + CHECKSUM_LENGTH + digits * 2 + 5
+}
+
+pub fn split(ciphertext: &str, max_chunk_length: usize) -> Result<Vec<String>, EciesErrorCode> {
+    if !ciphertext.is_ascii() {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+
+    // The chunk count depends on the overhead, which depends on the number of digits of the chunk count
+    let mut count = 1;
+    let capacity = loop {
+        let capacity = max_chunk_length.checked_sub(overhead(count)).filter(|capacity| *capacity > 0)
+            .ok_or(EciesErrorCode::InvalidArgument)?;
+        let needed = ciphertext.len().div_ceil(capacity).max(1);
+        if needed <= count {
+            break capacity;
+        }
+        count = needed;
+    };
+
+    let mut group_bytes = [0u8; GROUP_LENGTH / 2];
+    rng()?.fill_bytes(&mut group_bytes);
+    let group = hex::encode(group_bytes);
+
+    let slices: Vec<&str> = match ciphertext.is_empty() {
+        true => vec![""],
+        // The ciphertext is ASCII, so byte offsets are character boundaries
+        false => ciphertext.as_bytes().chunks(capacity).map(|slice| std::str::from_utf8(slice).unwrap_or_default()).collect(),
+    };
+    let count = slices.len();
+
+    Ok(slices
+        .iter()
+        .enumerate()
+        .map(|(index, data)| {
+            let checksum = checksum(&group, index, count, data);
+            format!("{}:{}:{}:{}:{}:{}", PREFIX, group, index, count, checksum, data)
+        })
+        .collect())
+}
+
+struct Chunk<'a> {
+    group: &'a str,
+    index: usize,
+    count: usize,
+    data: &'a str,
+}
+
+fn parse_chunk(chunk: &str) -> Result<Chunk<'_>, EciesErrorCode> {
+    let mut fields = chunk.trim().splitn(6, ':');
+    let mut next = || fields.next().ok_or(EciesErrorCode::InvalidChunk);
+
+    if next()? != PREFIX {
+        return Err(EciesErrorCode::InvalidChunk);
+    }
+    let group = next()?;
+    let index = next()?.parse().map_err(|_| EciesErrorCode::InvalidChunk)?;
+    let count = next()?.parse().map_err(|_| EciesErrorCode::InvalidChunk)?;
+    let expected_checksum = next()?;
+    let data = next()?;
+
+    if group.len() != GROUP_LENGTH || index >= count || checksum(group, index, count, data) != expected_checksum {
+        return Err(EciesErrorCode::InvalidChunk);
+    }
+    Ok(Chunk { group, index, count, data })
+}
+
+pub fn join(chunks: &[&str]) -> Result<String, EciesErrorCode> {
+    let chunks = chunks.iter().map(|chunk| parse_chunk(chunk)).collect::<Result<Vec<Chunk>, EciesErrorCode>>()?;
+    let first = chunks.first().ok_or(EciesErrorCode::IncompleteChunks)?;
+    // The count comes from the chunk itself, so it is bounded before it sizes an allocation. Fewer chunks than
+    // announced can never complete the group
+    if first.count > chunks.len() {
+        return Err(EciesErrorCode::IncompleteChunks);
+    }
+
+    let mut slots: Vec<Option<&str>> = vec![None; first.count];
+    for chunk in &chunks {
+        if chunk.group != first.group || chunk.count != first.count {
+            return Err(EciesErrorCode::InvalidChunk);
+        }
+        // A chunk received twice is fine as long as it is the same chunk
+        match slots[chunk.index] {
+            Some(data) if data != chunk.data => return Err(EciesErrorCode::InvalidChunk),
+            _ => slots[chunk.index] = Some(chunk.data),
+        }
+    }
+
+    slots.into_iter().collect::<Option<String>>().ok_or(EciesErrorCode::IncompleteChunks)
+}
+
+/**
+Splits a ciphertext string (e.g. the output of `ecies_encrypt`) into chunks of at most `max_chunk_length` characters.
+Returns an array of `*chunk_count_ptr` C strings, to be released with `ecies_string_array_free`, or null on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_split_envelope(
+    ciphertext_ptr: *const c_char,
+    max_chunk_length: usize,
+    chunk_count_ptr: *mut usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut *const c_char {
+    let result = (|| {
+        let ciphertext = str_from_ptr(ciphertext_ptr)?;
+        let chunks = split(ciphertext, max_chunk_length)?;
+        string_array_into_ptr(chunks)
+    })();
+
+    match result {
+        Ok((chunks_ptr, count)) => {
+            if !chunk_count_ptr.is_null() {
+                unsafe { *chunk_count_ptr = count };
+            }
+            write_error(error_ptr, EciesErrorCode::Success);
+            chunks_ptr
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/**
+Reassembles the ciphertext string from its chunks, given in any order.
+Fails with `EciesErrorCode::InvalidChunk` on a corrupted or foreign chunk, and `EciesErrorCode::IncompleteChunks`
+while chunks are still missing.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_join_chunks(
+    chunks_ptr: *const *const c_char,
+    chunk_count: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let chunks = str_array_from_ptr(chunks_ptr, chunk_count)?;
+
+        string_into_ptr(join(&chunks)?)
+    })();
+
+    finish(result, error_ptr)
+}
@@ -0,0 +1,230 @@
+use std::os::raw::c_char;
+
+use crate::armor::decode_text;
+use crate::buffer::{ecies_buffer_free, EciesBuffer};
+use crate::cipher::{NONCE_LENGTH, PUBLIC_KEY_LENGTH, TAG_LENGTH};
+use crate::encoding::{encode_base64, EciesBase64Flavor};
+use crate::envelope::{is_envelope, parse, Suite, MAGIC};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, string_into_ptr, write_error};
+use crate::sec1::MAC_LENGTH;
+
+/*
+The parts of a ciphertext, for callers storing them separately or running their own validation.
+    - header: the authenticated envelope prefix (magic, version, suite and header fields), empty for legacy ciphertexts
+    - ephemeral_public_key: the uncompressed ephemeral public key (65 bytes)
+    - nonce: the AES-GCM nonce, empty for the SEC 1 suites which use none
+    - body: the encrypted message
+    - tag: the AES-GCM tag, or the HMAC-SHA256 tag of the SEC 1 suites
+    - signature: the sender signature of signed envelopes, empty otherwise
+*/
+
+#[repr(C)]
+pub struct EciesComponents {
+    pub header: EciesBuffer,
+    pub ephemeral_public_key: EciesBuffer,
+    pub nonce: EciesBuffer,
+    pub body: EciesBuffer,
+    pub tag: EciesBuffer,
+    pub signature: EciesBuffer,
+}
+
+pub struct Components<'a> {
+    pub header: &'a [u8],
+    pub ephemeral_public_key: &'a [u8],
+    pub nonce: &'a [u8],
+    pub body: &'a [u8],
+    pub tag: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+fn payload_lengths(suite: Suite) -> (usize, usize) {
+    match suite {
+        Suite::Secp256k1HkdfSha256Aes256Gcm => (NONCE_LENGTH, TAG_LENGTH),
+        Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 | Suite::Secp256k1Kdf2Sha256XorHmacSha256 => (0, MAC_LENGTH),
+    }
+}
+
+// The ephemeral public key, nonce, body and tag of a payload
+type PayloadParts<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
+
+fn split_payload(suite: Suite, payload: &[u8]) -> Result<PayloadParts<'_>, EciesErrorCode> {
+    let (nonce_length, tag_length) = payload_lengths(suite);
+    if payload.len() < PUBLIC_KEY_LENGTH + nonce_length + tag_length {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+    let (ephemeral_public_key, rest) = payload.split_at(PUBLIC_KEY_LENGTH);
+    let (nonce, rest) = rest.split_at(nonce_length);
+
+    match suite {
+        Suite::Secp256k1HkdfSha256Aes256Gcm => {
+            let (tag, body) = rest.split_at(tag_length);
+            Ok((ephemeral_public_key, nonce, body, tag))
+        }
+        Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 | Suite::Secp256k1Kdf2Sha256XorHmacSha256 => {
+            let (body, tag) = rest.split_at(rest.len() - tag_length);
+            Ok((ephemeral_public_key, nonce, body, tag))
+        }
+    }
+}
+
+pub fn split(ciphertext: &[u8]) -> Result<Components<'_>, EciesErrorCode> {
+    if !is_envelope(ciphertext) {
+        let (ephemeral_public_key, nonce, body, tag) = split_payload(Suite::Secp256k1HkdfSha256Aes256Gcm, ciphertext)?;
+        return Ok(Components { header: &[], ephemeral_public_key, nonce, body, tag, signature: &[] });
+    }
+
+    let parsed = parse(ciphertext)?;
+    let (ephemeral_public_key, nonce, body, tag) = split_payload(parsed.suite, parsed.payload)?;
+    Ok(Components {
+        header: parsed.authenticated,
+        ephemeral_public_key,
+        nonce,
+        body,
+        tag,
+        signature: parsed.signature.unwrap_or(&[]),
+    })
+}
+
+pub fn join(components: &Components) -> Result<Vec<u8>, EciesErrorCode> {
+    let suite = if components.header.is_empty() {
+        if !components.signature.is_empty() {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        Suite::Secp256k1HkdfSha256Aes256Gcm
+    } else {
+        let suite = components.header.get(MAGIC.len() + 1).ok_or(EciesErrorCode::InvalidEnvelope)?;
+        Suite::from_byte(*suite)?
+    };
+
+    let (nonce_length, tag_length) = payload_lengths(suite);
+    if components.ephemeral_public_key.len() != PUBLIC_KEY_LENGTH
+        || components.nonce.len() != nonce_length
+        || components.tag.len() != tag_length
+    {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+
+    let mut ciphertext = components.header.to_vec();
+    ciphertext.extend_from_slice(components.ephemeral_public_key);
+    ciphertext.extend_from_slice(components.nonce);
+    match suite {
+        Suite::Secp256k1HkdfSha256Aes256Gcm => {
+            ciphertext.extend_from_slice(components.tag);
+            ciphertext.extend_from_slice(components.body);
+        }
+        Suite::Secp256k1Kdf2Sha256Aes256CbcHmacSha256 | Suite::Secp256k1Kdf2Sha256XorHmacSha256 => {
+            ciphertext.extend_from_slice(components.body);
+            ciphertext.extend_from_slice(components.tag);
+        }
+    }
+    ciphertext.extend_from_slice(components.signature);
+
+    // Catches headers and signatures that do not belong together before the caller stores the result
+    if !components.header.is_empty() {
+        parse(&ciphertext)?;
+    }
+
+    Ok(ciphertext)
+}
+
+// A null component is read as an empty one
+unsafe fn component_slice<'a>(buffer: &EciesBuffer) -> &'a [u8] {
+    if buffer.data.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(buffer.data, buffer.length) }
+    }
+}
+
+fn empty_components() -> EciesComponents {
+    EciesComponents {
+        header: EciesBuffer::null(),
+        ephemeral_public_key: EciesBuffer::null(),
+        nonce: EciesBuffer::null(),
+        body: EciesBuffer::null(),
+        tag: EciesBuffer::null(),
+        signature: EciesBuffer::null(),
+    }
+}
+
+/**
+Splits a ciphertext into its components.
+It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns its parts.
+On failure every buffer is null and the reason is written into `error_ptr`.
+The result must be released with `ecies_components_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_split_components(
+    ciphertext_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesComponents {
+    let result: Result<EciesComponents, EciesErrorCode> = (|| {
+        let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+        let components = split(&ciphertext)?;
+
+        Ok(EciesComponents {
+            header: EciesBuffer::from_vec(components.header.to_vec()),
+            ephemeral_public_key: EciesBuffer::from_vec(components.ephemeral_public_key.to_vec()),
+            nonce: EciesBuffer::from_vec(components.nonce.to_vec()),
+            body: EciesBuffer::from_vec(components.body.to_vec()),
+            tag: EciesBuffer::from_vec(components.tag.to_vec()),
+            signature: EciesBuffer::from_vec(components.signature.to_vec()),
+        })
+    })();
+
+    match result {
+        Ok(components) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            components
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            empty_components()
+        }
+    }
+}
+
+/**
+Reassembles a ciphertext from its components, the reverse of `ecies_split_components`.
+Empty or null `header` and `signature` buffers produce a legacy ciphertext. It returns the ciphertext as a base64
+C string, as returned by `ecies_encrypt`, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_join_components(
+    components_ptr: *const EciesComponents,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let components = unsafe { components_ptr.as_ref() }.ok_or(EciesErrorCode::NullPointer)?;
+        let components = Components {
+            header: component_slice(&components.header),
+            ephemeral_public_key: component_slice(&components.ephemeral_public_key),
+            nonce: component_slice(&components.nonce),
+            body: component_slice(&components.body),
+            tag: component_slice(&components.tag),
+            signature: component_slice(&components.signature),
+        };
+
+        string_into_ptr(encode_base64(&join(&components)?, EciesBase64Flavor::Standard))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Releases the buffers of components returned by `ecies_split_components`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_components_free(components: EciesComponents) {
+    for buffer in [
+        components.header,
+        components.ephemeral_public_key,
+        components.nonce,
+        components.body,
+        components.tag,
+        components.signature,
+    ] {
+        ecies_buffer_free(buffer);
+    }
+}
@@ -0,0 +1,11 @@
+pub use ecies_core::context::*;
+
+/**
+Builds the shared secp256k1 tables and detects the CPU features up front. Calling it is optional and repeated calls
+are no-ops; hosts call it at startup to move the one-time setup off the latency of their first operation, and
+hosts that want no lazy initialization at all (see the `go` profile) call it before anything else.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_init() {
+    init();
+}
@@ -0,0 +1,286 @@
+use std::os::raw::c_char;
+use ciborium::value::Value;
+use ecies::{PublicKey, SecretKey};
+use hkdf::Hkdf;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::context::{ecdh_point, ephemeral_keypair, public_key_of};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, string_into_ptr};
+use crate::random::rng;
+
+/*
+COSE encryption (RFC 9052 / RFC 9053) for interop with CBOR/COSE ecosystems.
+
+Content is encrypted with A256GCM (algorithm 3) under a key agreed with ECDH-ES + HKDF-256 (algorithm -25)
+between an ephemeral key and the recipient's secp256k1 key (COSE curve 8, RFC 8812).
+
+    COSE_Encrypt (tag 96): [protected {1: 3}, {5: iv}, ciphertext,
+                            [[protected {1: -25}, {-1: ephemeral COSE_Key}, empty ciphertext]]]
+    COSE_Encrypt0 (tag 16): [protected {1: 3}, {5: iv, -1: ephemeral COSE_Key}, ciphertext]
+
+COSE_Encrypt0 has no recipient structure, so the ephemeral key travels in its unprotected header and the KDF
+context is built with the body's protected header instead.
+*/
+
+const TAG_ENCRYPT0: u64 = 16;
+const TAG_ENCRYPT: u64 = 96;
+
+const LABEL_ALGORITHM: i64 = 1;
+const LABEL_IV: i64 = 5;
+const LABEL_EPHEMERAL_KEY: i64 = -1;
+
+const ALGORITHM_A256GCM: i64 = 3;
+const ALGORITHM_ECDH_ES_HKDF_256: i64 = -25;
+
+const KEY_TYPE_EC2: i64 = 2;
+const CURVE_SECP256K1: i64 = 8;
+
+const IV_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesCoseStructure {
+    Encrypt0 = 0,
+    Encrypt = 1,
+}
+
+fn int(value: i64) -> Value {
+    Value::Integer(value.into())
+}
+
+fn to_cbor(value: &Value) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes).map_err(|_| EciesErrorCode::InvalidCose)?;
+    Ok(bytes)
+}
+
+fn map_get(map: &Value, label: i64) -> Option<&Value> {
+    map.as_map()?.iter().find(|(key, _)| *key == int(label)).map(|(_, value)| value)
+}
+
+fn as_i64(value: Option<&Value>) -> Option<i64> {
+    value?.as_integer().and_then(|integer| i64::try_from(integer).ok())
+}
+
+fn protected_header(algorithm: i64) -> Result<Vec<u8>, EciesErrorCode> {
+    to_cbor(&Value::Map(vec![(int(LABEL_ALGORITHM), int(algorithm))]))
+}
+
+fn cose_key(public_key: &PublicKey) -> Value {
+    let serialized = public_key.serialize();
+    Value::Map(vec![
+        (int(1), int(KEY_TYPE_EC2)),
+        (int(-1), int(CURVE_SECP256K1)),
+        (int(-2), Value::Bytes(serialized[1..33].to_vec())),
+        (int(-3), Value::Bytes(serialized[33..65].to_vec())),
+    ])
+}
+
+fn public_key_from_cose_key(key: &Value) -> Result<PublicKey, EciesErrorCode> {
+    if as_i64(map_get(key, 1)) != Some(KEY_TYPE_EC2) || as_i64(map_get(key, -1)) != Some(CURVE_SECP256K1) {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+    let x = map_get(key, -2).and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+    let y = map_get(key, -3).and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+
+    let mut serialized = vec![0x04];
+    serialized.extend_from_slice(x);
+    serialized.extend_from_slice(y);
+    PublicKey::parse_slice(&serialized, None).map_err(|_| EciesErrorCode::InvalidCose)
+}
+
+// HKDF-256 over the ECDH x coordinate with the COSE_KDF_Context of RFC 9053, section 5.2, as info. The content key
+// is cleared once the message is encrypted or decrypted
+fn derive_key(
+    public_key: &PublicKey,
+    secret_key: &SecretKey,
+    kdf_protected: &[u8],
+) -> Result<Zeroizing<[u8; KEY_LENGTH]>, EciesErrorCode> {
+    let shared_point = ecdh_point(public_key, secret_key)?;
+    let z = &shared_point.serialize()[1..33];
+
+    let party_info = Value::Array(vec![Value::Null, Value::Null, Value::Null]);
+    let context = Value::Array(vec![
+        int(ALGORITHM_A256GCM),
+        party_info.clone(),
+        party_info,
+        Value::Array(vec![int((KEY_LENGTH * 8) as i64), Value::Bytes(kdf_protected.to_vec())]),
+    ]);
+
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    Hkdf::<Sha256>::new(None, z).expand(&to_cbor(&context)?, &mut key[..]).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    Ok(key)
+}
+
+// Enc_structure of RFC 9052, section 5.3, with empty external AAD
+fn enc_structure(context: &str, protected: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    to_cbor(&Value::Array(vec![
+        Value::Text(context.to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+    ]))
+}
+
+pub fn encrypt(public_key: &PublicKey, message: &[u8], structure: EciesCoseStructure) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_cose(public_key, message, structure);
+    operation.record(|| public_key.serialize_compressed(), message.len(), outcome(&result));
+    result
+}
+
+fn seal_cose(public_key: &PublicKey, message: &[u8], structure: EciesCoseStructure) -> Result<Vec<u8>, EciesErrorCode> {
+    let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+    let protected = protected_header(ALGORITHM_A256GCM)?;
+    let recipient_protected = protected_header(ALGORITHM_ECDH_ES_HKDF_256)?;
+
+    let kdf_protected = match structure {
+        EciesCoseStructure::Encrypt0 => &protected,
+        EciesCoseStructure::Encrypt => &recipient_protected,
+    };
+    let key = derive_key(public_key, &ephemeral_secret_key, kdf_protected)?;
+
+    let mut iv = [0u8; IV_LENGTH];
+    rng()?.fill_bytes(&mut iv);
+    let context = match structure {
+        EciesCoseStructure::Encrypt0 => "Encrypt0",
+        EciesCoseStructure::Encrypt => "Encrypt",
+    };
+    let aad = enc_structure(context, &protected)?;
+
+    let mut tag = [0u8; TAG_LENGTH];
+    let mut ciphertext = encrypt_aead(Cipher::aes_256_gcm(), &key[..], Some(&iv), &aad, message, &mut tag)
+        .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    ciphertext.extend_from_slice(&tag);
+
+    let message = match structure {
+        EciesCoseStructure::Encrypt0 => Value::Tag(TAG_ENCRYPT0, Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![
+                (int(LABEL_IV), Value::Bytes(iv.to_vec())),
+                (int(LABEL_EPHEMERAL_KEY), cose_key(&ephemeral_public_key)),
+            ]),
+            Value::Bytes(ciphertext),
+        ]))),
+        EciesCoseStructure::Encrypt => {
+            let recipient = Value::Array(vec![
+                Value::Bytes(recipient_protected),
+                Value::Map(vec![(int(LABEL_EPHEMERAL_KEY), cose_key(&ephemeral_public_key))]),
+                Value::Bytes(Vec::new()),
+            ]);
+            Value::Tag(TAG_ENCRYPT, Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(vec![(int(LABEL_IV), Value::Bytes(iv.to_vec()))]),
+                Value::Bytes(ciphertext),
+                Value::Array(vec![recipient]),
+            ])))
+        }
+    };
+
+    to_cbor(&message)
+}
+
+pub fn decrypt(secret_key: &SecretKey, cose: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_cose(secret_key, cose);
+    let public_key = || public_key_of(secret_key).serialize_compressed();
+    operation.record(public_key, cose.len(), outcome(&result));
+    result
+}
+
+fn open_cose(secret_key: &SecretKey, cose: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let message: Value = ciborium::de::from_reader(cose).map_err(|_| EciesErrorCode::InvalidCose)?;
+    let (tag, body) = match &message {
+        Value::Tag(tag, body) => (*tag, body.as_ref()),
+        _ => return Err(EciesErrorCode::InvalidCose),
+    };
+    let fields = body.as_array().ok_or(EciesErrorCode::InvalidCose)?;
+
+    let protected = fields.first().and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+    let unprotected = fields.get(1).ok_or(EciesErrorCode::InvalidCose)?;
+    let ciphertext = fields.get(2).and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+
+    let protected_map: Value = ciborium::de::from_reader(&protected[..]).map_err(|_| EciesErrorCode::InvalidCose)?;
+    if as_i64(map_get(&protected_map, LABEL_ALGORITHM)) != Some(ALGORITHM_A256GCM) {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+
+    let (context, ephemeral_key, kdf_protected) = match tag {
+        TAG_ENCRYPT0 => ("Encrypt0", map_get(unprotected, LABEL_EPHEMERAL_KEY), protected.clone()),
+        TAG_ENCRYPT => {
+            let recipients = fields.get(3).and_then(Value::as_array).ok_or(EciesErrorCode::InvalidCose)?;
+            let recipient = recipients.first().and_then(Value::as_array).ok_or(EciesErrorCode::InvalidCose)?;
+            let recipient_protected = recipient.first().and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+            let recipient_protected_map: Value =
+                ciborium::de::from_reader(&recipient_protected[..]).map_err(|_| EciesErrorCode::InvalidCose)?;
+            if as_i64(map_get(&recipient_protected_map, LABEL_ALGORITHM)) != Some(ALGORITHM_ECDH_ES_HKDF_256) {
+                return Err(EciesErrorCode::UnsupportedSuite);
+            }
+            let recipient_unprotected = recipient.get(1).ok_or(EciesErrorCode::InvalidCose)?;
+            ("Encrypt", map_get(recipient_unprotected, LABEL_EPHEMERAL_KEY), recipient_protected.clone())
+        }
+        _ => return Err(EciesErrorCode::InvalidCose),
+    };
+
+    // OpenSSL would accept other GCM nonce lengths, which A256GCM does not allow
+    let iv = map_get(unprotected, LABEL_IV).and_then(Value::as_bytes).ok_or(EciesErrorCode::InvalidCose)?;
+    if iv.len() != IV_LENGTH || ciphertext.len() < TAG_LENGTH {
+        return Err(EciesErrorCode::InvalidCose);
+    }
+
+    let ephemeral_public_key = public_key_from_cose_key(ephemeral_key.ok_or(EciesErrorCode::InvalidCose)?)?;
+    let key = derive_key(&ephemeral_public_key, secret_key, &kdf_protected)?;
+    let (encrypted, tag) = ciphertext.split_at(ciphertext.len() - TAG_LENGTH);
+    let aad = enc_structure(context, protected)?;
+
+    decrypt_aead(Cipher::aes_256_gcm(), &key[..], Some(iv), &aad, encrypted, tag).map_err(|_| EciesErrorCode::MacFailed)
+}
+
+/**
+Encrypts a message for the provided public key as a tagged COSE_Encrypt or COSE_Encrypt0 message.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_cose(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    structure: EciesCoseStructure,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        encrypt(&public_key, message_buffer, structure)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a tagged COSE_Encrypt or COSE_Encrypt0 message produced with ECDH-ES + HKDF-256 and A256GCM.
+Returns the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_cose(
+    secret_key_ptr: *const c_char,
+    cose_ptr: *const u8,
+    cose_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let cose = slice_from_raw(cose_ptr, cose_length)?;
+
+        string_into_ptr(decrypt(&secret_key, cose)?)
+    })();
+
+    finish(result, error_ptr)
+}
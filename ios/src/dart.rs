@@ -0,0 +1,148 @@
+use crate::buffer::slice_from_raw;
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish_status, secret_key_from_raw, write_error, write_raw};
+use crate::keys::{parse_public_key, wipe};
+
+/*
+Exports for Dart FFI, bound by `ffigen` with `ios/ffigen.yaml`, for Flutter apps that skip the JNI layer.
+
+Every buffer is a pointer and a length. Memory crossing the boundary in either direction comes from
+`ecies_dart_alloc` and goes back to `ecies_dart_free` with the same length, so Dart code allocates inputs and
+releases outputs through one allocator and never needs `package:ffi`'s `malloc` to match the library's.
+No state is tied to a thread: the functions can be called from any isolate, including `Isolate.run` workers.
+
+Keys are raw bytes: 32-byte secret keys, 33-byte compressed public keys on output (any layout on input).
+Ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`.
+*/
+
+// Transfers `bytes` to the caller as memory released by `ecies_dart_free`, writing its length
+unsafe fn bytes_into_raw(bytes: Vec<u8>, length_out: *mut usize) -> Result<*mut u8, EciesErrorCode> {
+    if length_out.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let boxed = bytes.into_boxed_slice();
+    unsafe { *length_out = boxed.len() };
+
+    Ok(Box::into_raw(boxed) as *mut u8)
+}
+
+unsafe fn finish_raw(result: Result<*mut u8, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> *mut u8 {
+    match result {
+        Ok(data) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            data
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/**
+Allocates `length` zeroed bytes, to be released with `ecies_dart_free`. The pointer is never null, even for a
+length of 0, so empty inputs can be passed like any other.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_dart_alloc(length: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; length].into_boxed_slice()) as *mut u8
+}
+
+/**
+Clears and releases memory from `ecies_dart_alloc` or returned by the `ecies_dart_*` functions. `length` must be
+the allocated length. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_dart_free(data: *mut u8, length: usize) {
+    if data.is_null() {
+        return;
+    }
+    let mut boxed = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(data, length)) };
+    wipe(&mut boxed);
+}
+
+/**
+Generates a keypair into caller-provided memory: 32 bytes at `secret_key_out` and 33 bytes (compressed) at
+`public_key_out`. Returns false on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_dart_generate_keypair(
+    secret_key_out: *mut u8,
+    public_key_out: *mut u8,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let (secret_key, public_key) = generate_keypair()?;
+        write_raw(secret_key_out, &secret_key.serialize())?;
+        write_raw(public_key_out, &public_key.serialize_compressed())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Writes the 33-byte compressed public key of a 32-byte secret key to `public_key_out`. Returns false on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_dart_public_key_from(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    public_key_out: *mut u8,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        write_raw(public_key_out, &public_key_of(&secret_key).serialize_compressed())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts a message into a binary envelope, writing its length to `envelope_length_out`. Returns null on failure;
+release the result with `ecies_dart_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_dart_encrypt(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    envelope_length_out: *mut usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut u8 {
+    let result = (|| {
+        let public_key = parse_public_key(slice_from_raw(public_key_ptr, public_key_length)?)?;
+        let message = slice_from_raw(message_ptr, message_length)?;
+        let envelope = seal(&public_key, &Header::default(), message, None)?;
+
+        bytes_into_raw(envelope, envelope_length_out)
+    })();
+
+    finish_raw(result, error_ptr)
+}
+
+/**
+Decrypts a binary envelope, or a legacy ciphertext, writing the message length to `message_length_out`.
+Returns null on failure; release the result with `ecies_dart_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_dart_decrypt(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    ciphertext_ptr: *const u8,
+    ciphertext_length: usize,
+    message_length_out: *mut usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut u8 {
+    let result = (|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        let (_, message) = open_or_legacy(&secret_key, slice_from_raw(ciphertext_ptr, ciphertext_length)?)?;
+
+        bytes_into_raw(message, message_length_out)
+    })();
+
+    finish_raw(result, error_ptr)
+}
@@ -0,0 +1,170 @@
+use crate::buffer::slice_from_raw;
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::ffi::{secret_key_from_raw, write_raw};
+use crate::keys::{parse_public_key, wipe};
+
+/*
+Exports for .NET P/Invoke, wrapped by `dotnet/Ecies.cs`.
+
+Every argument and return type is blittable, so the marshaller copies nothing and never guesses: no strings
+(messages and ciphertexts are bytes, keys are raw bytes), no `bool` (which .NET marshals as a 4-byte BOOL),
+only pointers, `usize` (nuint) lengths, the `EciesErrorCode` each function returns, and fixed-layout structs.
+Binary results are `EciesNetBytes` handles, writable into a `SafeHandle` subclass whose `ReleaseHandle` calls
+`ecies_net_bytes_free`.
+
+Ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`.
+*/
+
+pub const ECIES_NET_SECRET_KEY_LENGTH: usize = 32;
+pub const ECIES_NET_PUBLIC_KEY_LENGTH: usize = 33;
+
+// 65 bytes without padding, `[StructLayout(LayoutKind.Sequential)]` with two fixed buffers in C#
+#[repr(C)]
+pub struct EciesNetKeypair {
+    pub secret_key: [u8; ECIES_NET_SECRET_KEY_LENGTH],
+    pub public_key: [u8; ECIES_NET_PUBLIC_KEY_LENGTH],
+}
+
+#[repr(C)]
+pub struct EciesNetPublicKey {
+    pub bytes: [u8; ECIES_NET_PUBLIC_KEY_LENGTH],
+}
+
+// Opaque to .NET, which only holds the pointer
+pub struct EciesNetBytes {
+    bytes: Vec<u8>,
+}
+
+// Decrypted messages pass through these handles, so they are cleared on release
+impl Drop for EciesNetBytes {
+    fn drop(&mut self) {
+        wipe(&mut self.bytes);
+    }
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T) -> Result<(), EciesErrorCode> {
+    if out.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    unsafe { out.write(value) };
+
+    Ok(())
+}
+
+fn status(result: Result<(), EciesErrorCode>) -> EciesErrorCode {
+    result.err().unwrap_or(EciesErrorCode::Success)
+}
+
+/**
+Generates a keypair into `keypair_out`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_generate_keypair(keypair_out: *mut EciesNetKeypair) -> EciesErrorCode {
+    status(generate_keypair().and_then(|(secret_key, public_key)| {
+        let keypair = EciesNetKeypair {
+            secret_key: secret_key.serialize(),
+            public_key: public_key.serialize_compressed(),
+        };
+        write_out(keypair_out, keypair)
+    }))
+}
+
+/**
+Writes the compressed public key of a 32-byte secret key into `public_key_out`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_public_key_from(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    public_key_out: *mut EciesNetPublicKey,
+) -> EciesErrorCode {
+    status((|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        let public_key = EciesNetPublicKey { bytes: public_key_of(&secret_key).serialize_compressed() };
+        write_out(public_key_out, public_key)
+    })())
+}
+
+/**
+Encrypts a message into a binary envelope, stored in a new handle at `bytes_out`, which stays untouched on failure.
+The public key may use any layout accepted by `ecies_convert_public_key`, as raw bytes.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_encrypt(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    bytes_out: *mut *mut EciesNetBytes,
+) -> EciesErrorCode {
+    status((|| {
+        let public_key = parse_public_key(slice_from_raw(public_key_ptr, public_key_length)?)?;
+        let message = slice_from_raw(message_ptr, message_length)?;
+        let envelope = seal(&public_key, &Header::default(), message, None)?;
+        write_out(bytes_out, Box::into_raw(Box::new(EciesNetBytes { bytes: envelope })))
+    })())
+}
+
+/**
+Decrypts a binary envelope, or a legacy ciphertext, into a new handle at `bytes_out`, which stays untouched on
+failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_decrypt(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    ciphertext_ptr: *const u8,
+    ciphertext_length: usize,
+    bytes_out: *mut *mut EciesNetBytes,
+) -> EciesErrorCode {
+    status((|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        let (_, message) = open_or_legacy(&secret_key, slice_from_raw(ciphertext_ptr, ciphertext_length)?)?;
+        write_out(bytes_out, Box::into_raw(Box::new(EciesNetBytes { bytes: message })))
+    })())
+}
+
+/**
+Returns the length of the bytes held by a handle, 0 for null.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_bytes_length(bytes: *const EciesNetBytes) -> usize {
+    unsafe { bytes.as_ref() }.map(|bytes| bytes.bytes.len()).unwrap_or(0)
+}
+
+/**
+Copies the bytes held by a handle into `destination`, which must have room for `ecies_net_bytes_length` bytes,
+or fails with `BufferTooSmall`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_bytes_copy(
+    bytes: *const EciesNetBytes,
+    destination: *mut u8,
+    destination_length: usize,
+) -> EciesErrorCode {
+    let bytes = match unsafe { bytes.as_ref() } {
+        Some(bytes) => &bytes.bytes,
+        None => return EciesErrorCode::NullPointer,
+    };
+    if destination_length < bytes.len() {
+        return EciesErrorCode::BufferTooSmall;
+    }
+    if bytes.is_empty() {
+        return EciesErrorCode::Success;
+    }
+
+    status(write_raw(destination, bytes))
+}
+
+/**
+Releases a handle returned by `ecies_net_encrypt` or `ecies_net_decrypt`, clearing its bytes. Passing null is a
+no-op. This is the `ReleaseHandle` of the `SafeHandle` holding it.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_net_bytes_free(bytes: *mut EciesNetBytes) {
+    if !bytes.is_null() {
+        drop(unsafe { Box::from_raw(bytes) });
+    }
+}
@@ -0,0 +1,97 @@
+use std::os::raw::c_char;
+
+pub use ecies_core::encoding::*;
+
+use crate::envelope::{open_with_options, seal_with_options, EciesDecryptOptions, EciesEncryptOptions};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, str_from_ptr, string_into_ptr};
+
+/**
+Encrypts a message into an envelope using the provided public key and returns it as a lowercase hex C string.
+Takes the same options as `ecies_encrypt_with_options`; `base64_flavor` is ignored.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_hex(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        string_into_ptr(encode_hex(&envelope))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts a hex-encoded envelope (or legacy ciphertext) using the provided secret key.
+Takes the same options as `ecies_decrypt_with_options`. Upper and lower case hex are both accepted.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_hex(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let message_hex = str_from_ptr(message_ptr)?;
+        let envelope = decode_hex(message_hex.trim())?;
+
+        let (_, decrypted) = open_with_options(&secret_key, &envelope, options_ptr)?;
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Encrypts a message into an envelope using the provided public key and returns it as a base45 C string,
+ready to be placed in an alphanumeric-mode QR code. Takes the same options as `ecies_encrypt_with_options`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_base45(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        string_into_ptr(encode_base45(&envelope))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts a base45-encoded envelope using the provided secret key.
+Takes the same options as `ecies_decrypt_with_options`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_base45(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let message_base45 = str_from_ptr(message_ptr)?;
+        let envelope = decode_base45(message_base45)?;
+
+        let (_, decrypted) = open_with_options(&secret_key, &envelope, options_ptr)?;
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
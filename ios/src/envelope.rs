@@ -0,0 +1,314 @@
+use std::os::raw::{c_char, c_void};
+use ecies::{PublicKey, SecretKey};
+
+pub use ecies_core::envelope::*;
+
+use crate::armor::decode_text;
+use crate::buffer::{ecies_string_free, finish_buffer, slice_from_raw, EciesBuffer};
+use crate::encoding::{encode_base64, EciesBase64Flavor};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, str_from_ptr, string_into_ptr};
+
+// C entry points of the envelope format, which is implemented in `ecies_core::envelope`
+
+/*
+Options for `ecies_encrypt_with_options`.
+    - embed_timestamp: store the creation time in the authenticated header
+    - ttl_seconds: number of seconds the message stays valid after creation, 0 for no expiry. Implies `embed_timestamp`
+    - embed_message_id: store a random 16-byte message ID in the authenticated header, for replay detection. It is a
+      version 4 UUID, see `id.rs`
+    - compress: compress the message with zstd before encrypting it. Decryption decompresses transparently
+    - sender_secret_key_ptr: optional hexadecimal secret key of the sender. When set, the sender's public key is
+      stored in the header and the envelope is signed with this key
+    - base64_flavor: base64 alphabet and padding of the string output. Decryption accepts every flavor
+    - suite: suite byte of the payload construction, 0 for the default `Secp256k1HkdfSha256Aes256Gcm`.
+      2 and 3 select the SEC 1 / ISO 18033-2 variants for smartcard and HSM interop. Decryption reads the suite
+      from the envelope
+    - metadata_json_ptr: optional JSON object of string values, e.g. {"content-type": "image/png"}, stored in the
+      authenticated header and returned by `ecies_decrypt_with_metadata`. It is readable without the secret key
+    - key_id_ptr: optional identifier or version of the recipient key, stored in the authenticated header and
+      reported by `ecies_inspect`, so decryptors can pick the right secret key before decrypting
+*/
+
+#[repr(C)]
+pub struct EciesEncryptOptions {
+    pub embed_timestamp: bool,
+    pub ttl_seconds: u64,
+    pub embed_message_id: bool,
+    pub compress: bool,
+    pub sender_secret_key_ptr: *const c_char,
+    pub base64_flavor: EciesBase64Flavor,
+    pub suite: u8,
+    pub metadata_json_ptr: *const c_char,
+    pub key_id_ptr: *const c_char,
+}
+
+/*
+Options for `ecies_decrypt_with_options`.
+    - reject_expired: fail with `EciesErrorCode::Expired` when the envelope's TTL has elapsed
+    - seen_before: optional host callback asked whether a message ID has already been accepted. Returning true
+      fails decryption with `EciesErrorCode::Replayed`. The native layer keeps no state: the host owns the registry
+      and is expected to record the ID when answering false. When set, envelopes without a message ID are rejected
+      with `EciesErrorCode::MissingMessageId`
+    - seen_before_context: opaque pointer handed back to `seen_before`
+    - require_signature: fail with `EciesErrorCode::MissingSignature` when the envelope is not signed.
+      Signatures that are present are always verified
+*/
+
+pub type EciesSeenBeforeCallback =
+    Option<unsafe extern "C" fn(context: *mut c_void, message_id_ptr: *const u8, message_id_length: usize) -> bool>;
+
+#[repr(C)]
+pub struct EciesDecryptOptions {
+    pub reject_expired: bool,
+    pub seen_before: EciesSeenBeforeCallback,
+    pub seen_before_context: *mut c_void,
+    pub require_signature: bool,
+}
+
+// Shared by the encrypt entry points: builds the header from the options and seals the message
+pub(crate) unsafe fn seal_with_options(
+    public_key: &PublicKey,
+    message: &[u8],
+    options_ptr: *const EciesEncryptOptions,
+) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut header = Header::default();
+    let mut sender_secret_key = None;
+    let mut suite = Suite::Secp256k1HkdfSha256Aes256Gcm;
+    if let Some(options) = unsafe { options_ptr.as_ref() } {
+        if options.suite != 0 {
+            suite = Suite::from_byte(options.suite)?;
+        }
+        if options.embed_timestamp || options.ttl_seconds > 0 {
+            header.created_at = Some(now());
+        }
+        if options.ttl_seconds > 0 {
+            header.ttl = Some(options.ttl_seconds);
+        }
+        if options.embed_message_id {
+            header.message_id = Some(generate_message_id()?);
+        }
+        if options.compress {
+            header.compression = Some(Compression::Zstd);
+        }
+        if !options.metadata_json_ptr.is_null() {
+            let metadata_json = str_from_ptr(options.metadata_json_ptr)?;
+            header.metadata = serde_json::from_str(metadata_json).map_err(|_| EciesErrorCode::InvalidJson)?;
+        }
+        if !options.key_id_ptr.is_null() {
+            header.key_id = Some(str_from_ptr(options.key_id_ptr)?.to_string());
+        }
+        if !options.sender_secret_key_ptr.is_null() {
+            sender_secret_key = Some(secret_key_from_ptr(options.sender_secret_key_ptr)?);
+        }
+    }
+
+    seal_with_suite(suite, public_key, &header, message, sender_secret_key.as_ref())
+}
+
+/**
+Encrypts a message into an envelope using the provided public key.
+It takes a public key and a message as C strings, plus optional encryption options, and returns the
+envelope as a base64-encoded C string, or null on failure with the reason written into `error_ptr`.
+Steps:
+     - Parse the hexadecimal public key,
+     - Build the header from the options,
+     - Encrypt the message, authenticating the header as associated data
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_with_options(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        let flavor = unsafe { options_ptr.as_ref() }.map_or(EciesBase64Flavor::Standard, |options| options.base64_flavor);
+        string_into_ptr(encode_base64(&envelope, flavor))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Encrypts a binary message into an envelope using the provided public key.
+Works like `ecies_encrypt_with_options`, for messages given as `message_length` bytes that may contain NUL bytes,
+such as `NSData` or byte array contents.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_bytes(
+    public_key_ptr: *const c_char,
+    message_ptr: *const u8,
+    message_length: usize,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = slice_from_raw(message_ptr, message_length)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        let flavor = unsafe { options_ptr.as_ref() }.map_or(EciesBase64Flavor::Standard, |options| options.base64_flavor);
+        string_into_ptr(encode_base64(&envelope, flavor))
+    })();
+
+    finish(result, error_ptr)
+}
+
+// Shared by the decrypt entry points: decrypts and applies the decryption options
+pub(crate) unsafe fn open_with_options(
+    secret_key: &SecretKey,
+    envelope: &[u8],
+    options_ptr: *const EciesDecryptOptions,
+) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    open_requiring(secret_key, envelope, options_ptr, false)
+}
+
+// `open_with_options`, requiring a sender signature when `require_signature` is set whatever the options say. The
+// options are checked within the audited decryption, so a rejected envelope is reported with the rejection
+unsafe fn open_requiring(
+    secret_key: &SecretKey,
+    envelope: &[u8],
+    options_ptr: *const EciesDecryptOptions,
+    require_signature: bool,
+) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    let options = unsafe { options_ptr.as_ref() };
+
+    open_or_legacy_checked(secret_key, envelope, |header| {
+        let require_signature = require_signature || options.is_some_and(|options| options.require_signature);
+        if require_signature && header.sender.is_none() {
+            return Err(EciesErrorCode::MissingSignature);
+        }
+        if let Some(options) = options {
+            if options.reject_expired && header.is_expired(now()) {
+                return Err(EciesErrorCode::Expired);
+            }
+            if let Some(seen_before) = options.seen_before {
+                let message_id = header.message_id.ok_or(EciesErrorCode::MissingMessageId)?;
+                if unsafe { seen_before(options.seen_before_context, message_id.as_ptr(), message_id.len()) } {
+                    return Err(EciesErrorCode::Replayed);
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+// Decodes the base64 or armored envelope passed to the string-based decrypt entry points
+unsafe fn decrypt_envelope(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    require_signature: bool,
+) -> Result<(Header, Vec<u8>), EciesErrorCode> {
+    let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+    let message_buffer = bytes_from_ptr(message_ptr)?;
+    let envelope = decode_text(message_buffer)?;
+
+    open_requiring(&secret_key, &envelope, options_ptr, require_signature)
+}
+
+/**
+Decrypts an envelope using the provided secret key.
+It takes a secret key and a base64-encoded (or ASCII-armored) envelope as C strings, plus optional decryption options, and returns
+the decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+Steps:
+     - Parse the hexadecimal secret key and decode the base64 envelope,
+     - Verify the sender signature, if any, and decrypt the payload, verifying the header,
+     - Reject the message if it has expired and the options ask for it,
+     - Ask the host whether the message ID was seen before. This happens after decryption so only authenticated
+       IDs ever reach the host's registry
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_with_options(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = decrypt_envelope(secret_key_ptr, message_ptr, options_ptr, false)
+        .and_then(|(_, decrypted)| string_into_ptr(decrypted));
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts an envelope into a binary message using the provided secret key.
+Works like `ecies_decrypt_with_options`, returning the message as a buffer to release with `ecies_buffer_free`,
+so messages containing NUL bytes come back whole.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_bytes(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = decrypt_envelope(secret_key_ptr, message_ptr, options_ptr, false).map(|(_, decrypted)| decrypted);
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a signed envelope using the provided secret key.
+Works like `ecies_decrypt_with_options`, but always requires a valid sender signature and writes the verified
+sender public key (hexadecimal, compressed) into `sender_public_key_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_signed(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    sender_public_key_ptr: *mut *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let (header, decrypted) = decrypt_envelope(secret_key_ptr, message_ptr, options_ptr, true)?;
+        let sender = header.sender.ok_or(EciesErrorCode::MissingSignature)?;
+
+        let sender_hex = hex::encode(sender.serialize_compressed());
+
+        let decrypted_ptr = string_into_ptr(decrypted)?;
+        if !sender_public_key_ptr.is_null() {
+            let sender_ptr = string_into_ptr(sender_hex).inspect_err(|_| unsafe { ecies_string_free(decrypted_ptr) })?;
+            unsafe { *sender_public_key_ptr = sender_ptr };
+        }
+        Ok(decrypted_ptr)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts an envelope using the provided secret key.
+Works like `ecies_decrypt_with_options`, and writes the metadata stored at encryption into `metadata_ptr` as a JSON
+object C string, empty when the envelope has none.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_with_metadata(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    metadata_ptr: *mut *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let (header, decrypted) = decrypt_envelope(secret_key_ptr, message_ptr, options_ptr, false)?;
+
+        let metadata_json = serde_json::to_string(&header.metadata).map_err(|_| EciesErrorCode::InvalidJson)?;
+
+        let decrypted_ptr = string_into_ptr(decrypted)?;
+        if !metadata_ptr.is_null() {
+            let metadata_json_ptr =
+                string_into_ptr(metadata_json).inspect_err(|_| unsafe { ecies_string_free(decrypted_ptr) })?;
+            unsafe { *metadata_ptr = metadata_json_ptr };
+        }
+        Ok(decrypted_ptr)
+    })();
+
+    finish(result, error_ptr)
+}
@@ -0,0 +1,115 @@
+use std::os::raw::c_char;
+use crypto_box::aead::{generic_array::GenericArray, Aead};
+use crypto_box::{PublicKey as BoxPublicKey, SalsaBox, SecretKey as BoxSecretKey};
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr};
+use crate::json::EthEncryptedData;
+use crate::policy::allow_unapproved;
+
+/*
+Decryption of the payloads produced by eth-sig-util's `encrypt()`, as handled by MetaMask's `eth_decrypt`:
+
+    {
+        "version": "x25519-xsalsa20-poly1305",
+        "nonce": base64 (24 bytes),
+        "ephemPublicKey": base64 (32 bytes),
+        "ciphertext": base64 (Poly1305 tag and encrypted message)
+    }
+
+The ciphertext is a NaCl `crypto_box` from the ephemeral key to the recipient key. The recipient key is the
+MetaMask encryption private key: the 32-byte Ethereum account private key in hex, optionally `0x`-prefixed,
+used directly as the X25519 secret key. Its public counterpart is what `eth_getEncryptionPublicKey` returns.
+*/
+
+pub const VERSION: &str = "x25519-xsalsa20-poly1305";
+const NONCE_LENGTH: usize = 24;
+
+fn secret_key_from_text(text: &str) -> Result<BoxSecretKey, EciesErrorCode> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    let bytes: [u8; 32] = hex::decode(text)
+        .map_err(|_| EciesErrorCode::InvalidHex)?
+        .try_into()
+        .map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    Ok(BoxSecretKey::from(bytes))
+}
+
+// `eth_decrypt` receives the JSON as a `0x`-prefixed hex string, which is accepted as well
+fn parse_encrypted_data(text: &str) -> Result<EthEncryptedData, EciesErrorCode> {
+    let json = match text.trim().strip_prefix("0x") {
+        Some(hex_json) => {
+            let bytes = hex::decode(hex_json).map_err(|_| EciesErrorCode::InvalidHex)?;
+            String::from_utf8(bytes).map_err(|_| EciesErrorCode::InvalidUtf8)?
+        }
+        None => text.to_string(),
+    };
+    serde_json::from_str(&json).map_err(|_| EciesErrorCode::InvalidJson)
+}
+
+// The ciphertext is reported to the audit hook with the length of its base64 text
+pub fn decrypt(secret_key: &BoxSecretKey, encrypted_data: &EthEncryptedData) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open(secret_key, encrypted_data);
+    operation.record(|| *secret_key.public_key().as_bytes(), encrypted_data.ciphertext.len(), outcome(&result));
+    result
+}
+
+fn open(secret_key: &BoxSecretKey, encrypted_data: &EthEncryptedData) -> Result<Vec<u8>, EciesErrorCode> {
+    allow_unapproved()?;
+    if encrypted_data.version != VERSION {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+    let nonce = base64::decode(&encrypted_data.nonce).map_err(|_| EciesErrorCode::InvalidBase64)?;
+    let ephemeral_public_key = base64::decode(&encrypted_data.ephem_public_key).map_err(|_| EciesErrorCode::InvalidBase64)?;
+    let ciphertext = base64::decode(&encrypted_data.ciphertext).map_err(|_| EciesErrorCode::InvalidBase64)?;
+
+    if nonce.len() != NONCE_LENGTH {
+        return Err(EciesErrorCode::InvalidJson);
+    }
+    let ephemeral_public_key: [u8; 32] = ephemeral_public_key.try_into().map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+
+    let salsa_box = SalsaBox::new(&BoxPublicKey::from(ephemeral_public_key), secret_key);
+    salsa_box
+        .decrypt(GenericArray::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| EciesErrorCode::MacFailed)
+}
+
+/**
+Decrypts an eth-sig-util `encrypt()` payload using a MetaMask-format encryption private key.
+It takes the hex private key and the JSON (or its `0x`-prefixed hex encoding) as C strings and returns the
+decrypted message as a C string, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_eth_sig_util(
+    private_key_ptr: *const c_char,
+    json_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_text(str_from_ptr(private_key_ptr)?)?;
+        let encrypted_data = parse_encrypted_data(str_from_ptr(json_ptr)?)?;
+
+        string_into_ptr(decrypt(&secret_key, &encrypted_data)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Returns the encryption public key of a MetaMask-format encryption private key, base64 encoded like the result
+of `eth_getEncryptionPublicKey`, for dapps that encrypt to this account.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_eth_encryption_public_key(
+    private_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_text(str_from_ptr(private_key_ptr)?)?;
+
+        string_into_ptr(base64::encode(secret_key.public_key().as_bytes()))
+    })();
+
+    finish(result, error_ptr)
+}
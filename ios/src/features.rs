@@ -0,0 +1,24 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+pub use ecies_core::features::*;
+
+/**
+Reports whether a feature is available in this build on this device:
+    - "hw-aes": AES-GCM runs on the CPU's AES instructions
+    - "hw-sha": SHA-256 runs on the CPU's SHA instructions
+    - "simd": base64 and hex use SIMD encoders (the `simd` build feature)
+Unknown names, and null, report false.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_has_feature(feature_ptr: *const c_char) -> bool {
+    if feature_ptr.is_null() {
+        return false;
+    }
+    match unsafe { CStr::from_ptr(feature_ptr) }.to_bytes() {
+        b"hw-aes" => cpu_features().hw_aes,
+        b"hw-sha" => cpu_features().hw_sha,
+        b"simd" => cfg!(feature = "simd"),
+        _ => false,
+    }
+}
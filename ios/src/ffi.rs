@@ -0,0 +1,138 @@
+use std::mem::ManuallyDrop;
+use std::os::raw::c_char;
+use std::ffi::{CStr, CString};
+use ecies::{PublicKey, SecretKey};
+
+use crate::buffer::slice_from_raw;
+use crate::error::EciesErrorCode;
+use crate::keys::{parse_public_key_text, parse_secret_key, parse_secret_key_text};
+
+/*
+Helpers shared by the FFI functions that report failures through an `EciesErrorCode` instead of panicking.
+
+Nothing here relies on unwinding, so `panic = "abort"` builds behave the same: secrets are wiped on the success and
+error paths of each function, and no cleanup waits for a panic to unwind through it.
+*/
+
+pub(crate) unsafe fn str_from_ptr<'a>(ptr: *const c_char) -> Result<&'a str, EciesErrorCode> {
+    if ptr.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let cstr = unsafe { CStr::from_ptr(ptr) };
+    cstr.to_str().map_err(|_| EciesErrorCode::InvalidUtf8)
+}
+
+// Reads an array of `count` C strings, such as the chunk list of `ecies_join_chunks`
+pub(crate) unsafe fn str_array_from_ptr<'a>(ptrs: *const *const c_char, count: usize) -> Result<Vec<&'a str>, EciesErrorCode> {
+    if ptrs.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let ptrs = unsafe { std::slice::from_raw_parts(ptrs, count) };
+    ptrs.iter().map(|ptr| str_from_ptr(*ptr)).collect()
+}
+
+pub(crate) unsafe fn bytes_from_ptr<'a>(ptr: *const c_char) -> Result<&'a [u8], EciesErrorCode> {
+    if ptr.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let cstr = unsafe { CStr::from_ptr(ptr) };
+    Ok(cstr.to_bytes())
+}
+
+// Keys are accepted hex or base64 encoded, see `keys.rs`
+pub(crate) unsafe fn secret_key_from_ptr(secret_key_ptr: *const c_char) -> Result<SecretKey, EciesErrorCode> {
+    let secret_key_str = str_from_ptr(secret_key_ptr)?;
+
+    parse_secret_key_text(secret_key_str).map(|(secret_key, _)| secret_key)
+}
+
+// Raw 32-byte secret keys, as taken by the pointer-and-length layers (`scripting.rs`, `dotnet.rs`, `dart.rs`)
+pub(crate) unsafe fn secret_key_from_raw(data: *const u8, length: usize) -> Result<SecretKey, EciesErrorCode> {
+    parse_secret_key(slice_from_raw(data, length)?)
+}
+
+pub(crate) unsafe fn public_key_from_ptr(public_key_ptr: *const c_char) -> Result<PublicKey, EciesErrorCode> {
+    let public_key_str = str_from_ptr(public_key_ptr)?;
+
+    parse_public_key_text(public_key_str).map(|(public_key, _)| public_key)
+}
+
+// Ownership of the returned string is transferred to the caller, like the strings returned by `ecies_encrypt`
+pub(crate) fn string_into_ptr<T: Into<Vec<u8>>>(string: T) -> Result<*const c_char, EciesErrorCode> {
+    let cstring = CString::new(string).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+    let cstring = ManuallyDrop::new(cstring);
+
+    Ok(cstring.as_ptr())
+}
+
+// Copies `bytes` into caller-provided memory, which must have room for them
+pub(crate) unsafe fn write_raw(data: *mut u8, bytes: &[u8]) -> Result<(), EciesErrorCode> {
+    if data.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len()) };
+
+    Ok(())
+}
+
+pub(crate) unsafe fn write_error(error_ptr: *mut EciesErrorCode, code: EciesErrorCode) {
+    if !error_ptr.is_null() {
+        unsafe { *error_ptr = code };
+    }
+}
+
+// Unwraps an FFI result, reporting the error code and returning a null pointer on failure
+pub(crate) unsafe fn finish(result: Result<*const c_char, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> *const c_char {
+    match result {
+        Ok(ptr) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            ptr
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null()
+        }
+    }
+}
+
+// Unwraps an FFI result into an opaque handle owned by the caller, returning null on failure
+pub(crate) unsafe fn finish_handle<T>(result: Result<Box<T>, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> *mut T {
+    match result {
+        Ok(handle) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            Box::into_raw(handle)
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+// Unwraps an FFI result that carries no value, returning whether the call succeeded
+pub(crate) unsafe fn finish_status(result: Result<(), EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> bool {
+    match result {
+        Ok(()) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            true
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            false
+        }
+    }
+}
+
+// Unwraps an FFI result holding an array of C strings, returning null on failure
+pub(crate) unsafe fn finish_array(result: Result<*mut *const c_char, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> *mut *const c_char {
+    match result {
+        Ok(ptrs) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            ptrs
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::mem::ManuallyDrop;
+use std::os::raw::{c_char, c_int, c_void};
+use std::thread;
+use ecies::{PublicKey, SecretKey};
+use memmap2::{Mmap, MmapMut};
+use rayon::prelude::*;
+
+use crate::audit::{begin, EciesAuditOperation};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish_status, public_key_from_ptr, secret_key_from_ptr, str_from_ptr};
+use crate::progress::{EciesProgressOptions, Progress};
+use crate::stream::{decrypt_io, encrypt_io, frame_size, message_chunks, stream_size, EciesEncryptor, STREAM_HEADER_LENGTH};
+
+/*
+Encryption of files by path or by descriptor, streamed through the chunked format of `stream.rs` with bounded
+memory, so large files never have to be copied through host byte arrays.
+
+The output file of the path functions is created or truncated. If the operation fails it is removed, so no partial
+ciphertext or unauthenticated plaintext is left behind. The descriptor functions cannot do so; callers should
+discard the output on failure.
+
+For very large local files, `ecies_encrypt_file_mapped` memory-maps the input and a preallocated output and
+encrypts each chunk straight from one mapping into the other, without read/write syscalls or intermediate buffers.
+Its output is the same stream as that of `ecies_encrypt_file`. Since every frame has a known place in the output,
+`ecies_encrypt_file_parallel` goes further and seals the chunks on all cores, for multi-gigabyte backups.
+
+The `_with_progress` variants take `EciesProgressOptions` (see `progress.rs`) to report progress and support
+cancellation. A cancelled path operation removes its output like any failed one.
+
+The `_async` variants return as soon as their arguments are checked and run the operation on a thread of its own,
+then call the completion handler on that thread with `Success` or the error code, exactly once. Nothing blocks
+the calling thread, so Swift wraps them into `async` functions (see swift/EciesFiles.swift). Everything passed in,
+including the progress callback context and the cancel token, must stay valid until the completion handler runs.
+*/
+
+pub type EciesCompletionCallback = Option<unsafe extern "C" fn(context: *mut c_void, error: EciesErrorCode)>;
+
+fn with_output_file<F>(out_path: &str, operation: F) -> Result<(), EciesErrorCode>
+where
+    F: FnOnce(BufWriter<File>) -> Result<(), EciesErrorCode>,
+{
+    let output = File::create(out_path).map_err(|_| EciesErrorCode::IoFailed)?;
+    let result = operation(BufWriter::new(output));
+    if result.is_err() {
+        let _ = fs::remove_file(out_path);
+    }
+    result
+}
+
+// Size of a regular file, or 0 when unknown
+fn input_size(input: &File) -> u64 {
+    input.metadata().ok().filter(|metadata| metadata.is_file()).map_or(0, |metadata| metadata.len())
+}
+
+pub fn encrypt_file(
+    public_key: &PublicKey,
+    in_path: &str,
+    out_path: &str,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let input = File::open(in_path).map_err(|_| EciesErrorCode::IoFailed)?;
+    progress.set_total(input_size(&input));
+
+    with_output_file(out_path, |output| encrypt_io(public_key, BufReader::new(input), output, progress))
+}
+
+pub fn decrypt_file(
+    secret_key: SecretKey,
+    in_path: &str,
+    out_path: &str,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let input = File::open(in_path).map_err(|_| EciesErrorCode::IoFailed)?;
+    progress.set_total(input_size(&input));
+
+    with_output_file(out_path, |output| decrypt_io(secret_key, BufReader::new(input), output, progress))
+}
+
+// Chunks sealed between progress reports and cancellation checks: 4 MiB with the default chunk size
+const MAPPED_BATCH_CHUNKS: usize = 64;
+
+pub fn encrypt_file_mapped(
+    public_key: &PublicKey,
+    in_path: &str,
+    out_path: &str,
+    parallel: bool,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let input_file = File::open(in_path).map_err(|_| EciesErrorCode::IoFailed)?;
+    let input_length = input_file.metadata().map_err(|_| EciesErrorCode::IoFailed)?.len() as usize;
+    progress.set_total(input_length as u64);
+    // Empty files cannot be mapped
+    let input = match input_length {
+        0 => None,
+        _ => Some(unsafe { Mmap::map(&input_file) }.map_err(|_| EciesErrorCode::IoFailed)?),
+    };
+    let input: &[u8] = input.as_deref().unwrap_or(&[]);
+
+    let output_file = fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(out_path)
+        .map_err(|_| EciesErrorCode::IoFailed)?;
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = (|| {
+        let encryptor = EciesEncryptor::new(public_key)?;
+        let chunk_size = encryptor.chunk_size();
+
+        output_file.set_len(stream_size(input.len(), chunk_size) as u64).map_err(|_| EciesErrorCode::IoFailed)?;
+        let mut output = unsafe { MmapMut::map_mut(&output_file) }.map_err(|_| EciesErrorCode::IoFailed)?;
+        let (header, frames) = output.split_at_mut(STREAM_HEADER_LENGTH);
+        header.copy_from_slice(encryptor.header());
+
+        // Frame `index` of the output carries chunk `index` of the input
+        let chunks = message_chunks(input, chunk_size);
+        let mut frames: Vec<&mut [u8]> = frames.chunks_mut(frame_size(chunk_size)).collect();
+        let last_index = chunks.len() - 1;
+
+        for (batch_index, (chunk_batch, frame_batch)) in chunks
+            .chunks(MAPPED_BATCH_CHUNKS)
+            .zip(frames.chunks_mut(MAPPED_BATCH_CHUNKS))
+            .enumerate()
+        {
+            let first_index = batch_index * MAPPED_BATCH_CHUNKS;
+            let seal = |(offset, (chunk, frame)): (usize, (&&[u8], &mut &mut [u8]))| {
+                let index = first_index + offset;
+                let frame_index = u32::try_from(index).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+                encryptor.seal_frame(frame_index, chunk, index == last_index, frame)
+            };
+            match parallel {
+                true => chunk_batch.par_iter().zip(frame_batch.par_iter_mut()).enumerate().try_for_each(seal)?,
+                false => chunk_batch.iter().zip(frame_batch.iter_mut()).enumerate().try_for_each(seal)?,
+            }
+            progress.advance(chunk_batch.iter().map(|chunk| chunk.len()).sum())?;
+        }
+        output.flush().map_err(|_| EciesErrorCode::IoFailed)?;
+        Ok(output.len())
+    })();
+    // Frames are sealed with `seal_frame`, so the stream is reported here rather than by the encryptor
+    operation.record(|| public_key.serialize_compressed(), input.len(), result);
+
+    if result.is_err() {
+        let _ = fs::remove_file(out_path);
+    }
+    result.map(|_| ())
+}
+
+// Borrows a descriptor owned by the caller, who remains responsible for closing it
+#[cfg(unix)]
+pub unsafe fn borrow_fd(fd: c_int) -> Result<ManuallyDrop<File>, EciesErrorCode> {
+    use std::os::fd::FromRawFd;
+
+    if fd < 0 {
+        return Err(EciesErrorCode::InvalidArgument);
+    }
+    Ok(ManuallyDrop::new(unsafe { File::from_raw_fd(fd) }))
+}
+
+#[cfg(unix)]
+pub unsafe fn encrypt_fd(
+    public_key: &PublicKey,
+    fd_in: c_int,
+    fd_out: c_int,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let input = borrow_fd(fd_in)?;
+    let output = borrow_fd(fd_out)?;
+    progress.set_total(input_size(&input));
+
+    encrypt_io(public_key, BufReader::new(&*input), BufWriter::new(&*output), progress)
+}
+
+#[cfg(unix)]
+pub unsafe fn decrypt_fd(
+    secret_key: SecretKey,
+    fd_in: c_int,
+    fd_out: c_int,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let input = borrow_fd(fd_in)?;
+    let output = borrow_fd(fd_out)?;
+    progress.set_total(input_size(&input));
+
+    decrypt_io(secret_key, BufReader::new(&*input), BufWriter::new(&*output), progress)
+}
+
+/**
+Encrypts the file at `in_path` for the given public key into `out_path`, as a stream `ecies_decrypt_init` can read.
+Returns false on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file(&public_key, in_path, out_path, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Decrypts the stream in the file at `in_path` with the given secret key into `out_path`.
+Returns false on failure with the reason written into `error_ptr`, in which case `out_path` is removed.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_file(
+    secret_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        decrypt_file(secret_key, in_path, out_path, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts the file at `in_path` like `ecies_encrypt_file`, through memory mappings of the input and of the
+preallocated output. Intended for very large local files; the output is removed on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_mapped(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file_mapped(&public_key, in_path, out_path, false, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts the file at `in_path` like `ecies_encrypt_file_mapped`, sealing chunks in parallel across all cores.
+The output is the same stream, assembled in order.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_parallel(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file_mapped(&public_key, in_path, out_path, true, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts everything readable from `fd_in` for the given public key into `fd_out`, e.g. descriptors from a
+ParcelFileDescriptor or a pipe. Both stay open and owned by the caller. Returns false on failure with the reason
+written into `error_ptr`.
+*/
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_fd(
+    public_key_ptr: *const c_char,
+    fd_in: c_int,
+    fd_out: c_int,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+
+        encrypt_fd(&public_key, fd_in, fd_out, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Decrypts the stream readable from `fd_in` with the given secret key into `fd_out`. Both stay open and owned by the
+caller. Returns false on failure with the reason written into `error_ptr`; plaintext written before the failure
+must be discarded.
+*/
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_fd(
+    secret_key_ptr: *const c_char,
+    fd_in: c_int,
+    fd_out: c_int,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+
+        decrypt_fd(secret_key, fd_in, fd_out, &mut Progress::none())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Variants of the file and descriptor functions above reporting progress through `options_ptr`, and failing with
+`Cancelled` once its cancel token is cancelled. Null options behave like the plain functions.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_with_progress(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file(&public_key, in_path, out_path, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_file_with_progress(
+    secret_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        decrypt_file(secret_key, in_path, out_path, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_mapped_with_progress(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file_mapped(&public_key, in_path, out_path, false, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_fd_with_progress(
+    public_key_ptr: *const c_char,
+    fd_in: c_int,
+    fd_out: c_int,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+
+        encrypt_fd(&public_key, fd_in, fd_out, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_fd_with_progress(
+    secret_key_ptr: *const c_char,
+    fd_in: c_int,
+    fd_out: c_int,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+
+        decrypt_fd(secret_key, fd_in, fd_out, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_parallel_with_progress(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?;
+        let out_path = str_from_ptr(out_path_ptr)?;
+
+        encrypt_file_mapped(&public_key, in_path, out_path, true, &mut Progress::from_options(options_ptr))
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+// Host pointers only travel to the operation thread to be handed back to the host
+struct Background {
+    options: Option<EciesProgressOptions>,
+    completion: EciesCompletionCallback,
+    context: *mut c_void,
+}
+
+unsafe impl Send for Background {}
+
+unsafe fn run_in_background<F>(
+    options_ptr: *const EciesProgressOptions,
+    completion: EciesCompletionCallback,
+    completion_context: *mut c_void,
+    operation: F,
+) -> Result<(), EciesErrorCode>
+where
+    F: FnOnce(&mut Progress) -> Result<(), EciesErrorCode> + Send + 'static,
+{
+    completion.ok_or(EciesErrorCode::NullPointer)?;
+    // The options are copied, the pointers they hold are the caller's to keep alive
+    let options = unsafe { options_ptr.as_ref() }.map(|options| EciesProgressOptions {
+        callback: options.callback,
+        callback_context: options.callback_context,
+        cancel_token: options.cancel_token,
+    });
+    let background = Background { options, completion, context: completion_context };
+
+    thread::Builder::new()
+        .name("ecies-file".to_string())
+        .spawn(move || {
+            let background = background;
+            let result = match &background.options {
+                Some(options) => operation(&mut unsafe { Progress::from_options(options) }),
+                None => operation(&mut Progress::none()),
+            };
+            if let Some(completion) = background.completion {
+                unsafe { completion(background.context, result.err().unwrap_or(EciesErrorCode::Success)) };
+            }
+        })
+        .map_err(|_| EciesErrorCode::IoFailed)?;
+    Ok(())
+}
+
+/**
+Encrypts a file like `ecies_encrypt_file_with_progress`, on a background thread. Returns true once the operation
+has started, and `completion` later receives its outcome; returns false, without calling `completion`, when the
+arguments are invalid or no thread could be started. `options_ptr` may be null.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_async(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    completion: EciesCompletionCallback,
+    completion_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?.to_string();
+        let out_path = str_from_ptr(out_path_ptr)?.to_string();
+
+        run_in_background(options_ptr, completion, completion_context, move |progress| {
+            encrypt_file(&public_key, &in_path, &out_path, progress)
+        })
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Decrypts a file like `ecies_decrypt_file_with_progress`, on a background thread, reporting like
+`ecies_encrypt_file_async`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_file_async(
+    secret_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    completion: EciesCompletionCallback,
+    completion_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?.to_string();
+        let out_path = str_from_ptr(out_path_ptr)?.to_string();
+
+        run_in_background(options_ptr, completion, completion_context, move |progress| {
+            decrypt_file(secret_key, &in_path, &out_path, progress)
+        })
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts a file like `ecies_encrypt_file_parallel_with_progress`, on a background thread, reporting like
+`ecies_encrypt_file_async`. The chunks are still sealed on all cores.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_file_parallel_async(
+    public_key_ptr: *const c_char,
+    in_path_ptr: *const c_char,
+    out_path_ptr: *const c_char,
+    options_ptr: *const EciesProgressOptions,
+    completion: EciesCompletionCallback,
+    completion_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let in_path = str_from_ptr(in_path_ptr)?.to_string();
+        let out_path = str_from_ptr(out_path_ptr)?.to_string();
+
+        run_in_background(options_ptr, completion, completion_context, move |progress| {
+            encrypt_file_mapped(&public_key, &in_path, &out_path, true, progress)
+        })
+    })();
+
+    finish_status(result, error_ptr)
+}
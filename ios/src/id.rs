@@ -0,0 +1,27 @@
+use std::os::raw::c_char;
+
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, public_key_from_ptr, string_into_ptr};
+
+pub use ecies_core::id::*;
+
+/**
+Generates a random version 4 UUID, returned in its hyphenated lowercase form, e.g.
+`3f2b8c1e-9a4d-4e7f-b5c6-0d1e2f3a4b5c`. It fails with `RngFailure` once the random number generator failed its
+health tests.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_generate_uuid(error_ptr: *mut EciesErrorCode) -> *const c_char {
+    finish(generate_uuid().and_then(|uuid| string_into_ptr(format_uuid(&uuid))), error_ptr)
+}
+
+/**
+Returns the key ID of a public key (hex or base64, compressed or uncompressed): 16 lowercase hex characters
+derived from its SHA-256, the same for the key on every platform.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_key_id(public_key_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = public_key_from_ptr(public_key_ptr).and_then(|public_key| string_into_ptr(key_id(&public_key)));
+
+    finish(result, error_ptr)
+}
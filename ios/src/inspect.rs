@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::os::raw::c_char;
+use serde::Serialize;
+
+use crate::armor::decode_text;
+use crate::cipher::{OVERHEAD_LENGTH, PUBLIC_KEY_LENGTH};
+use crate::envelope::{is_envelope, now, parse, Header, Suite};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, string_into_ptr};
+use crate::id::format_uuid;
+
+/*
+Metadata of a ciphertext that can be read without the secret key, for triaging blobs that fail to decrypt.
+
+    {
+        "format": "envelope" | "legacy",
+        "version": 1,
+        "suite": 1,
+        "suite_name": "secp256k1-hkdf-sha256-aes-256-gcm",
+        "ephemeral_public_key": hex,
+        "payload_length": 130,
+        "has_aad": true,
+        "header": { "created_at": 1700000000, "ttl": 3600, "expired": false, "message_id": uuid,
+                    "compressed": false, "sender": hex,
+                    "metadata": { "content-type": "image/png" }, "key_id": "2024-01" },
+        "signed": false
+    }
+
+Legacy ciphertexts have no version or header, and no associated data. Absent values are null.
+Nothing here is verified: the header is authenticated only once the payload is decrypted.
+*/
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HeaderInspection {
+    pub created_at: Option<u64>,
+    pub ttl: Option<u64>,
+    pub expired: bool,
+    pub message_id: Option<String>,
+    pub compressed: bool,
+    pub sender: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub key_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Inspection {
+    pub format: &'static str,
+    pub version: Option<u8>,
+    pub suite: u8,
+    pub suite_name: &'static str,
+    pub ephemeral_public_key: Option<String>,
+    pub payload_length: usize,
+    pub has_aad: bool,
+    pub header: Option<HeaderInspection>,
+    pub signed: bool,
+}
+
+impl HeaderInspection {
+    fn from_header(header: &Header) -> HeaderInspection {
+        HeaderInspection {
+            created_at: header.created_at,
+            ttl: header.ttl,
+            expired: header.is_expired(now()),
+            message_id: header.message_id.as_ref().map(format_uuid),
+            compressed: header.compression.is_some(),
+            sender: header.sender.map(|sender| hex::encode(sender.serialize_compressed())),
+            metadata: header.metadata.clone(),
+            key_id: header.key_id.clone(),
+        }
+    }
+}
+
+// Every suite starts its payload with the uncompressed ephemeral public key
+fn ephemeral_public_key(payload: &[u8]) -> Option<String> {
+    payload.get(..PUBLIC_KEY_LENGTH).filter(|key| key[0] == 0x04).map(hex::encode)
+}
+
+pub fn inspect(bytes: &[u8]) -> Result<Inspection, EciesErrorCode> {
+    if is_envelope(bytes) {
+        let parsed = parse(bytes)?;
+        return Ok(Inspection {
+            format: "envelope",
+            version: Some(parsed.version),
+            suite: parsed.suite as u8,
+            suite_name: parsed.suite.name(),
+            ephemeral_public_key: ephemeral_public_key(parsed.payload),
+            payload_length: parsed.payload.len(),
+            has_aad: true,
+            header: Some(HeaderInspection::from_header(&parsed.header)),
+            signed: parsed.signature.is_some(),
+        });
+    }
+
+    if bytes.len() < OVERHEAD_LENGTH || bytes[0] != 0x04 {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+    let suite = Suite::Secp256k1HkdfSha256Aes256Gcm;
+    Ok(Inspection {
+        format: "legacy",
+        version: None,
+        suite: suite as u8,
+        suite_name: suite.name(),
+        ephemeral_public_key: ephemeral_public_key(bytes),
+        payload_length: bytes.len(),
+        has_aad: false,
+        header: None,
+        signed: false,
+    })
+}
+
+/**
+Describes a ciphertext without decrypting it.
+It takes a base64 or armored ciphertext as a C string, as accepted by `ecies_decrypt`, and returns its metadata
+as a JSON C string, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_inspect(ciphertext_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+
+        let inspection = inspect(&ciphertext)?;
+        let json = serde_json::to_string(&inspection).map_err(|_| EciesErrorCode::InvalidJson)?;
+
+        string_into_ptr(json)
+    })();
+
+    finish(result, error_ptr)
+}
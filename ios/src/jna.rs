@@ -0,0 +1,414 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+
+use crate::armor::decode_text;
+use crate::buffer::EciesBuffer;
+use crate::components::{ecies_join_components, split, EciesComponents};
+use crate::encoding::EciesBase64Flavor;
+use crate::envelope::{EciesDecryptOptions, EciesEncryptOptions, EciesSeenBeforeCallback};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish_handle};
+use crate::keys::wipe;
+use crate::progress::{EciesCancelToken, EciesProgressCallback, EciesProgressOptions};
+use crate::stream::{EciesDecryptor, EciesEncryptor};
+
+/*
+Structure-free variants of the C API for JNA direct mapping (`Native.register`), which only passes primitives,
+pointers, primitive arrays and callbacks: no struct by value and no `Structure` subclasses.
+
+    - Calls returning an `EciesBuffer` by value have an `ecies_jna_*` variant returning it boxed, null on
+      failure, read with `ecies_script_buffer_data` and `ecies_script_buffer_length` and released with
+      `ecies_script_buffer_free` (see `scripting.rs`, whose functions want the same).
+    - Calls taking an options struct take it as an opaque pointer instead, built from primitives by
+      `ecies_jna_encrypt_options_new`, `ecies_jna_decrypt_options_new` or `ecies_jna_progress_options_new`,
+      null still meaning the defaults.
+    - `ecies_split_components` and `ecies_join_components` become one call per component and a call taking
+      each component as a `(pointer, length)` pair; the .NET keypair structs are covered by
+      `ecies_script_generate_keypair` and `ecies_script_public_key_from`.
+
+Every other export already qualifies. On the Java side, declare `error_ptr` and the `const char **` outputs as
+one-element `int[]` and `long[]` arrays, returned strings as `Pointer` (released with `ecies_string_free`) and
+`bool` returns as `byte`, since JNA reads a `boolean` return as a full 32-bit int.
+*/
+
+// A buffer returned by value moved where a pointer reaches it, or null when the call failed
+fn boxed(buffer: EciesBuffer) -> *mut EciesBuffer {
+    if buffer.data.is_null() {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(buffer))
+}
+
+// Encryption options owning the strings they point to, which JNA only keeps alive for the duration of a call.
+// `options` comes first, so a pointer to the whole is a valid `*const EciesEncryptOptions`
+#[repr(C)]
+struct OwnedEncryptOptions {
+    options: EciesEncryptOptions,
+    strings: Vec<CString>,
+}
+
+unsafe fn owned_string(string_ptr: *const c_char, strings: &mut Vec<CString>) -> *const c_char {
+    if string_ptr.is_null() {
+        return std::ptr::null();
+    }
+    let string = CString::from(unsafe { CStr::from_ptr(string_ptr) });
+    // The bytes of a CString stay in place when it moves into the vector
+    let string_ptr = string.as_ptr();
+    strings.push(string);
+
+    string_ptr
+}
+
+/**
+Builds encryption options from their fields (see `EciesEncryptOptions`), copying the strings, which may be null.
+The result is accepted wherever `const EciesEncryptOptions *` is; release it with `ecies_jna_encrypt_options_free`.
+*/
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecies_jna_encrypt_options_new(
+    embed_timestamp: bool,
+    ttl_seconds: u64,
+    embed_message_id: bool,
+    compress: bool,
+    sender_secret_key_ptr: *const c_char,
+    base64_flavor: EciesBase64Flavor,
+    suite: u8,
+    metadata_json_ptr: *const c_char,
+    key_id_ptr: *const c_char,
+) -> *mut EciesEncryptOptions {
+    let mut strings = Vec::new();
+    let options = EciesEncryptOptions {
+        embed_timestamp,
+        ttl_seconds,
+        embed_message_id,
+        compress,
+        sender_secret_key_ptr: owned_string(sender_secret_key_ptr, &mut strings),
+        base64_flavor,
+        suite,
+        metadata_json_ptr: owned_string(metadata_json_ptr, &mut strings),
+        key_id_ptr: owned_string(key_id_ptr, &mut strings),
+    };
+
+    Box::into_raw(Box::new(OwnedEncryptOptions { options, strings })) as *mut EciesEncryptOptions
+}
+
+/**
+Releases options returned by `ecies_jna_encrypt_options_new`, clearing the copied strings, among which the sender
+secret key. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_options_free(options_ptr: *mut EciesEncryptOptions) {
+    if options_ptr.is_null() {
+        return;
+    }
+    let owned = unsafe { Box::from_raw(options_ptr as *mut OwnedEncryptOptions) };
+    for string in owned.strings {
+        wipe(&mut string.into_bytes());
+    }
+}
+
+/**
+Builds decryption options from their fields (see `EciesDecryptOptions`). `seen_before` may be null. The result is
+accepted wherever `const EciesDecryptOptions *` is; release it with `ecies_jna_decrypt_options_free`.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_jna_decrypt_options_new(
+    reject_expired: bool,
+    seen_before: EciesSeenBeforeCallback,
+    seen_before_context: *mut c_void,
+    require_signature: bool,
+) -> *mut EciesDecryptOptions {
+    Box::into_raw(Box::new(EciesDecryptOptions { reject_expired, seen_before, seen_before_context, require_signature }))
+}
+
+/**
+Releases options returned by `ecies_jna_decrypt_options_new`. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_decrypt_options_free(options_ptr: *mut EciesDecryptOptions) {
+    if !options_ptr.is_null() {
+        drop(unsafe { Box::from_raw(options_ptr) });
+    }
+}
+
+/**
+Builds progress options from their fields (see `EciesProgressOptions`), any of which may be null. The result is
+accepted wherever `const EciesProgressOptions *` is; release it with `ecies_jna_progress_options_free`.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_jna_progress_options_new(
+    callback: EciesProgressCallback,
+    callback_context: *mut c_void,
+    cancel_token: *const EciesCancelToken,
+) -> *mut EciesProgressOptions {
+    Box::into_raw(Box::new(EciesProgressOptions { callback, callback_context, cancel_token }))
+}
+
+/**
+Releases options returned by `ecies_jna_progress_options_new`. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_progress_options_free(options_ptr: *mut EciesProgressOptions) {
+    if !options_ptr.is_null() {
+        drop(unsafe { Box::from_raw(options_ptr) });
+    }
+}
+
+/**
+Returns one component of a ciphertext, in the order of the fields of `EciesComponents`: 0 for the header, then
+the ephemeral public key, the nonce, the body, the tag and 5 for the signature. Other indexes fail with
+`InvalidArgument`. Returns null on failure; release the result with `ecies_script_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_split_component(
+    ciphertext_ptr: *const c_char,
+    index: u32,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    let result = (|| {
+        let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+        let components = split(&ciphertext)?;
+        let component = match index {
+            0 => components.header,
+            1 => components.ephemeral_public_key,
+            2 => components.nonce,
+            3 => components.body,
+            4 => components.tag,
+            5 => components.signature,
+            _ => return Err(EciesErrorCode::InvalidArgument),
+        };
+
+        Ok(Box::new(EciesBuffer::from_vec(component.to_vec())))
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+// Borrows a `(pointer, length)` pair as a component, which `ecies_join_components` only reads
+fn component(data: *const u8, length: usize) -> EciesBuffer {
+    EciesBuffer { data: data as *mut u8, length }
+}
+
+/**
+Variant of `ecies_join_components` taking each component as a pointer and a length. Null or empty header and
+signature pointers produce a legacy ciphertext.
+*/
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecies_jna_join_components(
+    header_ptr: *const u8,
+    header_length: usize,
+    ephemeral_public_key_ptr: *const u8,
+    ephemeral_public_key_length: usize,
+    nonce_ptr: *const u8,
+    nonce_length: usize,
+    body_ptr: *const u8,
+    body_length: usize,
+    tag_ptr: *const u8,
+    tag_length: usize,
+    signature_ptr: *const u8,
+    signature_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    // The components are borrowed, so this is not released with `ecies_components_free`
+    let components = EciesComponents {
+        header: component(header_ptr, header_length),
+        ephemeral_public_key: component(ephemeral_public_key_ptr, ephemeral_public_key_length),
+        nonce: component(nonce_ptr, nonce_length),
+        body: component(body_ptr, body_length),
+        tag: component(tag_ptr, tag_length),
+        signature: component(signature_ptr, signature_length),
+    };
+
+    ecies_join_components(&components, error_ptr)
+}
+
+/**
+Variant of `ecies_decrypt_bytes` returning the message boxed, null on failure; release it with
+`ecies_script_buffer_free`. The same holds for the other `ecies_jna_*` variants of calls returning `EciesBuffer`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_decrypt_bytes(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::envelope::ecies_decrypt_bytes(secret_key_ptr, message_ptr, options_ptr, error_ptr))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_canonicalize(
+    ciphertext_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::canonical::ecies_canonicalize(ciphertext_ptr, error_ptr))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_update(
+    encryptor_ptr: *mut EciesEncryptor,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::stream::ecies_encrypt_update(encryptor_ptr, data_ptr, data_length, error_ptr))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_final(
+    encryptor_ptr: *mut EciesEncryptor,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::stream::ecies_encrypt_final(encryptor_ptr, error_ptr))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_decrypt_update(
+    decryptor_ptr: *mut EciesDecryptor,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::stream::ecies_decrypt_update(decryptor_ptr, data_ptr, data_length, error_ptr))
+}
+
+#[cfg(feature = "formats")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_cbor(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::cbor::ecies_encrypt_cbor(public_key_ptr, message_ptr, options_ptr, error_ptr))
+}
+
+#[cfg(feature = "formats")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_protobuf(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::protobuf::ecies_encrypt_protobuf(public_key_ptr, message_ptr, options_ptr, error_ptr))
+}
+
+#[cfg(feature = "formats")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_msgpack(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::msgpack::ecies_encrypt_msgpack(public_key_ptr, message_ptr, options_ptr, error_ptr))
+}
+
+#[cfg(feature = "formats")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_ur_decoder_message(
+    decoder_ptr: *const crate::bc_ur::EciesUrDecoder,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::bc_ur::ecies_ur_decoder_message(decoder_ptr, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_cose(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    structure: crate::cose::EciesCoseStructure,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::cose::ecies_encrypt_cose(public_key_ptr, message_ptr, structure, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_age(
+    recipients_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::age::ecies_encrypt_age(recipients_ptr, data_ptr, data_length, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_decrypt_age(
+    identity_ptr: *const c_char,
+    age_ptr: *const u8,
+    age_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::age::ecies_decrypt_age(identity_ptr, age_ptr, age_length, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_sealed_box_seal(
+    public_key_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::sealed_box::ecies_sealed_box_seal(public_key_ptr, data_ptr, data_length, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_sealed_box_open(
+    secret_key_ptr: *const c_char,
+    sealed_ptr: *const u8,
+    sealed_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::sealed_box::ecies_sealed_box_open(secret_key_ptr, sealed_ptr, sealed_length, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_encrypt_openpgp(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::openpgp::ecies_encrypt_openpgp(public_key_ptr, public_key_length, data_ptr, data_length, error_ptr))
+}
+
+#[cfg(feature = "interop")]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_decrypt_openpgp(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    let buffer = crate::openpgp::ecies_decrypt_openpgp(
+        secret_key_ptr,
+        secret_key_length,
+        message_ptr,
+        message_length,
+        error_ptr,
+    );
+    boxed(buffer)
+}
+
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn ecies_jna_agent_sign(
+    agent_ptr: *mut crate::agent::EciesAgent,
+    key_name_ptr: *const c_char,
+    message_ptr: *const u8,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    boxed(crate::agent::ecies_agent_sign(agent_ptr, key_name_ptr, message_ptr, message_length, error_ptr))
+}
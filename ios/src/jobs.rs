@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread;
+use ecies::{PublicKey, SecretKey};
+
+use crate::armor::decode_text;
+use crate::buffer::slice_from_raw;
+use crate::envelope::{open_or_legacy, seal_base64};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, public_key_from_ptr, secret_key_from_ptr, string_into_ptr, write_error};
+
+/*
+A background job queue, so hosts can move encryption off their UI thread without wrapping the blocking FFI calls
+in threads of their own.
+
+Submitted jobs run on a small pool of worker threads started on first use. Their inputs are copied at submission,
+so the caller may release them right away. Each job has a nonzero ID and completes in one of two ways:
+    - with a completion callback, which is called on a worker thread with the job ID and either the result C string
+      and `Success`, or null and the error code. The callback owns the result and releases it with
+      `ecies_string_free`
+    - without one, the outcome is kept until collected with `ecies_job_poll`
+
+Jobs produce the same results as `ecies_encrypt` and `ecies_decrypt`.
+*/
+
+pub type EciesJobCallback =
+    Option<unsafe extern "C" fn(context: *mut c_void, job_id: u64, result_ptr: *const c_char, error: EciesErrorCode)>;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesJobStatus {
+    Pending = 0,
+    Completed = 1,
+    Failed = 2,
+    UnknownJob = 3,
+}
+
+const MAX_WORKERS: usize = 4;
+
+enum Task {
+    Encrypt(PublicKey, Vec<u8>),
+    Decrypt(SecretKey, Vec<u8>),
+}
+
+impl Task {
+    fn run(self) -> Result<String, EciesErrorCode> {
+        match self {
+            Task::Encrypt(public_key, message) => {
+                let encrypted = seal_base64(&public_key, &message)?;
+                encrypted.into_string().map_err(|_| EciesErrorCode::InvalidUtf8)
+            }
+            Task::Decrypt(secret_key, message) => {
+                let (_, decrypted) = open_or_legacy(&secret_key, &decode_text(&message)?)?;
+                String::from_utf8(decrypted).map_err(|_| EciesErrorCode::InvalidUtf8)
+            }
+        }
+    }
+}
+
+// Host pointers only travel to the worker to be handed back to the host
+struct Completion {
+    callback: EciesJobCallback,
+    context: *mut c_void,
+}
+
+unsafe impl Send for Completion {}
+
+struct Job {
+    id: u64,
+    task: Task,
+    completion: Option<Completion>,
+}
+
+enum Outcome {
+    Pending,
+    Done(Result<String, EciesErrorCode>),
+}
+
+struct Queue {
+    sender: Mutex<Sender<Job>>,
+    outcomes: Arc<Mutex<HashMap<u64, Outcome>>>,
+    next_id: AtomicU64,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let outcomes = Arc::new(Mutex::new(HashMap::new()));
+
+        let workers = thread::available_parallelism().map_or(1, |count| count.get()).min(MAX_WORKERS);
+        for index in 0..workers {
+            let receiver = receiver.clone();
+            let outcomes = outcomes.clone();
+            // A pool short of workers still runs every job. With none, the channel is left without a receiver and
+            // `submit` fails instead
+            let _ = thread::Builder::new()
+                .name(format!("ecies-worker-{index}"))
+                .spawn(move || work(&receiver, &outcomes));
+        }
+
+        Queue { sender: Mutex::new(sender), outcomes, next_id: AtomicU64::new(1) }
+    })
+}
+
+// Jobs report failures as error codes and never panic while holding a lock, so a poisoned lock is still consistent
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>, outcomes: &Mutex<HashMap<u64, Outcome>>) {
+    loop {
+        let job = match lock(receiver).recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let result = job.task.run();
+
+        match job.completion {
+            Some(completion) => {
+                let (result_ptr, error) = match result.and_then(string_into_ptr) {
+                    Ok(ptr) => (ptr, EciesErrorCode::Success),
+                    Err(code) => (std::ptr::null(), code),
+                };
+                if let Some(callback) = completion.callback {
+                    unsafe { callback(completion.context, job.id, result_ptr, error) };
+                }
+            }
+            None => {
+                lock(outcomes).insert(job.id, Outcome::Done(result));
+            }
+        }
+    }
+}
+
+fn submit(task: Task, callback: EciesJobCallback, context: *mut c_void) -> Result<u64, EciesErrorCode> {
+    let queue = queue();
+    let id = queue.next_id.fetch_add(1, Ordering::Relaxed);
+    let completion = callback.is_some().then_some(Completion { callback, context });
+    if completion.is_none() {
+        lock(&queue.outcomes).insert(id, Outcome::Pending);
+    }
+
+    let job = Job { id, task, completion };
+    // Only fails when no worker could be started, whatever the task
+    if lock(&queue.sender).send(job).is_err() {
+        lock(&queue.outcomes).remove(&id);
+        return Err(EciesErrorCode::IoFailed);
+    }
+    Ok(id)
+}
+
+unsafe fn finish_job(result: Result<u64, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> u64 {
+    match result {
+        Ok(id) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            id
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            0
+        }
+    }
+}
+
+/**
+Queues the encryption of a message for the given public key, like `ecies_encrypt`.
+Returns the job ID, or 0 on failure with the reason written into `error_ptr`. `callback` may be null to collect
+the result with `ecies_job_poll` instead.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_submit_encrypt(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    callback: EciesJobCallback,
+    callback_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> u64 {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message = bytes_from_ptr(message_ptr)?.to_vec();
+
+        submit(Task::Encrypt(public_key, message), callback, callback_context)
+    })();
+
+    finish_job(result, error_ptr)
+}
+
+/**
+Queues the decryption of a message with the given secret key, like `ecies_decrypt`.
+Returns the job ID, or 0 on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_submit_decrypt(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    callback: EciesJobCallback,
+    callback_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> u64 {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let message = bytes_from_ptr(message_ptr)?.to_vec();
+
+        submit(Task::Decrypt(secret_key, message), callback, callback_context)
+    })();
+
+    finish_job(result, error_ptr)
+}
+
+/**
+Queues the encryption of a message given as `message_length` bytes, which may contain NUL bytes, like
+`ecies_encrypt_bytes`. Otherwise works like `ecies_submit_encrypt`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_submit_encrypt_bytes(
+    public_key_ptr: *const c_char,
+    message_ptr: *const u8,
+    message_length: usize,
+    callback: EciesJobCallback,
+    callback_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> u64 {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message = slice_from_raw(message_ptr, message_length)?.to_vec();
+
+        submit(Task::Encrypt(public_key, message), callback, callback_context)
+    })();
+
+    finish_job(result, error_ptr)
+}
+
+/**
+Queues the decryption of a ciphertext given as `message_length` bytes of base64 or armored text.
+Otherwise works like `ecies_submit_decrypt`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_submit_decrypt_bytes(
+    secret_key_ptr: *const c_char,
+    message_ptr: *const u8,
+    message_length: usize,
+    callback: EciesJobCallback,
+    callback_context: *mut c_void,
+    error_ptr: *mut EciesErrorCode,
+) -> u64 {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let message = slice_from_raw(message_ptr, message_length)?.to_vec();
+
+        submit(Task::Decrypt(secret_key, message), callback, callback_context)
+    })();
+
+    finish_job(result, error_ptr)
+}
+
+/**
+Checks on a job submitted without a completion callback.
+    - Pending: still queued or running
+    - Completed: the result C string is written into `result_ptr`, owned by the caller, or discarded when
+      `result_ptr` is null
+    - Failed: the job's error code is written into `error_ptr`
+    - UnknownJob: no such job, or its outcome was already collected
+Completed and failed jobs are forgotten once reported.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_job_poll(
+    job_id: u64,
+    result_ptr: *mut *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesJobStatus {
+    let mut outcomes = lock(&queue().outcomes);
+    let result = match outcomes.remove(&job_id) {
+        None => return EciesJobStatus::UnknownJob,
+        Some(Outcome::Pending) => {
+            outcomes.insert(job_id, Outcome::Pending);
+            return EciesJobStatus::Pending;
+        }
+        Some(Outcome::Done(result)) => result,
+    };
+
+    // The result is only copied into a C string when there is somewhere to write it
+    let written = result.and_then(|text| match result_ptr.is_null() {
+        true => Ok(()),
+        false => string_into_ptr(text).map(|ptr| unsafe { *result_ptr = ptr }),
+    });
+    match written {
+        Ok(()) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            EciesJobStatus::Completed
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            EciesJobStatus::Failed
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::os::raw::c_char;
+use serde::{Deserialize, Serialize};
+
+use crate::cipher::{decrypt_with_aad, encrypt_with_aad, NONCE_LENGTH, OVERHEAD_LENGTH, PUBLIC_KEY_LENGTH, TAG_LENGTH};
+use crate::envelope::Suite;
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, str_from_ptr, string_into_ptr};
+
+/*
+JSON representation of a ciphertext, following the structure used by eth-sig-util style tooling:
+
+    {
+        "version": "secp256k1-hkdf-sha256-aes-256-gcm",
+        "nonce": base64,
+        "ephemPublicKey": base64,
+        "ciphertext": base64,
+        "mac": base64
+    }
+
+The fields are the parts of the ecies payload (see `cipher.rs`); `mac` holds the AES-GCM tag.
+`iv` is accepted as an alias of `nonce` on input. Envelope headers have no place in this structure,
+so JSON ciphertexts carry no header options.
+*/
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthEncryptedData {
+    pub version: String,
+    #[serde(alias = "iv")]
+    pub nonce: String,
+    pub ephem_public_key: String,
+    pub ciphertext: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+}
+
+impl EthEncryptedData {
+    // Splits a raw ecies payload into its JSON fields
+    pub fn from_payload(payload: &[u8]) -> Result<EthEncryptedData, EciesErrorCode> {
+        if payload.len() < OVERHEAD_LENGTH {
+            return Err(EciesErrorCode::InvalidEnvelope);
+        }
+        let (ephemeral_public_key, rest) = payload.split_at(PUBLIC_KEY_LENGTH);
+        let (nonce, rest) = rest.split_at(NONCE_LENGTH);
+        let (tag, encrypted) = rest.split_at(TAG_LENGTH);
+
+        Ok(EthEncryptedData {
+            version: Suite::Secp256k1HkdfSha256Aes256Gcm.name().to_string(),
+            nonce: base64::encode(nonce),
+            ephem_public_key: base64::encode(ephemeral_public_key),
+            ciphertext: base64::encode(encrypted),
+            mac: Some(base64::encode(tag)),
+        })
+    }
+
+    // Reassembles the raw ecies payload from the JSON fields
+    pub fn to_payload(&self) -> Result<Vec<u8>, EciesErrorCode> {
+        if self.version != Suite::Secp256k1HkdfSha256Aes256Gcm.name() {
+            return Err(EciesErrorCode::UnsupportedSuite);
+        }
+        let mac = self.mac.as_ref().ok_or(EciesErrorCode::InvalidJson)?;
+
+        // The fixed-length fields must have exactly their length, or the payload would split at the wrong offsets
+        let mut payload = Vec::new();
+        for (field, length) in [
+            (&self.ephem_public_key, Some(PUBLIC_KEY_LENGTH)),
+            (&self.nonce, Some(NONCE_LENGTH)),
+            (mac, Some(TAG_LENGTH)),
+            (&self.ciphertext, None),
+        ] {
+            let bytes = base64::decode(field).map_err(|_| EciesErrorCode::InvalidBase64)?;
+            if length.is_some_and(|length| bytes.len() != length) {
+                return Err(EciesErrorCode::InvalidJson);
+            }
+            payload.extend_from_slice(&bytes);
+        }
+        Ok(payload)
+    }
+}
+
+/**
+Encrypts a message using the provided public key.
+It takes a public key and a message as C strings and returns the ciphertext as an eth-sig-util style JSON C string,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_json(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let payload = encrypt_with_aad(&public_key, message_buffer, &[])?;
+        let encrypted_data = EthEncryptedData::from_payload(&payload)?;
+        let json = serde_json::to_string(&encrypted_data).map_err(|_| EciesErrorCode::InvalidJson)?;
+
+        string_into_ptr(json)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts an eth-sig-util style JSON ciphertext using the provided secret key.
+It takes a secret key and the JSON as C strings and returns the decrypted message as a C string,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_json(
+    secret_key_ptr: *const c_char,
+    json_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let json = str_from_ptr(json_ptr)?;
+
+        let encrypted_data: EthEncryptedData = serde_json::from_str(json).map_err(|_| EciesErrorCode::InvalidJson)?;
+        let payload = encrypted_data.to_payload()?;
+        let decrypted = decrypt_with_aad(&secret_key, &payload, &[])?;
+
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_fields_of_the_wrong_length() {
+        let payload = vec![7u8; OVERHEAD_LENGTH + 5];
+        let encrypted_data = EthEncryptedData::from_payload(&payload).unwrap();
+        assert_eq!(encrypted_data.to_payload().unwrap(), payload);
+
+        let mut shortened = encrypted_data.clone();
+        shortened.nonce = base64::encode([7u8; NONCE_LENGTH - 4]);
+        shortened.ciphertext = base64::encode([7u8; 9]);
+        assert_eq!(shortened.to_payload(), Err(EciesErrorCode::InvalidJson));
+
+        let mut truncated = encrypted_data;
+        truncated.mac = Some(base64::encode([7u8; TAG_LENGTH - 1]));
+        assert_eq!(truncated.to_payload(), Err(EciesErrorCode::InvalidJson));
+    }
+}
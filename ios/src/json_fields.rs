@@ -0,0 +1,134 @@
+use std::os::raw::c_char;
+use ecies::{PublicKey, SecretKey};
+use serde_json::Value;
+
+use crate::armor::decode_text;
+use crate::encoding::{encode_base64, EciesBase64Flavor};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, public_key_from_ptr, secret_key_from_ptr, str_array_from_ptr, str_from_ptr, string_into_ptr};
+
+/*
+Field-level encryption of JSON documents: only the named fields are encrypted, so the rest of the document stays
+queryable.
+
+Fields are named with JSON pointers (RFC 6901), e.g. `/user/ssn` or `/cards/0/number`. The value of each field,
+whatever its type, is serialized as JSON, encrypted into an envelope, and replaced by the base64 envelope string.
+Decryption restores the original value and type. Paths missing from the document are skipped.
+
+Each envelope carries its pointer in the `json-pointer` metadata entry, which is authenticated, and decryption
+fails with `DecryptionFailed` for an envelope found under another path, so values cannot be swapped between fields.
+Paths may be nested, e.g. `/user/ssn` then `/user`: fields are decrypted in the reverse order of the paths, which
+restores the outer value before the one it contains.
+*/
+
+const POINTER_METADATA: &str = "json-pointer";
+
+pub fn encrypt_fields(public_key: &PublicKey, document: &mut Value, paths: &[&str]) -> Result<(), EciesErrorCode> {
+    for path in paths {
+        if let Some(field) = document.pointer_mut(path) {
+            let plaintext = serde_json::to_vec(field).map_err(|_| EciesErrorCode::InvalidJson)?;
+            let mut header = Header::default();
+            header.metadata.insert(POINTER_METADATA.to_string(), path.to_string());
+            let envelope = seal(public_key, &header, &plaintext, None)?;
+            *field = Value::String(encode_base64(&envelope, EciesBase64Flavor::Standard));
+        }
+    }
+    Ok(())
+}
+
+pub fn decrypt_fields(secret_key: &SecretKey, document: &mut Value, paths: &[&str]) -> Result<(), EciesErrorCode> {
+    for path in paths.iter().rev() {
+        if let Some(field) = document.pointer_mut(path) {
+            let envelope = field.as_str().ok_or(EciesErrorCode::InvalidJson)?;
+            let (header, plaintext) = open_or_legacy(secret_key, &decode_text(envelope.as_bytes())?)?;
+            if header.metadata.get(POINTER_METADATA).map(String::as_str) != Some(*path) {
+                return Err(EciesErrorCode::DecryptionFailed);
+            }
+            *field = serde_json::from_slice(&plaintext).map_err(|_| EciesErrorCode::InvalidJson)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+Encrypts the named fields of a JSON document using the provided public key.
+It takes a public key, the JSON document and an array of `path_count` JSON pointers as C strings, and returns the
+document with each named value replaced by its envelope, or null on failure with the reason written into
+`error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_json_fields(
+    public_key_ptr: *const c_char,
+    json_ptr: *const c_char,
+    paths_ptr: *const *const c_char,
+    path_count: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let mut document: Value = serde_json::from_str(str_from_ptr(json_ptr)?).map_err(|_| EciesErrorCode::InvalidJson)?;
+        let paths = str_array_from_ptr(paths_ptr, path_count)?;
+
+        encrypt_fields(&public_key, &mut document, &paths)?;
+        string_into_ptr(document.to_string())
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts the named fields of a JSON document produced by `ecies_encrypt_json_fields`.
+It takes a secret key, the JSON document and an array of `path_count` JSON pointers as C strings, and returns the
+document with the original values restored, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_json_fields(
+    secret_key_ptr: *const c_char,
+    json_ptr: *const c_char,
+    paths_ptr: *const *const c_char,
+    path_count: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let mut document: Value = serde_json::from_str(str_from_ptr(json_ptr)?).map_err(|_| EciesErrorCode::InvalidJson)?;
+        let paths = str_array_from_ptr(paths_ptr, path_count)?;
+
+        decrypt_fields(&secret_key, &mut document, &paths)?;
+        string_into_ptr(document.to_string())
+    })();
+
+    finish(result, error_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::generate_keypair;
+
+    #[test]
+    fn decrypts_nested_fields() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let original = serde_json::json!({ "user": { "name": "Ada", "ssn": "078-05-1120" } });
+        let paths = ["/user/ssn", "/user"];
+
+        let mut document = original.clone();
+        encrypt_fields(&public_key, &mut document, &paths).unwrap();
+        assert!(document["user"].is_string());
+        decrypt_fields(&secret_key, &mut document, &paths).unwrap();
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn rejects_fields_moved_to_another_path() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let mut document = serde_json::json!({ "ssn": "078-05-1120", "name": "Ada" });
+        encrypt_fields(&public_key, &mut document, &["/ssn", "/name"]).unwrap();
+
+        let ssn = document["ssn"].take();
+        document["name"] = ssn;
+        let result = decrypt_fields(&secret_key, &mut document, &["/name"]);
+        assert_eq!(result, Err(EciesErrorCode::DecryptionFailed));
+    }
+}
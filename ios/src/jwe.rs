@@ -0,0 +1,277 @@
+use std::os::raw::c_char;
+use ecies::{PublicKey, SecretKey};
+use openssl::aes::{unwrap_key, wrap_key, AesKey};
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::context::{ecdh_point, ephemeral_keypair, public_key_of};
+use crate::encoding::{decode_base64, encode_base64, EciesBase64Flavor};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, str_from_ptr, string_into_ptr};
+use crate::random::rng;
+
+/*
+JWE compact serialization (RFC 7516) with ECDH-ES key agreement (RFC 7518, section 4.6) on secp256k1 keys,
+so services on JOSE stacks can consume mobile-encrypted data without custom parsing.
+
+    BASE64URL(protected header) . BASE64URL(encrypted key) . BASE64URL(iv) . BASE64URL(ciphertext) . BASE64URL(tag)
+
+The protected header carries the ephemeral public key as a JWK ({"kty": "EC", "crv": "secp256k1", ...}).
+Content is encrypted with A256GCM, using either the Concat KDF output directly ("ECDH-ES", empty encrypted key)
+or a random content key wrapped with AES key wrap ("ECDH-ES+A256KW").
+*/
+
+const ENCRYPTION: &str = "A256GCM";
+const CURVE: &str = "secp256k1";
+const IV_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesJweAlgorithm {
+    // The agreed key is the content encryption key
+    EcdhEs = 0,
+    // The agreed key wraps a random content encryption key
+    EcdhEsA256Kw = 1,
+}
+
+impl EciesJweAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            EciesJweAlgorithm::EcdhEs => "ECDH-ES",
+            EciesJweAlgorithm::EcdhEsA256Kw => "ECDH-ES+A256KW",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<EciesJweAlgorithm, EciesErrorCode> {
+        match name {
+            "ECDH-ES" => Ok(EciesJweAlgorithm::EcdhEs),
+            "ECDH-ES+A256KW" => Ok(EciesJweAlgorithm::EcdhEsA256Kw),
+            _ => Err(EciesErrorCode::UnsupportedSuite),
+        }
+    }
+
+    // AlgorithmID of the Concat KDF: the "enc" value for direct agreement, the "alg" value for key wrapping
+    fn kdf_algorithm_id(&self) -> &'static str {
+        match self {
+            EciesJweAlgorithm::EcdhEs => ENCRYPTION,
+            EciesJweAlgorithm::EcdhEsA256Kw => self.name(),
+        }
+    }
+}
+
+fn base64url(bytes: &[u8]) -> String {
+    encode_base64(bytes, EciesBase64Flavor::UrlSafeNoPadding)
+}
+
+// The x coordinate of the ECDH shared point
+fn shared_secret(public_key: &PublicKey, secret_key: &SecretKey) -> Result<[u8; 32], EciesErrorCode> {
+    let shared_point = ecdh_point(public_key, secret_key)?;
+
+    let mut z = [0u8; 32];
+    z.copy_from_slice(&shared_point.serialize()[1..33]);
+    Ok(z)
+}
+
+// Concat KDF (NIST SP 800-56A) with SHA-256, with empty PartyUInfo and PartyVInfo, as specified by RFC 7518
+fn concat_kdf(z: &[u8], algorithm_id: &str) -> [u8; KEY_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    hasher.update((algorithm_id.len() as u32).to_be_bytes());
+    hasher.update(algorithm_id.as_bytes());
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(((KEY_LENGTH * 8) as u32).to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn public_key_to_jwk(public_key: &PublicKey) -> Value {
+    let serialized = public_key.serialize();
+    json!({
+        "kty": "EC",
+        "crv": CURVE,
+        "x": base64url(&serialized[1..33]),
+        "y": base64url(&serialized[33..65]),
+    })
+}
+
+fn public_key_from_jwk(jwk: &Value) -> Result<PublicKey, EciesErrorCode> {
+    if jwk["kty"] != "EC" || jwk["crv"] != CURVE {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+    let coordinate = |name: &str| {
+        let encoded = jwk[name].as_str().ok_or(EciesErrorCode::InvalidJwe)?;
+        decode_base64(encoded.as_bytes()).map_err(|_| EciesErrorCode::InvalidJwe)
+    };
+
+    let mut serialized = vec![0x04];
+    serialized.extend_from_slice(&coordinate("x")?);
+    serialized.extend_from_slice(&coordinate("y")?);
+    PublicKey::parse_slice(&serialized, None).map_err(|_| EciesErrorCode::InvalidJwe)
+}
+
+pub fn encrypt(public_key: &PublicKey, message: &[u8], algorithm: EciesJweAlgorithm) -> Result<String, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_jwe(public_key, message, algorithm);
+    operation.record(|| public_key.serialize_compressed(), message.len(), outcome(&result));
+    result
+}
+
+fn seal_jwe(public_key: &PublicKey, message: &[u8], algorithm: EciesJweAlgorithm) -> Result<String, EciesErrorCode> {
+    let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+    let z = shared_secret(public_key, &ephemeral_secret_key)?;
+    let agreed_key = concat_kdf(&z, algorithm.kdf_algorithm_id());
+
+    let (content_key, encrypted_key) = match algorithm {
+        EciesJweAlgorithm::EcdhEs => (agreed_key, Vec::new()),
+        EciesJweAlgorithm::EcdhEsA256Kw => {
+            let mut content_key = [0u8; KEY_LENGTH];
+            rng()?.fill_bytes(&mut content_key);
+
+            let key_encryption_key = AesKey::new_encrypt(&agreed_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+            let mut wrapped = vec![0u8; KEY_LENGTH + 8];
+            wrap_key(&key_encryption_key, None, &mut wrapped, &content_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+            (content_key, wrapped)
+        }
+    };
+
+    let header = json!({
+        "alg": algorithm.name(),
+        "enc": ENCRYPTION,
+        "epk": public_key_to_jwk(&ephemeral_public_key),
+    });
+    let encoded_header = base64url(header.to_string().as_bytes());
+
+    let mut iv = [0u8; IV_LENGTH];
+    rng()?.fill_bytes(&mut iv);
+    let mut tag = [0u8; TAG_LENGTH];
+    let ciphertext = encrypt_aead(Cipher::aes_256_gcm(), &content_key, Some(&iv), encoded_header.as_bytes(), message, &mut tag)
+        .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    Ok([encoded_header, base64url(&encrypted_key), base64url(&iv), base64url(&ciphertext), base64url(&tag)].join("."))
+}
+
+pub fn decrypt(secret_key: &SecretKey, jwe: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_jwe(secret_key, jwe);
+    let public_key = || public_key_of(secret_key).serialize_compressed();
+    operation.record(public_key, jwe.len(), outcome(&result));
+    result
+}
+
+fn open_jwe(secret_key: &SecretKey, jwe: &str) -> Result<Vec<u8>, EciesErrorCode> {
+    let parts: Vec<&str> = jwe.trim().split('.').collect();
+    let [encoded_header, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+        return Err(EciesErrorCode::InvalidJwe);
+    };
+    let decode = |part: &str| decode_base64(part.as_bytes()).map_err(|_| EciesErrorCode::InvalidJwe);
+
+    let header: Value = serde_json::from_slice(&decode(encoded_header)?).map_err(|_| EciesErrorCode::InvalidJwe)?;
+    if header["enc"] != ENCRYPTION {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+    let algorithm = EciesJweAlgorithm::from_name(header["alg"].as_str().ok_or(EciesErrorCode::InvalidJwe)?)?;
+    let ephemeral_public_key = public_key_from_jwk(&header["epk"])?;
+
+    // OpenSSL accepts GCM tags down to a single byte, which would make forgeries cheap
+    let (iv, tag) = (decode(iv)?, decode(tag)?);
+    if iv.len() != IV_LENGTH || tag.len() != TAG_LENGTH {
+        return Err(EciesErrorCode::InvalidJwe);
+    }
+    if algorithm == EciesJweAlgorithm::EcdhEs && !encrypted_key.is_empty() {
+        return Err(EciesErrorCode::InvalidJwe);
+    }
+
+    let z = shared_secret(&ephemeral_public_key, secret_key)?;
+    let agreed_key = concat_kdf(&z, algorithm.kdf_algorithm_id());
+
+    let content_key = match algorithm {
+        EciesJweAlgorithm::EcdhEs => agreed_key,
+        EciesJweAlgorithm::EcdhEsA256Kw => {
+            let key_encryption_key = AesKey::new_decrypt(&agreed_key).map_err(|_| EciesErrorCode::DecryptionFailed)?;
+            let wrapped = decode(encrypted_key)?;
+            let mut content_key = [0u8; KEY_LENGTH];
+            if wrapped.len() != KEY_LENGTH + 8 {
+                return Err(EciesErrorCode::InvalidJwe);
+            }
+            unwrap_key(&key_encryption_key, None, &mut content_key, &wrapped).map_err(|_| EciesErrorCode::DecryptionFailed)?;
+            content_key
+        }
+    };
+
+    decrypt_aead(Cipher::aes_256_gcm(), &content_key, Some(&iv), encoded_header.as_bytes(), &decode(ciphertext)?, &tag)
+        .map_err(|_| EciesErrorCode::MacFailed)
+}
+
+/**
+Encrypts a message for the provided public key as a compact JWE C string, using the given key management
+algorithm. Returns null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_jwe(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    algorithm: EciesJweAlgorithm,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        string_into_ptr(encrypt(&public_key, message_buffer, algorithm)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Decrypts a compact JWE C string (ECDH-ES or ECDH-ES+A256KW with A256GCM on secp256k1) using the provided
+secret key. Returns the decrypted message as a C string, or null on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_jwe(
+    secret_key_ptr: *const c_char,
+    jwe_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let jwe = str_from_ptr(jwe_ptr)?;
+
+        string_into_ptr(decrypt(&secret_key, jwe)?)
+    })();
+
+    finish(result, error_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::generate_keypair;
+
+    #[test]
+    fn rejects_truncated_tags() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let jwe = encrypt(&public_key, b"hello", EciesJweAlgorithm::EcdhEs).unwrap();
+        assert_eq!(decrypt(&secret_key, &jwe).unwrap(), b"hello");
+
+        let (rest, tag) = jwe.rsplit_once('.').unwrap();
+        let truncated = format!("{}.{}", rest, base64url(&decode_base64(tag.as_bytes()).unwrap()[..1]));
+        assert_eq!(decrypt(&secret_key, &truncated).unwrap_err(), EciesErrorCode::InvalidJwe);
+    }
+
+    #[test]
+    fn rejects_encrypted_keys_with_direct_agreement() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let jwe = encrypt(&public_key, b"hello", EciesJweAlgorithm::EcdhEs).unwrap();
+
+        let parts: Vec<&str> = jwe.split('.').collect();
+        let with_key = [parts[0], "AAAA", parts[2], parts[3], parts[4]].join(".");
+        assert_eq!(decrypt(&secret_key, &with_key).unwrap_err(), EciesErrorCode::InvalidJwe);
+    }
+}
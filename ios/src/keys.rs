@@ -0,0 +1,83 @@
+use std::os::raw::c_char;
+
+pub use ecies_core::keys::*;
+
+use crate::context::public_key_of;
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr, write_error};
+
+/**
+Converts a public key between representations.
+It takes a public key in any supported layout and encoding, and returns it in the requested layout and encoding,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_convert_public_key(
+    public_key_ptr: *const c_char,
+    format: EciesPublicKeyFormat,
+    encoding: EciesKeyEncoding,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let (public_key, _) = parse_public_key_text(str_from_ptr(public_key_ptr)?)?;
+
+        string_into_ptr(encode_key_text(&serialize_public_key(&public_key, format), encoding))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Derives the public key of a secret key, like `ecies_public_key_from`.
+Returns the compressed public key as a hex C string, or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_derive_public_key(
+    secret_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let (secret_key, _) = parse_secret_key_text(str_from_ptr(secret_key_ptr)?)?;
+
+        string_into_ptr(hex::encode(public_key_of(&secret_key).serialize_compressed()))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Detects the encoding of a secret or public key, as the key-accepting functions do.
+It writes `Hex` or `Base64` into `encoding_ptr` and returns true when the key decodes to a valid secret key
+(32 bytes) or public key (33, 64 or 65 bytes), false otherwise with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_detect_key_encoding(
+    key_ptr: *const c_char,
+    encoding_ptr: *mut EciesKeyEncoding,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let key = str_from_ptr(key_ptr)?;
+        let (bytes, encoding) = decode_key_text(key)?;
+        if bytes.len() == 32 {
+            parse_secret_key(&bytes)?;
+        } else {
+            parse_public_key(&bytes)?;
+        }
+        Ok(encoding)
+    })();
+
+    match result {
+        Ok(encoding) => {
+            if !encoding_ptr.is_null() {
+                unsafe { *encoding_ptr = encoding };
+            }
+            write_error(error_ptr, EciesErrorCode::Success);
+            true
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            false
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::cell::Cell;
+use std::os::raw::c_char;
+
+use crate::error::EciesErrorCode;
+
+/*
+The original functions (`ecies_generate_secret_key`, `ecies_public_key_from`, `ecies_encrypt` and `ecies_decrypt`)
+predate `error_ptr` and panic on invalid input. A panic never unwinds out of them, since every exported function is
+`extern "C"` rather than `extern "C-unwind"`, so it aborts the host process, and with `panic = "abort"` (the `go`
+profile) before any destructor runs.
+
+Building with the `no-panic` feature turns those panics into null returns, which abort builds need to keep
+reporting bad input instead of crashing on it. Either way the code of the last call on the calling thread is kept
+for `ecies_last_error`. Hosts whose calls move between OS threads, such as Go, should use the `error_ptr`
+functions instead.
+*/
+
+thread_local! {
+    static LAST_ERROR: Cell<EciesErrorCode> = const { Cell::new(EciesErrorCode::Success) };
+}
+
+// Unwraps the result of an original function, recording its code on the calling thread first
+pub(crate) fn finish_legacy(result: Result<*const c_char, EciesErrorCode>) -> *const c_char {
+    let code = result.err().unwrap_or(EciesErrorCode::Success);
+    LAST_ERROR.with(|last_error| last_error.set(code));
+
+    match result {
+        Ok(ptr) => ptr,
+        #[cfg(not(feature = "no-panic"))]
+        Err(code) => panic!("ecies: {code}"),
+        #[cfg(feature = "no-panic")]
+        Err(_) => std::ptr::null(),
+    }
+}
+
+/**
+Returns the `EciesErrorCode` of the last `ecies_generate_secret_key`, `ecies_public_key_from`, `ecies_encrypt` or
+`ecies_decrypt` call on the calling thread, `Success` when it succeeded. It is how builds with the `no-panic`
+feature learn why one of them returned null.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_last_error() -> EciesErrorCode {
+    LAST_ERROR.with(Cell::get)
+}
@@ -1,8 +1,92 @@
+// The exported functions are `unsafe` for the raw pointers they take, whose requirements their `/** */` comments
+// and ecies.h describe, rather than `# Safety` sections
+#![allow(clippy::missing_safety_doc)]
+
 use std::mem::ManuallyDrop;
 use std::os::raw::c_char;
-use std::ffi::{CStr, CString};
-use ecies::{PublicKey, SecretKey};
-use ecies::{encrypt, decrypt, utils::generate_keypair};
+
+pub use ecies_core::{self, cipher, error, sec1, symmetric};
+pub mod random;
+pub mod buffer;
+pub mod context;
+pub mod audit;
+pub mod metrics;
+pub mod policy;
+pub mod features;
+pub mod envelope;
+pub mod json;
+#[cfg(feature = "formats")]
+pub mod json_fields;
+#[cfg(feature = "interop")]
+pub mod eth_sig_util;
+#[cfg(feature = "formats")]
+pub mod cbor;
+#[cfg(feature = "formats")]
+pub mod protobuf;
+#[cfg(feature = "formats")]
+pub mod msgpack;
+pub mod armor;
+pub mod encoding;
+pub mod keys;
+pub mod id;
+#[cfg(feature = "formats")]
+pub mod bc_ur;
+pub mod chunking;
+#[cfg(feature = "interop")]
+pub mod jwe;
+#[cfg(feature = "interop")]
+pub mod cose;
+#[cfg(feature = "interop")]
+pub mod age;
+#[cfg(feature = "interop")]
+pub mod sealed_box;
+pub mod inspect;
+pub mod components;
+pub mod canonical;
+pub mod migrate;
+pub mod p256;
+#[cfg(feature = "keystore")]
+pub mod pki;
+#[cfg(feature = "keystore")]
+pub mod attestation;
+#[cfg(feature = "keystore")]
+pub mod ssh;
+#[cfg(feature = "interop")]
+pub mod openpgp;
+pub mod progress;
+pub mod stream;
+pub mod file;
+pub mod callback;
+pub mod jobs;
+pub mod batch;
+pub mod wide;
+pub mod scripting;
+pub mod jna;
+pub mod dotnet;
+pub mod dart;
+pub mod last_error;
+#[cfg(unix)]
+pub mod agent;
+#[cfg(feature = "uniffi")]
+pub mod uniffi_bindings;
+#[cfg(feature = "napi")]
+pub mod napi_bindings;
+#[cfg(feature = "python")]
+pub mod python_bindings;
+#[cfg(feature = "nif")]
+pub mod nif_bindings;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_bindings;
+mod ffi;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("ecies");
+
+use context::{generate_keypair, public_key_of};
+use envelope::{open_or_legacy, seal_base64};
+use error::EciesErrorCode;
+use ffi::{bytes_from_ptr, str_from_ptr, string_into_ptr};
+use last_error::finish_legacy;
 
 /*
 This module implements a Rust Foreign Function Interface (FFI) crypto framework to C-based libraries e.g .a staticlib, dylib, xcframework etc
@@ -16,137 +100,126 @@ Summary:
 This module uses the Elliptic Curve Integrated Encryption Scheme (ECIES)
 */
 
-/*
+/**
 Generates a new secret key using the libsecp256k1 library 
 It returns the hexadecimal representation of the serialized secret key as a C string.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_generate_secret_key() -> *const c_char {
-    let key_pair = generate_keypair();
-    // Ignore public key for now
-    let secret_key = key_pair.0;
+    let result = generate_keypair().and_then(|key_pair| {
+        // Ignore public key for now
+        let secret_key = key_pair.0;
 
-    let secret_key_buffer = secret_key.serialize();
-    let secret_key_hex = hex::encode(secret_key_buffer);
+        let secret_key_buffer = secret_key.serialize();
+        let secret_key_hex = hex::encode(secret_key_buffer);
 
-    let secret_key_cstring_result = CString::new(secret_key_hex);
-    let secret_key_cstr = ManuallyDrop::new(secret_key_cstring_result.unwrap());
+        // Ownership of the string is transferred to the caller, see `string_into_ptr`
+        string_into_ptr(secret_key_hex)
+    });
 
-    let secret_key_ptr = secret_key_cstr.as_ptr();
-
-    secret_key_ptr
+    finish_legacy(result)
 }
 
 
-/*
+/**
 Generates a public key from the given secret key
 It takes a secret key as a C string and returns the corresponding public key as a C string. 
 Steps: 
      - Convert the secret key from a C string to a Rust string, 
-     - Decode the hexadecimal representation of the secret key, and then 
+     - Decode the hexadecimal (or base64) representation of the secret key, and then 
      - Generate the public key from the secret key.
+Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_public_key_from(secret_key_ptr: *const c_char) -> *const c_char {
-    let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
-    let secret_key_str_result = secret_key_cstr.to_str();
-    let secret_key_str = secret_key_str_result.unwrap();
-    let secret_key_string = secret_key_str.to_string();
-    let secret_key_buffer = hex::decode(secret_key_string).unwrap();
-
-    let secret_key = SecretKey::parse_slice(&secret_key_buffer[..]).unwrap();
+    let result = (|| {
+        let secret_key_str = str_from_ptr(secret_key_ptr)?;
+        let (secret_key_buffer, _) = keys::decode_key_text(secret_key_str)?;
 
-    let public_key = PublicKey::from_secret_key(&secret_key);
+        let secret_key = keys::parse_secret_key(&secret_key_buffer[..])?;
 
-    let public_key_buffer = public_key.serialize_compressed();
-    let public_key_hex = hex::encode(public_key_buffer);
+        let public_key = public_key_of(&secret_key);
 
-    let public_key_cstring_result = CString::new(public_key_hex);
-    // ManuallyDrop is useful when the ownership of the underlying resource is transferred to code outside of Rust
-    let public_key_cstr = ManuallyDrop::new(public_key_cstring_result.unwrap());
+        let public_key_buffer = public_key.serialize_compressed();
+        let public_key_hex = hex::encode(public_key_buffer);
 
-    let public_key_ptr = public_key_cstr.as_ptr();
+        // Ownership of the string is transferred to the caller, see `string_into_ptr`
+        string_into_ptr(public_key_hex)
+    })();
 
-
-    public_key_ptr
+    finish_legacy(result)
 }
 
-/*
+/**
 Encrypts a message using the provided public key.
-It takes a public key and a message as C strings and returns the encrypted message as a base64-encoded C string. 
+It takes a public key and a message as C strings and returns the encrypted message as a base64-encoded envelope C string. 
 Steps:
      - Convert the public key from a C string to a Rust string, 
-     - Decode the hexadecimal representation of the public key, 
-     - Encrypt the the message using ecies encryption
+     - Decode the hexadecimal (or base64) representation of the public key, 
+     - Encrypt the the message using ecies encryption into a versioned envelope (see `envelope.rs`), encoded in the same buffer
+Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_encrypt(public_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
-    let public_key_cstr = unsafe { CStr::from_ptr(public_key_ptr) };
-    let public_key_str_result = public_key_cstr.to_str();
-    let public_key_str = public_key_str_result.unwrap();
-    let public_key_string = public_key_str.to_string();
-    let public_key_buffer = hex::decode(public_key_string).unwrap();
-
-    let public_key_result = PublicKey::parse_slice(&public_key_buffer[..], None);
-    let public_key = public_key_result.unwrap();
+    let result = (|| {
+        let public_key_str = str_from_ptr(public_key_ptr)?;
+        let (public_key_buffer, _) = keys::decode_key_text(public_key_str)?;
 
-    let serialized_public_key_buffer = public_key.serialize_compressed();
+        let public_key = keys::parse_public_key(&public_key_buffer[..])?;
 
-    let message_cstr = unsafe { CStr::from_ptr(message_ptr) };
-    let message_buffer = message_cstr.to_bytes();
-    
-    let encrypted_result = encrypt(&serialized_public_key_buffer, message_buffer);
-    let encrypted = encrypted_result.unwrap();
-    let encrypted_buffer = &encrypted[..];
-    let encoded = base64::encode(encrypted_buffer);
+        let message_buffer = bytes_from_ptr(message_ptr)?;
 
-    let encrypted_message_cstring = ManuallyDrop::new(CString::new(encoded).unwrap());
-    let encrypted_message_cstr = encrypted_message_cstring.as_c_str().to_str().unwrap();
+        // Encrypted, encoded and NUL-terminated in a single allocation, see `envelope::seal_base64`
+        let encrypted_message_cstring = ManuallyDrop::new(seal_base64(&public_key, message_buffer)?);
 
-    let encrypted_message_ptr = encrypted_message_cstr.as_ptr();
+        Ok(encrypted_message_cstring.as_ptr())
+    })();
 
-    encrypted_message_ptr as *const c_char
+    finish_legacy(result)
 }
 
 
-/*
+/**
 Decrypts a message using the provided secret key.
 It takes a secret key and a message as C string and returns the decrypted message as a C string. 
 Steps:
      - Convert the private key and encrypted message from C strings to Rust strings 
-     - Decode the hexadecimal representation of the private key, 
-     - Decrypt the message using ecies decryption
+     - Decode the hexadecimal (or base64) representation of the private key, 
+     - Decrypt the message using ecies decryption. Both envelopes and legacy ciphertexts (raw base64 ecies payloads) are accepted, base64-encoded or ASCII-armored
+Invalid input panics, or returns null with the `no-panic` feature, see `ecies_last_error`.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_decrypt(secret_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
-    let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
-    let secret_key_str_result = secret_key_cstr.to_str();
-    let secret_key_str = secret_key_str_result.unwrap();
-    let secret_key_string = secret_key_str.to_string();
-    let secret_key_buffer = hex::decode(secret_key_string).unwrap();
+    let result = (|| {
+        let secret_key_str = str_from_ptr(secret_key_ptr)?;
+        let (secret_key_buffer, _) = keys::decode_key_text(secret_key_str)?;
 
-    let secret_key_result = SecretKey::parse_slice(&secret_key_buffer[..]);
-    let secret_key = secret_key_result.unwrap();
+        let secret_key = keys::parse_secret_key(&secret_key_buffer[..])?;
 
-    let serialized_secret_key_buffer = secret_key.serialize();
+        let message_buffer = bytes_from_ptr(message_ptr)?;
 
-    let message_cstr = unsafe { CStr::from_ptr(message_ptr) };
-    let message_buffer = message_cstr.to_bytes();
+        let message_vec = armor::decode_text(message_buffer)?;
 
-    let message_decode_result = base64::decode(message_buffer);
-    let message_vec = message_decode_result.unwrap();
-    
-    let decrypted_result = decrypt(&serialized_secret_key_buffer, &message_vec[..]);
-    let decrypted = decrypted_result.unwrap();
+        let (_, decrypted) = open_or_legacy(&secret_key, &message_vec[..])?;
 
-    let decrypted_message_cstring = ManuallyDrop::new(CString::new(decrypted).unwrap());
-    let decrypted_message_cstr = decrypted_message_cstring.as_c_str().to_str().unwrap();
+        // The message is returned as text, so it must be UTF-8 without interior NULs
+        let decrypted_message = String::from_utf8(decrypted).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+        string_into_ptr(decrypted_message)
+    })();
 
-    let decrypted_message_ptr = decrypted_message_cstr.as_ptr();
+    finish_legacy(result)
+}
 
-    decrypted_message_ptr as *const c_char
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    // build.rs generates the header of the current sources into OUT_DIR
+    #[test]
+    fn committed_header_is_up_to_date() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/ecies.h"));
+        let committed = include_str!("../include/ecies.h");
+        assert!(
+            generated == committed,
+            "include/ecies.h is stale, regenerate it with the cbindgen command in cbindgen.toml"
+        );
+    }
+}
@@ -16,11 +16,10 @@ Summary:
 This module uses the Elliptic Curve Integrated Encryption Scheme (ECIES)
 */
 
-/*
+/**
 Generates a new secret key using the libsecp256k1 library 
 It returns the hexadecimal representation of the serialized secret key as a C string.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_generate_secret_key() -> *const c_char {
     let key_pair = generate_keypair();
@@ -39,7 +38,7 @@ pub unsafe extern "C" fn ecies_generate_secret_key() -> *const c_char {
 }
 
 
-/*
+/**
 Generates a public key from the given secret key
 It takes a secret key as a C string and returns the corresponding public key as a C string. 
 Steps: 
@@ -47,7 +46,6 @@ Steps:
      - Decode the hexadecimal representation of the secret key, and then 
      - Generate the public key from the secret key.
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_public_key_from(secret_key_ptr: *const c_char) -> *const c_char {
     let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
@@ -73,7 +71,7 @@ pub unsafe extern "C" fn ecies_public_key_from(secret_key_ptr: *const c_char) ->
     public_key_ptr
 }
 
-/*
+/**
 Encrypts a message using the provided public key.
 It takes a public key and a message as C strings and returns the encrypted message as a base64-encoded C string. 
 Steps:
@@ -81,7 +79,6 @@ Steps:
      - Decode the hexadecimal representation of the public key, 
      - Encrypt the the message using ecies encryption
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_encrypt(public_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
     let public_key_cstr = unsafe { CStr::from_ptr(public_key_ptr) };
@@ -112,7 +109,7 @@ pub unsafe extern "C" fn ecies_encrypt(public_key_ptr: *const c_char, message_pt
 }
 
 
-/*
+/**
 Decrypts a message using the provided secret key.
 It takes a secret key and a message as C string and returns the decrypted message as a C string. 
 Steps:
@@ -120,7 +117,6 @@ Steps:
      - Decode the hexadecimal representation of the private key, 
      - Decrypt the message using ecies decryption
 */
-
 #[no_mangle]
 pub unsafe extern "C" fn ecies_decrypt(secret_key_ptr: *const c_char, message_ptr: *const c_char) -> *const c_char {
     let secret_key_cstr = unsafe { CStr::from_ptr(secret_key_ptr) };
@@ -0,0 +1,61 @@
+use std::os::raw::c_char;
+use serde_json::{json, Map, Value};
+
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, string_into_ptr};
+
+pub use ecies_core::metrics::*;
+
+/*
+The counters of `ecies_core::metrics` as JSON, so telemetry SDKs forward them as they are and new error codes
+need no change to a struct layout.
+
+    {
+      "key_generation": {"count": 2, "failures": 0, "input_bytes": 0, "output_bytes": 0, "average_latency_ns": 41250},
+      "encryption": {...},
+      "decryption": {...},
+      "failures": {"MacFailed": 3}
+    }
+
+Input and output bytes are the lengths of the messages and ciphertexts; failures are keyed by `EciesErrorCode`
+variant name and only list the codes that occurred.
+*/
+
+fn operation_json(metrics: &OperationMetrics) -> Value {
+    json!({
+        "count": metrics.count,
+        "failures": metrics.failures,
+        "input_bytes": metrics.input_bytes,
+        "output_bytes": metrics.output_bytes,
+        "average_latency_ns": metrics.average_latency().as_nanos() as u64,
+    })
+}
+
+pub fn metrics_json(metrics: &Metrics) -> Value {
+    let failures: Map<String, Value> =
+        metrics.failures.iter().map(|(code, count)| (code.to_string(), Value::from(*count))).collect();
+
+    json!({
+        "key_generation": operation_json(&metrics.key_generation),
+        "encryption": operation_json(&metrics.encryption),
+        "decryption": operation_json(&metrics.decryption),
+        "failures": failures,
+    })
+}
+
+/**
+Returns the counters of the key generations, encryptions and decryptions since the library was loaded or
+`ecies_reset_metrics` was called, as a JSON C string to release with `ecies_string_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_get_metrics(error_ptr: *mut EciesErrorCode) -> *const c_char {
+    finish(string_into_ptr(metrics_json(&metrics()).to_string()), error_ptr)
+}
+
+/**
+Sets every counter back to zero, e.g. after each telemetry upload.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_reset_metrics() {
+    reset_metrics();
+}
@@ -0,0 +1,157 @@
+use std::os::raw::c_char;
+use ecies::{PublicKey, SecretKey};
+
+use crate::armor::decode_text;
+use crate::context::public_key_of;
+use crate::envelope::{is_envelope, open_or_legacy, parse, seal_with_suite, Suite, VERSION};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, string_into_ptr, write_error};
+use crate::encoding::{encode_base64, EciesBase64Flavor};
+
+/*
+Migration of stored ciphertexts between format versions.
+
+Version 0 is the legacy format, the raw ecies payload returned by `ecies_encrypt` before envelopes existed, and
+version 1 the current envelope. Migrating decrypts the ciphertext and encrypts the message again into an envelope
+of the target version, for the same recipient or, when given, a new public key. Header fields are carried over,
+except the sender signature which cannot be reproduced without the sender's key, and the key ID when the
+recipient changes.
+*/
+
+pub const LEGACY_VERSION: u8 = 0;
+
+fn version_of(ciphertext: &[u8]) -> Result<u8, EciesErrorCode> {
+    if is_envelope(ciphertext) {
+        Ok(parse(ciphertext)?.version)
+    } else {
+        Ok(LEGACY_VERSION)
+    }
+}
+
+pub fn migrate(
+    ciphertext: &[u8],
+    from_version: u8,
+    to_version: u8,
+    secret_key: &SecretKey,
+    new_public_key: Option<&PublicKey>,
+) -> Result<Vec<u8>, EciesErrorCode> {
+    if to_version != VERSION || version_of(ciphertext)? != from_version {
+        return Err(EciesErrorCode::UnsupportedVersion);
+    }
+
+    let suite = if is_envelope(ciphertext) { parse(ciphertext)?.suite } else { Suite::Secp256k1HkdfSha256Aes256Gcm };
+    let (mut header, message) = open_or_legacy(secret_key, ciphertext)?;
+    header.sender = None;
+    // The key ID names the old recipient key
+    if new_public_key.is_some() {
+        header.key_id = None;
+    }
+
+    let public_key = new_public_key.copied().unwrap_or_else(|| public_key_of(secret_key));
+    seal_with_suite(suite, &public_key, &header, &message, None)
+}
+
+unsafe fn migrate_ptr(
+    ciphertext_ptr: *const c_char,
+    from_version: u8,
+    to_version: u8,
+    secret_key: &SecretKey,
+    new_public_key: Option<&PublicKey>,
+) -> Result<*const c_char, EciesErrorCode> {
+    let ciphertext = decode_text(bytes_from_ptr(ciphertext_ptr)?)?;
+    let migrated = migrate(&ciphertext, from_version, to_version, secret_key, new_public_key)?;
+
+    string_into_ptr(encode_base64(&migrated, EciesBase64Flavor::Standard))
+}
+
+unsafe fn new_public_key_from_ptr(new_public_key_ptr: *const c_char) -> Result<Option<PublicKey>, EciesErrorCode> {
+    if new_public_key_ptr.is_null() {
+        Ok(None)
+    } else {
+        public_key_from_ptr(new_public_key_ptr).map(Some)
+    }
+}
+
+/**
+Migrates a ciphertext to another format version.
+It takes:
+    - the base64 or armored ciphertext, as accepted by `ecies_decrypt`
+    - the version it is expected to have (0 for legacy ciphertexts) and the version to produce (1)
+    - the hexadecimal secret key able to decrypt it
+    - optionally the hexadecimal public key to encrypt it for instead, null to keep the same recipient
+It returns the migrated ciphertext as a base64 C string, or null on failure with the reason written into
+`error_ptr`. A ciphertext of another version than `from_version` fails with `UnsupportedVersion`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_migrate(
+    ciphertext_ptr: *const c_char,
+    from_version: u8,
+    to_version: u8,
+    secret_key_ptr: *const c_char,
+    new_public_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let new_public_key = new_public_key_from_ptr(new_public_key_ptr)?;
+
+        migrate_ptr(ciphertext_ptr, from_version, to_version, &secret_key, new_public_key.as_ref())
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Migrates a list of ciphertexts, e.g. a page of database rows, parsing the keys only once.
+It takes the same arguments as `ecies_migrate`, with an array of `count` ciphertexts, and returns an array of
+`count` migrated ciphertexts to release with `ecies_string_array_free`.
+A ciphertext that fails to migrate does not stop the others: its entry is null, and when `error_codes_ptr` points
+to an array of `count` codes, the reason is written at its index. `error_ptr` only reports failures of the whole
+call, such as invalid keys.
+*/
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecies_migrate_all(
+    ciphertexts_ptr: *const *const c_char,
+    count: usize,
+    from_version: u8,
+    to_version: u8,
+    secret_key_ptr: *const c_char,
+    new_public_key_ptr: *const c_char,
+    error_codes_ptr: *mut EciesErrorCode,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut *const c_char {
+    let result = (|| {
+        if ciphertexts_ptr.is_null() {
+            return Err(EciesErrorCode::NullPointer);
+        }
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let new_public_key = new_public_key_from_ptr(new_public_key_ptr)?;
+        let ciphertexts = unsafe { std::slice::from_raw_parts(ciphertexts_ptr, count) };
+
+        let migrated: Vec<*const c_char> = ciphertexts
+            .iter()
+            .enumerate()
+            .map(|(index, ciphertext_ptr)| {
+                let result = migrate_ptr(*ciphertext_ptr, from_version, to_version, &secret_key, new_public_key.as_ref());
+                if !error_codes_ptr.is_null() {
+                    write_error(unsafe { error_codes_ptr.add(index) }, result.err().unwrap_or(EciesErrorCode::Success));
+                }
+                result.unwrap_or(std::ptr::null())
+            })
+            .collect();
+
+        Ok(Box::into_raw(migrated.into_boxed_slice()) as *mut *const c_char)
+    })();
+
+    match result {
+        Ok(ptrs) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            ptrs
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null_mut()
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::os::raw::c_char;
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::envelope::{
+    assemble, open_with_options, parse, seal_with_options, EciesDecryptOptions, EciesEncryptOptions, Suite,
+};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, string_into_ptr};
+
+/*
+MessagePack encoding of the envelope, for hosts (React Native, game servers) that already use msgpack on the wire.
+The envelope is a map with named keys, byte fields use the msgpack bin type:
+
+    { "version": uint, "suite": uint, "header": bin, "payload": bin, "signature": bin (signed envelopes only) }
+*/
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MsgpackEnvelope {
+    pub version: u8,
+    pub suite: u8,
+    #[serde(with = "serde_bytes")]
+    pub header: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub payload: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "serde_bytes")]
+    pub signature: Option<Vec<u8>>,
+}
+
+// Converts a binary envelope into its MessagePack form
+pub fn encode(envelope: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let parsed = parse(envelope)?;
+
+    let message = MsgpackEnvelope {
+        version: parsed.version,
+        suite: parsed.suite as u8,
+        header: parsed.header_bytes().to_vec(),
+        payload: parsed.payload.to_vec(),
+        signature: parsed.signature.map(|signature| signature.to_vec()),
+    };
+
+    rmp_serde::to_vec_named(&message).map_err(|_| EciesErrorCode::InvalidMsgpack)
+}
+
+// Converts the MessagePack form back into the binary envelope
+pub fn decode(bytes: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let message: MsgpackEnvelope = rmp_serde::from_slice(bytes).map_err(|_| EciesErrorCode::InvalidMsgpack)?;
+
+    assemble(
+        message.version,
+        Suite::from_byte(message.suite)?,
+        &message.header,
+        &message.payload,
+        message.signature.as_deref(),
+    )
+}
+
+/**
+Encrypts a message into an envelope using the provided public key and returns its MessagePack encoding.
+Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_msgpack(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        encode(&envelope)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a MessagePack-encoded envelope using the provided secret key.
+Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_msgpack(
+    secret_key_ptr: *const c_char,
+    msgpack_ptr: *const u8,
+    msgpack_length: usize,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let msgpack = slice_from_raw(msgpack_ptr, msgpack_length)?;
+
+        let envelope = decode(msgpack)?;
+        let (_, decrypted) = open_with_options(&secret_key, &envelope, options_ptr)?;
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
@@ -0,0 +1,119 @@
+use ecies::{PublicKey, SecretKey};
+use napi::bindgen_prelude::{AsyncTask, Buffer, Either};
+use napi::{Env, Error, Task};
+use napi_derive::napi;
+
+use crate::armor::decode_text;
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal_base64};
+use crate::error::EciesErrorCode;
+use crate::keys::parse_public_key;
+
+/*
+Node.js addon, built with the `napi` feature from the `android/` cdylib (see the README). It lets backend
+services read and write the envelopes of the mobile apps in-process.
+
+Keys are `Buffer`s: 32-byte secret keys, public keys in any layout accepted by the C API (compressed on output).
+`encrypt` resolves to the base64 envelope of `ecies_encrypt`; `decrypt` takes that text form (or armor) as a
+string, or a binary envelope as a `Buffer`. Both run on the libuv thread pool and return Promises, which reject
+with an `Error` whose message is the `EciesErrorCode` variant name, e.g. "DecryptionFailed".
+*/
+
+fn napi_error(code: EciesErrorCode) -> Error {
+    Error::from_reason(code.to_string())
+}
+
+fn parse_secret_key(bytes: &[u8]) -> napi::Result<SecretKey> {
+    crate::keys::parse_secret_key(bytes).map_err(napi_error)
+}
+
+#[napi(object)]
+pub struct Keypair {
+    pub secret_key: Buffer,
+    pub public_key: Buffer,
+}
+
+#[napi(js_name = "generateKeypair")]
+pub fn new_keypair() -> napi::Result<Keypair> {
+    let (secret_key, public_key) = generate_keypair().map_err(napi_error)?;
+
+    Ok(Keypair {
+        secret_key: secret_key.serialize().to_vec().into(),
+        public_key: public_key.serialize_compressed().to_vec().into(),
+    })
+}
+
+#[napi]
+pub fn public_key_from(secret_key: Buffer) -> napi::Result<Buffer> {
+    let secret_key = parse_secret_key(&secret_key)?;
+
+    Ok(public_key_of(&secret_key).serialize_compressed().to_vec().into())
+}
+
+pub struct EncryptTask {
+    public_key: PublicKey,
+    message: Vec<u8>,
+}
+
+impl Task for EncryptTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<String> {
+        seal_base64(&self.public_key, &self.message)
+            .map_err(napi_error)?
+            .into_string()
+            .map_err(|_| napi_error(EciesErrorCode::InvalidUtf8))
+    }
+
+    fn resolve(&mut self, _env: Env, output: String) -> napi::Result<String> {
+        Ok(output)
+    }
+}
+
+// Keys are parsed before the task is queued, so bad keys reject without a trip through the thread pool
+#[napi]
+pub fn encrypt(public_key: Buffer, message: Buffer) -> napi::Result<AsyncTask<EncryptTask>> {
+    let public_key = parse_public_key(&public_key).map_err(napi_error)?;
+
+    Ok(AsyncTask::new(EncryptTask { public_key, message: message.to_vec() }))
+}
+
+pub struct DecryptTask {
+    secret_key: SecretKey,
+    ciphertext: Either<String, Vec<u8>>,
+}
+
+impl Task for DecryptTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Vec<u8>> {
+        let decoded;
+        let envelope = match &self.ciphertext {
+            Either::A(text) => {
+                decoded = decode_text(text.as_bytes()).map_err(napi_error)?;
+                &decoded
+            }
+            Either::B(bytes) => bytes,
+        };
+        let (_, message) = open_or_legacy(&self.secret_key, envelope).map_err(napi_error)?;
+
+        Ok(message)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Vec<u8>) -> napi::Result<Buffer> {
+        Ok(output.into())
+    }
+}
+
+#[napi]
+pub fn decrypt(secret_key: Buffer, ciphertext: Either<String, Buffer>) -> napi::Result<AsyncTask<DecryptTask>> {
+    let secret_key = parse_secret_key(&secret_key)?;
+    let ciphertext = match ciphertext {
+        Either::A(text) => Either::A(text),
+        Either::B(bytes) => Either::B(bytes.to_vec()),
+    };
+
+    Ok(AsyncTask::new(DecryptTask { secret_key, ciphertext }))
+}
@@ -0,0 +1,93 @@
+use ecies::SecretKey;
+use rustler::{Atom, Binary, Env, OwnedBinary};
+
+use crate::context::{generate_keypair as new_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::keys::{parse_public_key, wipe};
+
+/*
+Erlang NIF module `Elixir.Ecies`, built with the `nif` feature from the `android/` cdylib and loaded by Rustler
+from `elixir/lib/ecies.ex`, so BEAM services open envelopes in process instead of through a port or a CLI.
+
+Keys, messages and ciphertexts are binaries: 32-byte secret keys, public keys in any layout accepted by the C API
+(compressed on output), and binary envelopes, the base64-decoded output of `ecies_encrypt`. Functions return
+`{:ok, result}` or `{:error, reason}`, the reason being the `EciesErrorCode` variant name as a snake_case atom
+(`:decryption_failed`, `:invalid_public_key`, ...).
+
+Encryption and decryption run on dirty CPU schedulers, since their time grows with the message and would
+otherwise hold a normal scheduler past the 1 ms a NIF is allowed. Key generation and derivation are a single
+scalar multiplication and stay on the calling scheduler.
+*/
+
+// `InvalidPublicKey` becomes `:invalid_public_key`
+fn reason(env: Env, code: EciesErrorCode) -> Atom {
+    let mut name = String::new();
+    for (index, character) in code.to_string().chars().enumerate() {
+        if character.is_ascii_uppercase() && index > 0 {
+            name.push('_');
+        }
+        name.push(character.to_ascii_lowercase());
+    }
+
+    Atom::from_str(env, &name).unwrap_or_else(|_| rustler::types::atom::error())
+}
+
+fn binary<'a>(env: Env<'a>, bytes: &[u8]) -> Result<Binary<'a>, EciesErrorCode> {
+    let mut binary = OwnedBinary::new(bytes.len()).ok_or(EciesErrorCode::InvalidArgument)?;
+    binary.as_mut_slice().copy_from_slice(bytes);
+
+    Ok(binary.release(env))
+}
+
+fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey, EciesErrorCode> {
+    crate::keys::parse_secret_key(bytes)
+}
+
+// Returns `{:ok, {secret_key, public_key}}`
+#[rustler::nif]
+fn generate_keypair(env: Env) -> Result<(Binary, Binary), Atom> {
+    let (secret_key, public_key) = new_keypair().map_err(|code| reason(env, code))?;
+    let mut secret_key = secret_key.serialize();
+    let keypair = binary(env, &secret_key).and_then(|secret_key| {
+        Ok((secret_key, binary(env, &public_key.serialize_compressed())?))
+    });
+    wipe(&mut secret_key);
+
+    keypair.map_err(|code| reason(env, code))
+}
+
+#[rustler::nif]
+fn public_key_from<'a>(env: Env<'a>, secret_key: Binary) -> Result<Binary<'a>, Atom> {
+    let result = parse_secret_key(&secret_key).and_then(|secret_key| {
+        binary(env, &public_key_of(&secret_key).serialize_compressed())
+    });
+
+    result.map_err(|code| reason(env, code))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encrypt<'a>(env: Env<'a>, public_key: Binary, message: Binary) -> Result<Binary<'a>, Atom> {
+    let result = (|| {
+        let public_key = parse_public_key(&public_key)?;
+        let envelope = seal(&public_key, &Header::default(), &message, None)?;
+        binary(env, &envelope)
+    })();
+
+    result.map_err(|code| reason(env, code))
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decrypt<'a>(env: Env<'a>, secret_key: Binary, ciphertext: Binary) -> Result<Binary<'a>, Atom> {
+    let result = (|| {
+        let secret_key = parse_secret_key(&secret_key)?;
+        let (_, mut message) = open_or_legacy(&secret_key, &ciphertext)?;
+        let result = binary(env, &message);
+        wipe(&mut message);
+        result
+    })();
+
+    result.map_err(|code| reason(env, code))
+}
+
+rustler::init!("Elixir.Ecies", [generate_keypair, public_key_from, encrypt, decrypt]);
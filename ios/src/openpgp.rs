@@ -0,0 +1,498 @@
+use std::io::Read;
+use ecies::{PublicKey, SecretKey};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use openssl::aes::{unwrap_key, wrap_key, AesKey};
+use openssl::sha::{sha1, sha256, sha384, sha512};
+use openssl::symm::{decrypt, encrypt, Cipher};
+use rand::RngCore;
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::context::{ecdh_point, ephemeral_keypair};
+use crate::encoding::decode_base64;
+use crate::envelope::MAX_DECOMPRESSED_LENGTH;
+use crate::error::EciesErrorCode;
+use crate::random::rng;
+
+/*
+OpenPGP ECDH (RFC 6637, RFC 4880bis) on secp256k1, so attachments can be exchanged with GnuPG users holding a
+secp256k1 encryption subkey.
+
+A message is a public-key encrypted session key packet (tag 1) for the recipient's ECDH subkey, followed by a
+symmetrically encrypted integrity protected data packet (tag 18, version 1 with MDC). The session key is wrapped
+with AES key wrap under a key derived from the shared point as described in RFC 6637 section 7, binding the
+recipient's fingerprint.
+
+Messages are produced with AES-256 and an uncompressed literal data packet. Decryption also accepts AES-128/192
+and ZIP or ZLIB compressed data, as produced by GnuPG by default, expanding to at most 64 MiB and without
+compressed packets nested inside. AEAD encrypted packets (tag 20) and password protected secret keys are not
+supported.
+Keys and messages can be binary or ASCII-armored.
+*/
+
+const TAG_PKESK: u8 = 1;
+const TAG_SECRET_KEY: u8 = 5;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_SECRET_SUBKEY: u8 = 7;
+const TAG_COMPRESSED: u8 = 8;
+const TAG_LITERAL: u8 = 11;
+const TAG_PUBLIC_SUBKEY: u8 = 14;
+const TAG_SEIPD: u8 = 18;
+
+const ALGORITHM_ECDH: u8 = 18;
+const CIPHER_AES128: u8 = 7;
+const CIPHER_AES192: u8 = 8;
+const CIPHER_AES256: u8 = 9;
+const HASH_SHA256: u8 = 8;
+const HASH_SHA384: u8 = 9;
+const HASH_SHA512: u8 = 10;
+
+// 1.3.132.0.10
+const SECP256K1_OID: &[u8] = &[0x2B, 0x81, 0x04, 0x00, 0x0A];
+const ANONYMOUS_SENDER: &[u8; 20] = b"Anonymous Sender    ";
+const MDC_HEADER: [u8; 2] = [0xD3, 0x14];
+const BLOCK_LENGTH: usize = 16;
+
+struct Packet {
+    tag: u8,
+    body: Vec<u8>,
+}
+
+// The ECDH subkey of a certificate, with everything its key derivation binds
+struct EcdhKey {
+    fingerprint: [u8; 20],
+    point: PublicKey,
+    hash: u8,
+    cipher: u8,
+}
+
+fn invalid<T>(value: Option<T>) -> Result<T, EciesErrorCode> {
+    value.ok_or(EciesErrorCode::InvalidOpenPgp)
+}
+
+fn take<'a>(data: &'a [u8], offset: &mut usize, length: usize) -> Result<&'a [u8], EciesErrorCode> {
+    let end = invalid(offset.checked_add(length))?;
+    let bytes = invalid(data.get(*offset..end))?;
+    *offset = end;
+    Ok(bytes)
+}
+
+fn take_byte(data: &[u8], offset: &mut usize) -> Result<u8, EciesErrorCode> {
+    Ok(take(data, offset, 1)?[0])
+}
+
+fn take_be(data: &[u8], offset: &mut usize, length: usize) -> Result<usize, EciesErrorCode> {
+    Ok(take(data, offset, length)?.iter().fold(0usize, |value, byte| (value << 8) | *byte as usize))
+}
+
+// Removes ASCII armor: the base64 lines between the armor headers and the CRC24 line
+fn dearmor(data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    if !data.starts_with(b"-----BEGIN PGP") {
+        return Ok(data.to_vec());
+    }
+    let text = std::str::from_utf8(data).map_err(|_| EciesErrorCode::InvalidOpenPgp)?;
+    let body: String = text
+        .lines()
+        .skip(1)
+        .skip_while(|line| !line.trim().is_empty())
+        .take_while(|line| !line.starts_with('=') && !line.starts_with("-----"))
+        .collect();
+    decode_base64(body.as_bytes()).map_err(|_| EciesErrorCode::InvalidOpenPgp)
+}
+
+fn read_packets(data: &[u8]) -> Result<Vec<Packet>, EciesErrorCode> {
+    let mut packets = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let header = take_byte(data, &mut offset)?;
+        if header & 0x80 == 0 {
+            return Err(EciesErrorCode::InvalidOpenPgp);
+        }
+
+        if header & 0x40 != 0 {
+            // New format, where streaming writers such as GnuPG split bodies into partial lengths
+            let mut body = Vec::new();
+            loop {
+                let first = take_byte(data, &mut offset)? as usize;
+                let (length, partial) = match first {
+                    0..=191 => (first, false),
+                    192..=223 => (((first - 192) << 8) + take_byte(data, &mut offset)? as usize + 192, false),
+                    224..=254 => (1 << (first & 0x1F), true),
+                    _ => (take_be(data, &mut offset, 4)?, false),
+                };
+                body.extend_from_slice(take(data, &mut offset, length)?);
+                if !partial {
+                    break;
+                }
+            }
+            packets.push(Packet { tag: header & 0x3F, body });
+        } else {
+            let length = match header & 0x03 {
+                0 => take_be(data, &mut offset, 1)?,
+                1 => take_be(data, &mut offset, 2)?,
+                2 => take_be(data, &mut offset, 4)?,
+                // Indeterminate length: the packet runs to the end of the data
+                _ => data.len() - offset,
+            };
+            let body = take(data, &mut offset, length)?.to_vec();
+            packets.push(Packet { tag: (header >> 2) & 0x0F, body });
+        }
+    }
+    Ok(packets)
+}
+
+fn write_packet(output: &mut Vec<u8>, tag: u8, body: &[u8]) {
+    output.push(0xC0 | tag);
+    match body.len() {
+        length @ 0..=191 => output.push(length as u8),
+        length @ 192..=8383 => {
+            let length = length - 192;
+            output.push((length >> 8) as u8 + 192);
+            output.push(length as u8);
+        }
+        length => {
+            output.push(0xFF);
+            output.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+    output.extend_from_slice(body);
+}
+
+fn read_mpi<'a>(data: &'a [u8], offset: &mut usize) -> Result<&'a [u8], EciesErrorCode> {
+    let bits = take_be(data, offset, 2)?;
+    take(data, offset, bits.div_ceil(8))
+}
+
+fn write_mpi(output: &mut Vec<u8>, value: &[u8]) {
+    let start = value.iter().position(|byte| *byte != 0).unwrap_or(value.len());
+    let value = &value[start..];
+    let bits = match value.first() {
+        Some(first) => (value.len() - 1) * 8 + (8 - first.leading_zeros() as usize),
+        None => 0,
+    };
+    output.extend_from_slice(&(bits as u16).to_be_bytes());
+    output.extend_from_slice(value);
+}
+
+// Parses a version 4 secp256k1 ECDH key packet body, returning the key and the length of its public part.
+// Keys of other versions, algorithms or curves are skipped with None
+fn parse_ecdh_key(body: &[u8]) -> Result<Option<(EcdhKey, usize)>, EciesErrorCode> {
+    if body.len() < 7 || body[0] != 4 || body[5] != ALGORITHM_ECDH {
+        return Ok(None);
+    }
+    let mut offset = 6;
+    let oid_length = take_byte(body, &mut offset)? as usize;
+    if take(body, &mut offset, oid_length)? != SECP256K1_OID {
+        return Ok(None);
+    }
+    let point =
+        PublicKey::parse_slice(read_mpi(body, &mut offset)?, None).map_err(|_| EciesErrorCode::InvalidOpenPgp)?;
+
+    let kdf_length = take_byte(body, &mut offset)? as usize;
+    let kdf = take(body, &mut offset, kdf_length)?;
+    let [1, hash, cipher] = *kdf else {
+        return Err(EciesErrorCode::InvalidOpenPgp);
+    };
+
+    let mut hashed = vec![0x99];
+    hashed.extend_from_slice(&(offset as u16).to_be_bytes());
+    hashed.extend_from_slice(&body[..offset]);
+
+    Ok(Some((EcdhKey { fingerprint: sha1(&hashed), point, hash, cipher }, offset)))
+}
+
+fn find_ecdh_key(packets: &[Packet], tags: &[u8]) -> Result<Option<(EcdhKey, Vec<u8>)>, EciesErrorCode> {
+    for packet in packets.iter().filter(|packet| tags.contains(&packet.tag)) {
+        if let Some((key, public_length)) = parse_ecdh_key(&packet.body)? {
+            return Ok(Some((key, packet.body[public_length..].to_vec())));
+        }
+    }
+    Ok(None)
+}
+
+fn key_wrap_length(cipher: u8) -> Result<usize, EciesErrorCode> {
+    match cipher {
+        CIPHER_AES128 => Ok(16),
+        CIPHER_AES192 => Ok(24),
+        CIPHER_AES256 => Ok(32),
+        _ => Err(EciesErrorCode::UnsupportedSuite),
+    }
+}
+
+fn cfb_cipher(cipher: u8) -> Result<Cipher, EciesErrorCode> {
+    match cipher {
+        CIPHER_AES128 => Ok(Cipher::aes_128_cfb128()),
+        CIPHER_AES192 => Ok(Cipher::aes_192_cfb128()),
+        CIPHER_AES256 => Ok(Cipher::aes_256_cfb128()),
+        _ => Err(EciesErrorCode::UnsupportedSuite),
+    }
+}
+
+// RFC 6637 section 7: one hash over 00 00 00 01 | x coordinate of the shared point | parameters
+fn derive_kek(key: &EcdhKey, shared_point: &PublicKey) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut input = vec![0, 0, 0, 1];
+    input.extend_from_slice(&shared_point.serialize()[1..33]);
+    input.push(SECP256K1_OID.len() as u8);
+    input.extend_from_slice(SECP256K1_OID);
+    input.extend_from_slice(&[ALGORITHM_ECDH, 3, 1, key.hash, key.cipher]);
+    input.extend_from_slice(ANONYMOUS_SENDER);
+    input.extend_from_slice(&key.fingerprint);
+
+    let mut kek = match key.hash {
+        HASH_SHA256 => sha256(&input).to_vec(),
+        HASH_SHA384 => sha384(&input).to_vec(),
+        HASH_SHA512 => sha512(&input).to_vec(),
+        _ => return Err(EciesErrorCode::UnsupportedSuite),
+    };
+    kek.truncate(key_wrap_length(key.cipher)?);
+    Ok(kek)
+}
+
+fn checksum(session_key: &[u8]) -> [u8; 2] {
+    let sum = session_key.iter().fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+    sum.to_be_bytes()
+}
+
+pub fn encrypt_message(public_key_block: &[u8], data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let packets = read_packets(&dearmor(public_key_block)?)?;
+    let (key, _) =
+        find_ecdh_key(&packets, &[TAG_PUBLIC_SUBKEY, TAG_PUBLIC_KEY])?.ok_or(EciesErrorCode::UnsupportedCurve)?;
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal_message(&key, data);
+    operation.record(|| key.point.serialize_compressed(), data.len(), outcome(&result));
+    result
+}
+
+fn seal_message(key: &EcdhKey, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut session_key = [0u8; 32];
+    rng()?.fill_bytes(&mut session_key);
+
+    // Cipher | session key | checksum, padded to 8 bytes as in PKCS#5
+    let mut plain_session_key = vec![CIPHER_AES256];
+    plain_session_key.extend_from_slice(&session_key);
+    plain_session_key.extend_from_slice(&checksum(&session_key));
+    let padding = 8 - plain_session_key.len() % 8;
+    plain_session_key.extend(std::iter::repeat_n(padding as u8, padding));
+
+    let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+    let shared_point = ecdh_point(&key.point, &ephemeral_secret_key)?;
+    let kek = derive_kek(key, &shared_point)?;
+
+    let aes_key = AesKey::new_encrypt(&kek).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    let mut wrapped = vec![0u8; plain_session_key.len() + 8];
+    wrap_key(&aes_key, None, &mut wrapped, &plain_session_key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    let mut pkesk = vec![3];
+    pkesk.extend_from_slice(&key.fingerprint[12..]);
+    pkesk.push(ALGORITHM_ECDH);
+    write_mpi(&mut pkesk, &ephemeral_public_key.serialize());
+    pkesk.push(wrapped.len() as u8);
+    pkesk.extend_from_slice(&wrapped);
+
+    // Binary literal data without file name or date
+    let mut literal_body = vec![b'b', 0, 0, 0, 0, 0];
+    literal_body.extend_from_slice(data);
+    let mut literal = Vec::new();
+    write_packet(&mut literal, TAG_LITERAL, &literal_body);
+
+    let mut prefix = [0u8; BLOCK_LENGTH];
+    rng()?.fill_bytes(&mut prefix);
+    let mut plaintext = prefix.to_vec();
+    plaintext.extend_from_slice(&prefix[BLOCK_LENGTH - 2..]);
+    plaintext.extend_from_slice(&literal);
+    plaintext.extend_from_slice(&MDC_HEADER);
+    let mdc = sha1(&plaintext);
+    plaintext.extend_from_slice(&mdc);
+
+    let mut seipd = vec![1];
+    let encrypted = encrypt(Cipher::aes_256_cfb128(), &session_key, Some(&[0u8; BLOCK_LENGTH]), &plaintext)
+        .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+    seipd.extend_from_slice(&encrypted);
+
+    let mut message = Vec::new();
+    write_packet(&mut message, TAG_PKESK, &pkesk);
+    write_packet(&mut message, TAG_SEIPD, &seipd);
+    Ok(message)
+}
+
+// Unprotected secret key packets store the scalar as an MPI after the public part and a zero S2K usage byte
+fn parse_secret_scalar(secret_part: &[u8]) -> Result<SecretKey, EciesErrorCode> {
+    let mut offset = 0;
+    if take_byte(secret_part, &mut offset)? != 0 {
+        return Err(EciesErrorCode::UnsupportedSuite);
+    }
+    let scalar = read_mpi(secret_part, &mut offset)?;
+    if scalar.len() > 32 {
+        return Err(EciesErrorCode::InvalidSecretKey);
+    }
+    let mut padded = [0u8; 32];
+    padded[32 - scalar.len()..].copy_from_slice(scalar);
+    SecretKey::parse(&padded).map_err(|_| EciesErrorCode::InvalidSecretKey)
+}
+
+fn unwrap_session_key(key: &EcdhKey, secret_key: &SecretKey, pkesk: &[u8]) -> Result<(u8, Vec<u8>), EciesErrorCode> {
+    let mut offset = 10;
+    let ephemeral_public_key = PublicKey::parse_slice(read_mpi(pkesk, &mut offset)?, None)
+        .map_err(|_| EciesErrorCode::InvalidOpenPgp)?;
+    let wrapped_length = take_byte(pkesk, &mut offset)? as usize;
+    let wrapped = take(pkesk, &mut offset, wrapped_length)?;
+    if wrapped.len() < 16 {
+        return Err(EciesErrorCode::InvalidOpenPgp);
+    }
+
+    let shared_point = ecdh_point(&ephemeral_public_key, secret_key)?;
+    let kek = derive_kek(key, &shared_point)?;
+
+    let aes_key = AesKey::new_decrypt(&kek).map_err(|_| EciesErrorCode::DecryptionFailed)?;
+    let mut unwrapped = vec![0u8; wrapped.len() - 8];
+    unwrap_key(&aes_key, None, &mut unwrapped, wrapped).map_err(|_| EciesErrorCode::DecryptionFailed)?;
+
+    let padding = *invalid(unwrapped.last())? as usize;
+    let content_length = invalid(unwrapped.len().checked_sub(padding + 3))?;
+    let cipher = unwrapped[0];
+    let session_key = &unwrapped[1..1 + content_length];
+    let stored_checksum = &unwrapped[1 + content_length..3 + content_length];
+    if session_key.len() != key_wrap_length(cipher)? || stored_checksum != checksum(session_key) {
+        return Err(EciesErrorCode::DecryptionFailed);
+    }
+    Ok((cipher, session_key.to_vec()))
+}
+
+// Returns the literal data of the decrypted packets, decompressing them when needed. A compressed packet may not
+// hold another one, so a message decompresses at most once
+fn literal_data(packets: Vec<Packet>, compressed_allowed: bool) -> Result<Vec<u8>, EciesErrorCode> {
+    for packet in packets {
+        match packet.tag {
+            TAG_LITERAL => {
+                let mut offset = 1;
+                let name_length = take_byte(&packet.body, &mut offset)? as usize;
+                take(&packet.body, &mut offset, name_length + 4)?;
+                return Ok(packet.body[offset..].to_vec());
+            }
+            TAG_COMPRESSED if !compressed_allowed => return Err(EciesErrorCode::InvalidOpenPgp),
+            TAG_COMPRESSED => {
+                let compressed = invalid(packet.body.get(1..))?;
+                let decompressed = decompress(packet.body[0], compressed)?;
+                return literal_data(read_packets(&decompressed)?, false);
+            }
+            // One-pass signature and signature packets of signed messages are not verified here
+            _ => {}
+        }
+    }
+    Err(EciesErrorCode::InvalidOpenPgp)
+}
+
+// Limited to the size of decompressed envelopes, so a few kilobytes cannot inflate into gigabytes
+fn decompress(algorithm: u8, compressed: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let mut decompressed = Vec::new();
+    let limit = MAX_DECOMPRESSED_LENGTH as u64 + 1;
+    let result = match algorithm {
+        0 => {
+            decompressed.extend_from_slice(compressed);
+            Ok(0)
+        }
+        1 => DeflateDecoder::new(compressed).take(limit).read_to_end(&mut decompressed),
+        2 => ZlibDecoder::new(compressed).take(limit).read_to_end(&mut decompressed),
+        _ => return Err(EciesErrorCode::UnsupportedSuite),
+    };
+    result.map_err(|_| EciesErrorCode::CompressionFailed)?;
+    if decompressed.len() > MAX_DECOMPRESSED_LENGTH {
+        return Err(EciesErrorCode::CompressionFailed);
+    }
+    Ok(decompressed)
+}
+
+pub fn decrypt_message(secret_key_block: &[u8], message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let key_packets = read_packets(&dearmor(secret_key_block)?)?;
+    let (key, secret_part) =
+        find_ecdh_key(&key_packets, &[TAG_SECRET_SUBKEY, TAG_SECRET_KEY])?.ok_or(EciesErrorCode::UnsupportedCurve)?;
+    let secret_key = parse_secret_scalar(&secret_part)?;
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open_message(&key, &secret_key, message);
+    operation.record(|| key.point.serialize_compressed(), message.len(), outcome(&result));
+    result
+}
+
+fn open_message(key: &EcdhKey, secret_key: &SecretKey, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let packets = read_packets(&dearmor(message)?)?;
+    let key_id = &key.fingerprint[12..];
+    let pkesk = packets
+        .iter()
+        .filter(|packet| packet.tag == TAG_PKESK && packet.body.len() > 10 && packet.body[0] == 3)
+        // A zero key ID stands for a hidden recipient
+        .find(|packet| {
+            let packet_key_id = &packet.body[1..9];
+            packet.body[9] == ALGORITHM_ECDH && (packet_key_id == key_id || packet_key_id == [0; 8])
+        })
+        .ok_or(EciesErrorCode::DecryptionFailed)?;
+    let (cipher, session_key) = unwrap_session_key(key, secret_key, &pkesk.body)?;
+
+    let seipd = packets.iter().find(|packet| packet.tag == TAG_SEIPD).ok_or(EciesErrorCode::InvalidOpenPgp)?;
+    if seipd.body.first() != Some(&1) {
+        return Err(EciesErrorCode::UnsupportedVersion);
+    }
+    let plaintext = decrypt(cfb_cipher(cipher)?, &session_key, Some(&[0u8; BLOCK_LENGTH]), &seipd.body[1..])
+        .map_err(|_| EciesErrorCode::DecryptionFailed)?;
+
+    // The random prefix repeats its last two bytes, a quick check that the session key is right
+    if plaintext.len() < BLOCK_LENGTH + 2 + MDC_HEADER.len() + 20
+        || plaintext[BLOCK_LENGTH - 2..BLOCK_LENGTH] != plaintext[BLOCK_LENGTH..BLOCK_LENGTH + 2]
+    {
+        return Err(EciesErrorCode::DecryptionFailed);
+    }
+    let (protected, mdc) = plaintext.split_at(plaintext.len() - 20);
+    if !protected.ends_with(&MDC_HEADER) || sha1(protected) != mdc {
+        return Err(EciesErrorCode::DecryptionFailed);
+    }
+
+    literal_data(read_packets(&protected[BLOCK_LENGTH + 2..protected.len() - 2])?, true)
+}
+
+/**
+Encrypts data into an OpenPGP message. It takes:
+    - the recipient's OpenPGP public key, binary or armored, with a secp256k1 ECDH key or subkey
+    - the data to encrypt and its length
+It returns the binary message, as written by `gpg --encrypt`, to release with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_openpgp(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key_block = slice_from_raw(public_key_ptr, public_key_length)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        encrypt_message(public_key_block, data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts an OpenPGP message. It takes:
+    - the recipient's unprotected OpenPGP secret key, binary or armored, with a secp256k1 ECDH key or subkey,
+      e.g. exported with `gpg --export-secret-subkeys` after removing the passphrase
+    - the binary or armored message and its length
+It returns the literal data, to release with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_openpgp(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let secret_key_block = slice_from_raw(secret_key_ptr, secret_key_length)?;
+        let message = slice_from_raw(message_ptr, message_length)?;
+
+        decrypt_message(secret_key_block, message)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
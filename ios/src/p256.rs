@@ -0,0 +1,296 @@
+use std::cmp::Ordering;
+use std::os::raw::c_char;
+use hkdf::Hkdf;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey, EcKeyRef, EcPoint, PointConversionForm};
+use openssl::nid::Nid;
+use openssl::pkey::{HasParams, HasPublic, PKey, Private, Public};
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use ecies_core::health;
+use sha2::Sha256;
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr};
+use crate::keys::{decode_key_text, wipe};
+use crate::random::rng;
+
+/*
+ECIES on NIST P-256, for keys from certificates and `ecdsa-sha2-nistp256` SSH keys, and for deployments under the
+FIPS policy, which refuses secp256k1 key agreement: every primitive here is FIPS 140-3 approved.
+
+Output layout:
+    ephemeral public key (65 bytes, uncompressed) | IV (12 bytes) | encrypted message | GCM tag (16 bytes)
+
+The shared secret is the x coordinate of the ECDH point (SP 800-56A), expanded by HKDF-SHA256 (SP 800-56C)
+without salt, with the uncompressed ephemeral and recipient public keys as info, into an AES-256-GCM key.
+Secret keys are 32-byte scalars and public keys SEC 1 points, compressed or uncompressed, in hex or base64.
+*/
+
+pub const PUBLIC_KEY_LENGTH: usize = 65;
+pub const SECRET_KEY_LENGTH: usize = 32;
+const IV_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+pub const OVERHEAD_LENGTH: usize = PUBLIC_KEY_LENGTH + IV_LENGTH + TAG_LENGTH;
+
+fn group() -> Result<EcGroup, EciesErrorCode> {
+    EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(|_| EciesErrorCode::UnsupportedCurve)
+}
+
+fn point_bytes<T>(key: &EcKeyRef<T>, form: PointConversionForm) -> Result<Vec<u8>, EciesErrorCode>
+where
+    T: HasParams + HasPublic,
+{
+    let mut context = BigNumContext::new().map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+    key.public_key().to_bytes(key.group(), form, &mut context).map_err(|_| EciesErrorCode::InvalidPublicKey)
+}
+
+// The compressed point, the encoding fingerprinted by the audit hook
+fn compressed<T: HasParams + HasPublic>(key: &EcKeyRef<T>) -> Vec<u8> {
+    point_bytes(key, PointConversionForm::COMPRESSED).unwrap_or_default()
+}
+
+pub fn parse_public_key(bytes: &[u8]) -> Result<EcKey<Public>, EciesErrorCode> {
+    if bytes.len() != 33 && bytes.len() != PUBLIC_KEY_LENGTH {
+        return Err(EciesErrorCode::InvalidKeyLength);
+    }
+    let group = group()?;
+    let mut context = BigNumContext::new().map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+    let point = EcPoint::from_bytes(&group, bytes, &mut context).map_err(|_| EciesErrorCode::InvalidCurvePoint)?;
+
+    let public_key = EcKey::from_public_key(&group, &point).map_err(|_| EciesErrorCode::InvalidCurvePoint)?;
+    public_key.check_key().map_err(|_| EciesErrorCode::InvalidCurvePoint)?;
+    Ok(public_key)
+}
+
+// Fails with `InvalidSecretKey` unless the scalar is in [1, n - 1]
+pub fn parse_secret_key(bytes: &[u8]) -> Result<EcKey<Private>, EciesErrorCode> {
+    if bytes.len() != SECRET_KEY_LENGTH {
+        return Err(EciesErrorCode::InvalidKeyLength);
+    }
+    let group = group()?;
+    let mut context = BigNumContext::new().map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    let scalar = BigNum::from_slice(bytes).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    let mut order = BigNum::new().map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    group.order(&mut order, &mut context).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    if scalar.num_bits() == 0 || scalar.ucmp(&order) != Ordering::Less {
+        return Err(EciesErrorCode::InvalidSecretKey);
+    }
+
+    let mut point = EcPoint::new(&group).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    point.mul_generator2(&group, &scalar, &mut context).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    EcKey::from_private_components(&group, &scalar, &point).map_err(|_| EciesErrorCode::InvalidSecretKey)
+}
+
+// Draws scalars from the library generator until one is valid, checking its health tests on every draw like
+// `context::ephemeral_keypair`
+fn ephemeral_key() -> Result<EcKey<Private>, EciesErrorCode> {
+    let mut rng = rng()?;
+    let mut bytes = [0u8; SECRET_KEY_LENGTH];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        let secret_key = parse_secret_key(&bytes);
+        wipe(&mut bytes);
+        health::check()?;
+        if let Ok(secret_key) = secret_key {
+            return Ok(secret_key);
+        }
+    }
+}
+
+pub fn generate_secret_key() -> Result<EcKey<Private>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::KeyGeneration);
+    let secret_key = ephemeral_key();
+    match &secret_key {
+        Ok(secret_key) => operation.record(|| compressed(secret_key), 0, Ok(0)),
+        Err(code) => operation.record(|| [0u8; 0], 0, Err(*code)),
+    }
+    secret_key
+}
+
+// The AES-256-GCM key agreed between a secret key and the peer public key
+fn derive_key(
+    secret_key: &EcKeyRef<Private>,
+    peer: &EcKeyRef<Public>,
+    ephemeral_public_key: &[u8],
+    recipient_public_key: &[u8],
+) -> Result<[u8; 32], EciesErrorCode> {
+    let secret_key = PKey::from_ec_key(secret_key.to_owned()).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    let peer = PKey::from_ec_key(peer.to_owned()).map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+    let mut deriver = Deriver::new(&secret_key).map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    deriver.set_peer(&peer).map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+    let mut shared_secret = deriver.derive_to_vec().map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+
+    let info = [ephemeral_public_key, recipient_public_key].concat();
+    let mut key = [0u8; 32];
+    let expanded = Hkdf::<Sha256>::new(None, &shared_secret).expand(&info, &mut key);
+    wipe(&mut shared_secret);
+    expanded.map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    Ok(key)
+}
+
+pub fn encrypt(public_key: &EcKeyRef<Public>, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = seal(public_key, message);
+    operation.record(|| compressed(public_key), message.len(), outcome(&result));
+    result
+}
+
+fn seal(public_key: &EcKeyRef<Public>, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let ephemeral_secret_key = ephemeral_key()?;
+    let ephemeral_public_key = point_bytes(&ephemeral_secret_key, PointConversionForm::UNCOMPRESSED)?;
+    let recipient_public_key = point_bytes(public_key, PointConversionForm::UNCOMPRESSED)?;
+    let mut key = derive_key(&ephemeral_secret_key, public_key, &ephemeral_public_key, &recipient_public_key)?;
+
+    let mut iv = [0u8; IV_LENGTH];
+    rng()?.fill_bytes(&mut iv);
+    health::check()?;
+    let mut tag = [0u8; TAG_LENGTH];
+    let ciphertext = encrypt_aead(Cipher::aes_256_gcm(), &key, Some(&iv), &[], message, &mut tag);
+    wipe(&mut key);
+    let ciphertext = ciphertext.map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    let mut output = Vec::with_capacity(OVERHEAD_LENGTH + message.len());
+    output.extend_from_slice(&ephemeral_public_key);
+    output.extend_from_slice(&iv);
+    output.extend_from_slice(&ciphertext);
+    output.extend_from_slice(&tag);
+    Ok(output)
+}
+
+pub fn decrypt(secret_key: &EcKeyRef<Private>, payload: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = open(secret_key, payload);
+    operation.record(|| compressed(secret_key), payload.len(), outcome(&result));
+    result
+}
+
+fn open(secret_key: &EcKeyRef<Private>, payload: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    if payload.len() < OVERHEAD_LENGTH {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+    let (ephemeral_public_key, rest) = payload.split_at(PUBLIC_KEY_LENGTH);
+    let (iv, rest) = rest.split_at(IV_LENGTH);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+    if ephemeral_public_key[0] != 0x04 {
+        return Err(EciesErrorCode::InvalidEnvelope);
+    }
+
+    let peer = parse_public_key(ephemeral_public_key)?;
+    let recipient_public_key = point_bytes(secret_key, PointConversionForm::UNCOMPRESSED)?;
+    let mut key = derive_key(secret_key, &peer, ephemeral_public_key, &recipient_public_key)?;
+
+    let message = decrypt_aead(Cipher::aes_256_gcm(), &key, Some(iv), &[], ciphertext, tag);
+    wipe(&mut key);
+    message.map_err(|_| EciesErrorCode::MacFailed)
+}
+
+/**
+Generates a new P-256 secret key, returned as hex.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_p256_generate_secret_key(error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        let secret_key = generate_secret_key()?;
+        let bytes = secret_key.private_key().to_vec_padded(SECRET_KEY_LENGTH as i32);
+        let mut bytes = bytes.map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+        let secret_key_hex = hex::encode(&bytes);
+        wipe(&mut bytes);
+
+        string_into_ptr(secret_key_hex)
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Derives the public key of a hex (or base64) P-256 secret key, returned as a hex uncompressed point like the P-256
+keys of `ecies_public_key_from_x509` and `ecies_public_key_from_ssh`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_p256_public_key_from(
+    secret_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = parse_secret_key(&decode_key_text(str_from_ptr(secret_key_ptr)?)?.0)?;
+
+        string_into_ptr(hex::encode(point_bytes(&secret_key, PointConversionForm::UNCOMPRESSED)?))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Encrypts data for a hex (or base64) P-256 public key, compressed or uncompressed.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_p256_encrypt(
+    public_key_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = parse_public_key(&decode_key_text(str_from_ptr(public_key_ptr)?)?.0)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        encrypt(&public_key, data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts the output of `ecies_p256_encrypt` with a hex (or base64) P-256 secret key.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_p256_decrypt(
+    secret_key_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let secret_key = parse_secret_key(&decode_key_text(str_from_ptr(secret_key_ptr)?)?.0)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        decrypt(&secret_key, data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_messages() {
+        let secret_key = generate_secret_key().unwrap();
+        let public_key = parse_public_key(&compressed(&secret_key)).unwrap();
+        let payload = encrypt(&public_key, b"hello").unwrap();
+
+        assert_eq!(payload.len(), OVERHEAD_LENGTH + 5);
+        assert_eq!(decrypt(&secret_key, &payload).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn rejects_tampered_payloads() {
+        let secret_key = generate_secret_key().unwrap();
+        let public_key = parse_public_key(&compressed(&secret_key)).unwrap();
+        let mut payload = encrypt(&public_key, b"hello").unwrap();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+
+        assert_eq!(decrypt(&secret_key, &payload).unwrap_err(), EciesErrorCode::MacFailed);
+        assert_eq!(decrypt(&secret_key, &payload[..OVERHEAD_LENGTH - 1]).unwrap_err(), EciesErrorCode::InvalidEnvelope);
+        assert_eq!(parse_secret_key(&[0; 32]).unwrap_err(), EciesErrorCode::InvalidSecretKey);
+    }
+}
@@ -0,0 +1,87 @@
+use std::os::raw::c_char;
+use openssl::bn::BigNumContext;
+use openssl::ec::PointConversionForm;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Public};
+use openssl::x509::X509;
+
+use crate::buffer::slice_from_raw;
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, string_into_ptr};
+
+/*
+Public keys distributed through a PKI: X.509 certificates and SubjectPublicKeyInfo structures, DER or PEM encoded.
+
+The key is returned as a hexadecimal uncompressed point, the format taken by the encryption functions, together
+with its curve, so hosts can route it: secp256k1 keys go to the encryption functions, P-256 keys to
+`ecies_p256_encrypt` (see `p256.rs`).
+*/
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesCurve {
+    Secp256k1 = 0,
+    P256 = 1,
+    X25519 = 2,
+}
+
+pub(crate) fn is_pem(data: &[u8]) -> bool {
+    data.windows(11).any(|window| window == b"-----BEGIN ")
+}
+
+fn public_key_from_der_or_pem(data: &[u8]) -> Result<PKey<Public>, EciesErrorCode> {
+    let pem = is_pem(data);
+    let certificate = if pem { X509::from_pem(data) } else { X509::from_der(data) };
+    if let Ok(certificate) = certificate {
+        return certificate.public_key().map_err(|_| EciesErrorCode::InvalidCertificate);
+    }
+
+    let public_key = if pem { PKey::public_key_from_pem(data) } else { PKey::public_key_from_der(data) };
+    public_key.map_err(|_| EciesErrorCode::InvalidCertificate)
+}
+
+// Returns the curve and uncompressed point of the EC key in a certificate or SubjectPublicKeyInfo
+pub fn extract_public_key(data: &[u8]) -> Result<(EciesCurve, Vec<u8>), EciesErrorCode> {
+    let public_key = public_key_from_der_or_pem(data)?;
+    let ec_key = public_key.ec_key().map_err(|_| EciesErrorCode::UnsupportedCurve)?;
+
+    let curve = match ec_key.group().curve_name() {
+        Some(Nid::SECP256K1) => EciesCurve::Secp256k1,
+        Some(Nid::X9_62_PRIME256V1) => EciesCurve::P256,
+        _ => return Err(EciesErrorCode::UnsupportedCurve),
+    };
+
+    let mut context = BigNumContext::new().map_err(|_| EciesErrorCode::InvalidCertificate)?;
+    let point = ec_key
+        .public_key()
+        .to_bytes(ec_key.group(), PointConversionForm::UNCOMPRESSED, &mut context)
+        .map_err(|_| EciesErrorCode::InvalidCertificate)?;
+
+    Ok((curve, point))
+}
+
+/**
+Extracts the public key of an X.509 certificate or a SubjectPublicKeyInfo.
+It takes the DER or PEM bytes and their length, and returns the key as a hexadecimal uncompressed point, writing its
+curve into `curve_ptr` when not null. Keys on other curves, or of other types, fail with `UnsupportedCurve`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_public_key_from_x509(
+    data_ptr: *const u8,
+    data_length: usize,
+    curve_ptr: *mut EciesCurve,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let data = slice_from_raw(data_ptr, data_length)?;
+        let (curve, point) = extract_public_key(data)?;
+
+        let public_key_ptr = string_into_ptr(hex::encode(point))?;
+        if !curve_ptr.is_null() {
+            unsafe { *curve_ptr = curve };
+        }
+        Ok(public_key_ptr)
+    })();
+
+    finish(result, error_ptr)
+}
@@ -0,0 +1,21 @@
+use crate::error::EciesErrorCode;
+use crate::ffi::finish_status;
+
+pub use ecies_core::policy::*;
+
+/*
+The algorithm policy of `ecies_core::policy` for C hosts, usually set once at startup before any other call.
+*/
+
+/**
+Sets the algorithm policy of the process from its `EciesPolicy` value, or fails with `InvalidArgument` for any other
+value, returning whether it was set. Under `Fips`, the algorithms outside the FIPS 140-3 approved set (secp256k1
+key agreement, X25519, XSalsa20, ChaCha20 and the XOR scheme of SEC 1) fail with `PolicyViolation` from the next
+call on, leaving the P-256 functions.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_set_policy(policy: u32, error_ptr: *mut EciesErrorCode) -> bool {
+    let result = EciesPolicy::from_code(policy).map(set_policy).ok_or(EciesErrorCode::InvalidArgument);
+
+    finish_status(result, error_ptr)
+}
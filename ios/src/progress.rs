@@ -0,0 +1,105 @@
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::EciesErrorCode;
+
+/*
+Progress reporting and cancellation for the long-running file and descriptor operations.
+
+    - callback: optional host callback, called after each chunk with the number of input bytes processed so far
+      and the total, or 0 when the input size is unknown, e.g. for pipes
+    - callback_context: opaque pointer handed back to `callback`
+    - cancel_token: optional token from `ecies_cancel_token_new`. Once `ecies_cancel` is called on it, possibly from
+      another thread, the operation stops at the next chunk and fails with `EciesErrorCode::Cancelled`
+
+The callback runs on the thread performing the operation.
+*/
+
+pub type EciesProgressCallback = Option<unsafe extern "C" fn(context: *mut c_void, processed: u64, total: u64)>;
+
+#[repr(C)]
+pub struct EciesProgressOptions {
+    pub callback: EciesProgressCallback,
+    pub callback_context: *mut c_void,
+    pub cancel_token: *const EciesCancelToken,
+}
+
+pub struct EciesCancelToken {
+    cancelled: AtomicBool,
+}
+
+impl EciesCancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Progress state of one operation
+pub struct Progress<'a> {
+    callback: EciesProgressCallback,
+    context: *mut c_void,
+    cancel_token: Option<&'a EciesCancelToken>,
+    processed: u64,
+    total: u64,
+}
+
+impl<'a> Progress<'a> {
+    pub fn none() -> Progress<'a> {
+        Progress { callback: None, context: std::ptr::null_mut(), cancel_token: None, processed: 0, total: 0 }
+    }
+
+    // Null options report nothing and cannot be cancelled
+    pub unsafe fn from_options(options_ptr: *const EciesProgressOptions) -> Progress<'a> {
+        match unsafe { options_ptr.as_ref() } {
+            Some(options) => Progress {
+                callback: options.callback,
+                context: options.callback_context,
+                cancel_token: unsafe { options.cancel_token.as_ref() },
+                processed: 0,
+                total: 0,
+            },
+            None => Progress::none(),
+        }
+    }
+
+    pub fn set_total(&mut self, total: u64) {
+        self.total = total;
+    }
+
+    // Records processed input, failing with `Cancelled` if the operation was cancelled meanwhile
+    pub fn advance(&mut self, bytes: usize) -> Result<(), EciesErrorCode> {
+        if self.cancel_token.is_some_and(EciesCancelToken::is_cancelled) {
+            return Err(EciesErrorCode::Cancelled);
+        }
+        self.processed += bytes as u64;
+        if let Some(callback) = self.callback {
+            unsafe { callback(self.context, self.processed, self.total) };
+        }
+        Ok(())
+    }
+}
+
+/**
+Creates a cancellation token. Release it with `ecies_cancel_token_free` once no operation uses it anymore.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_cancel_token_new() -> *mut EciesCancelToken {
+    Box::into_raw(Box::new(EciesCancelToken { cancelled: AtomicBool::new(false) }))
+}
+
+/**
+Cancels the operations using the token. Safe to call from any thread, and more than once.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_cancel(cancel_token_ptr: *const EciesCancelToken) {
+    if let Some(cancel_token) = unsafe { cancel_token_ptr.as_ref() } {
+        cancel_token.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ecies_cancel_token_free(cancel_token_ptr: *mut EciesCancelToken) {
+    if !cancel_token_ptr.is_null() {
+        drop(unsafe { Box::from_raw(cancel_token_ptr) });
+    }
+}
@@ -0,0 +1,101 @@
+use std::os::raw::c_char;
+use prost::Message;
+
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::envelope::{
+    assemble, open_with_options, parse, seal_with_options, EciesDecryptOptions, EciesEncryptOptions, Suite,
+};
+use crate::error::EciesErrorCode;
+use crate::ffi::{bytes_from_ptr, finish, public_key_from_ptr, secret_key_from_ptr, string_into_ptr};
+
+/*
+Protobuf encoding of the envelope, matching the schema in `proto/envelope.proto`, so services already using
+protobuf can store and route ciphertexts without re-encoding them.
+The message is declared by hand with prost's derive so the crate needs no build-time protoc.
+*/
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoEnvelope {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    #[prost(uint32, tag = "2")]
+    pub suite: u32,
+    #[prost(bytes = "vec", tag = "3")]
+    pub header: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub payload: Vec<u8>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub signature: Option<Vec<u8>>,
+}
+
+// Converts a binary envelope into its protobuf form
+pub fn encode(envelope: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let parsed = parse(envelope)?;
+
+    let message = ProtoEnvelope {
+        version: parsed.version as u32,
+        suite: parsed.suite as u32,
+        header: parsed.header_bytes().to_vec(),
+        payload: parsed.payload.to_vec(),
+        signature: parsed.signature.map(|signature| signature.to_vec()),
+    };
+
+    Ok(message.encode_to_vec())
+}
+
+// Converts the protobuf form back into the binary envelope
+pub fn decode(bytes: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let message = ProtoEnvelope::decode(bytes).map_err(|_| EciesErrorCode::InvalidProtobuf)?;
+
+    let version = u8::try_from(message.version).map_err(|_| EciesErrorCode::UnsupportedVersion)?;
+    let suite = u8::try_from(message.suite).map_err(|_| EciesErrorCode::UnsupportedSuite)?;
+
+    assemble(version, Suite::from_byte(suite)?, &message.header, &message.payload, message.signature.as_deref())
+}
+
+/**
+Encrypts a message into an envelope using the provided public key and returns its protobuf encoding.
+Takes the same options as `ecies_encrypt_with_options`. The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_protobuf(
+    public_key_ptr: *const c_char,
+    message_ptr: *const c_char,
+    options_ptr: *const EciesEncryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+        let message_buffer = bytes_from_ptr(message_ptr)?;
+
+        let envelope = seal_with_options(&public_key, message_buffer, options_ptr)?;
+        encode(&envelope)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Decrypts a protobuf-encoded envelope using the provided secret key.
+Takes the same options as `ecies_decrypt_with_options` and returns the decrypted message as a C string,
+or null on failure with the reason written into `error_ptr`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_protobuf(
+    secret_key_ptr: *const c_char,
+    protobuf_ptr: *const u8,
+    protobuf_length: usize,
+    options_ptr: *const EciesDecryptOptions,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+        let protobuf = slice_from_raw(protobuf_ptr, protobuf_length)?;
+
+        let envelope = decode(protobuf)?;
+        let (_, decrypted) = open_with_options(&secret_key, &envelope, options_ptr)?;
+        string_into_ptr(decrypted)
+    })();
+
+    finish(result, error_ptr)
+}
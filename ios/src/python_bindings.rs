@@ -0,0 +1,87 @@
+use ecies::SecretKey;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::armor::decode_text;
+use crate::context::{generate_keypair as new_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::keys::parse_public_key;
+
+/*
+Python extension module `ecies_ffi`, built with the `python` feature from the `android/` cdylib (see
+android/pyproject.toml), so Python code calls the library directly instead of through ctypes.
+
+Keys are `bytes`: 32-byte secret keys, public keys in any layout accepted by the C API (compressed on output).
+`encrypt` returns a binary envelope, the base64-decoded output of `ecies_encrypt`; `decrypt` takes one as `bytes`,
+or the text form (base64 or armor) as `str`. The GIL is released while encrypting and decrypting. Failures raise
+`ecies_ffi.EciesError`, whose args are the `EciesErrorCode` variant name and value.
+*/
+
+create_exception!(ecies_ffi, EciesError, PyException);
+
+// `EciesErrorCode` comes from `ecies_core`, so this is a function rather than a `From` impl
+fn py_error(code: EciesErrorCode) -> PyErr {
+    EciesError::new_err((code.to_string(), code as i32))
+}
+
+fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey, EciesErrorCode> {
+    crate::keys::parse_secret_key(bytes)
+}
+
+#[derive(FromPyObject)]
+enum Ciphertext<'a> {
+    Binary(&'a [u8]),
+    Text(&'a str),
+}
+
+// Returns `(secret_key, public_key)`
+#[pyfunction]
+fn generate_keypair(py: Python<'_>) -> PyResult<(&PyBytes, &PyBytes)> {
+    let (secret_key, public_key) = new_keypair().map_err(py_error)?;
+
+    Ok((PyBytes::new(py, &secret_key.serialize()), PyBytes::new(py, &public_key.serialize_compressed())))
+}
+
+#[pyfunction]
+fn public_key_from<'py>(py: Python<'py>, secret_key: &[u8]) -> PyResult<&'py PyBytes> {
+    let secret_key = parse_secret_key(secret_key).map_err(py_error)?;
+
+    Ok(PyBytes::new(py, &public_key_of(&secret_key).serialize_compressed()))
+}
+
+#[pyfunction]
+fn encrypt<'py>(py: Python<'py>, public_key: &[u8], message: &[u8]) -> PyResult<&'py PyBytes> {
+    let public_key = parse_public_key(public_key).map_err(py_error)?;
+    let envelope = py.allow_threads(|| seal(&public_key, &Header::default(), message, None)).map_err(py_error)?;
+
+    Ok(PyBytes::new(py, &envelope))
+}
+
+#[pyfunction]
+fn decrypt<'py>(py: Python<'py>, secret_key: &[u8], ciphertext: Ciphertext) -> PyResult<&'py PyBytes> {
+    let secret_key = parse_secret_key(secret_key).map_err(py_error)?;
+    let message = py.allow_threads(|| {
+        let (_, message) = match ciphertext {
+            Ciphertext::Binary(bytes) => open_or_legacy(&secret_key, bytes)?,
+            Ciphertext::Text(text) => open_or_legacy(&secret_key, &decode_text(text.as_bytes())?)?,
+        };
+        Ok::<_, EciesErrorCode>(message)
+    })
+    .map_err(py_error)?;
+
+    Ok(PyBytes::new(py, &message))
+}
+
+#[pymodule]
+fn ecies_ffi(py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add("EciesError", py.get_type::<EciesError>())?;
+    module.add_function(wrap_pyfunction!(generate_keypair, module)?)?;
+    module.add_function(wrap_pyfunction!(public_key_from, module)?)?;
+    module.add_function(wrap_pyfunction!(encrypt, module)?)?;
+    module.add_function(wrap_pyfunction!(decrypt, module)?)?;
+
+    Ok(())
+}
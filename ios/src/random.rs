@@ -0,0 +1,29 @@
+use crate::error::EciesErrorCode;
+use crate::ffi::finish_status;
+
+pub use ecies_core::random::*;
+
+/*
+The random number generator of `ecies_core::random` for C hosts, so salts, nonces and tokens of the app come from
+the same health-tested source as the keys of the library rather than from platform wrappers of varying quality.
+*/
+
+/**
+Fills the `length` bytes at `out` with cryptographically secure random bytes. It fails with `RngFailure`, leaving
+the buffer zeroed, once the random number generator failed its health tests; a zero `length` only checks them.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_random_bytes(out: *mut u8, length: usize, error_ptr: *mut EciesErrorCode) -> bool {
+    let result = (|| {
+        if length == 0 {
+            return random_bytes(&mut []);
+        }
+        if out.is_null() {
+            return Err(EciesErrorCode::NullPointer);
+        }
+
+        random_bytes(unsafe { std::slice::from_raw_parts_mut(out, length) })
+    })();
+
+    finish_status(result, error_ptr)
+}
@@ -0,0 +1,134 @@
+use crate::buffer::{slice_from_raw, EciesBuffer};
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish_handle, finish_status, secret_key_from_raw, write_raw};
+use crate::keys::parse_public_key;
+
+/*
+Exports for the FFI of scripting runtimes (`Deno.dlopen`, `bun:ffi`), which only pass integers and pointers:
+no structs by value and no C strings. Every parameter is a pointer, a `usize` length or a bool, so the symbol
+table in `scripting/ecies.symbols.json` describes them completely.
+
+Inputs are `(pointer, length)` pairs, which the runtimes produce from a `Uint8Array`. Binary outputs are returned
+as a pointer to an `EciesBuffer`: read it with `ecies_script_buffer_data` and `ecies_script_buffer_length`, copy
+the bytes out and release it with `ecies_script_buffer_free`. `scripting/ecies.ts` wraps all of this.
+
+Keys are raw bytes: 32-byte secret keys, 33-byte compressed public keys on output (any layout on input).
+Ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`.
+*/
+
+/**
+Generates a keypair into caller-provided memory: 32 bytes at `secret_key_out` and 33 bytes (compressed) at
+`public_key_out`. Returns false on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_generate_keypair(
+    secret_key_out: *mut u8,
+    public_key_out: *mut u8,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let (secret_key, public_key) = generate_keypair()?;
+        write_raw(secret_key_out, &secret_key.serialize())?;
+        write_raw(public_key_out, &public_key.serialize_compressed())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Writes the 33-byte compressed public key of a 32-byte secret key to `public_key_out`. Returns false on failure.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_public_key_from(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    public_key_out: *mut u8,
+    error_ptr: *mut EciesErrorCode,
+) -> bool {
+    let result = (|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        write_raw(public_key_out, &public_key_of(&secret_key).serialize_compressed())
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Encrypts a message into a binary envelope. Returns null on failure; release the result with
+`ecies_script_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_encrypt(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    let result = (|| {
+        let public_key = parse_public_key(slice_from_raw(public_key_ptr, public_key_length)?)?;
+        let message = slice_from_raw(message_ptr, message_length)?;
+        let envelope = seal(&public_key, &Header::default(), message, None)?;
+
+        Ok(Box::new(EciesBuffer::from_vec(envelope)))
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Decrypts a binary envelope, or a legacy ciphertext. Returns null on failure; release the result with
+`ecies_script_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_decrypt(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    ciphertext_ptr: *const u8,
+    ciphertext_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesBuffer {
+    let result = (|| {
+        let secret_key = secret_key_from_raw(secret_key_ptr, secret_key_length)?;
+        let ciphertext = slice_from_raw(ciphertext_ptr, ciphertext_length)?;
+        let (_, message) = open_or_legacy(&secret_key, ciphertext)?;
+
+        Ok(Box::new(EciesBuffer::from_vec(message)))
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Returns the address of the bytes of a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions, null for null.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_buffer_data(buffer: *const EciesBuffer) -> *const u8 {
+    match unsafe { buffer.as_ref() } {
+        Some(buffer) => buffer.data as *const u8,
+        None => std::ptr::null(),
+    }
+}
+
+/**
+Returns the length of a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions, 0 for null.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_buffer_length(buffer: *const EciesBuffer) -> usize {
+    match unsafe { buffer.as_ref() } {
+        Some(buffer) => buffer.length,
+        None => 0,
+    }
+}
+
+/**
+Releases a buffer returned by the `ecies_script_*` or `ecies_jna_*` functions. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_script_buffer_free(buffer: *mut EciesBuffer) {
+    if !buffer.is_null() {
+        crate::buffer::ecies_buffer_free(*unsafe { Box::from_raw(buffer) });
+    }
+}
@@ -0,0 +1,126 @@
+use std::os::raw::c_char;
+use crypto_box::{PublicKey as BoxPublicKey, SecretKey as BoxSecretKey};
+
+use crate::audit::{begin, outcome, EciesAuditOperation};
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish, str_from_ptr, string_into_ptr};
+use crate::keys::decode_key_text;
+use crate::policy::allow_unapproved;
+use crate::random::rng;
+
+/*
+libsodium sealed boxes (`crypto_box_seal`): X25519 key agreement with an ephemeral key and XSalsa20-Poly1305,
+the nonce being derived from both public keys.
+
+Output layout:
+    ephemeral public key (32 bytes) | Poly1305 tag (16 bytes) | encrypted message
+
+Keys are 32-byte X25519 keys in hex (or base64 on input), the same bytes as libsodium's `crypto_box_keypair` outputs.
+*/
+
+fn box_public_key_from_text(text: &str) -> Result<BoxPublicKey, EciesErrorCode> {
+    let bytes: [u8; 32] = (*decode_key_text(text)?.0)
+        .try_into()
+        .map_err(|_| EciesErrorCode::InvalidPublicKey)?;
+    Ok(BoxPublicKey::from(bytes))
+}
+
+fn box_secret_key_from_text(text: &str) -> Result<BoxSecretKey, EciesErrorCode> {
+    let bytes: [u8; 32] = (*decode_key_text(text)?.0)
+        .try_into()
+        .map_err(|_| EciesErrorCode::InvalidSecretKey)?;
+    Ok(BoxSecretKey::from(bytes))
+}
+
+pub fn seal(public_key: &BoxPublicKey, message: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Encryption);
+    let result = allow_unapproved()
+        .and_then(|()| rng())
+        .and_then(|mut rng| public_key.seal(&mut rng, message).map_err(|_| EciesErrorCode::EncryptionFailed));
+    operation.record(|| *public_key.as_bytes(), message.len(), outcome(&result));
+    result
+}
+
+pub fn unseal(secret_key: &BoxSecretKey, sealed: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+    let operation = begin(EciesAuditOperation::Decryption);
+    let result = allow_unapproved().and_then(|()| secret_key.unseal(sealed).map_err(|_| EciesErrorCode::MacFailed));
+    operation.record(|| *secret_key.public_key().as_bytes(), sealed.len(), outcome(&result));
+    result
+}
+
+/**
+Generates a new X25519 secret key for sealed boxes, returned as hex.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_sealed_box_generate_secret_key(error_ptr: *mut EciesErrorCode) -> *const c_char {
+    let result = (|| {
+        allow_unapproved()?;
+        let operation = begin(EciesAuditOperation::KeyGeneration);
+        let secret_key = BoxSecretKey::generate(&mut rng()?);
+        operation.record(|| *secret_key.public_key().as_bytes(), 0, Ok(0));
+
+        string_into_ptr(hex::encode(secret_key.to_bytes()))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Derives the hex X25519 public key of a hex sealed box secret key.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_sealed_box_public_key_from(
+    secret_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> *const c_char {
+    let result = (|| {
+        let secret_key = box_secret_key_from_text(str_from_ptr(secret_key_ptr)?)?;
+
+        string_into_ptr(hex::encode(secret_key.public_key().as_bytes()))
+    })();
+
+    finish(result, error_ptr)
+}
+
+/**
+Seals data for a hex X25519 public key, like `crypto_box_seal`.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_sealed_box_seal(
+    public_key_ptr: *const c_char,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let public_key = box_public_key_from_text(str_from_ptr(public_key_ptr)?)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        seal(&public_key, data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Opens a sealed box with a hex X25519 secret key, like `crypto_box_seal_open`.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_sealed_box_open(
+    secret_key_ptr: *const c_char,
+    sealed_ptr: *const u8,
+    sealed_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let secret_key = box_secret_key_from_text(str_from_ptr(secret_key_ptr)?)?;
+        let sealed = slice_from_raw(sealed_ptr, sealed_length)?;
+
+        unseal(&secret_key, sealed)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
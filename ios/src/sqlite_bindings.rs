@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::{Arc, Mutex, PoisonError};
+use ecies::{PublicKey, SecretKey};
+use rand::RngCore;
+use rusqlite::ffi;
+use rusqlite::functions::{Context, FunctionFlags};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, Error};
+
+use ecies_core::Keypair;
+
+use crate::armor::decode_text;
+use crate::envelope::{open_or_legacy, seal, Header};
+use crate::error::EciesErrorCode;
+use crate::ffi::{secret_key_from_ptr, write_error};
+use crate::keys::{parse_public_key, parse_public_key_text};
+use crate::random::rng;
+
+/*
+SQLite loadable extension, built with the `sqlite` feature from the `android/` cdylib, for column-level envelope
+encryption inside queries, e.g. from Room on top of a SQLite build that loads extensions:
+
+    SELECT load_extension('libecies');
+    INSERT INTO notes (body) VALUES (ecies_encrypt(:public_key, :body));
+    SELECT ecies_decrypt(:secret_handle, body) FROM notes;
+
+`ecies_encrypt(pubkey, message)` takes the public key as hex or base64 TEXT, or as a BLOB in any layout, and
+returns the binary envelope as a BLOB. TEXT messages are encrypted as their UTF-8 bytes.
+
+`ecies_decrypt(secret_handle, ciphertext)` takes a handle from `ecies_sqlite_add_secret_key` rather than the
+secret key itself, so keys never appear in SQL text, query logs or bound parameters. BLOB ciphertexts are binary
+envelopes (or legacy ciphertexts), TEXT ones the base64 or armored form of `ecies_encrypt`. It returns a BLOB;
+`CAST(... AS TEXT)` reads text messages back. Sender signatures are verified when present. It is registered as
+direct-only, so triggers, views and schema expressions planted in a database cannot call it.
+
+Either function returns NULL for NULL arguments. Failures raise an SQL error whose message is the
+`EciesErrorCode` variant name.
+*/
+
+// Secret keys registered for `ecies_decrypt`, shared by every connection of the process. Handles are random, so a
+// query cannot reach the keys of other connections by counting
+static SECRET_KEYS: Mutex<Option<HashMap<i64, Arc<Keypair>>>> = Mutex::new(None);
+
+fn sql_error(code: EciesErrorCode) -> Error {
+    Error::UserFunctionError(Box::new(code))
+}
+
+fn public_key_argument(ctx: &Context, index: usize) -> rusqlite::Result<Option<PublicKey>> {
+    let public_key = match ctx.get_raw(index) {
+        ValueRef::Null => return Ok(None),
+        ValueRef::Text(text) => std::str::from_utf8(text)
+            .map_err(|_| EciesErrorCode::InvalidUtf8)
+            .and_then(parse_public_key_text)
+            .map(|(public_key, _)| public_key),
+        ValueRef::Blob(bytes) => parse_public_key(bytes),
+        _ => Err(EciesErrorCode::InvalidPublicKey),
+    };
+    public_key.map(Some).map_err(sql_error)
+}
+
+fn secret_key_argument(ctx: &Context, index: usize) -> rusqlite::Result<Option<Arc<Keypair>>> {
+    let handle = match ctx.get_raw(index) {
+        ValueRef::Null => return Ok(None),
+        ValueRef::Integer(handle) => handle,
+        _ => return Err(sql_error(EciesErrorCode::InvalidArgument)),
+    };
+    let secret_keys = SECRET_KEYS.lock().unwrap_or_else(PoisonError::into_inner);
+    let keypair = secret_keys.as_ref().and_then(|secret_keys| secret_keys.get(&handle));
+
+    keypair.cloned().map(Some).ok_or_else(|| sql_error(EciesErrorCode::InvalidSecretKey))
+}
+
+fn encrypt(ctx: &Context) -> rusqlite::Result<Option<Vec<u8>>> {
+    let Some(public_key) = public_key_argument(ctx, 0)? else {
+        return Ok(None);
+    };
+    let message = match ctx.get_raw(1) {
+        ValueRef::Null => return Ok(None),
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes,
+        _ => return Err(sql_error(EciesErrorCode::InvalidArgument)),
+    };
+
+    seal(&public_key, &Header::default(), message, None).map(Some).map_err(sql_error)
+}
+
+fn decrypt(ctx: &Context) -> rusqlite::Result<Option<Vec<u8>>> {
+    let Some(keypair) = secret_key_argument(ctx, 0)? else {
+        return Ok(None);
+    };
+    let result = match ctx.get_raw(1) {
+        ValueRef::Null => return Ok(None),
+        ValueRef::Blob(envelope) => open_or_legacy(keypair.secret_key(), envelope),
+        ValueRef::Text(text) => {
+            decode_text(text).and_then(|envelope| open_or_legacy(keypair.secret_key(), &envelope))
+        }
+        _ => Err(EciesErrorCode::InvalidEnvelope),
+    };
+
+    result.map(|(_, message)| Some(message)).map_err(sql_error)
+}
+
+fn register_functions(db: Connection) -> rusqlite::Result<bool> {
+    // Encryption is randomized, so it is not deterministic, while it can safely run from triggers
+    db.create_scalar_function("ecies_encrypt", 2, FunctionFlags::SQLITE_UTF8, encrypt)?;
+    // Keys can be removed between calls, so decryption is not deterministic either
+    let decrypt_flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DIRECTONLY;
+    db.create_scalar_function("ecies_decrypt", 2, decrypt_flags, decrypt)?;
+
+    // The functions are registered on this connection only
+    Ok(false)
+}
+
+/**
+Entry point of the extension, found by `load_extension` from the library name (`libecies` becomes
+`sqlite3_ecies_init`). Registers `ecies_encrypt` and `ecies_decrypt` on the connection.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn sqlite3_ecies_init(
+    db: *mut ffi::sqlite3,
+    error_message_ptr: *mut *mut c_char,
+    api_ptr: *mut ffi::sqlite3_api_routines,
+) -> c_int {
+    Connection::extension_init2(db, error_message_ptr, api_ptr, register_functions)
+}
+
+/**
+Registers a secret key (hexadecimal or base64 C string) for the `ecies_decrypt` SQL function and returns its
+handle, a random positive integer to bind as the first argument of `ecies_decrypt`, or 0 on failure with the reason
+written into `error_ptr`. The key stays in native memory until `ecies_sqlite_remove_secret_key`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_sqlite_add_secret_key(
+    secret_key_ptr: *const c_char,
+    error_ptr: *mut EciesErrorCode,
+) -> i64 {
+    match secret_key_from_ptr(secret_key_ptr).and_then(add_secret_key) {
+        Ok(handle) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            handle
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            0
+        }
+    }
+}
+
+// Backs `ecies_sqlite_add_secret_key` and the `sqliteAddSecretKey` native on Android
+pub fn add_secret_key(secret_key: SecretKey) -> Result<i64, EciesErrorCode> {
+    let mut rng = rng()?;
+    let mut secret_keys = SECRET_KEYS.lock().unwrap_or_else(PoisonError::into_inner);
+    let secret_keys = secret_keys.get_or_insert_with(HashMap::new);
+    // A random positive 63-bit integer, drawn again on the unlikely collision
+    let handle = loop {
+        let handle = (rng.next_u64() >> 1) as i64;
+        if handle != 0 && !secret_keys.contains_key(&handle) {
+            break handle;
+        }
+    };
+    secret_keys.insert(handle, Arc::new(Keypair::from_secret_key(secret_key)));
+
+    Ok(handle)
+}
+
+/**
+Releases a secret key registered with `ecies_sqlite_add_secret_key`, which clears it once no query still uses it.
+Later calls to `ecies_decrypt` with the handle fail with `InvalidSecretKey`. Unknown handles are ignored.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_sqlite_remove_secret_key(handle: i64) {
+    let mut secret_keys = SECRET_KEYS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(secret_keys) = secret_keys.as_mut() {
+        secret_keys.remove(&handle);
+    }
+}
@@ -0,0 +1,671 @@
+use std::io::{Read, Write};
+use std::os::raw::c_char;
+use ecies::{PublicKey, SecretKey};
+use hkdf::Hkdf;
+use openssl::symm::{decrypt_aead, Cipher, Crypter, Mode};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::audit::{begin, EciesAuditOperation, Operation};
+use crate::buffer::{finish_buffer, slice_from_raw, EciesBuffer};
+use crate::cipher::{PUBLIC_KEY_LENGTH, TAG_LENGTH};
+use ecies_core::Keypair;
+
+use crate::context::{ecdh_point, ephemeral_keypair};
+use crate::error::EciesErrorCode;
+use crate::ffi::{finish_handle, finish_status, public_key_from_ptr, secret_key_from_ptr};
+use crate::keys::wipe;
+use crate::progress::Progress;
+use crate::random::rng;
+
+/*
+Streaming encryption and decryption, for payloads too large to hold in memory.
+
+The message is cut into chunks, each encrypted with AES-256-GCM under a key derived once per stream from an
+ephemeral ECDH exchange, as in `cipher.rs`. Stream layout:
+    magic "ECIS" | version | chunk size (u32) | ephemeral public key (65 bytes) | nonce prefix (7 bytes) | frame...
+
+and each frame:
+    chunk index (u32) | flags (1 byte) | length of what follows (u32) | encrypted chunk | tag (16 bytes)
+
+Integers are big endian. The only flag is `FLAG_LAST`, set on the last frame. Every chunk but the last holds
+exactly `chunk size` bytes, at most `MAX_CHUNK_SIZE`; the last one holds up to that many and may be empty.
+
+Chunk nonces are the nonce prefix, the chunk index and a byte set to 1 on the last chunk only, and the associated
+data of each chunk is the stream header followed by its frame header. A frame moved, dropped, replayed from another
+stream or relabelled as last therefore fails authentication, and since indexes, flags and lengths are explicit the
+decryptor rejects out-of-order, oversized or trailing frames before decrypting them, bounding its memory by the
+chunk size. A stream that stops before its last frame is reported as truncated.
+
+Decryption releases the plaintext of each chunk as soon as it is authenticated and stops at the first failure.
+*/
+
+pub const STREAM_MAGIC: &[u8; 4] = b"ECIS";
+pub const STREAM_VERSION: u8 = 1;
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+pub const NONCE_PREFIX_LENGTH: usize = 7;
+pub const STREAM_HEADER_LENGTH: usize = STREAM_MAGIC.len() + 1 + 4 + PUBLIC_KEY_LENGTH + NONCE_PREFIX_LENGTH;
+pub const FRAME_HEADER_LENGTH: usize = 4 + 1 + 4;
+pub const FLAG_LAST: u8 = 0x01;
+
+const KEY_INFO: &[u8] = b"ecies-stream";
+
+// HKDF-SHA256 over the uncompressed ephemeral public key and shared point, bound to the streaming construction
+pub fn derive_stream_key(ephemeral_public_key: &PublicKey, shared_point: &PublicKey) -> Result<[u8; 32], EciesErrorCode> {
+    let mut master = Vec::with_capacity(PUBLIC_KEY_LENGTH * 2);
+    master.extend_from_slice(&ephemeral_public_key.serialize());
+    master.extend_from_slice(&shared_point.serialize());
+
+    let hkdf = Hkdf::<Sha256>::new(None, &master);
+    let mut key = [0u8; 32];
+    hkdf.expand(KEY_INFO, &mut key).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+    Ok(key)
+}
+
+// Size of the frame carrying a chunk of the given length
+pub fn frame_size(chunk_length: usize) -> usize {
+    FRAME_HEADER_LENGTH + chunk_length + TAG_LENGTH
+}
+
+// Size of the stream encrypting a message of the given length, which always has at least one chunk
+pub fn stream_size(message_length: usize, chunk_size: usize) -> usize {
+    let chunk_count = message_length.div_ceil(chunk_size).max(1);
+    STREAM_HEADER_LENGTH + chunk_count * frame_size(0) + message_length
+}
+
+// The chunks a message is cut into. An empty message is a single empty chunk
+pub fn message_chunks(message: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    match message.is_empty() {
+        true => vec![&[]],
+        false => message.chunks(chunk_size).collect(),
+    }
+}
+
+fn frame_header(index: u32, last: bool, chunk_length: usize) -> [u8; FRAME_HEADER_LENGTH] {
+    let mut header = [0u8; FRAME_HEADER_LENGTH];
+    header[..4].copy_from_slice(&index.to_be_bytes());
+    header[4] = if last { FLAG_LAST } else { 0 };
+    header[5..].copy_from_slice(&((chunk_length + TAG_LENGTH) as u32).to_be_bytes());
+    header
+}
+
+pub fn chunk_nonce(nonce_prefix: &[u8; NONCE_PREFIX_LENGTH], index: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LENGTH].copy_from_slice(nonce_prefix);
+    nonce[NONCE_PREFIX_LENGTH..11].copy_from_slice(&index.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+pub struct EciesEncryptor {
+    key: [u8; 32],
+    nonce_prefix: [u8; NONCE_PREFIX_LENGTH],
+    header: Vec<u8>,
+    header_written: bool,
+    chunk_size: usize,
+    index: u32,
+    // Plaintext not yet encrypted. A full chunk is only sealed once more data follows, since the last chunk
+    // must be marked as such
+    pending: Vec<u8>,
+    finished: bool,
+    // Reported to the audit hook once finished, or on the first failure
+    operation: Operation,
+    recipient: PublicKey,
+    message_length: usize,
+    stream_length: usize,
+    reported: bool,
+}
+
+impl EciesEncryptor {
+    pub fn new(public_key: &PublicKey) -> Result<EciesEncryptor, EciesErrorCode> {
+        EciesEncryptor::with_chunk_size(public_key, DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(public_key: &PublicKey, chunk_size: usize) -> Result<EciesEncryptor, EciesErrorCode> {
+        if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        let operation = begin(EciesAuditOperation::Encryption);
+        let (ephemeral_secret_key, ephemeral_public_key) = ephemeral_keypair()?;
+
+        let shared_point = ecdh_point(public_key, &ephemeral_secret_key)?;
+        let key = derive_stream_key(&ephemeral_public_key, &shared_point)?;
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LENGTH];
+        rng()?.fill_bytes(&mut nonce_prefix);
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_LENGTH);
+        header.extend_from_slice(STREAM_MAGIC);
+        header.push(STREAM_VERSION);
+        header.extend_from_slice(&(chunk_size as u32).to_be_bytes());
+        header.extend_from_slice(&ephemeral_public_key.serialize());
+        header.extend_from_slice(&nonce_prefix);
+
+        Ok(EciesEncryptor {
+            key,
+            nonce_prefix,
+            header,
+            header_written: false,
+            chunk_size,
+            index: 0,
+            pending: Vec::with_capacity(chunk_size),
+            finished: false,
+            operation,
+            recipient: *public_key,
+            message_length: 0,
+            stream_length: 0,
+            reported: false,
+        })
+    }
+
+    fn seal_chunk(&mut self, chunk: &[u8], last: bool, output: &mut Vec<u8>) -> Result<(), EciesErrorCode> {
+        let start = output.len();
+        output.resize(start + frame_size(chunk.len()), 0);
+        self.seal_chunk_into(chunk, last, &mut output[start..])
+    }
+
+    // Writes the frame of the next chunk into `output`, which must hold exactly `frame_size(chunk.len())` bytes
+    pub fn seal_chunk_into(&mut self, chunk: &[u8], last: bool, output: &mut [u8]) -> Result<(), EciesErrorCode> {
+        self.seal_frame(self.index, chunk, last, output)?;
+        self.index = self.index.checked_add(1).ok_or(EciesErrorCode::EncryptionFailed)?;
+        Ok(())
+    }
+
+    // Writes the frame of chunk `index` into `output`, like `seal_chunk_into`. Frames do not depend on each other,
+    // so callers knowing every chunk up front may seal them in any order, or in parallel
+    pub fn seal_frame(&self, index: u32, chunk: &[u8], last: bool, output: &mut [u8]) -> Result<(), EciesErrorCode> {
+        if output.len() != frame_size(chunk.len()) {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        if chunk.len() > self.chunk_size {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        let nonce = chunk_nonce(&self.nonce_prefix, index, last);
+        let (header, frame) = output.split_at_mut(FRAME_HEADER_LENGTH);
+        header.copy_from_slice(&frame_header(index, last, chunk.len()));
+
+        let mut crypter = Crypter::new(Cipher::aes_256_gcm(), Mode::Encrypt, &self.key, Some(&nonce))
+            .map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        crypter.aad_update(&self.header).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        crypter.aad_update(header).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        // The tag space after the chunk leaves the cipher the spare room it requires
+        let mut written = crypter.update(chunk, frame).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        written += crypter.finalize(&mut frame[written..]).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+        crypter.get_tag(&mut frame[written..]).map_err(|_| EciesErrorCode::EncryptionFailed)?;
+
+        Ok(())
+    }
+
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn take_header(&mut self, output: &mut Vec<u8>) {
+        if !self.header_written {
+            output.extend_from_slice(&self.header);
+            self.header_written = true;
+        }
+    }
+
+    // Encrypts more of the message, returning the stream bytes that became ready
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        let result = self.seal_more(data);
+        self.message_length += data.len();
+        self.account(&result, false);
+        result
+    }
+
+    // Encrypts the rest of the message as the last chunk, returning the end of the stream
+    pub fn finish(&mut self) -> Result<Vec<u8>, EciesErrorCode> {
+        let result = self.seal_rest();
+        self.account(&result, true);
+        result
+    }
+
+    // Frames sealed with `seal_chunk_into` or `seal_frame` are not counted, their callers report the stream
+    fn account(&mut self, result: &Result<Vec<u8>, EciesErrorCode>, last: bool) {
+        let stream_length = match result {
+            Ok(output) => {
+                self.stream_length += output.len();
+                if !last {
+                    return;
+                }
+                Ok(self.stream_length)
+            }
+            Err(code) => Err(*code),
+        };
+        if !std::mem::replace(&mut self.reported, true) {
+            self.operation.record(|| self.recipient.serialize_compressed(), self.message_length, stream_length);
+        }
+    }
+
+    fn seal_more(&mut self, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        if self.finished {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        let mut output = Vec::new();
+        self.take_header(&mut output);
+
+        self.pending.extend_from_slice(data);
+        if self.pending.len() > self.chunk_size {
+            let pending = std::mem::take(&mut self.pending);
+            let mut chunks = pending.chunks(self.chunk_size).peekable();
+            while let Some(chunk) = chunks.next() {
+                // Keep the last piece, full or not, until more data or the end of the message
+                if chunks.peek().is_none() {
+                    self.pending.extend_from_slice(chunk);
+                    break;
+                }
+                self.seal_chunk(chunk, false, &mut output)?;
+            }
+        }
+        Ok(output)
+    }
+
+    fn seal_rest(&mut self) -> Result<Vec<u8>, EciesErrorCode> {
+        if self.finished {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        let mut output = Vec::new();
+        self.take_header(&mut output);
+
+        let pending = std::mem::take(&mut self.pending);
+        self.seal_chunk(&pending, true, &mut output)?;
+        self.finished = true;
+        Ok(output)
+    }
+}
+
+// The stream key and unsealed plaintext live as long as the handle, so they are cleared on release
+impl Drop for EciesEncryptor {
+    fn drop(&mut self) {
+        wipe(&mut self.key);
+        wipe(&mut self.pending);
+    }
+}
+
+pub struct EciesDecryptor {
+    // Clears the secret key on drop
+    keypair: Keypair,
+    // Set once the stream header has been read
+    key: Option<[u8; 32]>,
+    nonce_prefix: [u8; NONCE_PREFIX_LENGTH],
+    header: Vec<u8>,
+    chunk_size: usize,
+    index: u32,
+    // Stream bytes not yet decrypted
+    pending: Vec<u8>,
+    last_seen: bool,
+    failed: bool,
+    // Reported to the audit hook once finished, or on the first failure
+    operation: Operation,
+    stream_length: usize,
+    message_length: usize,
+    reported: bool,
+}
+
+impl EciesDecryptor {
+    pub fn new(secret_key: SecretKey) -> EciesDecryptor {
+        EciesDecryptor {
+            keypair: Keypair::from_secret_key(secret_key),
+            key: None,
+            nonce_prefix: [0u8; NONCE_PREFIX_LENGTH],
+            header: Vec::new(),
+            chunk_size: 0,
+            index: 0,
+            pending: Vec::new(),
+            last_seen: false,
+            failed: false,
+            operation: begin(EciesAuditOperation::Decryption),
+            stream_length: 0,
+            message_length: 0,
+            reported: false,
+        }
+    }
+
+    fn read_header(&mut self) -> Result<bool, EciesErrorCode> {
+        if self.pending.len() < STREAM_HEADER_LENGTH {
+            return Ok(false);
+        }
+        let header: Vec<u8> = self.pending.drain(..STREAM_HEADER_LENGTH).collect();
+        if !header.starts_with(STREAM_MAGIC) {
+            return Err(EciesErrorCode::InvalidStream);
+        }
+        if header[STREAM_MAGIC.len()] != STREAM_VERSION {
+            return Err(EciesErrorCode::UnsupportedVersion);
+        }
+
+        let chunk_size_start = STREAM_MAGIC.len() + 1;
+        let chunk_size_bytes = header[chunk_size_start..chunk_size_start + 4]
+            .try_into()
+            .map_err(|_| EciesErrorCode::InvalidStream)?;
+        let chunk_size = u32::from_be_bytes(chunk_size_bytes) as usize;
+        if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(EciesErrorCode::InvalidStream);
+        }
+
+        let public_key_start = chunk_size_start + 4;
+        let public_key_end = public_key_start + PUBLIC_KEY_LENGTH;
+        let ephemeral_public_key = PublicKey::parse_slice(&header[public_key_start..public_key_end], None)
+            .map_err(|_| EciesErrorCode::InvalidStream)?;
+        let shared_point = ecdh_point(&ephemeral_public_key, self.keypair.secret_key())?;
+
+        self.key = Some(derive_stream_key(&ephemeral_public_key, &shared_point)?);
+        self.nonce_prefix.copy_from_slice(&header[public_key_end..]);
+        self.chunk_size = chunk_size;
+        self.header = header;
+        Ok(true)
+    }
+
+    fn open_chunk(
+        &self,
+        key: &[u8; 32],
+        frame_header: &[u8],
+        frame: &[u8],
+        last: bool,
+    ) -> Result<Vec<u8>, EciesErrorCode> {
+        let (encrypted, tag) = frame.split_at(frame.len() - TAG_LENGTH);
+        let nonce = chunk_nonce(&self.nonce_prefix, self.index, last);
+        let aad = [self.header.as_slice(), frame_header].concat();
+
+        decrypt_aead(Cipher::aes_256_gcm(), key, Some(&nonce), &aad, encrypted, tag)
+            .map_err(|_| EciesErrorCode::MacFailed)
+    }
+
+    fn decrypt_pending(&mut self, output: &mut Vec<u8>) -> Result<(), EciesErrorCode> {
+        if self.key.is_none() && !self.read_header()? {
+            return Ok(());
+        }
+        let key = self.key.ok_or(EciesErrorCode::InvalidStream)?;
+
+        while !self.pending.is_empty() {
+            // Nothing may follow the last frame
+            if self.last_seen {
+                return Err(EciesErrorCode::InvalidStream);
+            }
+            if self.pending.len() < FRAME_HEADER_LENGTH {
+                break;
+            }
+            let frame_header = &self.pending[..FRAME_HEADER_LENGTH];
+            let index = u32::from_be_bytes(frame_header[..4].try_into().map_err(|_| EciesErrorCode::InvalidStream)?);
+            let last = match frame_header[4] {
+                0 => false,
+                FLAG_LAST => true,
+                _ => return Err(EciesErrorCode::InvalidStream),
+            };
+            let frame_length = frame_header[5..].try_into().map_err(|_| EciesErrorCode::InvalidStream)?;
+            let frame_length = u32::from_be_bytes(frame_length) as usize;
+
+            // Checked before waiting for the frame, so a forged length cannot make the decryptor buffer more
+            let chunk_length = frame_length.checked_sub(TAG_LENGTH).ok_or(EciesErrorCode::InvalidStream)?;
+            if index != self.index || chunk_length > self.chunk_size || (!last && chunk_length != self.chunk_size) {
+                return Err(EciesErrorCode::InvalidStream);
+            }
+            let frame_end = FRAME_HEADER_LENGTH + frame_length;
+            if self.pending.len() < frame_end {
+                break;
+            }
+
+            let chunk = self.open_chunk(&key, frame_header, &self.pending[FRAME_HEADER_LENGTH..frame_end], last)?;
+            output.extend_from_slice(&chunk);
+            self.pending.drain(..frame_end);
+            self.last_seen = last;
+            self.index = self.index.checked_add(1).ok_or(EciesErrorCode::InvalidStream)?;
+        }
+        Ok(())
+    }
+
+    // Decrypts more of the stream, returning the plaintext of the chunks it completed
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, EciesErrorCode> {
+        if self.failed {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        self.pending.extend_from_slice(data);
+        self.stream_length += data.len();
+
+        let mut output = Vec::new();
+        let result = self.decrypt_pending(&mut output);
+        self.message_length += output.len();
+        self.failed = result.is_err();
+        if let Err(code) = result {
+            self.report(Err(code));
+        }
+        result.map(|_| output)
+    }
+
+    // Checks that the stream ended with its last chunk
+    pub fn finish(&mut self) -> Result<(), EciesErrorCode> {
+        if self.failed {
+            return Err(EciesErrorCode::InvalidArgument);
+        }
+        if !self.last_seen || !self.pending.is_empty() {
+            self.failed = true;
+            self.report(Err(EciesErrorCode::TruncatedStream));
+            return Err(EciesErrorCode::TruncatedStream);
+        }
+        self.report(Ok(self.message_length));
+        Ok(())
+    }
+
+    fn report(&mut self, message_length: Result<usize, EciesErrorCode>) {
+        if !std::mem::replace(&mut self.reported, true) {
+            let public_key = || self.keypair.public_key().serialize_compressed();
+            self.operation.record(public_key, self.stream_length, message_length);
+        }
+    }
+}
+
+// Once the header was read, the stream key lives as long as the handle, so it is cleared on release like the
+// secret key
+impl Drop for EciesDecryptor {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.as_mut() {
+            wipe(key);
+        }
+    }
+}
+
+// Encrypts everything `reader` yields into `writer`, holding at most a chunk of plaintext in memory
+pub fn encrypt_io<R: Read, W: Write>(
+    public_key: &PublicKey,
+    mut reader: R,
+    mut writer: W,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let mut encryptor = EciesEncryptor::new(public_key)?;
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+    loop {
+        let read = read_some(&mut reader, &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&encryptor.update(&buffer[..read])?).map_err(|_| EciesErrorCode::IoFailed)?;
+        progress.advance(read)?;
+    }
+    writer.write_all(&encryptor.finish()?).map_err(|_| EciesErrorCode::IoFailed)?;
+    writer.flush().map_err(|_| EciesErrorCode::IoFailed)
+}
+
+// Decrypts the stream `reader` yields into `writer`. On failure, part of the plaintext may have been written
+pub fn decrypt_io<R: Read, W: Write>(
+    secret_key: SecretKey,
+    mut reader: R,
+    mut writer: W,
+    progress: &mut Progress,
+) -> Result<(), EciesErrorCode> {
+    let mut decryptor = EciesDecryptor::new(secret_key);
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+    loop {
+        let read = read_some(&mut reader, &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&decryptor.update(&buffer[..read])?).map_err(|_| EciesErrorCode::IoFailed)?;
+        progress.advance(read)?;
+    }
+    decryptor.finish()?;
+    writer.flush().map_err(|_| EciesErrorCode::IoFailed)
+}
+
+fn read_some<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize, EciesErrorCode> {
+    loop {
+        match reader.read(buffer) {
+            Ok(read) => return Ok(read),
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return Err(EciesErrorCode::IoFailed),
+        }
+    }
+}
+
+/**
+Starts encrypting a stream for the given public key. Returns null on failure.
+Feed the message with `ecies_encrypt_update`, end it with `ecies_encrypt_final`, and release the handle with
+`ecies_encryptor_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_init(public_key_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *mut EciesEncryptor {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+
+        EciesEncryptor::new(&public_key).map(Box::new)
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Like `ecies_encrypt_init`, with chunks of `chunk_size` bytes instead of the default 64 KiB, between 1 byte and
+1 MiB. Larger chunks lower the per-chunk overhead of 25 bytes; decryptors buffer up to one chunk.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_init_with_chunk_size(
+    public_key_ptr: *const c_char,
+    chunk_size: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *mut EciesEncryptor {
+    let result = (|| {
+        let public_key = public_key_from_ptr(public_key_ptr)?;
+
+        EciesEncryptor::with_chunk_size(&public_key, chunk_size).map(Box::new)
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Encrypts the next part of the message. Parts may have any size. Returns the stream bytes that became ready, which
+may be empty, to be written out in order. The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_update(
+    encryptor_ptr: *mut EciesEncryptor,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let encryptor = unsafe { encryptor_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        encryptor.update(data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Ends the message, returning the last stream bytes. The encryptor accepts no further data afterwards.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_final(encryptor_ptr: *mut EciesEncryptor, error_ptr: *mut EciesErrorCode) -> EciesBuffer {
+    let result = (|| {
+        let encryptor = unsafe { encryptor_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+
+        encryptor.finish()
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Releases an encryptor returned by `ecies_encrypt_init` or `ecies_encrypt_init_with_chunk_size`, clearing its stream
+key. The handle must not be used afterwards; null is ignored. Releasing an encryptor before `ecies_encrypt_final`
+abandons the stream.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encryptor_free(encryptor_ptr: *mut EciesEncryptor) {
+    if !encryptor_ptr.is_null() {
+        drop(unsafe { Box::from_raw(encryptor_ptr) });
+    }
+}
+
+/**
+Starts decrypting a stream produced by `ecies_encrypt_init` with the given secret key. Returns null on failure.
+Feed the stream with `ecies_decrypt_update`, check its end with `ecies_decrypt_final`, and release the handle with
+`ecies_decryptor_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_init(secret_key_ptr: *const c_char, error_ptr: *mut EciesErrorCode) -> *mut EciesDecryptor {
+    let result = (|| {
+        let secret_key = secret_key_from_ptr(secret_key_ptr)?;
+
+        Ok(Box::new(EciesDecryptor::new(secret_key)))
+    })();
+
+    finish_handle(result, error_ptr)
+}
+
+/**
+Decrypts the next part of the stream. Parts may have any size. Returns the plaintext of every chunk completed and
+authenticated so far, which may be empty. After a failure the decryptor rejects further input.
+The returned buffer must be released with `ecies_buffer_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_update(
+    decryptor_ptr: *mut EciesDecryptor,
+    data_ptr: *const u8,
+    data_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> EciesBuffer {
+    let result = (|| {
+        let decryptor = unsafe { decryptor_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+        let data = slice_from_raw(data_ptr, data_length)?;
+
+        decryptor.update(data)
+    })();
+
+    finish_buffer(result, error_ptr)
+}
+
+/**
+Ends the stream, returning false with `TruncatedStream` if it stopped before its last chunk. Plaintext already
+returned by `ecies_decrypt_update` must not be trusted as complete until this returns true.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_final(decryptor_ptr: *mut EciesDecryptor, error_ptr: *mut EciesErrorCode) -> bool {
+    let result = (|| {
+        let decryptor = unsafe { decryptor_ptr.as_mut() }.ok_or(EciesErrorCode::NullPointer)?;
+
+        decryptor.finish()
+    })();
+
+    finish_status(result, error_ptr)
+}
+
+/**
+Releases a decryptor returned by `ecies_decrypt_init`, clearing its secret key and stream key. The handle must not
+be used afterwards; null is ignored.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decryptor_free(decryptor_ptr: *mut EciesDecryptor) {
+    if !decryptor_ptr.is_null() {
+        drop(unsafe { Box::from_raw(decryptor_ptr) });
+    }
+}
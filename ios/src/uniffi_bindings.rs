@@ -0,0 +1,52 @@
+use crate::armor::decode_text;
+use crate::context::{generate_keypair, public_key_of};
+use crate::envelope::{open_or_legacy, seal_base64};
+use crate::error::EciesErrorCode;
+use crate::keys::{parse_public_key_text, parse_secret_key_text};
+
+/*
+UniFFI interface, built with the `uniffi` feature. `uniffi-bindgen generate` turns it into Kotlin and Swift
+wrappers where failures are thrown as `EciesErrorCode` exceptions and memory is managed by the generated code,
+so new consumers do not go through the string-based JNI layer or raw C pointers.
+
+Keys and ciphertexts use the same text forms as the C API: hex secret keys, compressed hex public keys (any
+supported layout and encoding on input) and base64 envelopes.
+*/
+
+#[derive(uniffi::Record)]
+pub struct Keypair {
+    pub secret_key: String,
+    pub public_key: String,
+}
+
+#[uniffi::export]
+pub fn new_keypair() -> Result<Keypair, EciesErrorCode> {
+    let (secret_key, public_key) = generate_keypair()?;
+
+    Ok(Keypair {
+        secret_key: hex::encode(secret_key.serialize()),
+        public_key: hex::encode(public_key.serialize_compressed()),
+    })
+}
+
+#[uniffi::export]
+pub fn public_key_from(secret_key: String) -> Result<String, EciesErrorCode> {
+    let (secret_key, _) = parse_secret_key_text(&secret_key)?;
+
+    Ok(hex::encode(public_key_of(&secret_key).serialize_compressed()))
+}
+
+#[uniffi::export]
+pub fn encrypt(public_key: String, message: Vec<u8>) -> Result<String, EciesErrorCode> {
+    let (public_key, _) = parse_public_key_text(&public_key)?;
+
+    seal_base64(&public_key, &message)?.into_string().map_err(|_| EciesErrorCode::InvalidUtf8)
+}
+
+#[uniffi::export]
+pub fn decrypt(secret_key: String, ciphertext: String) -> Result<Vec<u8>, EciesErrorCode> {
+    let (secret_key, _) = parse_secret_key_text(&secret_key)?;
+    let (_, decrypted) = open_or_legacy(&secret_key, &decode_text(ciphertext.as_bytes())?)?;
+
+    Ok(decrypted)
+}
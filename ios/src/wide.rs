@@ -0,0 +1,219 @@
+use std::ptr::slice_from_raw_parts_mut;
+use ecies::{PublicKey, SecretKey};
+
+use crate::armor::decode_text;
+use crate::context::public_key_of;
+use crate::envelope::{open_or_legacy, seal_base64};
+use crate::error::EciesErrorCode;
+use crate::ffi::write_error;
+use crate::keys::{parse_public_key_text, parse_secret_key_text, wipe};
+
+/*
+UTF-16 (`_w`) variants of the string-taking functions, for Windows hosts whose strings are `wchar_t` based.
+
+Inputs are passed as a pointer and a length in UTF-16 code units, without terminator. Outputs are NUL-terminated
+UTF-16 strings, released with `ecies_wide_string_free`. Messages are encrypted as UTF-8, so ciphertexts produced
+here decrypt to the same text through the UTF-8 functions on other platforms, and the other way around.
+*/
+
+// Also decodes the strings read from Java by the JNI layer; unpaired surrogates are rejected
+pub fn string_from_utf16_units(units: &[u16]) -> Result<String, EciesErrorCode> {
+    String::from_utf16(units).map_err(|_| EciesErrorCode::InvalidUtf16)
+}
+
+unsafe fn string_from_utf16(ptr: *const u16, length: usize) -> Result<String, EciesErrorCode> {
+    if ptr.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    string_from_utf16_units(unsafe { std::slice::from_raw_parts(ptr, length) })
+}
+
+// The decoded key text is wiped once parsed, see `keys::KeyBytes`
+unsafe fn secret_key_from_utf16(ptr: *const u16, length: usize) -> Result<SecretKey, EciesErrorCode> {
+    let mut text = string_from_utf16(ptr, length)?;
+    let parsed = parse_secret_key_text(&text).map(|(secret_key, _)| secret_key);
+    wipe(unsafe { text.as_bytes_mut() });
+
+    parsed
+}
+
+unsafe fn public_key_from_utf16(ptr: *const u16, length: usize) -> Result<PublicKey, EciesErrorCode> {
+    parse_public_key_text(&string_from_utf16(ptr, length)?).map(|(public_key, _)| public_key)
+}
+
+fn wide_string_into_ptr(string: &str) -> Result<*const u16, EciesErrorCode> {
+    if string.contains('\0') {
+        return Err(EciesErrorCode::InvalidUtf16);
+    }
+    let units: Vec<u16> = string.encode_utf16().chain(std::iter::once(0)).collect();
+
+    Ok(Box::into_raw(units.into_boxed_slice()) as *const u16)
+}
+
+unsafe fn finish_wide(result: Result<*const u16, EciesErrorCode>, error_ptr: *mut EciesErrorCode) -> *const u16 {
+    match result {
+        Ok(ptr) => {
+            write_error(error_ptr, EciesErrorCode::Success);
+            ptr
+        }
+        Err(code) => {
+            write_error(error_ptr, code);
+            std::ptr::null()
+        }
+    }
+}
+
+/**
+Generates the compressed hex public key of a secret key, like `ecies_derive_public_key`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_public_key_from_w(
+    secret_key_ptr: *const u16,
+    secret_key_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const u16 {
+    let result = (|| {
+        let secret_key = secret_key_from_utf16(secret_key_ptr, secret_key_length)?;
+
+        wide_string_into_ptr(&hex::encode(public_key_of(&secret_key).serialize_compressed()))
+    })();
+
+    finish_wide(result, error_ptr)
+}
+
+/**
+Encrypts a message into a base64 envelope, like `ecies_encrypt`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_encrypt_w(
+    public_key_ptr: *const u16,
+    public_key_length: usize,
+    message_ptr: *const u16,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const u16 {
+    let result = (|| {
+        let public_key = public_key_from_utf16(public_key_ptr, public_key_length)?;
+        let message = string_from_utf16(message_ptr, message_length)?;
+
+        let encrypted = seal_base64(&public_key, message.as_bytes())?;
+        wide_string_into_ptr(encrypted.to_str().map_err(|_| EciesErrorCode::InvalidUtf8)?)
+    })();
+
+    finish_wide(result, error_ptr)
+}
+
+/**
+Decrypts a base64 or armored envelope (or legacy ciphertext), like `ecies_decrypt`.
+The decrypted message must be UTF-8 text, otherwise `InvalidUtf8` is reported.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_decrypt_w(
+    secret_key_ptr: *const u16,
+    secret_key_length: usize,
+    message_ptr: *const u16,
+    message_length: usize,
+    error_ptr: *mut EciesErrorCode,
+) -> *const u16 {
+    let result = (|| {
+        let secret_key = secret_key_from_utf16(secret_key_ptr, secret_key_length)?;
+        let message = string_from_utf16(message_ptr, message_length)?;
+
+        let (_, decrypted) = open_or_legacy(&secret_key, &decode_text(message.as_bytes())?)?;
+        let decrypted = String::from_utf8(decrypted).map_err(|_| EciesErrorCode::InvalidUtf8)?;
+        wide_string_into_ptr(&decrypted)
+    })();
+
+    finish_wide(result, error_ptr)
+}
+
+/**
+Releases a UTF-16 string returned by the `_w` functions. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wide_string_free(string_ptr: *const u16) {
+    if string_ptr.is_null() {
+        return;
+    }
+    let mut length = 0;
+    while unsafe { *string_ptr.add(length) } != 0 {
+        length += 1;
+    }
+    drop(unsafe { Box::from_raw(slice_from_raw_parts_mut(string_ptr as *mut u16, length + 1)) });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::generate_keypair;
+
+    fn units(text: &str) -> Vec<u16> {
+        text.encode_utf16().collect()
+    }
+
+    unsafe fn take_wide_string(ptr: *const u16) -> String {
+        assert!(!ptr.is_null());
+        let mut length = 0;
+        while unsafe { *ptr.add(length) } != 0 {
+            length += 1;
+        }
+        let text = String::from_utf16(unsafe { std::slice::from_raw_parts(ptr, length) }).unwrap();
+        unsafe { ecies_wide_string_free(ptr) };
+        text
+    }
+
+    #[test]
+    fn decodes_supplementary_plane_characters() {
+        let text = "🔐 sealed 😀";
+        let encoded = units(text);
+        // Each emoji is a surrogate pair, which modified UTF-8 would split into two 3-byte sequences
+        assert_eq!(encoded.len(), text.chars().count() + 2);
+
+        assert_eq!(string_from_utf16_units(&encoded).unwrap(), text);
+    }
+
+    #[test]
+    fn decodes_cjk() {
+        let text = "你好，世界 암호화 暗号化";
+
+        assert_eq!(string_from_utf16_units(&units(text)).unwrap(), text);
+    }
+
+    #[test]
+    fn keeps_embedded_nul() {
+        assert_eq!(string_from_utf16_units(&units("a\0b")).unwrap().as_bytes(), b"a\0b");
+    }
+
+    #[test]
+    fn rejects_unpaired_surrogates() {
+        assert_eq!(string_from_utf16_units(&[0x61, 0xD83D]), Err(EciesErrorCode::InvalidUtf16));
+        assert_eq!(string_from_utf16_units(&[0xDE00, 0x61]), Err(EciesErrorCode::InvalidUtf16));
+    }
+
+    #[test]
+    fn round_trips_emoji_and_cjk() {
+        let (secret_key, public_key) = generate_keypair().unwrap();
+        let secret_key = units(&hex::encode(secret_key.serialize()));
+        let public_key = units(&hex::encode(public_key.serialize_compressed()));
+
+        for message in ["😀🔐👩‍👩‍👧", "你好，世界", "暗号化されたメッセージ 🙂"] {
+            let message = units(message);
+            let mut error = EciesErrorCode::Success;
+            let ciphertext = unsafe {
+                let ptr =
+                    ecies_encrypt_w(public_key.as_ptr(), public_key.len(), message.as_ptr(), message.len(), &mut error);
+                units(&take_wide_string(ptr))
+            };
+            assert_eq!(error, EciesErrorCode::Success);
+
+            let decrypted = unsafe {
+                let length = ciphertext.len();
+                let ptr =
+                    ecies_decrypt_w(secret_key.as_ptr(), secret_key.len(), ciphertext.as_ptr(), length, &mut error);
+                take_wide_string(ptr)
+            };
+            assert_eq!(error, EciesErrorCode::Success);
+            assert_eq!(units(&decrypted), message);
+        }
+    }
+}
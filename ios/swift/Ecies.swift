@@ -0,0 +1,42 @@
+import Foundation
+
+/*
+Swift API for the iOS app, so it no longer handles the raw C pointers of `ecies.h`.
+
+It sits on the UniFFI bindings generated by build.sh into swift/generated (`ecies.swift`, plus the `eciesFFI`
+header shipped in the xcframework): add this file and `ecies.swift` to the same target. Failures are thrown as
+`EciesErrorCode`, and every string and buffer crossing the boundary is freed by the generated code.
+*/
+
+public enum Ecies {
+    public static func generateKeypair() -> Keypair {
+        newKeypair()
+    }
+
+    public static func publicKey(fromSecretKey secretKey: String) throws -> String {
+        try publicKeyFrom(secretKey: secretKey)
+    }
+
+    public static func encrypt(publicKey: String, message: Data) throws -> String {
+        try generatedEncrypt(publicKey, message)
+    }
+
+    public static func encrypt(publicKey: String, message: String) throws -> String {
+        try encrypt(publicKey: publicKey, message: Data(message.utf8))
+    }
+
+    public static func decrypt(secretKey: String, ciphertext: String) throws -> Data {
+        try generatedDecrypt(secretKey, ciphertext)
+    }
+
+    public static func decryptString(secretKey: String, ciphertext: String) throws -> String {
+        guard let message = String(data: try decrypt(secretKey: secretKey, ciphertext: ciphertext), encoding: .utf8) else {
+            throw EciesErrorCode.InvalidUtf8(message: "InvalidUtf8")
+        }
+        return message
+    }
+}
+
+// The generated functions, which the members of `Ecies` shadow inside the enum
+private let generatedEncrypt: (String, Data) throws -> String = encrypt(publicKey:message:)
+private let generatedDecrypt: (String, String) throws -> Data = decrypt(secretKey:ciphertext:)
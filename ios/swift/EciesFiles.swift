@@ -0,0 +1,108 @@
+import Foundation
+import Ecies
+
+/*
+`async` file encryption for the iOS app, over the `_async` functions of `ecies.h` (the `Ecies` C module of the
+xcframework): the file is processed on a thread of the library while the calling task is suspended, so awaiting
+a large file blocks neither the main thread nor a thread of the Swift concurrency pool.
+
+Add this file next to Ecies.swift. Cancelling the awaiting task cancels the operation, which removes its output
+and throws `CancellationError`; other failures are thrown as `EciesFileError`, whose code is the `EciesErrorCode`
+value. The progress closure is called on the thread of the operation with the bytes processed and the total.
+*/
+
+public struct EciesFileError: Error {
+    public let code: UInt32
+}
+
+extension Ecies {
+    public static func encryptFile(
+        publicKey: String,
+        from input: URL,
+        to output: URL,
+        progress: ((UInt64, UInt64) -> Void)? = nil
+    ) async throws {
+        try await runInBackground(progress: progress) { options, context in
+            var error = Success
+            let started = ecies_encrypt_file_async(
+                publicKey, input.path, output.path, options, fileCompletion, context, &error)
+            return started ? nil : error.rawValue
+        }
+    }
+
+    public static func decryptFile(
+        secretKey: String,
+        from input: URL,
+        to output: URL,
+        progress: ((UInt64, UInt64) -> Void)? = nil
+    ) async throws {
+        try await runInBackground(progress: progress) { options, context in
+            var error = Success
+            let started = ecies_decrypt_file_async(
+                secretKey, input.path, output.path, options, fileCompletion, context, &error)
+            return started ? nil : error.rawValue
+        }
+    }
+
+    // `start` calls one of the `_async` functions and returns the error code when it did not start
+    private static func runInBackground(
+        progress: ((UInt64, UInt64) -> Void)?,
+        start: (UnsafePointer<EciesProgressOptions>, UnsafeMutableRawPointer) -> UInt32?
+    ) async throws {
+        let operation = FileOperation(progress: progress)
+
+        try await withTaskCancellationHandler {
+            try await withCheckedThrowingContinuation { (continuation: CheckedContinuation<Void, Error>) in
+                operation.continuation = continuation
+                // Released by `fileCompletion`, or below when the operation did not start
+                let context = Unmanaged.passRetained(operation).toOpaque()
+                var options = EciesProgressOptions(
+                    callback: progress == nil ? nil : fileProgress,
+                    callback_context: context,
+                    cancel_token: operation.cancelToken)
+
+                if let code = withUnsafePointer(to: &options, { start($0, context) }) {
+                    Unmanaged<FileOperation>.fromOpaque(context).release()
+                    operation.finish(code: code)
+                }
+            }
+        } onCancel: {
+            ecies_cancel(operation.cancelToken)
+        }
+    }
+}
+
+// Context of an operation: keeps the progress closure and the cancel token alive until the completion handler
+private final class FileOperation {
+    let progress: ((UInt64, UInt64) -> Void)?
+    let cancelToken: OpaquePointer = ecies_cancel_token_new()
+    var continuation: CheckedContinuation<Void, Error>?
+
+    init(progress: ((UInt64, UInt64) -> Void)?) {
+        self.progress = progress
+    }
+
+    func finish(code: UInt32) {
+        let continuation = self.continuation
+        self.continuation = nil
+
+        switch code {
+        case Success.rawValue: continuation?.resume()
+        case Cancelled.rawValue: continuation?.resume(throwing: CancellationError())
+        default: continuation?.resume(throwing: EciesFileError(code: code))
+        }
+    }
+
+    deinit {
+        ecies_cancel_token_free(cancelToken)
+    }
+}
+
+private let fileProgress: EciesProgressCallback = { context, processed, total in
+    let operation = Unmanaged<FileOperation>.fromOpaque(context!).takeUnretainedValue()
+    operation.progress?(processed, total)
+}
+
+private let fileCompletion: EciesCompletionCallback = { context, error in
+    Unmanaged<FileOperation>.fromOpaque(context!).takeRetainedValue().finish(code: error.rawValue)
+}
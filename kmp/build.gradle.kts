@@ -0,0 +1,44 @@
+// Kotlin Multiplatform module over the ecies library. Build the native libraries first: `cargo build --release
+// --target <triple>` in ios/ for the Apple targets and in android/ for the others (with `--features uniffi` for
+// Android, whose Kotlin bindings are generated into src/androidMain/kotlin by uniffi-bindgen). Both land in the
+// workspace target/ directory.
+plugins {
+    kotlin("multiplatform")
+    id("com.android.library")
+}
+
+// Maps each Kotlin/Native target to the Rust target whose libecies.a it links
+val rustTargets = mapOf(
+    "iosArm64" to "../target/aarch64-apple-ios/release",
+    "iosSimulatorArm64" to "../target/aarch64-apple-ios-sim/release",
+    "macosArm64" to "../target/aarch64-apple-darwin/release",
+    "linuxX64" to "../target/x86_64-unknown-linux-gnu/release",
+    "mingwX64" to "../target/x86_64-pc-windows-gnu/release",
+)
+
+kotlin {
+    androidTarget()
+
+    listOf(iosArm64(), iosSimulatorArm64(), macosArm64(), linuxX64(), mingwX64()).forEach { target ->
+        val libraryPath = file(rustTargets.getValue(target.name)).absolutePath
+        target.compilations.getByName("main").cinterops.create("ecies") {
+            defFile("src/nativeInterop/cinterop/ecies.def")
+            includeDirs("../ios/include")
+            extraOpts("-libraryPath", libraryPath)
+        }
+    }
+
+    sourceSets {
+        androidMain.dependencies {
+            implementation("net.java.dev.jna:jna:5.14.0@aar")
+        }
+    }
+}
+
+android {
+    namespace = "io.metamask.ecies"
+    compileSdk = 34
+    defaultConfig {
+        minSdk = 21
+    }
+}
@@ -0,0 +1,27 @@
+package io.metamask.ecies
+
+import uniffi.ecies.EciesErrorCode
+
+// Backed by the UniFFI Kotlin bindings, generated into this source set from the android/ library built with
+// `--features uniffi` (see the README), which loads libecies.so through JNA
+actual object Ecies {
+    actual fun generateKeypair(): Keypair {
+        val keypair = uniffi.ecies.newKeypair()
+        return Keypair(keypair.secretKey, keypair.publicKey)
+    }
+
+    actual fun publicKeyFrom(secretKey: String): String = rethrow { uniffi.ecies.publicKeyFrom(secretKey) }
+
+    actual fun encrypt(publicKey: String, message: ByteArray): String =
+        rethrow { uniffi.ecies.encrypt(publicKey, message) }
+
+    actual fun decrypt(secretKey: String, ciphertext: String): ByteArray =
+        rethrow { uniffi.ecies.decrypt(secretKey, ciphertext) }
+
+    // The generated exceptions are one subclass per variant
+    private inline fun <T> rethrow(call: () -> T): T = try {
+        call()
+    } catch (error: EciesErrorCode) {
+        throw EciesException(error::class.simpleName ?: "EciesErrorCode")
+    }
+}
@@ -0,0 +1,27 @@
+package io.metamask.ecies
+
+/**
+ * One Kotlin API over the native library for every KMP target: Kotlin/Native targets call the C API through
+ * cinterop, Android calls the UniFFI Kotlin bindings.
+ *
+ * Keys and ciphertexts use the text forms of the C API: hex secret keys, compressed hex public keys (any supported
+ * layout and encoding on input) and base64 envelopes, so ciphertexts are interchangeable with every other platform.
+ */
+expect object Ecies {
+    fun generateKeypair(): Keypair
+
+    fun publicKeyFrom(secretKey: String): String
+
+    fun encrypt(publicKey: String, message: ByteArray): String
+
+    fun decrypt(secretKey: String, ciphertext: String): ByteArray
+}
+
+data class Keypair(val secretKey: String, val publicKey: String)
+
+/** A failed call, named after its `EciesErrorCode` variant, e.g. "DecryptionFailed", on every target. */
+class EciesException(val error: String) : Exception(error)
+
+fun Ecies.encrypt(publicKey: String, message: String): String = encrypt(publicKey, message.encodeToByteArray())
+
+fun Ecies.decryptString(secretKey: String, ciphertext: String): String = decrypt(secretKey, ciphertext).decodeToString()
@@ -0,0 +1,78 @@
+package io.metamask.ecies
+
+import io.metamask.ecies.native.EciesErrorCode
+import io.metamask.ecies.native.EciesErrorCodeVar
+import io.metamask.ecies.native.ecies_buffer_free
+import io.metamask.ecies.native.ecies_decrypt_bytes
+import io.metamask.ecies.native.ecies_derive_public_key
+import io.metamask.ecies.native.ecies_encrypt_bytes
+import io.metamask.ecies.native.ecies_generate_secret_key
+import io.metamask.ecies.native.ecies_string_free
+import kotlinx.cinterop.ByteVar
+import kotlinx.cinterop.CPointer
+import kotlinx.cinterop.ExperimentalForeignApi
+import kotlinx.cinterop.MemScope
+import kotlinx.cinterop.UByteVar
+import kotlinx.cinterop.addressOf
+import kotlinx.cinterop.alloc
+import kotlinx.cinterop.convert
+import kotlinx.cinterop.memScoped
+import kotlinx.cinterop.ptr
+import kotlinx.cinterop.readBytes
+import kotlinx.cinterop.reinterpret
+import kotlinx.cinterop.toKString
+import kotlinx.cinterop.usePinned
+import kotlinx.cinterop.useContents
+import kotlinx.cinterop.value
+
+@OptIn(ExperimentalForeignApi::class)
+actual object Ecies {
+    actual fun generateKeypair(): Keypair {
+        val secretKey = take(ecies_generate_secret_key(), EciesErrorCode.Success)
+        return Keypair(secretKey, publicKeyFrom(secretKey))
+    }
+
+    actual fun publicKeyFrom(secretKey: String): String = memScoped {
+        val code = errorCode()
+        take(ecies_derive_public_key(secretKey, code.ptr), code.value)
+    }
+
+    actual fun encrypt(publicKey: String, message: ByteArray): String = memScoped {
+        val code = errorCode()
+        // An empty array has no address to pin, and the library rejects null
+        val bytes = if (message.isEmpty()) ByteArray(1) else message
+        val ciphertext = bytes.usePinned { pinned ->
+            ecies_encrypt_bytes(
+                publicKey,
+                pinned.addressOf(0).reinterpret<UByteVar>(),
+                message.size.convert(),
+                null,
+                code.ptr,
+            )
+        }
+        take(ciphertext, code.value)
+    }
+
+    actual fun decrypt(secretKey: String, ciphertext: String): ByteArray = memScoped {
+        val code = errorCode()
+        val buffer = ecies_decrypt_bytes(secretKey, ciphertext, null, code.ptr)
+        val message = buffer.useContents { data?.readBytes(length.toInt()) }
+        ecies_buffer_free(buffer)
+        message ?: throw failure(code.value)
+    }
+
+    private fun MemScope.errorCode(): EciesErrorCodeVar =
+        alloc<EciesErrorCodeVar>().also { it.value = EciesErrorCode.Success }
+
+    private fun failure(code: EciesErrorCode) = EciesException(code.name)
+
+    // Copies a C string returned by the library and releases it
+    private fun take(string: CPointer<ByteVar>?, code: EciesErrorCode): String {
+        string ?: throw failure(code)
+        try {
+            return string.toKString()
+        } finally {
+            ecies_string_free(string)
+        }
+    }
+}
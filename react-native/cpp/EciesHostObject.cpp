@@ -0,0 +1,209 @@
+#include "EciesHostObject.h"
+
+#include <cstdint>
+#include <string>
+#include <thread>
+#include <utility>
+
+extern "C" {
+#include "ecies.h"
+}
+
+namespace ecies::react {
+
+namespace jsi = facebook::jsi;
+
+namespace {
+
+using Bytes = std::vector<std::uint8_t>;
+
+constexpr std::size_t kSecretKeyLength = 32;
+constexpr std::size_t kPublicKeyLength = 33;
+
+// Carries the EciesErrorCode of a failed call until it is thrown or rejected on the JS thread
+struct Failure {
+    EciesErrorCode code;
+};
+
+// Lets an ArrayBuffer take over a vector without copying it
+class VectorBuffer : public jsi::MutableBuffer {
+public:
+    explicit VectorBuffer(Bytes bytes) : bytes_(std::move(bytes)) {}
+
+    size_t size() const override { return bytes_.size(); }
+
+    uint8_t *data() override { return bytes_.data(); }
+
+private:
+    Bytes bytes_;
+};
+
+std::string error_message(EciesErrorCode code) {
+    return "EciesErrorCode " + std::to_string(static_cast<int>(code));
+}
+
+jsi::Value array_buffer(jsi::Runtime &runtime, Bytes bytes) {
+    return jsi::ArrayBuffer(runtime, std::make_shared<VectorBuffer>(std::move(bytes)));
+}
+
+// The library rejects null pointers, which an empty vector may have
+const std::uint8_t *input(const Bytes &bytes) {
+    static const std::uint8_t empty = 0;
+    return bytes.empty() ? &empty : bytes.data();
+}
+
+Bytes bytes_argument(jsi::Runtime &runtime, const jsi::Value *arguments, size_t count, size_t index) {
+    if (index >= count || !arguments[index].isObject() || !arguments[index].asObject(runtime).isArrayBuffer(runtime)) {
+        throw jsi::JSError(runtime, "Expected an ArrayBuffer as argument " + std::to_string(index + 1));
+    }
+    auto buffer = arguments[index].asObject(runtime).getArrayBuffer(runtime);
+    auto data = buffer.data(runtime);
+    return Bytes(data, data + buffer.size(runtime));
+}
+
+// Copies a buffer returned by an `ecies_script_*` function and releases it
+Bytes take(EciesBuffer *buffer, EciesErrorCode code) {
+    if (buffer == nullptr) {
+        throw Failure{code};
+    }
+    const std::uint8_t *data = ecies_script_buffer_data(buffer);
+    Bytes bytes(data, data + ecies_script_buffer_length(buffer));
+    ecies_script_buffer_free(buffer);
+    return bytes;
+}
+
+Bytes encrypt(const Bytes &public_key, const Bytes &message) {
+    EciesErrorCode code = Success;
+    EciesBuffer *buffer =
+        ecies_script_encrypt(input(public_key), public_key.size(), input(message), message.size(), &code);
+    return take(buffer, code);
+}
+
+Bytes decrypt(const Bytes &secret_key, const Bytes &ciphertext) {
+    EciesErrorCode code = Success;
+    EciesBuffer *buffer =
+        ecies_script_decrypt(input(secret_key), secret_key.size(), input(ciphertext), ciphertext.size(), &code);
+    return take(buffer, code);
+}
+
+using Operation = Bytes (*)(const Bytes &, const Bytes &);
+
+jsi::Value run_sync(jsi::Runtime &runtime, Operation operation, Bytes first, Bytes second) {
+    try {
+        return array_buffer(runtime, operation(first, second));
+    } catch (const Failure &failure) {
+        throw jsi::JSError(runtime, error_message(failure.code));
+    }
+}
+
+// Runs the operation on a background thread and settles the returned Promise on the JS thread
+jsi::Value run_async(jsi::Runtime &runtime,
+                     std::shared_ptr<facebook::react::CallInvoker> js_invoker,
+                     Operation operation,
+                     Bytes first,
+                     Bytes second) {
+    auto executor = jsi::Function::createFromHostFunction(
+        runtime, jsi::PropNameID::forAscii(runtime, "executor"), 2,
+        [js_invoker, operation, first = std::move(first), second = std::move(second)](
+            jsi::Runtime &runtime, const jsi::Value &, const jsi::Value *arguments, size_t) -> jsi::Value {
+            auto resolve = std::make_shared<jsi::Value>(runtime, arguments[0]);
+            auto reject = std::make_shared<jsi::Value>(runtime, arguments[1]);
+
+            // The JS values are moved along so they are only ever released on the JS thread
+            std::thread([&runtime, js_invoker, operation, first, second, resolve = std::move(resolve),
+                         reject = std::move(reject)]() mutable {
+                Bytes result;
+                EciesErrorCode code = Success;
+                try {
+                    result = operation(first, second);
+                } catch (const Failure &failure) {
+                    code = failure.code;
+                }
+                js_invoker->invokeAsync([&runtime, result = std::move(result), code, resolve = std::move(resolve),
+                                         reject = std::move(reject)]() mutable {
+                    if (code == Success) {
+                        resolve->asObject(runtime).asFunction(runtime).call(
+                            runtime, array_buffer(runtime, std::move(result)));
+                    } else {
+                        auto error = runtime.global().getPropertyAsFunction(runtime, "Error").callAsConstructor(
+                            runtime, jsi::String::createFromUtf8(runtime, error_message(code)));
+                        reject->asObject(runtime).asFunction(runtime).call(runtime, error);
+                    }
+                });
+            }).detach();
+
+            return jsi::Value::undefined();
+        });
+
+    return runtime.global().getPropertyAsFunction(runtime, "Promise").callAsConstructor(runtime, executor);
+}
+
+} // namespace
+
+EciesHostObject::EciesHostObject(std::shared_ptr<facebook::react::CallInvoker> js_invoker)
+    : js_invoker_(std::move(js_invoker)) {}
+
+void EciesHostObject::install(jsi::Runtime &runtime, std::shared_ptr<facebook::react::CallInvoker> js_invoker) {
+    auto host_object = std::make_shared<EciesHostObject>(std::move(js_invoker));
+    runtime.global().setProperty(runtime, "__ecies", jsi::Object::createFromHostObject(runtime, host_object));
+}
+
+std::vector<jsi::PropNameID> EciesHostObject::getPropertyNames(jsi::Runtime &runtime) {
+    std::vector<jsi::PropNameID> names;
+    for (const char *name :
+         {"generateKeypair", "publicKeyFrom", "encrypt", "decrypt", "encryptAsync", "decryptAsync"}) {
+        names.push_back(jsi::PropNameID::forAscii(runtime, name));
+    }
+    return names;
+}
+
+jsi::Value EciesHostObject::get(jsi::Runtime &runtime, const jsi::PropNameID &prop_name) {
+    auto name = prop_name.utf8(runtime);
+    auto function = [&runtime, &prop_name](size_t parameter_count, jsi::HostFunctionType body) -> jsi::Value {
+        return jsi::Function::createFromHostFunction(runtime, prop_name, parameter_count, std::move(body));
+    };
+
+    if (name == "generateKeypair") {
+        return function(0, [](jsi::Runtime &runtime, const jsi::Value &, const jsi::Value *, size_t) -> jsi::Value {
+            Bytes secret_key(kSecretKeyLength);
+            Bytes public_key(kPublicKeyLength);
+            EciesErrorCode code = Success;
+            if (!ecies_script_generate_keypair(secret_key.data(), public_key.data(), &code)) {
+                throw jsi::JSError(runtime, error_message(code));
+            }
+            jsi::Object keypair(runtime);
+            keypair.setProperty(runtime, "secretKey", array_buffer(runtime, std::move(secret_key)));
+            keypair.setProperty(runtime, "publicKey", array_buffer(runtime, std::move(public_key)));
+            return keypair;
+        });
+    }
+    if (name == "publicKeyFrom") {
+        return function(1, [](jsi::Runtime &runtime, const jsi::Value &, const jsi::Value *arguments,
+                              size_t count) -> jsi::Value {
+            Bytes secret_key = bytes_argument(runtime, arguments, count, 0);
+            Bytes public_key(kPublicKeyLength);
+            EciesErrorCode code = Success;
+            if (!ecies_script_public_key_from(input(secret_key), secret_key.size(), public_key.data(), &code)) {
+                throw jsi::JSError(runtime, error_message(code));
+            }
+            return array_buffer(runtime, std::move(public_key));
+        });
+    }
+    if (name == "encrypt" || name == "decrypt" || name == "encryptAsync" || name == "decryptAsync") {
+        Operation operation = name.rfind("encrypt", 0) == 0 ? encrypt : decrypt;
+        bool async = name.size() > 7 && name.compare(name.size() - 5, 5, "Async") == 0;
+        auto js_invoker = js_invoker_;
+        return function(2, [operation, async, js_invoker](jsi::Runtime &runtime, const jsi::Value &,
+                                                         const jsi::Value *arguments, size_t count) -> jsi::Value {
+            Bytes key = bytes_argument(runtime, arguments, count, 0);
+            Bytes data = bytes_argument(runtime, arguments, count, 1);
+            if (async) {
+                return run_async(runtime, js_invoker, operation, std::move(key), std::move(data));
+            }
+            return run_sync(runtime, operation, std::move(key), std::move(data));
+        });
+    }
+    return jsi::Value::undefined();
+}
+
+} // namespace ecies::react
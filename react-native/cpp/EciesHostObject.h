@@ -0,0 +1,36 @@
+// JSI host object exposing the library to React Native without the bridge: keys, messages and ciphertexts are
+// ArrayBuffers passed by reference, never base64 strings. Built from the C API in ecies.h (the `ecies_script_*`
+// functions, which take raw bytes).
+//
+// Install it once per runtime from the native module, e.g. in a TurboModule's `install()`:
+//
+//     ecies::react::EciesHostObject::install(runtime, jsCallInvoker);
+//
+// which defines `global.__ecies`, wrapped by src/index.ts.
+
+#pragma once
+
+#include <memory>
+#include <vector>
+
+#include <ReactCommon/CallInvoker.h>
+#include <jsi/jsi.h>
+
+namespace ecies::react {
+
+class EciesHostObject : public facebook::jsi::HostObject {
+public:
+    explicit EciesHostObject(std::shared_ptr<facebook::react::CallInvoker> js_invoker);
+
+    static void install(facebook::jsi::Runtime &runtime,
+                        std::shared_ptr<facebook::react::CallInvoker> js_invoker);
+
+    facebook::jsi::Value get(facebook::jsi::Runtime &runtime, const facebook::jsi::PropNameID &name) override;
+
+    std::vector<facebook::jsi::PropNameID> getPropertyNames(facebook::jsi::Runtime &runtime) override;
+
+private:
+    std::shared_ptr<facebook::react::CallInvoker> js_invoker_;
+};
+
+} // namespace ecies::react
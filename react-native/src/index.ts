@@ -0,0 +1,63 @@
+// Typed access to the JSI host object installed by cpp/EciesHostObject.cpp. Accepts Uint8Arrays (or any view)
+// and returns Uint8Arrays; ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`.
+// Failures throw, or reject, an Error whose message carries the EciesErrorCode.
+
+interface EciesHostObject {
+  generateKeypair(): { secretKey: ArrayBuffer; publicKey: ArrayBuffer };
+  publicKeyFrom(secretKey: ArrayBuffer): ArrayBuffer;
+  encrypt(publicKey: ArrayBuffer, message: ArrayBuffer): ArrayBuffer;
+  decrypt(secretKey: ArrayBuffer, ciphertext: ArrayBuffer): ArrayBuffer;
+  encryptAsync(publicKey: ArrayBuffer, message: ArrayBuffer): Promise<ArrayBuffer>;
+  decryptAsync(secretKey: ArrayBuffer, ciphertext: ArrayBuffer): Promise<ArrayBuffer>;
+}
+
+declare global {
+  // eslint-disable-next-line no-var
+  var __ecies: EciesHostObject | undefined;
+}
+
+export interface Keypair {
+  secretKey: Uint8Array;
+  publicKey: Uint8Array;
+}
+
+function host(): EciesHostObject {
+  if (!global.__ecies) {
+    throw new Error("The ecies JSI module is not installed, see EciesHostObject::install");
+  }
+  return global.__ecies;
+}
+
+// The host object reads whole ArrayBuffers, so views into a larger buffer are copied out first
+function buffer(view: ArrayBufferView): ArrayBuffer {
+  if (view.byteOffset === 0 && view.byteLength === view.buffer.byteLength) {
+    return view.buffer as ArrayBuffer;
+  }
+  return view.buffer.slice(view.byteOffset, view.byteOffset + view.byteLength) as ArrayBuffer;
+}
+
+export function generateKeypair(): Keypair {
+  const { secretKey, publicKey } = host().generateKeypair();
+  return { secretKey: new Uint8Array(secretKey), publicKey: new Uint8Array(publicKey) };
+}
+
+export function publicKeyFrom(secretKey: ArrayBufferView): Uint8Array {
+  return new Uint8Array(host().publicKeyFrom(buffer(secretKey)));
+}
+
+export function encrypt(publicKey: ArrayBufferView, message: ArrayBufferView): Uint8Array {
+  return new Uint8Array(host().encrypt(buffer(publicKey), buffer(message)));
+}
+
+export function decrypt(secretKey: ArrayBufferView, ciphertext: ArrayBufferView): Uint8Array {
+  return new Uint8Array(host().decrypt(buffer(secretKey), buffer(ciphertext)));
+}
+
+// Run on a background thread, for large messages
+export async function encryptAsync(publicKey: ArrayBufferView, message: ArrayBufferView): Promise<Uint8Array> {
+  return new Uint8Array(await host().encryptAsync(buffer(publicKey), buffer(message)));
+}
+
+export async function decryptAsync(secretKey: ArrayBufferView, ciphertext: ArrayBufferView): Promise<Uint8Array> {
+  return new Uint8Array(await host().decryptAsync(buffer(secretKey), buffer(ciphertext)));
+}
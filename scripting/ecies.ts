@@ -0,0 +1,160 @@
+// Loads the ecies shared library (the android/ cdylib built for the host) from Deno or Bun through the
+// `ecies_script_*` exports described in ecies.symbols.json, see `scripting.rs`.
+//
+//     const ecies = await openEcies("./libecies.so");
+//     const { secretKey, publicKey } = ecies.generateKeypair();
+//     const ciphertext = ecies.encrypt(publicKey, new TextEncoder().encode("hello"));
+//     const message = ecies.decrypt(secretKey, ciphertext);
+//
+// Deno needs --allow-ffi (and --allow-read for the symbol table).
+
+import symbols from "./ecies.symbols.json" with { type: "json" };
+
+type Pointer = unknown;
+
+interface Symbols {
+  ecies_script_generate_keypair(secretKey: Uint8Array, publicKey: Uint8Array, error: Int32Array): boolean;
+  ecies_script_public_key_from(
+    secretKey: Uint8Array,
+    secretKeyLength: number,
+    publicKey: Uint8Array,
+    error: Int32Array,
+  ): boolean;
+  ecies_script_encrypt(
+    publicKey: Uint8Array,
+    publicKeyLength: number,
+    message: Uint8Array,
+    messageLength: number,
+    error: Int32Array,
+  ): Pointer;
+  ecies_script_decrypt(
+    secretKey: Uint8Array,
+    secretKeyLength: number,
+    ciphertext: Uint8Array,
+    ciphertextLength: number,
+    error: Int32Array,
+  ): Pointer;
+  ecies_script_buffer_data(buffer: Pointer): Pointer;
+  ecies_script_buffer_length(buffer: Pointer): number | bigint;
+  ecies_script_buffer_free(buffer: Pointer): void;
+}
+
+// Failures carry the numeric `EciesErrorCode` of ecies.h
+export class EciesError extends Error {
+  constructor(readonly code: number) {
+    super(`EciesErrorCode ${code}`);
+  }
+}
+
+export interface Keypair {
+  secretKey: Uint8Array;
+  publicKey: Uint8Array;
+}
+
+export interface Ecies {
+  generateKeypair(): Keypair;
+  publicKeyFrom(secretKey: Uint8Array): Uint8Array;
+  encrypt(publicKey: Uint8Array, message: Uint8Array): Uint8Array;
+  decrypt(secretKey: Uint8Array, ciphertext: Uint8Array): Uint8Array;
+  close(): void;
+}
+
+const BUN_TYPES: Record<string, string> = {
+  buffer: "ptr",
+  pointer: "ptr",
+  usize: "usize",
+  bool: "bool",
+  void: "void",
+};
+
+// The runtimes may pass an empty Uint8Array as a null pointer, which the library rejects
+function input(bytes: Uint8Array): Uint8Array {
+  return bytes.length > 0 ? bytes : new Uint8Array(1);
+}
+
+export async function openEcies(path: string): Promise<Ecies> {
+  let lib: Symbols;
+  let copy: (data: Pointer, length: number) => Uint8Array;
+  let close: () => void;
+
+  // deno-lint-ignore no-explicit-any
+  const deno = (globalThis as any).Deno;
+  if (deno) {
+    const library = deno.dlopen(path, symbols);
+    lib = library.symbols;
+    copy = (data, length) => new Uint8Array(deno.UnsafePointerView.getArrayBuffer(data, length)).slice();
+    close = () => library.close();
+  } else {
+    const { dlopen, toArrayBuffer } = await import("bun:ffi");
+    const bunSymbols = Object.fromEntries(
+      Object.entries(symbols).map(([name, { parameters, result }]) => [
+        name,
+        { args: parameters.map((type) => BUN_TYPES[type]), returns: BUN_TYPES[result] },
+      ]),
+    );
+    const library = dlopen(path, bunSymbols);
+    lib = library.symbols as unknown as Symbols;
+    copy = (data, length) => new Uint8Array(toArrayBuffer(data as number, 0, length)).slice();
+    close = () => library.close();
+  }
+
+  // Copies a returned buffer into JavaScript memory and releases it
+  function take(buffer: Pointer, error: Int32Array): Uint8Array {
+    if (!buffer) {
+      throw new EciesError(error[0]);
+    }
+    try {
+      const length = Number(lib.ecies_script_buffer_length(buffer));
+      return length > 0 ? copy(lib.ecies_script_buffer_data(buffer), length) : new Uint8Array(0);
+    } finally {
+      lib.ecies_script_buffer_free(buffer);
+    }
+  }
+
+  return {
+    generateKeypair() {
+      const secretKey = new Uint8Array(32);
+      const publicKey = new Uint8Array(33);
+      const error = new Int32Array(1);
+      if (!lib.ecies_script_generate_keypair(secretKey, publicKey, error)) {
+        throw new EciesError(error[0]);
+      }
+      return { secretKey, publicKey };
+    },
+
+    publicKeyFrom(secretKey) {
+      const publicKey = new Uint8Array(33);
+      const error = new Int32Array(1);
+      if (!lib.ecies_script_public_key_from(input(secretKey), secretKey.length, publicKey, error)) {
+        throw new EciesError(error[0]);
+      }
+      return publicKey;
+    },
+
+    encrypt(publicKey, message) {
+      const error = new Int32Array(1);
+      const buffer = lib.ecies_script_encrypt(
+        input(publicKey),
+        publicKey.length,
+        input(message),
+        message.length,
+        error,
+      );
+      return take(buffer, error);
+    },
+
+    decrypt(secretKey, ciphertext) {
+      const error = new Int32Array(1);
+      const buffer = lib.ecies_script_decrypt(
+        input(secretKey),
+        secretKey.length,
+        input(ciphertext),
+        ciphertext.length,
+        error,
+      );
+      return take(buffer, error);
+    },
+
+    close,
+  };
+}
@@ -0,0 +1,18 @@
+#!/bin/bash
+
+# ASSUMPTIONS
+# 1. Cargo package manager is already installed on your computer
+# 2. wasi-sdk is installed in /opt/wasi-sdk (or WASI_SDK_PATH points to it): zstd-sys needs its clang and sysroot
+
+# This generates wasi/target/wasm32-wasip1/release/ecies.wasm, a WASI reactor module exporting the `ecies_wasi_*`
+# functions of src/lib.rs
+
+WASI_SDK_PATH=${WASI_SDK_PATH:-/opt/wasi-sdk}
+
+rustup target add wasm32-wasip1
+
+# wasm32-wasip1 is the target formerly named wasm32-wasi. Add `--no-default-features --features host-rng` for
+# hosts that supply the randomness themselves
+CC_wasm32_wasip1="$WASI_SDK_PATH/bin/clang" \
+CFLAGS_wasm32_wasip1="--sysroot=$WASI_SDK_PATH/share/wasi-sysroot" \
+cargo build --release --target wasm32-wasip1 "$@"
@@ -0,0 +1,186 @@
+use ecies_core::context::{generate_keypair, public_key_of};
+use ecies_core::envelope::{open_or_legacy, seal, Header};
+use ecies_core::error::EciesErrorCode;
+use ecies_core::keys::{parse_public_key, wipe};
+use ecies_core::SecretKey;
+
+/*
+WASI module, built for wasm32-wasip1 with build.sh, so edge-compute services and sandboxed plugins running on a
+WASI runtime (Wasmtime, WasmEdge, Wasmer, ...) open the envelopes of the mobile apps with the same `ecies-core`.
+
+The exports follow the Dart ones of ios/src/dart.rs, which suit a linear memory the host reads and writes: every
+buffer is a pointer and a length into the module memory, inputs are allocated with `ecies_wasi_alloc`, and inputs
+and outputs alike are released with `ecies_wasi_free` and their length. Functions return the `EciesErrorCode` as
+an `i32`, 0 on success, and write their results through out-pointers, which stay untouched on failure.
+
+Keys are raw bytes: 32-byte secret keys, 33-byte compressed public keys on output (any layout on input).
+Ciphertexts are binary envelopes, the base64-decoded output of `ecies_encrypt`.
+
+Randomness comes from the WASI `random_get` call by default. Runtimes that do not grant it, or that want their
+own source, build with the `host-rng` feature instead, and the module imports
+
+    (import "ecies_host" "fill_random" (func (param i32 i32)))
+
+which must fill the `length` bytes at `pointer` (its two parameters) with cryptographically secure random bytes.
+*/
+
+#[cfg(feature = "host-rng")]
+mod host {
+    #[link(wasm_import_module = "ecies_host")]
+    extern "C" {
+        fn fill_random(pointer: *mut u8, length: usize);
+    }
+
+    fn fill_bytes(buffer: &mut [u8]) {
+        unsafe { fill_random(buffer.as_mut_ptr(), buffer.len()) }
+    }
+
+    // Installed before every call that draws randomness, since a reactor module has no entry point to do it once
+    pub fn install_rng() {
+        ecies_core::random::set_fill_bytes(fill_bytes);
+    }
+}
+
+#[cfg(feature = "host-rng")]
+use host::install_rng;
+
+#[cfg(not(feature = "host-rng"))]
+fn install_rng() {}
+
+fn status(result: Result<(), EciesErrorCode>) -> EciesErrorCode {
+    result.err().unwrap_or(EciesErrorCode::Success)
+}
+
+unsafe fn slice<'a>(pointer: *const u8, length: usize) -> Result<&'a [u8], EciesErrorCode> {
+    if pointer.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    Ok(unsafe { std::slice::from_raw_parts(pointer, length) })
+}
+
+unsafe fn write_bytes(destination: *mut u8, bytes: &[u8]) -> Result<(), EciesErrorCode> {
+    if destination.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), destination, bytes.len()) };
+    Ok(())
+}
+
+// Transfers `bytes` to the host as memory released by `ecies_wasi_free`
+unsafe fn write_owned(bytes: Vec<u8>, pointer_out: *mut *mut u8, length_out: *mut usize) -> Result<(), EciesErrorCode> {
+    if pointer_out.is_null() || length_out.is_null() {
+        return Err(EciesErrorCode::NullPointer);
+    }
+    let boxed = bytes.into_boxed_slice();
+    unsafe {
+        *length_out = boxed.len();
+        *pointer_out = Box::into_raw(boxed) as *mut u8;
+    }
+    Ok(())
+}
+
+unsafe fn secret_key(pointer: *const u8, length: usize) -> Result<SecretKey, EciesErrorCode> {
+    ecies_core::keys::parse_secret_key(unsafe { slice(pointer, length)? })
+}
+
+/**
+Allocates `length` zeroed bytes of module memory, to be released with `ecies_wasi_free`. The pointer is never
+null, even for a length of 0.
+*/
+#[no_mangle]
+pub extern "C" fn ecies_wasi_alloc(length: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; length].into_boxed_slice()) as *mut u8
+}
+
+/**
+Clears and releases memory from `ecies_wasi_alloc` or returned by the `ecies_wasi_*` functions. `length` must be
+the allocated length. Passing null is a no-op.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wasi_free(pointer: *mut u8, length: usize) {
+    if pointer.is_null() {
+        return;
+    }
+    let mut boxed = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(pointer, length)) };
+    wipe(&mut boxed);
+}
+
+/**
+Generates a keypair into host-allocated memory: 32 bytes at `secret_key_out` and 33 bytes (compressed) at
+`public_key_out`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wasi_generate_keypair(
+    secret_key_out: *mut u8,
+    public_key_out: *mut u8,
+) -> EciesErrorCode {
+    install_rng();
+    status((|| {
+        let (secret_key, public_key) = generate_keypair()?;
+        let mut secret_key = secret_key.serialize();
+        let result = unsafe { write_bytes(secret_key_out, &secret_key) };
+        wipe(&mut secret_key);
+        result?;
+
+        unsafe { write_bytes(public_key_out, &public_key.serialize_compressed()) }
+    })())
+}
+
+/**
+Writes the 33-byte compressed public key of a 32-byte secret key to `public_key_out`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wasi_public_key_from(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    public_key_out: *mut u8,
+) -> EciesErrorCode {
+    status((|| {
+        let secret_key = unsafe { secret_key(secret_key_ptr, secret_key_length)? };
+        unsafe { write_bytes(public_key_out, &public_key_of(&secret_key).serialize_compressed()) }
+    })())
+}
+
+/**
+Encrypts a message into a binary envelope, written to `envelope_out` and `envelope_length_out`. Release it with
+`ecies_wasi_free`.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wasi_encrypt(
+    public_key_ptr: *const u8,
+    public_key_length: usize,
+    message_ptr: *const u8,
+    message_length: usize,
+    envelope_out: *mut *mut u8,
+    envelope_length_out: *mut usize,
+) -> EciesErrorCode {
+    install_rng();
+    status((|| {
+        let public_key = parse_public_key(unsafe { slice(public_key_ptr, public_key_length)? })?;
+        let message = unsafe { slice(message_ptr, message_length)? };
+        let envelope = seal(&public_key, &Header::default(), message, None)?;
+
+        unsafe { write_owned(envelope, envelope_out, envelope_length_out) }
+    })())
+}
+
+/**
+Decrypts a binary envelope, or a legacy ciphertext, into a message written to `message_out` and
+`message_length_out`. Release it with `ecies_wasi_free`, which clears it.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn ecies_wasi_decrypt(
+    secret_key_ptr: *const u8,
+    secret_key_length: usize,
+    ciphertext_ptr: *const u8,
+    ciphertext_length: usize,
+    message_out: *mut *mut u8,
+    message_length_out: *mut usize,
+) -> EciesErrorCode {
+    status((|| {
+        let secret_key = unsafe { secret_key(secret_key_ptr, secret_key_length)? };
+        let (_, message) = open_or_legacy(&secret_key, unsafe { slice(ciphertext_ptr, ciphertext_length)? })?;
+
+        unsafe { write_owned(message, message_out, message_length_out) }
+    })())
+}
@@ -0,0 +1,14 @@
+#!/bin/bash
+
+# ASSUMPTIONS
+# 1. Cargo package manager is already installed on your computer
+# 2. wasm-pack is installed: `$ cargo install wasm-pack`
+# 3. clang supports the wasm32 target, which zstd-sys needs to compile zstd
+
+# This generates an npm package in ./pkg: ecies.js, the ecies_bg.wasm module and TypeScript declarations
+
+rustup target add wasm32-unknown-unknown
+
+# --target web emits an ES module loaded with `await init()`; use --target bundler for webpack or vite,
+# and --target nodejs for Node
+wasm-pack build --release --target web --out-name ecies
@@ -0,0 +1,86 @@
+use wasm_bindgen::prelude::*;
+
+use ecies_core::SecretKey;
+use ecies_core::context::{self, public_key_of};
+use ecies_core::envelope::{open_or_legacy, seal, Header};
+use ecies_core::error::EciesErrorCode;
+use ecies_core::keys::{parse_public_key, wipe};
+
+/*
+WebAssembly interface, built with `wasm-pack build --target web` (see build.sh).
+
+This crate is a thin layer over `ecies-core`, the envelope code of `ios/` and `android/`, so the web app produces
+and reads exactly the ciphertexts of the mobile apps and runs the same test vectors. On wasm32 the core takes AES
+from the RustCrypto crates instead of OpenSSL, see core/src/symmetric.rs.
+
+Keys and ciphertexts are `Uint8Array`s: 32-byte secret keys, public keys in any layout accepted by the C API
+(compressed on output) and binary envelopes, i.e. the base64-decoded output of `ecies_encrypt`. Failures are
+thrown as JavaScript `Error`s whose message is the `EciesErrorCode` variant name, e.g. "DecryptionFailed".
+*/
+
+fn js_error(code: EciesErrorCode) -> JsError {
+    JsError::new(&code.to_string())
+}
+
+fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey, JsError> {
+    ecies_core::keys::parse_secret_key(bytes).map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub struct Keypair {
+    secret_key: Vec<u8>,
+    public_key: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Keypair {
+    #[wasm_bindgen(getter, js_name = secretKey)]
+    pub fn secret_key(&self) -> Vec<u8> {
+        self.secret_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+}
+
+// Called by `free()` on the JavaScript side; the copies handed out by `secretKey` are the caller's to clear
+impl Drop for Keypair {
+    fn drop(&mut self) {
+        wipe(&mut self.secret_key);
+    }
+}
+
+#[wasm_bindgen(js_name = generateKeypair)]
+pub fn generate_keypair() -> Result<Keypair, JsError> {
+    let (secret_key, public_key) = context::generate_keypair().map_err(js_error)?;
+
+    Ok(Keypair {
+        secret_key: secret_key.serialize().to_vec(),
+        public_key: public_key.serialize_compressed().to_vec(),
+    })
+}
+
+#[wasm_bindgen(js_name = publicKeyFrom)]
+pub fn public_key_from(secret_key: &[u8]) -> Result<Vec<u8>, JsError> {
+    let secret_key = parse_secret_key(secret_key)?;
+
+    Ok(public_key_of(&secret_key).serialize_compressed().to_vec())
+}
+
+#[wasm_bindgen]
+pub fn encrypt(public_key: &[u8], message: &[u8]) -> Result<Vec<u8>, JsError> {
+    let public_key = parse_public_key(public_key).map_err(js_error)?;
+
+    seal(&public_key, &Header::default(), message, None).map_err(js_error)
+}
+
+// Also accepts the legacy ciphertexts produced before envelopes existed
+#[wasm_bindgen]
+pub fn decrypt(secret_key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, JsError> {
+    let secret_key = parse_secret_key(secret_key)?;
+    let (_, message) = open_or_legacy(&secret_key, ciphertext).map_err(js_error)?;
+
+    Ok(message)
+}